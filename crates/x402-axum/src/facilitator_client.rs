@@ -26,7 +26,8 @@
 //! - URL construction
 //! - HTTP transport failures
 //! - JSON deserialization errors
-//! - Unexpected HTTP status responses
+//! - Errors answered by the facilitator, as [`FacilitatorClientError::Facilitator`]
+//! - Other unexpected HTTP status responses
 
 use http::{HeaderMap, StatusCode};
 use reqwest::Client;
@@ -35,7 +36,8 @@ use std::time::Duration;
 use url::Url;
 use x402_rs::facilitator::Facilitator;
 use x402_rs::types::{
-    SettleRequest, SettleResponse, SupportedPaymentKindsResponse, VerifyRequest, VerifyResponse,
+    CancelRequest, CancelResponse, ErrorResponse, QuoteRequest, QuoteResponse, SettleRequest,
+    SettleResponse, SupportedPaymentKindsResponse, VerifyRequest, VerifyResponse,
 };
 
 #[cfg(feature = "telemetry")]
@@ -55,6 +57,10 @@ pub struct FacilitatorClient {
     settle_url: Url,
     /// Full URL to `GET /supported` requests
     supported_url: Url,
+    /// Full URL to `POST /quote` requests
+    quote_url: Url,
+    /// Full URL to `POST /cancel` requests
+    cancel_url: Url,
     /// Shared Reqwest HTTP client
     client: Client,
     /// Optional custom headers sent with each request
@@ -117,6 +123,14 @@ impl Facilitator for FacilitatorClient {
     async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
         FacilitatorClient::supported(self).await
     }
+
+    async fn quote(&self, request: &QuoteRequest) -> Option<Result<QuoteResponse, Self::Error>> {
+        Some(FacilitatorClient::quote(self, request).await)
+    }
+
+    async fn cancel(&self, request: &CancelRequest) -> Option<Result<CancelResponse, Self::Error>> {
+        Some(FacilitatorClient::cancel(self, request).await)
+    }
 }
 
 /// Errors that can occur while interacting with a remote facilitator.
@@ -140,6 +154,13 @@ pub enum FacilitatorClientError {
         #[source]
         source: reqwest::Error,
     },
+    /// The facilitator answered with a non-200 status and an `{"error": ...}` body.
+    #[error("Facilitator error {status}: {context}: {error}")]
+    Facilitator {
+        context: &'static str,
+        status: StatusCode,
+        error: String,
+    },
     #[error("Unexpected HTTP status {status}: {context}: {body}")]
     HttpStatus {
         context: &'static str,
@@ -179,6 +200,18 @@ impl FacilitatorClient {
         &self.supported_url
     }
 
+    /// Returns the computed `./quote` URL relative to [`FacilitatorClient::base_url`]
+    #[allow(dead_code)] // Public for consumption by downstream crates.
+    pub fn quote_url(&self) -> &Url {
+        &self.quote_url
+    }
+
+    /// Returns the computed `./cancel` URL relative to [`FacilitatorClient::base_url`]
+    #[allow(dead_code)] // Public for consumption by downstream crates.
+    pub fn cancel_url(&self) -> &Url {
+        &self.cancel_url
+    }
+
     /// Returns any custom headers configured on the client.
     #[allow(dead_code)] // Public for consumption by downstream crates.
     pub fn headers(&self) -> &HeaderMap {
@@ -217,12 +250,27 @@ impl FacilitatorClient {
                     context: "Failed to construct ./supported URL",
                     source: e,
                 })?;
+        let quote_url = base_url
+            .join("./quote")
+            .map_err(|e| FacilitatorClientError::UrlParse {
+                context: "Failed to construct ./quote URL",
+                source: e,
+            })?;
+        let cancel_url =
+            base_url
+                .join("./cancel")
+                .map_err(|e| FacilitatorClientError::UrlParse {
+                    context: "Failed to construct ./cancel URL",
+                    source: e,
+                })?;
         Ok(Self {
             client,
             base_url,
             verify_url,
             settle_url,
             supported_url,
+            quote_url,
+            cancel_url,
             headers: HeaderMap::new(),
             timeout: None,
         })
@@ -236,6 +284,14 @@ impl FacilitatorClient {
         this
     }
 
+    /// Sends all future requests with `client`, e.g. one sharing a connection pool with the rest of the application.
+    #[allow(dead_code)] // Public for consumption by downstream crates.
+    pub fn with_client(&self, client: Client) -> Self {
+        let mut this = self.clone();
+        this.client = client;
+        this
+    }
+
     /// Sets a timeout for all future requests.
     #[allow(dead_code)] // Public for consumption by downstream crates.
     pub fn with_timeout(&self, timeout: Duration) -> Self {
@@ -266,6 +322,24 @@ impl FacilitatorClient {
         self.get_json(&self.supported_url, "GET /supported").await
     }
 
+    /// Sends a `POST /quote` request to the facilitator.
+    pub async fn quote(
+        &self,
+        request: &QuoteRequest,
+    ) -> Result<QuoteResponse, FacilitatorClientError> {
+        self.post_json(&self.quote_url, "POST /quote", request)
+            .await
+    }
+
+    /// Sends a `POST /cancel` request to the facilitator.
+    pub async fn cancel(
+        &self,
+        request: &CancelRequest,
+    ) -> Result<CancelResponse, FacilitatorClientError> {
+        self.post_json(&self.cancel_url, "POST /cancel", request)
+            .await
+    }
+
     /// Generic POST helper that handles JSON serialization, error mapping,
     /// timeout application, and telemetry integration.
     ///
@@ -298,16 +372,7 @@ impl FacilitatorClient {
                 .await
                 .map_err(|e| FacilitatorClientError::JsonDeserialization { context, source: e })
         } else {
-            let status = http_response.status();
-            let body = http_response
-                .text()
-                .await
-                .map_err(|e| FacilitatorClientError::ResponseBodyRead { context, source: e })?;
-            Err(FacilitatorClientError::HttpStatus {
-                context,
-                status,
-                body,
-            })
+            Err(error_from_response(context, http_response).await)
         };
 
         record_result_on_span(&result);
@@ -345,16 +410,7 @@ impl FacilitatorClient {
                 .await
                 .map_err(|e| FacilitatorClientError::JsonDeserialization { context, source: e })
         } else {
-            let status = http_response.status();
-            let body = http_response
-                .text()
-                .await
-                .map_err(|e| FacilitatorClientError::ResponseBodyRead { context, source: e })?;
-            Err(FacilitatorClientError::HttpStatus {
-                context,
-                status,
-                body,
-            })
+            Err(error_from_response(context, http_response).await)
         };
 
         record_result_on_span(&result);
@@ -363,6 +419,31 @@ impl FacilitatorClient {
     }
 }
 
+/// Maps a non-200 response to [`FacilitatorClientError::Facilitator`] if its body is an [`ErrorResponse`],
+/// otherwise to [`FacilitatorClientError::HttpStatus`].
+async fn error_from_response(
+    context: &'static str,
+    http_response: reqwest::Response,
+) -> FacilitatorClientError {
+    let status = http_response.status();
+    let body = match http_response.text().await {
+        Ok(body) => body,
+        Err(e) => return FacilitatorClientError::ResponseBodyRead { context, source: e },
+    };
+    match serde_json::from_str::<ErrorResponse>(&body) {
+        Ok(ErrorResponse { error }) => FacilitatorClientError::Facilitator {
+            context,
+            status,
+            error,
+        },
+        Err(_) => FacilitatorClientError::HttpStatus {
+            context,
+            status,
+            body,
+        },
+    }
+}
+
 /// Converts a string URL into a `FacilitatorClient`, parsing the URL and calling `try_new`.
 impl TryFrom<&str> for FacilitatorClient {
     type Error = FacilitatorClientError;
@@ -405,3 +486,38 @@ fn record_result_on_span<R, E: Display>(_result: &Result<R, E>) {}
 fn with_span<F: Future>(fut: F, span: Span) -> impl Future<Output = F::Output> {
     fut.instrument(span)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, body: &'static str) -> reqwest::Response {
+        http::Response::builder()
+            .status(status)
+            .body(body)
+            .unwrap()
+            .into()
+    }
+
+    #[tokio::test]
+    async fn test_error_bodies_map_to_facilitator_errors() {
+        let error = error_from_response(
+            "POST /settle",
+            response(400, r#"{"error":"Invalid payment"}"#),
+        )
+        .await;
+        assert!(matches!(
+            error,
+            FacilitatorClientError::Facilitator { context: "POST /settle", status, ref error }
+                if status == StatusCode::BAD_REQUEST && error == "Invalid payment"
+        ));
+
+        let error =
+            error_from_response("POST /settle", response(502, "<html>Bad Gateway</html>")).await;
+        assert!(matches!(
+            error,
+            FacilitatorClientError::HttpStatus { status, ref body, .. }
+                if status == StatusCode::BAD_GATEWAY && body == "<html>Bad Gateway</html>"
+        ));
+    }
+}
@@ -394,6 +394,8 @@ where
                         asset: price_tag.token.address(),
                         extra,
                         output_schema: complete_output_schema.clone(),
+                        splits: None,
+                        authorization_kind: None,
                     }
                 })
                 .collect::<Vec<_>>();
@@ -918,6 +920,8 @@ impl PaymentRequirementsNoResource {
             asset: self.asset.clone(),
             extra: self.extra.clone(),
             output_schema: self.output_schema.clone(),
+            splits: None,
+            authorization_kind: None,
         }
     }
 }
@@ -1,7 +1,7 @@
 use std::fmt::Debug;
 use x402_rs::network::USDCDeployment;
 use x402_rs::types::{EvmAddress, MixedAddress, TokenDeployment};
-use x402_rs::types::{MoneyAmount, TokenAmount};
+use x402_rs::types::{MoneyAmount, MoneyAmountParseError, TokenAmount};
 
 /// A complete x402-compatible price tag, describing a required payment.
 ///
@@ -117,6 +117,8 @@ pub enum PriceTagBuilderError {
     NoAmount,
     #[error("Invalid amount value")]
     InvalidAmount,
+    #[error("Amount has {money} decimal places, but the token supports only {token}")]
+    WrongPrecision { money: u32, token: u32 },
     #[error("No pay_to address provided")]
     NoPayTo,
     #[error("Invalid pay_to address")]
@@ -173,8 +175,12 @@ where
             .ok_or(PriceTagBuilderError::InvalidAmount)?;
         let amount = money_amount
             .as_token_amount(token.decimals as u32)
-            .ok()
-            .ok_or(PriceTagBuilderError::InvalidAmount)?;
+            .map_err(|e| match e {
+                MoneyAmountParseError::WrongPrecision { money, token } => {
+                    PriceTagBuilderError::WrongPrecision { money, token }
+                }
+                _ => PriceTagBuilderError::InvalidAmount,
+            })?;
         let pay_to = self.pay_to.ok_or(PriceTagBuilderError::NoPayTo)?;
         let pay_to = pay_to.into();
         let price_tag = PriceTag {
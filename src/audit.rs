@@ -0,0 +1,320 @@
+//! Tamper-evident audit log of admin requests.
+//!
+//! Every request to an admin endpoint, `GET /admin/*` and `GET /settlements/{tx_hash}`, is recorded
+//! by the [`audit`] middleware as an [`AuditRecord`]: who made it, by a fingerprint of its bearer
+//! token; what, by method and path; when; from which address; and how it was answered, rejected
+//! tokens included. Records are chained: each carries the keccak256 hash of its content and of the
+//! previous record's hash, so a record removed or edited breaks the chain, which is checked
+//! whenever the log is opened.
+//!
+//! Records are appended to an [`AuditSink`]: [`FileAuditSink`], an append-only JSON Lines file, or
+//! any other store implementing it, installed with [`AuditLog::new`]. The most recent records are
+//! also kept in memory, and served by `GET /admin/audit`, which is itself audited.
+//!
+//! Environment variables used:
+//! - `ADMIN_AUDIT_LOG_PATH` — file the audit log is appended to (default: unset, kept in memory only),
+//! - `ADMIN_API_TOKEN` — bearer token required by admin endpoints; nothing is audited without it.
+
+use alloy::hex;
+use alloy::primitives::{B256, keccak256};
+use async_trait::async_trait;
+use axum::extract::{ConnectInfo, Request};
+use axum::http::{HeaderMap, header};
+use axum::middleware::Next;
+use axum::response::Response;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::from_env::{ENV_ADMIN_API_TOKEN, ENV_ADMIN_AUDIT_LOG_PATH};
+use crate::timestamp::UnixTimestamp;
+
+/// Number of records kept in memory for `GET /admin/audit`.
+pub const MAX_RECENT: usize = 1000;
+
+/// An audited admin request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    pub at: UnixTimestamp,
+    /// Who made the request: `token:` and the first bytes of the keccak256 hash of its bearer
+    /// token, or `anonymous` without one.
+    pub actor: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_ip: Option<IpAddr>,
+    /// Method and path, e.g. `GET /admin/failures`.
+    pub action: String,
+    /// Status the request was answered, e.g. `401` for a rejected token.
+    pub status: u16,
+    /// Hash of the previous record, zero for the first one.
+    pub previous: B256,
+    /// keccak256 hash of this record's content and `previous`.
+    pub hash: B256,
+}
+
+impl AuditRecord {
+    /// The hash this record must carry.
+    fn digest(&self) -> B256 {
+        let content = (
+            &self.previous,
+            self.at,
+            &self.actor,
+            &self.client_ip,
+            &self.action,
+            self.status,
+        );
+        keccak256(serde_json::to_vec(&content).unwrap_or_default())
+    }
+}
+
+/// Index of the first of `records` breaking the chain from `previous`, if any.
+fn broken_link<'a>(
+    mut previous: B256,
+    records: impl IntoIterator<Item = &'a AuditRecord>,
+) -> Option<usize> {
+    for (index, record) in records.into_iter().enumerate() {
+        if record.previous != previous || record.hash != record.digest() {
+            return Some(index);
+        }
+        previous = record.hash;
+    }
+    None
+}
+
+/// Where audit records are appended.
+#[async_trait]
+pub trait AuditSink: Send + Sync + std::fmt::Debug {
+    /// Appends `record`, after every record appended before.
+    async fn append(&self, record: &AuditRecord) -> Result<(), std::io::Error>;
+}
+
+/// [`AuditSink`] appending to a JSON Lines file, never rewritten.
+#[derive(Debug)]
+pub struct FileAuditSink {
+    file: Mutex<tokio::fs::File>,
+}
+
+#[async_trait]
+impl AuditSink for FileAuditSink {
+    async fn append(&self, record: &AuditRecord) -> Result<(), std::io::Error> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        let mut file = self.file.lock().await;
+        file.write_all(&line).await?;
+        file.flush().await
+    }
+}
+
+/// The end of the chain and the most recent records.
+#[derive(Debug)]
+struct Chain {
+    last: B256,
+    recent: VecDeque<AuditRecord>,
+}
+
+/// The audit log: appends chained [`AuditRecord`]s to its [`AuditSink`], if any.
+#[derive(Debug)]
+pub struct AuditLog {
+    sink: Option<Arc<dyn AuditSink>>,
+    chain: Mutex<Chain>,
+}
+
+impl AuditLog {
+    /// An audit log appending to `sink` after `records`, the ones it already holds, oldest first.
+    pub fn new(sink: Option<Arc<dyn AuditSink>>, records: Vec<AuditRecord>) -> Self {
+        let last = records.last().map_or(B256::ZERO, |record| record.hash);
+        let skipped = records.len().saturating_sub(MAX_RECENT);
+        Self {
+            sink,
+            chain: Mutex::new(Chain {
+                last,
+                recent: records.into_iter().skip(skipped).collect(),
+            }),
+        }
+    }
+
+    /// Opens the audit log at `path`, creating it if needed, and checks its chain.
+    ///
+    /// A broken chain is reported, as the log was tampered with, but does not prevent opening it.
+    pub async fn open(path: PathBuf) -> Result<Self, std::io::Error> {
+        let records = match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => contents
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str::<AuditRecord>)
+                .collect::<Result<Vec<_>, _>>()?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error),
+        };
+        if let Some(index) = broken_link(B256::ZERO, &records) {
+            tracing::error!(
+                path = %path.display(),
+                line = index + 1,
+                "Admin audit log chain is broken: records were removed or edited"
+            );
+        }
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        let sink = FileAuditSink {
+            file: Mutex::new(file),
+        };
+        Ok(Self::new(Some(Arc::new(sink)), records))
+    }
+
+    /// Opens the audit log at `ADMIN_AUDIT_LOG_PATH`, or keeps it in memory if it is not set.
+    pub async fn from_env() -> Result<Self, std::io::Error> {
+        match std::env::var(ENV_ADMIN_AUDIT_LOG_PATH) {
+            Ok(path) if !path.trim().is_empty() => Self::open(path.into()).await,
+            _ => Ok(Self::new(None, Vec::new())),
+        }
+    }
+
+    /// Chains and appends a record of `action`, made by `actor` from `client_ip` and answered `status`.
+    ///
+    /// A record that cannot be appended is reported and not chained, so the chain stays whole.
+    pub async fn record(
+        &self,
+        actor: String,
+        client_ip: Option<IpAddr>,
+        action: String,
+        status: u16,
+    ) {
+        let mut chain = self.chain.lock().await;
+        let mut record = AuditRecord {
+            at: UnixTimestamp::try_now().unwrap_or(UnixTimestamp(0)),
+            actor,
+            client_ip,
+            action,
+            status,
+            previous: chain.last,
+            hash: B256::ZERO,
+        };
+        record.hash = record.digest();
+        if let Some(sink) = &self.sink
+            && let Err(error) = sink.append(&record).await
+        {
+            tracing::error!(%error, action = %record.action, "Failed to append to the admin audit log");
+            return;
+        }
+        chain.last = record.hash;
+        if chain.recent.len() == MAX_RECENT {
+            chain.recent.pop_front();
+        }
+        chain.recent.push_back(record);
+    }
+
+    /// Up to `limit` of the most recent records, most recent first.
+    pub async fn recent(&self, limit: usize) -> Vec<AuditRecord> {
+        let chain = self.chain.lock().await;
+        chain.recent.iter().rev().take(limit).cloned().collect()
+    }
+}
+
+static AUDIT_LOG: OnceCell<AuditLog> = OnceCell::new();
+
+/// Makes `audit_log` the process-wide audit log. Only the first call has an effect.
+pub fn install(audit_log: AuditLog) {
+    let _ = AUDIT_LOG.set(audit_log);
+}
+
+/// The process-wide audit log, `None` if it was not installed.
+pub fn global() -> Option<&'static AuditLog> {
+    AUDIT_LOG.get()
+}
+
+/// Middleware recording admin requests and their answers in the process-wide audit log.
+///
+/// Records nothing if `ADMIN_API_TOKEN` is not set, as admin endpoints are then disabled.
+pub async fn audit(request: Request, next: Next) -> Response {
+    let Some(audit_log) = global().filter(|_| std::env::var(ENV_ADMIN_API_TOKEN).is_ok()) else {
+        return next.run(request).await;
+    };
+    let actor = actor(request.headers());
+    let client_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let action = format!("{} {}", request.method(), request.uri().path());
+    let response = next.run(request).await;
+    audit_log
+        .record(actor, client_ip, action, response.status().as_u16())
+        .await;
+    response
+}
+
+/// Who sent `headers`: a fingerprint of the bearer token, which identifies it without revealing it.
+fn actor(headers: &HeaderMap) -> String {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map_or_else(
+            || "anonymous".to_string(),
+            |token| format!("token:{}", hex::encode(&keccak256(token.as_bytes())[..4])),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_audit_log_chains_records_across_reopens() {
+        let path = std::env::temp_dir().join(format!(
+            "x402-audit-{}.jsonl",
+            UnixTimestamp::try_now().unwrap().0 ^ u64::from(std::process::id())
+        ));
+        let audit_log = AuditLog::open(path.clone()).await.unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer secret".parse().unwrap());
+        let admin = actor(&headers);
+        assert!(admin.starts_with("token:"));
+        assert!(!admin.contains("secret"));
+        assert_eq!(actor(&HeaderMap::new()), "anonymous");
+        audit_log
+            .record(admin.clone(), None, "GET /admin/failures".to_string(), 200)
+            .await;
+        audit_log
+            .record(
+                "anonymous".to_string(),
+                None,
+                "GET /admin/audit".to_string(),
+                401,
+            )
+            .await;
+        drop(audit_log);
+
+        let reopened = AuditLog::open(path.clone()).await.unwrap();
+        reopened
+            .record(admin, None, "GET /admin/inflight".to_string(), 200)
+            .await;
+        let recent = reopened.recent(10).await;
+        let actions: Vec<_> = recent.iter().map(|record| record.action.as_str()).collect();
+        assert_eq!(
+            actions,
+            [
+                "GET /admin/inflight",
+                "GET /admin/audit",
+                "GET /admin/failures"
+            ]
+        );
+        assert_eq!(recent[1].status, 401);
+        let mut records: Vec<_> = recent.into_iter().rev().collect();
+        assert_eq!(broken_link(B256::ZERO, &records), None);
+
+        // Editing or removing a record breaks the chain.
+        records[1].status = 200;
+        assert_eq!(broken_link(B256::ZERO, &records), Some(1));
+        records.remove(1);
+        assert_eq!(broken_link(B256::ZERO, &records), Some(1));
+        let _ = std::fs::remove_file(path);
+    }
+}
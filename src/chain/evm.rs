@@ -14,43 +14,72 @@
 //! - Settlement is atomic: deploy (if needed) + transfer happen in a single user flow.
 //! - Verification does not persist state.
 
-use alloy::contract::SolCallBuilder;
+use alloy::consensus::Transaction as _;
 use alloy::dyn_abi::SolType;
+use alloy::eips::eip2718::Encodable2718;
 use alloy::network::{
     Ethereum as AlloyEthereum, EthereumWallet, NetworkWallet, TransactionBuilder,
 };
-use alloy::primitives::{Address, Bytes, FixedBytes, U256, address};
-use alloy::providers::ProviderBuilder;
+use alloy::primitives::{
+    Address, B256, Bytes, FixedBytes, Signature, TxHash, U256, address, b256, keccak256,
+};
 use alloy::providers::bindings::IMulticall3;
 use alloy::providers::fillers::NonceManager;
 use alloy::providers::fillers::{
     BlobGasFiller, ChainIdFiller, FillProvider, GasFiller, JoinFill, NonceFiller, WalletFiller,
 };
 use alloy::providers::{
-    Identity, MULTICALL3_ADDRESS, MulticallItem, Provider, RootProvider, WalletProvider,
+    CallItem, Identity, MULTICALL3_ADDRESS, Provider, RootProvider, WalletProvider,
+};
+use alloy::providers::{
+    PendingTransactionBuilder, PendingTransactionError, ProviderBuilder, WatchTxError,
 };
 use alloy::rpc::client::RpcClient;
-use alloy::rpc::types::{TransactionReceipt, TransactionRequest};
+use alloy::rpc::types::{BlockNumberOrTag, TransactionReceipt, TransactionRequest};
 use alloy::sol_types::{Eip712Domain, SolCall, SolStruct, eip712_domain};
 use alloy::{hex, sol};
 use async_trait::async_trait;
 use dashmap::DashMap;
-use std::sync::Arc;
+use once_cell::sync::Lazy;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use tokio::sync::Mutex;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock, RwLockReadGuard, oneshot};
 use tracing::{Instrument, instrument};
 use tracing_core::Level;
+use url::Url;
 
-use crate::chain::{FacilitatorLocalError, FromEnvByNetworkBuild, NetworkProviderOps};
+use crate::chain::finality::{FinalityStrategy, ReorgMonitor};
+use crate::chain::forwarding::{self, ForwardLeg, PendingForward};
+use crate::chain::gas_strategy::{FeeBump, GasStrategy};
+use crate::chain::http_transport::{DEFAULT_MAX_RESPONSE_BYTES, LimitedHttp, RpcTimeouts};
+use crate::chain::nonce_filter::NonceFilter;
+use crate::chain::nonce_rules::NonceRules;
+use crate::chain::nonce_store::{self, NonceKey, NonceStore};
+use crate::chain::revert;
+use crate::chain::rpc_retry::RetryPolicy;
+use crate::chain::submission;
+use crate::chain::token_list::TokenList;
+use crate::chain::token_registry::TokenRegistry;
+use crate::chain::value_model::ValueModel;
+use crate::chain::verified_authorizations::{self, VerifiedAuthorizations};
+use crate::chain::{
+    FacilitatorLocalError, FromEnvByNetworkBuild, NetworkProviderOps, native_cost_usd,
+    native_usd_price, usd_value,
+};
 use crate::facilitator::Facilitator;
 use crate::from_env;
 use crate::network::{Network, USDCDeployment};
 use crate::timestamp::UnixTimestamp;
+use crate::tokens::{ImplementationCheck, TokenConfigs};
 use crate::types::{
-    EvmAddress, EvmSignature, ExactPaymentPayload, FacilitatorErrorReason, HexEncodedNonce,
-    MixedAddress, PaymentPayload, PaymentRequirements, Scheme, SettleRequest, SettleResponse,
-    SupportedPaymentKind, SupportedPaymentKindsResponse, TokenAmount, TransactionHash,
-    TransferWithAuthorization, VerifyRequest, VerifyResponse, X402Version,
+    AmountScheme, AuthorizationKind, CancelRequest, CancelResponse, EvmAddress, EvmSignature,
+    ExactPaymentPayload, FacilitatorErrorReason, HexEncodedNonce, MixedAddress,
+    OfflineVerifyRequest, PaymentPayload, PaymentRequirements, QuoteRequest, QuoteResponse,
+    ReceiveWithAuthorization, RecoverRequest, RecoverResponse, Scheme, SettleRequest,
+    SettleResponse, SettlementCost, SupportedPaymentKind, SupportedPaymentKindsResponse,
+    TokenAmount, TransactionHash, TransferWithAuthorization, VerifyRequest, VerifyResponse,
+    X402Version,
 };
 
 sol!(
@@ -71,6 +100,65 @@ sol! {
     "abi/Validator6492.json"
 }
 
+sol! {
+    /// ERC-5267 domain retrieval, implemented by tokens that publish their own EIP-712 domain.
+    #[sol(rpc)]
+    interface IERC5267 {
+        function eip712Domain() external view returns (
+            bytes1 fields,
+            string name,
+            string version,
+            uint256 chainId,
+            address verifyingContract,
+            bytes32 salt,
+            uint256[] extensions
+        );
+    }
+}
+
+sol! {
+    /// EIP-1271 signature validation, implemented by contract wallets.
+    #[sol(rpc)]
+    interface IERC1271 {
+        function isValidSignature(bytes32 hash, bytes signature) external view returns (bytes4 magicValue);
+    }
+}
+
+/// Value returned by `isValidSignature` for a valid EIP-1271 signature.
+const EIP1271_MAGIC_VALUE: FixedBytes<4> = FixedBytes([0x16, 0x26, 0xba, 0x7e]);
+
+/// ERC-5267 domains by `(chain_id, token)`; `None` records a token that does not implement ERC-5267.
+static ERC5267_DOMAINS: Lazy<DashMap<(u64, Address), Option<Eip712Domain>>> =
+    Lazy::new(DashMap::new);
+
+/// Token settings of every EVM network served, for [`recover_payer`], which has no provider at hand.
+static NETWORK_TOKENS: Lazy<DashMap<Network, TokenConfigs>> = Lazy::new(DashMap::new);
+
+/// Providers of the EIP-1271 checks of [`recover`], built once per network.
+static RECOVER_PROVIDERS: Lazy<DashMap<Network, RootProvider>> = Lazy::new(DashMap::new);
+
+/// The provider [`recover`] checks contract wallets with on `network`, from its `RPC_URL_*`.
+fn recover_provider(network: Network) -> Option<RootProvider> {
+    if let Some(provider) = RECOVER_PROVIDERS.get(&network) {
+        return Some(provider.clone());
+    }
+    let rpc_url = std::env::var(from_env::rpc_env_name_from_network(network))
+        .ok()?
+        .parse()
+        .ok()?;
+    let provider = RECOVER_PROVIDERS
+        .entry(network)
+        .or_insert_with(|| RootProvider::new_http(rpc_url));
+    Some(provider.clone())
+}
+
+sol! {
+    /// Wrapped native currency (WETH9-style) used by tokens with `unwrapNative` enabled.
+    interface IWETH {
+        function withdraw(uint256 wad) external;
+    }
+}
+
 /// Signature verifier for EIP-6492, EIP-1271, EOA, universally deployed on the supported EVM chains
 /// If absent on a target chain, verification will fail; you should deploy the validator there.
 const VALIDATOR_ADDRESS: alloy::primitives::Address =
@@ -136,6 +224,8 @@ impl TryFrom<Network> for EvmChain {
             Network::Celo => Ok(EvmChain::new(value, 42220)),
             Network::Sei => Ok(EvmChain::new(value, 1329)),
             Network::SeiTestnet => Ok(EvmChain::new(value, 1328)),
+            Network::Arbitrum => Ok(EvmChain::new(value, 42161)),
+            Network::Optimism => Ok(EvmChain::new(value, 10)),
         }
     }
 }
@@ -159,26 +249,60 @@ pub struct ExactEvmPayment {
     pub nonce: HexEncodedNonce,
     /// Raw signature bytes (EIP-1271 or EIP-6492-wrapped).
     pub signature: EvmSignature,
+    /// The ERC-3009 function the authorization is signed for.
+    pub kind: AuthorizationKind,
 }
 
 /// EVM implementation of the x402 facilitator.
 ///
 /// Holds a composed Alloy ethereum provider [`InnerProvider`],
-/// an `eip1559` toggle for gas pricing strategy, and the `EvmChain` context.
+/// the [`GasStrategy`] pricing its transactions, and the `EvmChain` context.
 #[derive(Debug)]
 pub struct EvmProvider {
     /// Composed Alloy provider with all fillers.
     inner: InnerProvider,
-    /// Whether network supports EIP-1559 gas pricing.
-    eip1559: bool,
+    /// How transactions are priced: legacy gas price or EIP-1559 fees.
+    gas_strategy: GasStrategy,
+    /// Replacement of transactions pending too long at higher fees, if enabled.
+    fee_bump: Option<FeeBump>,
     /// Chain descriptor (network + chain ID).
     chain: EvmChain,
-    /// Available signer addresses for round-robin selection.
+    /// Available signer addresses for round-robin selection. Empty in verify-only mode.
     signer_addresses: Arc<Vec<Address>>,
     /// Current position in round-robin signer rotation.
     signer_cursor: Arc<AtomicUsize>,
     /// Nonce manager for resetting nonces on transaction failures.
     nonce_manager: PendingNonceManager,
+    /// Settings for tokens that need special handling on this network.
+    tokens: TokenConfigs,
+    /// Minimum gas price in wei that transactions are clamped up to.
+    min_gas_price: Option<u128>,
+    /// Held shared while signers hold payments in transit, and exclusively while sweeping.
+    intermediary_lock: Arc<RwLock<()>>,
+    /// Authorizations settled by this process, to skip `authorizationState` calls for fresh nonces.
+    nonce_filter: Option<Arc<NonceFilter>>,
+    /// Authorizations claimed by settlements in flight, to refuse replays.
+    nonce_store: Option<Arc<dyn NonceStore>>,
+    /// Heuristics rejecting suspicious authorization nonces, if configured.
+    nonce_rules: Option<Arc<NonceRules>>,
+    /// Registry that tokens must be approved in, if configured.
+    token_registry: Option<Arc<TokenRegistry>>,
+    /// Contract enumerating tokens advertised in `/supported`, if configured.
+    token_list: Option<Arc<TokenList>>,
+    /// Receipt wait timeout in blocks, converted with the measured block time. `None` uses seconds.
+    receipt_timeout_blocks: Option<u64>,
+    /// When a settlement transaction counts as final on this network.
+    finality: FinalityStrategy,
+    /// Reorgs recently observed on this network, deepening `finality` while frequent.
+    reorg_monitor: Arc<ReorgMonitor>,
+    /// Last measured average block time, and when it was measured.
+    block_time: Arc<std::sync::Mutex<Option<(Duration, Instant)>>>,
+    /// Retries of transient RPC failures while submitting transactions.
+    rpc_retry: RetryPolicy,
+    /// Authorizations verified here, which `/cancel` is restricted to.
+    verified_authorizations: Arc<VerifiedAuthorizations>,
+    /// Tag of the memo appended to settlement calldata, if enabled, see [`settlement_memo`].
+    memo_tag: Option<Bytes>,
 }
 
 impl EvmProvider {
@@ -190,17 +314,32 @@ impl EvmProvider {
         network: Network,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let chain = EvmChain::try_from(network)?;
+        // A wallet without signers builds a verify-only provider, which can not send transactions.
         let signer_addresses: Vec<Address> =
             NetworkWallet::<AlloyEthereum>::signer_addresses(&wallet).collect();
-        if signer_addresses.is_empty() {
-            return Err("wallet must contain at least one signer".into());
-        }
         let signer_addresses = Arc::new(signer_addresses);
         let signer_cursor = Arc::new(AtomicUsize::new(0));
-        let client = RpcClient::builder()
-            .connect(rpc_url)
-            .await
-            .map_err(|e| format!("Failed to connect to {network}: {e}"))?;
+        let max_response_bytes = std::env::var(from_env::ENV_RPC_MAX_RESPONSE_BYTES)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_RESPONSE_BYTES);
+        let timeouts = RpcTimeouts::from_env(network)?;
+        let limited = max_response_bytes > 0 || timeouts != RpcTimeouts::default();
+        let client = match Url::parse(rpc_url) {
+            Ok(url) if limited && matches!(url.scheme(), "http" | "https") => {
+                let is_local = alloy::transports::utils::guess_local_url(url.as_str());
+                let max_response_bytes = match max_response_bytes {
+                    0 => usize::MAX,
+                    max_response_bytes => max_response_bytes,
+                };
+                let transport = LimitedHttp::new(url, max_response_bytes, timeouts)?;
+                RpcClient::builder().transport(transport, is_local)
+            }
+            _ => RpcClient::builder()
+                .connect(rpc_url)
+                .await
+                .map_err(|e| format!("Failed to connect to {network}: {e}"))?,
+        };
 
         // Create nonce manager explicitly so we can store a reference for error handling
         let nonce_manager = PendingNonceManager::default();
@@ -220,18 +359,117 @@ impl EvmProvider {
             .wallet(wallet)
             .connect_client(client);
 
-        tracing::info!(network=%network, rpc=rpc_url, signers=?signer_addresses, "Initialized provider");
+        tracing::info!(network=%network, rpc=rpc_url, signer_pool=signer_addresses.len(), signers=?signer_addresses, "Initialized provider");
 
         Ok(Self {
             inner,
-            eip1559,
+            gas_strategy: GasStrategy::for_eip1559(eip1559),
+            fee_bump: None,
             chain,
             signer_addresses,
             signer_cursor,
             nonce_manager,
+            tokens: TokenConfigs::default(),
+            min_gas_price: None,
+            intermediary_lock: Arc::new(RwLock::new(())),
+            nonce_filter: None,
+            nonce_store: None,
+            nonce_rules: None,
+            token_registry: None,
+            token_list: None,
+            receipt_timeout_blocks: None,
+            finality: FinalityStrategy::default(),
+            reorg_monitor: Arc::new(ReorgMonitor::from_env()),
+            block_time: Arc::new(std::sync::Mutex::new(None)),
+            rpc_retry: RetryPolicy::default(),
+            verified_authorizations: Arc::new(VerifiedAuthorizations::default()),
+            memo_tag: None,
         })
     }
 
+    /// Use per-token settings from `tokens` for verification and settlement.
+    pub fn with_tokens(mut self, tokens: TokenConfigs) -> Self {
+        self.tokens = tokens;
+        self
+    }
+
+    /// Never submit transactions priced below `min_gas_price` wei, for networks that enforce a floor.
+    ///
+    /// On EIP-1559 networks both the max fee and the priority fee are clamped up to the floor.
+    pub fn with_min_gas_price(mut self, min_gas_price: Option<u128>) -> Self {
+        self.min_gas_price = min_gas_price;
+        self
+    }
+
+    /// Price transactions by `gas_strategy` instead of the default for the network.
+    pub fn with_gas_strategy(mut self, gas_strategy: GasStrategy) -> Self {
+        self.gas_strategy = gas_strategy;
+        self
+    }
+
+    /// Replace transactions still pending after [`FeeBump::after`] with higher fees, see [`FeeBump`].
+    pub fn with_fee_bump(mut self, fee_bump: Option<FeeBump>) -> Self {
+        self.fee_bump = fee_bump;
+        self
+    }
+
+    /// Reject reused authorization nonces before simulating the transfer, see [`NonceFilter`].
+    pub fn with_nonce_filter(mut self, nonce_filter: Option<NonceFilter>) -> Self {
+        self.nonce_filter = nonce_filter.map(Arc::new);
+        self
+    }
+
+    /// Claim authorizations in `nonce_store` while settling them, refusing replays.
+    pub fn with_nonce_store(mut self, nonce_store: Option<Arc<dyn NonceStore>>) -> Self {
+        self.nonce_store = nonce_store;
+        self
+    }
+
+    /// Reject authorizations whose nonce breaks one of `nonce_rules`.
+    pub fn with_nonce_rules(mut self, nonce_rules: Option<NonceRules>) -> Self {
+        self.nonce_rules = nonce_rules.map(Arc::new);
+        self
+    }
+
+    /// Only accept payments in tokens approved by `token_registry`.
+    pub fn with_token_registry(mut self, token_registry: Option<TokenRegistry>) -> Self {
+        self.token_registry = token_registry.map(Arc::new);
+        self
+    }
+
+    /// Advertise the tokens enumerated by `token_list` in `/supported`.
+    pub fn with_token_list(mut self, token_list: Option<TokenList>) -> Self {
+        self.token_list = token_list.map(Arc::new);
+        self
+    }
+
+    /// Tag settlement calldata with `memo_tag` followed by the payment id, see [`settlement_memo`].
+    pub fn with_memo_tag(mut self, memo_tag: Option<Bytes>) -> Self {
+        self.memo_tag = memo_tag;
+        self
+    }
+
+    /// Express the receipt wait timeout as a number of blocks instead of `TX_RECEIPT_TIMEOUT_SECS`.
+    ///
+    /// Blocks are converted to wall time using the network's average block time, measured over
+    /// recent blocks and refreshed every few minutes.
+    pub fn with_receipt_timeout_blocks(mut self, receipt_timeout_blocks: Option<u64>) -> Self {
+        self.receipt_timeout_blocks = receipt_timeout_blocks;
+        self
+    }
+
+    /// Only report settlements as successful once they are final by `finality`.
+    pub fn with_finality(mut self, finality: FinalityStrategy) -> Self {
+        self.finality = finality;
+        self
+    }
+
+    /// Retry transient RPC failures while submitting transactions by `rpc_retry`.
+    pub fn with_rpc_retry(mut self, rpc_retry: RetryPolicy) -> Self {
+        self.rpc_retry = rpc_retry;
+        self
+    }
+
     /// Round-robin selection of next signer from wallet.
     fn next_signer_address(&self) -> Address {
         debug_assert!(!self.signer_addresses.is_empty());
@@ -256,22 +494,61 @@ pub trait MetaEvmProvider {
     fn inner(&self) -> &Self::Inner;
     /// Returns reference to chain descriptor.
     fn chain(&self) -> &EvmChain;
+    /// Returns per-token settings for this network.
+    fn tokens(&self) -> &TokenConfigs;
+    /// Returns addresses of all signers that can send transactions, none in verify-only mode.
+    fn signer_addresses(&self) -> &[Address];
+    /// Lock guarding signer balances: settlements that route funds through a signer hold it shared,
+    /// so a sweep, which holds it exclusively, never takes funds that are yet to be forwarded.
+    fn intermediary_lock(&self) -> &RwLock<()>;
+    /// Returns the filter of settled authorization nonces, if enabled.
+    fn nonce_filter(&self) -> Option<&NonceFilter>;
+    /// Returns the store of claimed authorizations, if replay protection is enabled.
+    fn nonce_store(&self) -> Option<&dyn NonceStore>;
+    /// Returns the suspicious nonce rules, if configured.
+    fn nonce_rules(&self) -> Option<&NonceRules>;
+    /// Returns the approved-token registry, if configured.
+    fn token_registry(&self) -> Option<&TokenRegistry>;
+    /// Returns the on-chain token list, if configured.
+    fn token_list(&self) -> Option<&TokenList>;
+    /// Returns the tag of settlement memos, if enabled.
+    fn memo_tag(&self) -> Option<&Bytes>;
+    /// Returns the authorizations verified by this provider, the only ones it cancels.
+    fn verified_authorizations(&self) -> &VerifiedAuthorizations;
 
     /// Sends a meta-transaction to the network.
     fn send_transaction(
         &self,
         tx: MetaTransaction,
     ) -> impl Future<Output = Result<TransactionReceipt, Self::Error>> + Send;
+
+    /// Waits until the transaction of `receipt` is final by the network's finality strategy.
+    fn await_finality(
+        &self,
+        receipt: &TransactionReceipt,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
 }
 
-/// Meta-transaction parameters: target address, calldata, and required confirmations.
+/// Meta-transaction parameters: target address, calldata, native value, sender, gas limit floor,
+/// and required confirmations.
 pub struct MetaTransaction {
     /// Target contract address.
     pub to: Address,
     /// Transaction calldata (encoded function call).
     pub calldata: Bytes,
+    /// Native currency to send along with the call.
+    pub value: U256,
+    /// Signer to send from. If `None`, the next signer is picked round-robin.
+    pub from: Option<Address>,
+    /// Minimum gas limit. If set, the larger of this and the node's estimate is used.
+    pub gas_limit: Option<u64>,
     /// Number of block confirmations to wait for.
     pub confirmations: u64,
+    /// Send the transaction again if a reorg orphans it, up to `EVM_REORG_RETRIES` times.
+    ///
+    /// Only safe for calls that can take effect at most once, such as ERC-3009 transfers,
+    /// where the authorization nonce makes any duplicate revert.
+    pub rebroadcast_on_reorg: bool,
 }
 
 impl MetaEvmProvider for EvmProvider {
@@ -286,11 +563,51 @@ impl MetaEvmProvider for EvmProvider {
         &self.chain
     }
 
+    fn tokens(&self) -> &TokenConfigs {
+        &self.tokens
+    }
+
+    fn signer_addresses(&self) -> &[Address] {
+        &self.signer_addresses
+    }
+
+    fn intermediary_lock(&self) -> &RwLock<()> {
+        &self.intermediary_lock
+    }
+
+    fn nonce_filter(&self) -> Option<&NonceFilter> {
+        self.nonce_filter.as_deref()
+    }
+
+    fn nonce_store(&self) -> Option<&dyn NonceStore> {
+        self.nonce_store.as_deref()
+    }
+
+    fn nonce_rules(&self) -> Option<&NonceRules> {
+        self.nonce_rules.as_deref()
+    }
+
+    fn token_registry(&self) -> Option<&TokenRegistry> {
+        self.token_registry.as_deref()
+    }
+
+    fn token_list(&self) -> Option<&TokenList> {
+        self.token_list.as_deref()
+    }
+
+    fn memo_tag(&self) -> Option<&Bytes> {
+        self.memo_tag.as_ref()
+    }
+
+    fn verified_authorizations(&self) -> &VerifiedAuthorizations {
+        &self.verified_authorizations
+    }
+
     /// Send a meta-transaction with provided `to`, `calldata`, and automatically selected signer.
     ///
-    /// This method constructs a transaction from the provided [`MetaTransaction`], automatically
-    /// selects the next available signer using round-robin selection, and handles gas pricing
-    /// based on whether the network supports EIP-1559.
+    /// This method constructs a transaction from the provided [`MetaTransaction`], selects the next
+    /// available signer using round-robin selection unless [`MetaTransaction::from`] pins one,
+    /// and handles gas pricing based on whether the network supports EIP-1559.
     ///
     /// If the transaction fails at any point (during submission or receipt fetching), the nonce
     /// for the sending address is reset to force a fresh query on the next transaction. This
@@ -299,16 +616,37 @@ impl MetaEvmProvider for EvmProvider {
     ///
     /// # Gas Pricing Strategy
     ///
-    /// - **EIP-1559 networks**: Uses automatic gas pricing via the provider's fillers.
-    /// - **Legacy networks**: Fetches the current gas price using `get_gas_price()` and sets it explicitly.
+    /// Fees are set explicitly by the network's [`GasStrategy`]:
+    /// - **EIP-1559 networks**: The node's fee estimate, or the base fee times a multiplier plus a priority fee.
+    /// - **Legacy networks**: The current gas price from `get_gas_price()`, times a multiplier.
+    /// - **Minimum gas price**: If configured (see [`EvmProvider::with_min_gas_price`]), the gas price, or the
+    ///   EIP-1559 max and priority fees, are clamped up to the floor.
+    ///
+    /// With a [`FeeBump`], a transaction pending for too long is replaced by the same one, with the same
+    /// nonce and higher fees.
+    ///
+    /// # Gas Limit
+    ///
+    /// If [`MetaTransaction::gas_limit`] is set, the gas limit is the maximum of that floor and
+    /// `eth_estimateGas`. This keeps hook-heavy tokens from running out of gas, while ordinary
+    /// tokens keep the tight estimate.
     ///
     /// # Timeout Configuration
     ///
     /// Receipt fetching is subject to a configurable timeout:
     /// - Default: 30 seconds
     /// - Override via `TX_RECEIPT_TIMEOUT_SECS` environment variable
+    /// - Or in blocks per network, see [`EvmProvider::with_receipt_timeout_blocks`]
     /// - If the timeout expires, the nonce is reset and an error is returned
     ///
+    /// # Reorgs
+    ///
+    /// If [`MetaTransaction::rebroadcast_on_reorg`] is set and `EVM_REORG_RETRIES` is positive,
+    /// the receipt is re-fetched after the confirmation wait. If its block is no longer canonical,
+    /// the same call is sent again as a new transaction. Whenever an orphaned transaction turns
+    /// out to be re-included, its receipt is returned instead, so a duplicate that reverts on
+    /// an already consumed authorization is not reported as a failure.
+    ///
     /// # Parameters
     ///
     /// - `tx`: A [`MetaTransaction`] containing the target address and calldata.
@@ -320,49 +658,289 @@ impl MetaEvmProvider for EvmProvider {
     /// # Errors
     ///
     /// Returns [`FacilitatorLocalError::ContractCall`] if:
+    /// - The pinned `from` address is not one of this provider's signers
     /// - Gas price fetching fails (on legacy networks)
+    /// - Gas estimation fails (when a gas limit floor is set)
     /// - Transaction sending fails
     /// - Receipt retrieval fails or times out
+    /// - The transaction is still orphaned after `EVM_REORG_RETRIES` rebroadcasts
     async fn send_transaction(
         &self,
         tx: MetaTransaction,
     ) -> Result<TransactionReceipt, Self::Error> {
-        let from_address = self.next_signer_address();
+        if self.signer_addresses.is_empty() {
+            return Err(FacilitatorLocalError::SettlementDisabled);
+        }
+        let from_address = match tx.from {
+            Some(from) if self.signer_addresses.contains(&from) => from,
+            Some(from) => {
+                return Err(FacilitatorLocalError::ContractCall(format!(
+                    "{from} is not a signer of this facilitator"
+                )));
+            }
+            None => self.next_signer_address(),
+        };
+        tracing::debug!(
+            network = %self.chain.network(),
+            signer = %from_address,
+            signer_pool = self.signer_addresses.len(),
+            "Selected signer"
+        );
         let mut txr = TransactionRequest::default()
             .with_to(tx.to)
             .with_from(from_address)
+            .with_value(tx.value)
             .with_input(tx.calldata);
-        if !self.eip1559 {
-            let provider = &self.inner;
-            let gas: u128 = provider
-                .get_gas_price()
-                .instrument(tracing::info_span!("get_gas_price"))
-                .await
-                .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
-            txr.set_gas_price(gas);
+        self.gas_strategy
+            .apply(&self.inner, self.min_gas_price, &mut txr)
+            .await?;
+        apply_gas_floor(&self.inner, &mut txr, tx.gas_limit).await?;
+
+        let reorg_retries = if tx.rebroadcast_on_reorg {
+            settings().reorg_retries
+        } else {
+            0
+        };
+        let mut orphaned: Vec<TxHash> = Vec::new();
+        loop {
+            let receipt = self
+                .send_and_confirm(txr.clone(), from_address, tx.confirmations)
+                .await?;
+            if reorg_retries == 0 {
+                return Ok(receipt);
+            }
+            if !receipt.status() {
+                // The rebroadcast may revert because an earlier, orphaned transaction came back.
+                if let Some(resurfaced) = find_canonical(&self.inner, &orphaned).await? {
+                    return Ok(resurfaced);
+                }
+                return Ok(receipt);
+            }
+            if is_canonical(&self.inner, &receipt).await? {
+                return Ok(receipt);
+            }
+            orphaned.push(receipt.transaction_hash);
+            self.reorg_monitor.record();
+            submission::notify_reorged(TransactionHash::Evm(receipt.transaction_hash.0));
+            if let Some(resurfaced) = find_canonical(&self.inner, &orphaned).await? {
+                return Ok(resurfaced);
+            }
+            if orphaned.len() > reorg_retries {
+                return Err(FacilitatorLocalError::Reorged(format!(
+                    "transaction {} orphaned by reorg, giving up after {reorg_retries} rebroadcasts",
+                    receipt.transaction_hash
+                )));
+            }
+            tracing::warn!(
+                tx = %receipt.transaction_hash,
+                attempt = orphaned.len(),
+                "transaction orphaned by reorg, rebroadcasting"
+            );
+            // The orphaned transaction may or may not be back in the mempool: requery the nonce.
+            self.nonce_manager.reset_nonce(from_address).await;
+        }
+    }
+
+    /// Polls the chain until the block of `receipt` is final, see [`FinalityStrategy`],
+    /// checking on every poll that the transaction is still in the canonical chain. While the
+    /// network reorgs often, more confirmations are required, see [`ReorgMonitor`].
+    ///
+    /// Returns immediately for a single-confirmation strategy, which the receipt wait already satisfies.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::Reorged`] if the transaction was reorged out before reaching
+    /// the required depth, and [`FacilitatorLocalError::ContractCall`] if the block is not final within
+    /// `EVM_FINALITY_TIMEOUT_SECS` or if the chain cannot be read.
+    async fn await_finality(&self, receipt: &TransactionReceipt) -> Result<(), Self::Error> {
+        let finality = self.reorg_monitor.effective(self.finality);
+        if finality.is_immediate() {
+            return Ok(());
+        }
+        let Some(block_number) = receipt.block_number else {
+            return Ok(());
+        };
+        let timeout = FinalityStrategy::timeout();
+        let deadline = Instant::now() + timeout;
+        let poll_interval = self
+            .average_block_time()
+            .await
+            .unwrap_or(Duration::from_secs(1))
+            .clamp(Duration::from_millis(250), Duration::from_secs(12));
+        loop {
+            if !is_canonical(&self.inner, receipt).await? {
+                self.reorg_monitor.record();
+                submission::notify_reorged(TransactionHash::Evm(receipt.transaction_hash.0));
+                return Err(FacilitatorLocalError::Reorged(format!(
+                    "transaction {} orphaned by reorg before finality",
+                    receipt.transaction_hash
+                )));
+            }
+            if self.is_final(finality, block_number).await? {
+                return Ok(());
+            }
+            if submission::is_streaming()
+                && let Ok(latest) = self.latest_block().await
+            {
+                submission::notify_confirmations(
+                    TransactionHash::Evm(receipt.transaction_hash.0),
+                    (latest + 1).saturating_sub(block_number),
+                );
+            }
+            if Instant::now() >= deadline {
+                return Err(FacilitatorLocalError::ContractCall(format!(
+                    "transaction {} not final after {timeout:?}",
+                    receipt.transaction_hash
+                )));
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
+
+impl EvmProvider {
+    /// Latest block number, a cheap check that the RPC endpoint answers.
+    pub async fn latest_block(&self) -> Result<u64, FacilitatorLocalError> {
+        self.inner()
+            .get_block_number()
+            .await
+            .map_err(FacilitatorLocalError::contract_call)
+    }
+
+    /// Whether the transaction `hash` succeeded, or `None` if it is not mined.
+    pub async fn transaction_status(
+        &self,
+        hash: [u8; 32],
+    ) -> Result<Option<bool>, FacilitatorLocalError> {
+        let receipt = self
+            .inner()
+            .get_transaction_receipt(TxHash::from(hash))
+            .await
+            .map_err(FacilitatorLocalError::contract_call)?;
+        Ok(receipt.map(|receipt| receipt.status()))
+    }
+
+    /// Transfers the whole balance of every token with a `sweepThreshold` to `treasury`,
+    /// from each signer holding more than the threshold.
+    ///
+    /// Each balance is read and its sweep broadcast while holding the intermediary lock exclusively, so
+    /// no payment in transit is swept; the lock is released once the sweep is broadcast, as payments
+    /// received after it are not part of it. Signers keep the amounts of their pending forwards, see
+    /// [`forwarding`].
+    /// Nonces are allocated by the same nonce manager as settlements. Returns the number of sweeps sent.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::ContractCall`] on the first balance query or transfer that fails;
+    /// later sweeps are left for the next run.
+    pub async fn sweep(&self, treasury: Address) -> Result<usize, FacilitatorLocalError> {
+        let mut swept = 0;
+        for token in self.tokens.iter() {
+            let Some(threshold) = token.sweep_threshold else {
+                continue;
+            };
+            let Ok(EvmAddress(token_address)) = token.address.clone().try_into() else {
+                continue;
+            };
+            let contract = USDC::new(token_address, self.inner());
+            for signer in self.signer_addresses.iter() {
+                let sweeping = self.intermediary_lock.write().await;
+                let balance = contract
+                    .balanceOf(*signer)
+                    .call()
+                    .await
+                    .map_err(FacilitatorLocalError::contract_call)?;
+                let reserved =
+                    forwarding::global().reserved(self.chain.network, *signer, token_address);
+                let balance = balance.saturating_sub(reserved);
+                if balance <= threshold.0 {
+                    continue;
+                }
+                let transfer_call = USDC::transferCall {
+                    to: treasury,
+                    value: balance,
+                };
+                let sweep = self.send_transaction(MetaTransaction {
+                    to: token_address,
+                    calldata: transfer_call.abi_encode().into(),
+                    value: U256::ZERO,
+                    from: Some(*signer),
+                    gas_limit: token.gas_limit,
+                    confirmations: 1,
+                    rebroadcast_on_reorg: false,
+                });
+                let (submitted, on_submitted) = tokio::sync::oneshot::channel();
+                let release = async move {
+                    // Resolves with an error if the sweep is done without broadcasting.
+                    let _ = on_submitted.await;
+                    drop(sweeping);
+                };
+                let (receipt, ()) = tokio::join!(submission::track(submitted, sweep), release);
+                let receipt = receipt?;
+                if !receipt.status() {
+                    return Err(FacilitatorLocalError::ContractCall(format!(
+                        "sweep of {token_address} from {signer} reverted in {}",
+                        receipt.transaction_hash
+                    )));
+                }
+                tracing::info!(
+                    network = %self.chain.network(),
+                    token_contract = %token_address,
+                    from = %signer,
+                    to = %treasury,
+                    value = %balance,
+                    tx = %receipt.transaction_hash,
+                    "Swept signer balance to treasury"
+                );
+                swept += 1;
+            }
         }
+        Ok(swept)
+    }
+
+    /// Sends the legs of a pending forward that are left, returning what is still pending, see [`forwarding`].
+    pub async fn retry_forward(&self, pending: PendingForward) -> PendingForward {
+        retry_forward(self, pending).await
+    }
 
+    /// Submits `txr` from `from_address` and waits for `confirmations`.
+    ///
+    /// If the transaction fails at any point (submission or receipt fetching), the nonce for
+    /// `from_address` is reset to force a fresh query on the next transaction.
+    async fn send_and_confirm(
+        &self,
+        txr: TransactionRequest,
+        from_address: Address,
+        confirmations: u64,
+    ) -> Result<TransactionReceipt, FacilitatorLocalError> {
         // Send transaction with error handling for nonce reset
-        let pending_tx = match self.inner.send_transaction(txr).await {
-            Ok(pending) => pending,
+        let pending_tx = match self.broadcast(txr.clone(), from_address).await {
+            Ok(pending) => {
+                submission::notify(TransactionHash::Evm(pending.tx_hash().0));
+                pending
+            }
             Err(e) => {
                 // Transaction submission failed - reset nonce to force requery
                 self.nonce_manager.reset_nonce(from_address).await;
-                return Err(FacilitatorLocalError::ContractCall(format!("{e:?}")));
+                return Err(e);
             }
         };
 
         // Get receipt with timeout and error handling for nonce reset
-        // Default timeout of 30 seconds is reasonable for most EVM chains
-        let timeout = std::time::Duration::from_secs(
-            std::env::var("TX_RECEIPT_TIMEOUT_SECS")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(30)
-        );
+        let timeout = self.receipt_timeout().await;
+        if let Some(fee_bump) = self.fee_bump {
+            return self
+                .confirm_with_fee_bumps(
+                    fee_bump,
+                    txr,
+                    pending_tx,
+                    from_address,
+                    confirmations,
+                    timeout,
+                )
+                .await;
+        }
 
         let watcher = pending_tx
-            .with_required_confirmations(tx.confirmations)
+            .with_required_confirmations(confirmations)
             .with_timeout(Some(timeout));
 
         match watcher.get_receipt().await {
@@ -374,12 +952,337 @@ impl MetaEvmProvider for EvmProvider {
             }
         }
     }
+
+    /// Waits up to `timeout` for the receipt of `pending_tx`, replacing it with higher fees whenever it
+    /// is pending for [`FeeBump::after`], up to [`FeeBump::max_bumps`] times.
+    ///
+    /// Replacements reuse the nonce of the first transaction, so only one of them can be mined: whichever
+    /// it is, its receipt is returned.
+    async fn confirm_with_fee_bumps(
+        &self,
+        fee_bump: FeeBump,
+        mut txr: TransactionRequest,
+        pending_tx: PendingTransactionBuilder<AlloyEthereum>,
+        from_address: Address,
+        confirmations: u64,
+        timeout: Duration,
+    ) -> Result<TransactionReceipt, FacilitatorLocalError> {
+        let deadline = Instant::now() + timeout;
+        let mut sent = vec![*pending_tx.tx_hash()];
+        let mut watcher = pending_tx;
+        let mut nonce = None;
+        let mut bumps = 0;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let (wait, can_bump) = fee_bump.next_wait(bumps, remaining);
+            let latest = *watcher.tx_hash();
+            let result = watcher
+                .with_required_confirmations(confirmations)
+                .with_timeout(Some(wait))
+                .get_receipt()
+                .await;
+            match result {
+                Ok(receipt) => return Ok(receipt),
+                Err(PendingTransactionError::TxWatcher(WatchTxError::Timeout)) => {}
+                Err(e) => {
+                    self.nonce_manager.reset_nonce(from_address).await;
+                    return Err(FacilitatorLocalError::ContractCall(format!("{e:?}")));
+                }
+            }
+            // A transaction replaced meanwhile may have been mined instead.
+            if let Some(receipt) = self.first_mined(&sent).await? {
+                return Ok(receipt);
+            }
+            if !can_bump {
+                self.nonce_manager.reset_nonce(from_address).await;
+                return Err(FacilitatorLocalError::ContractCall(format!(
+                    "transaction {latest} not mined within {timeout:?}"
+                )));
+            }
+            let nonce = match nonce {
+                Some(nonce) => nonce,
+                None => {
+                    let first = self
+                        .inner
+                        .get_transaction_by_hash(sent[0])
+                        .await
+                        .map_err(FacilitatorLocalError::contract_call)?
+                        .ok_or_else(|| {
+                            FacilitatorLocalError::ContractCall(format!(
+                                "transaction {} dropped from the mempool",
+                                sent[0]
+                            ))
+                        })?;
+                    *nonce.insert(first.nonce())
+                }
+            };
+            fee_bump.bump(&mut txr);
+            txr.set_nonce(nonce);
+            bumps += 1;
+            watcher = match self.broadcast(txr.clone(), from_address).await {
+                Ok(pending) => pending,
+                Err(error) => {
+                    // The node refuses to replace a transaction that was mined in the meantime.
+                    if let Some(receipt) = self.first_mined(&sent).await? {
+                        return Ok(receipt);
+                    }
+                    self.nonce_manager.reset_nonce(from_address).await;
+                    return Err(error);
+                }
+            };
+            tracing::warn!(
+                tx = %latest,
+                replacement = %watcher.tx_hash(),
+                nonce,
+                bump = bumps,
+                max_fee_per_gas = ?txr.max_fee_per_gas.or(txr.gas_price),
+                "transaction pending too long, replaced with higher fees"
+            );
+            submission::notify_replaced(
+                TransactionHash::Evm(latest.0),
+                TransactionHash::Evm(watcher.tx_hash().0),
+            );
+            sent.push(*watcher.tx_hash());
+        }
+    }
+
+    /// Receipt of the first of `hashes` that is mined, if any.
+    async fn first_mined(
+        &self,
+        hashes: &[TxHash],
+    ) -> Result<Option<TransactionReceipt>, FacilitatorLocalError> {
+        for hash in hashes {
+            let receipt = self
+                .inner
+                .get_transaction_receipt(*hash)
+                .await
+                .map_err(FacilitatorLocalError::contract_call)?;
+            if receipt.is_some() {
+                return Ok(receipt);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Signs `txr` from `from_address` and submits it, retrying transient RPC failures, see [`RetryPolicy`].
+    ///
+    /// The transaction is signed once, so every attempt submits the same transaction. Before a retry,
+    /// the node is asked for its hash: a submission that failed on the way back may have reached the
+    /// mempool anyway, and is then watched instead of being broadcast again.
+    async fn broadcast(
+        &self,
+        txr: TransactionRequest,
+        from_address: Address,
+    ) -> Result<PendingTransactionBuilder<AlloyEthereum>, FacilitatorLocalError> {
+        let mut attempt = 1;
+        let envelope = loop {
+            match self.inner.fill(txr.clone()).await {
+                Ok(filled) => {
+                    break filled.try_into_envelope().map_err(|e| {
+                        FacilitatorLocalError::ContractCall(format!(
+                            "transaction not signed: {e:?}"
+                        ))
+                    })?;
+                }
+                Err(e) if self.rpc_retry.should_retry(attempt, &e) => {
+                    tracing::warn!(%from_address, attempt, error = %e, "transient RPC error preparing transaction, retrying");
+                    // Filling may have allocated a nonce already: requery it.
+                    self.nonce_manager.reset_nonce(from_address).await;
+                    tokio::time::sleep(self.rpc_retry.delay(attempt)).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(FacilitatorLocalError::contract_call(e)),
+            }
+        };
+        let tx_hash = *envelope.tx_hash();
+        let raw = envelope.encoded_2718();
+        let mut attempt = 1;
+        loop {
+            let error = match self.inner.send_raw_transaction(&raw).await {
+                Ok(pending) => return Ok(pending),
+                Err(error) => error,
+            };
+            // A retry may be refused, e.g. as already known, because an earlier attempt got through.
+            if attempt > 1 && self.is_in_mempool(tx_hash).await {
+                return Ok(PendingTransactionBuilder::new(
+                    self.inner.root().clone(),
+                    tx_hash,
+                ));
+            }
+            if !self.rpc_retry.should_retry(attempt, &error) {
+                return Err(FacilitatorLocalError::contract_call(error));
+            }
+            tracing::warn!(tx = %tx_hash, attempt, %error, "transient RPC error submitting transaction, retrying");
+            tokio::time::sleep(self.rpc_retry.delay(attempt)).await;
+            attempt += 1;
+            if self.is_in_mempool(tx_hash).await {
+                tracing::info!(tx = %tx_hash, "transaction reached the node despite the failed submission");
+                return Ok(PendingTransactionBuilder::new(
+                    self.inner.root().clone(),
+                    tx_hash,
+                ));
+            }
+        }
+    }
+
+    /// Whether the node knows transaction `tx_hash`, pending or mined.
+    async fn is_in_mempool(&self, tx_hash: TxHash) -> bool {
+        matches!(
+            self.inner.get_transaction_by_hash(tx_hash).await,
+            Ok(Some(_))
+        )
+    }
+
+    /// How long to wait for a receipt: `receipt_timeout_blocks` times the average block time if set
+    /// and measurable, otherwise `TX_RECEIPT_TIMEOUT_SECS` (default: 30 seconds).
+    async fn receipt_timeout(&self) -> Duration {
+        let fixed = Duration::from_secs(settings().receipt_timeout_secs);
+        let Some(blocks) = self.receipt_timeout_blocks else {
+            return fixed;
+        };
+        match self.average_block_time().await {
+            Some(block_time) => block_time.saturating_mul(blocks.try_into().unwrap_or(u32::MAX)),
+            None => fixed,
+        }
+    }
+
+    /// Average block time over the last [`BLOCK_TIME_SAMPLE`] blocks, cached for [`BLOCK_TIME_TTL`].
+    async fn average_block_time(&self) -> Option<Duration> {
+        if let Ok(cached) = self.block_time.lock()
+            && let Some((block_time, measured_at)) = *cached
+            && measured_at.elapsed() < BLOCK_TIME_TTL
+        {
+            return Some(block_time);
+        }
+        let block_time = measure_block_time(&self.inner).await?;
+        if let Ok(mut cached) = self.block_time.lock() {
+            *cached = Some((block_time, Instant::now()));
+        }
+        tracing::debug!(network = %self.chain.network(), ?block_time, "Measured average block time");
+        Some(block_time)
+    }
+
+    /// Whether block `block_number` is final by `finality`.
+    async fn is_final(
+        &self,
+        finality: FinalityStrategy,
+        block_number: u64,
+    ) -> Result<bool, FacilitatorLocalError> {
+        let block = |tag| async move {
+            self.inner
+                .get_block_by_number(tag)
+                .await
+                .map_err(FacilitatorLocalError::contract_call)
+        };
+        match finality {
+            FinalityStrategy::Confirmations(confirmations) => {
+                let latest = self
+                    .inner
+                    .get_block_number()
+                    .await
+                    .map_err(FacilitatorLocalError::contract_call)?;
+                Ok(latest + 1 >= block_number.saturating_add(confirmations))
+            }
+            FinalityStrategy::Finalized => Ok(block(BlockNumberOrTag::Finalized)
+                .await?
+                .is_some_and(|finalized| finalized.header.number >= block_number)),
+            FinalityStrategy::BlockTime(age) => {
+                let (Some(included), Some(latest)) = (
+                    block(BlockNumberOrTag::Number(block_number)).await?,
+                    block(BlockNumberOrTag::Latest).await?,
+                ) else {
+                    return Ok(false);
+                };
+                Ok(latest.header.timestamp
+                    >= included.header.timestamp.saturating_add(age.as_secs()))
+            }
+        }
+    }
+}
+
+/// Whether the block that included `receipt` is still part of `provider`'s canonical chain.
+async fn is_canonical<P: Provider>(
+    provider: &P,
+    receipt: &TransactionReceipt,
+) -> Result<bool, FacilitatorLocalError> {
+    let current = provider
+        .get_transaction_receipt(receipt.transaction_hash)
+        .await
+        .map_err(FacilitatorLocalError::contract_call)?;
+    Ok(current.is_some_and(|current| current.block_hash == receipt.block_hash))
+}
+
+/// Returns the first of `hashes` that is now included in `provider`'s canonical chain, if any.
+async fn find_canonical<P: Provider>(
+    provider: &P,
+    hashes: &[TxHash],
+) -> Result<Option<TransactionReceipt>, FacilitatorLocalError> {
+    for hash in hashes {
+        let receipt = provider
+            .get_transaction_receipt(*hash)
+            .await
+            .map_err(FacilitatorLocalError::contract_call)?;
+        if let Some(receipt) = receipt {
+            tracing::info!(tx = %hash, "orphaned transaction was re-included");
+            return Ok(Some(receipt));
+        }
+    }
+    Ok(None)
+}
+
+/// Number of recent blocks the average block time is measured over.
+const BLOCK_TIME_SAMPLE: u64 = 100;
+
+/// How long a measured average block time is reused before measuring again.
+const BLOCK_TIME_TTL: Duration = Duration::from_secs(600);
+
+/// Sets the gas limit of `txr` to the larger of `floor` and `provider`'s estimate; without a floor,
+/// the gas filler's estimate is left to apply.
+async fn apply_gas_floor<P: Provider>(
+    provider: &P,
+    txr: &mut TransactionRequest,
+    floor: Option<u64>,
+) -> Result<(), FacilitatorLocalError> {
+    let Some(floor) = floor else {
+        return Ok(());
+    };
+    let estimate = provider
+        .estimate_gas(txr.clone())
+        .into_future()
+        .instrument(tracing::info_span!("estimate_gas"))
+        .await
+        .map_err(FacilitatorLocalError::contract_call)?;
+    txr.set_gas_limit(estimate.max(floor));
+    Ok(())
+}
+
+/// Average block time over the last [`BLOCK_TIME_SAMPLE`] blocks of `provider`'s chain, if it has that many.
+async fn measure_block_time<P: Provider>(provider: &P) -> Option<Duration> {
+    let latest = provider
+        .get_block_by_number(BlockNumberOrTag::Latest)
+        .await
+        .ok()??;
+    let earlier_number = latest.header.number.checked_sub(BLOCK_TIME_SAMPLE)?;
+    let earlier = provider
+        .get_block_by_number(BlockNumberOrTag::Number(earlier_number))
+        .await
+        .ok()??;
+    let elapsed_ms = latest
+        .header
+        .timestamp
+        .checked_sub(earlier.header.timestamp)?
+        .saturating_mul(1000);
+    Some(Duration::from_millis(
+        (elapsed_ms / BLOCK_TIME_SAMPLE).max(1),
+    ))
 }
 
 impl NetworkProviderOps for EvmProvider {
     /// Address of the default signer used by this provider (for tx sending).
-    fn signer_address(&self) -> MixedAddress {
-        self.inner.default_signer_address().into()
+    fn signer_address(&self) -> Option<MixedAddress> {
+        self.signer_addresses
+            .first()
+            .map(|_| self.inner.default_signer_address().into())
     }
 
     /// x402 network handled by this provider.
@@ -398,7 +1301,11 @@ impl FromEnvByNetworkBuild for EvmProvider {
                 return Ok(None);
             }
         };
-        let wallet = from_env::SignerType::from_env()?.make_evm_wallet()?;
+        let wallet = match from_env::SignerType::from_env_optional()? {
+            Some(signer_type) => signer_type.make_evm_wallet_for(network)?,
+            // Verify-only mode: a wallet without signers, see `EvmProvider::try_new`.
+            None => EthereumWallet::default(),
+        };
         let is_eip1559 = match network {
             Network::BaseSepolia => true,
             Network::Base => true,
@@ -413,8 +1320,57 @@ impl FromEnvByNetworkBuild for EvmProvider {
             Network::Celo => true,
             Network::Sei => true,
             Network::SeiTestnet => true,
+            Network::Arbitrum => true,
+            Network::Optimism => true,
+        };
+        let tokens = TokenConfigs::from_env(network)?;
+        NETWORK_TOKENS.insert(network, tokens.clone());
+        let min_gas_price_env =
+            from_env::env_name_for_network(from_env::ENV_EVM_MIN_GAS_PRICE_PREFIX, network);
+        let min_gas_price = match std::env::var(&min_gas_price_env) {
+            Ok(raw) => Some(
+                raw.trim()
+                    .parse::<u128>()
+                    .map_err(|e| format!("env {min_gas_price_env} is invalid: {e}"))?,
+            ),
+            Err(_) => None,
         };
-        let provider = EvmProvider::try_new(wallet, &rpc_url, is_eip1559, network).await?;
+        let nonce_filter = std::env::var(from_env::ENV_EVM_NONCE_FILTER_BITS)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|bits| *bits > 0)
+            .map(|bits| {
+                let hashes = std::env::var(from_env::ENV_EVM_NONCE_FILTER_HASHES)
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(4);
+                NonceFilter::new(bits, hashes)
+            });
+        let receipt_timeout_blocks_env =
+            from_env::env_name_for_network(from_env::ENV_TX_RECEIPT_TIMEOUT_BLOCKS_PREFIX, network);
+        let receipt_timeout_blocks = match std::env::var(&receipt_timeout_blocks_env) {
+            Ok(raw) => Some(
+                raw.trim()
+                    .parse::<u64>()
+                    .map_err(|e| format!("env {receipt_timeout_blocks_env} is invalid: {e}"))?,
+            ),
+            Err(_) => None,
+        };
+        let provider = EvmProvider::try_new(wallet, &rpc_url, is_eip1559, network)
+            .await?
+            .with_tokens(tokens)
+            .with_gas_strategy(GasStrategy::from_env(network, is_eip1559)?)
+            .with_fee_bump(FeeBump::from_env(network)?)
+            .with_min_gas_price(min_gas_price)
+            .with_nonce_filter(nonce_filter)
+            .with_nonce_store(nonce_store::store_from_env())
+            .with_nonce_rules(NonceRules::from_env()?)
+            .with_token_registry(TokenRegistry::from_env(network)?)
+            .with_token_list(TokenList::from_env(network)?)
+            .with_receipt_timeout_blocks(receipt_timeout_blocks)
+            .with_finality(FinalityStrategy::from_env(network)?)
+            .with_rpc_retry(RetryPolicy::from_env())
+            .with_memo_tag(memo_tag_from_env()?);
         Ok(Some(provider))
     }
 }
@@ -435,19 +1391,66 @@ where
     ///
     /// # Errors
     /// - [`FacilitatorLocalError::NetworkMismatch`], [`FacilitatorLocalError::SchemeMismatch`], [`FacilitatorLocalError::ReceiverMismatch`] if inputs are inconsistent.
+    /// - [`FacilitatorLocalError::UnsupportedScheme`] if the scheme is not offered for the token.
+    /// - [`FacilitatorLocalError::UnsupportedAuthorizationKind`] if the token does not implement the requested ERC-3009 function.
+    /// - [`FacilitatorLocalError::UnsupportedAsset`] if the token is not on the network's allowlist, or a token registry
+    ///   is configured and does not approve it.
+    /// - [`FacilitatorLocalError::UnexpectedNonce`] if the nonce is not the one expected for the order.
+    /// - [`FacilitatorLocalError::SuspiciousNonce`] if the nonce breaks a configured nonce rule.
+    /// - [`FacilitatorLocalError::ImplementationChanged`] if the token was upgraded since the requirements were issued.
     /// - [`FacilitatorLocalError::InvalidTiming`] if outside `validAfter/validBefore`.
     /// - [`FacilitatorLocalError::InsufficientFunds`] / `FacilitatorLocalError::InsufficientValue` on balance/value checks.
-    /// - [`FacilitatorLocalError::ContractCall`] if on-chain calls revert.
+    /// - [`FacilitatorLocalError::InvalidSignature`] if the signature is not valid for the payer's account kind.
+    /// - [`FacilitatorLocalError::Reverted`] with the decoded revert reason if the transfer simulation reverts.
+    /// - [`FacilitatorLocalError::ContractCall`] if the chain cannot be read.
     async fn verify(&self, request: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
         let payload = &request.payment_payload;
         let requirements = &request.payment_requirements;
-        let (contract, payment, eip712_domain) =
-            assert_valid_payment(self.inner(), self.chain(), payload, requirements).await?;
+        self.tokens().assert_asset_allowed(requirements)?;
+        self.tokens().assert_scheme_offered(requirements)?;
+        self.tokens()
+            .assert_authorization_kind_offered(requirements)?;
+        let receiver = authorized_receiver(self, requirements);
+        let (contract, payment, eip712_domain) = assert_valid_payment(
+            self.inner(),
+            self.chain(),
+            receiver,
+            payload,
+            requirements,
+            self.tokens(),
+        )
+        .await?;
+        assert_valid_splits(self, &payment, requirements)?;
+        assert_expected_nonce(receiver, &payment, requirements)?;
+        assert_plausible_nonce(self, &payment)?;
+        if let Some(token_registry) = self.token_registry() {
+            token_registry
+                .assert_approved(self.inner(), payment.from, *contract.address())
+                .await?;
+        }
+        assert_same_implementation(self, &contract, &payment, requirements).await?;
+        assert_fresh_nonce(self, &contract, &payment).await?;
+        assert_unclaimed_nonce(self, &contract, &payment).await?;
 
         let signed_message = SignedMessage::extract(&payment, &eip712_domain)?;
         let payer = signed_message.address;
         let hash = signed_message.hash;
         match signed_message.signature {
+            StructuredSignature::EIP6492 { inner, .. }
+                if payment.kind == AuthorizationKind::Receive =>
+            {
+                if !is_contract_deployed(self.inner(), &payer).await? {
+                    return Err(counterfactual_receive(payer));
+                }
+                let transfer_call = authorization_call(*contract.address(), &payment, inner);
+                simulate_authorization_call(
+                    self.inner(),
+                    &transfer_call,
+                    self.signer_addresses().first().copied(),
+                    "EIP6492.deployed",
+                )
+                .await?;
+            }
             StructuredSignature::EIP6492 {
                 factory: _,
                 factory_calldata: _,
@@ -459,61 +1462,61 @@ where
                 let is_valid_signature_call =
                     validator6492.isValidSigWithSideEffects(payer, hash, original);
                 // Prepare the call to simulate transfer the funds
-                let transfer_call = transferWithAuthorization_0(&contract, &payment, inner).await?;
+                let transfer_call = authorization_call(*contract.address(), &payment, inner);
                 // Execute both calls in a single transaction simulation to accommodate for possible smart wallet creation
                 let (is_valid_signature_result, transfer_result) = self
                     .inner()
                     .multicall()
                     .add(is_valid_signature_call)
-                    .add(transfer_call.tx)
-                    .aggregate3()
-                    .instrument(tracing::info_span!("call_transferWithAuthorization_0",
-                            from = %transfer_call.from,
-                            to = %transfer_call.to,
-                            value = %transfer_call.value,
-                            valid_after = %transfer_call.valid_after,
-                            valid_before = %transfer_call.valid_before,
-                            nonce = %transfer_call.nonce,
-                            signature = %transfer_call.signature,
-                            token_contract = %transfer_call.contract_address,
-                            otel.kind = "client",
+                    .add_call(CallItem::<USDC::transferWithAuthorization_0Call>::new(
+                        transfer_call.contract_address,
+                        transfer_call.calldata.clone(),
                     ))
+                    .aggregate3()
+                    .instrument(transfer_call.span("EIP6492"))
                     .await
-                    .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
-                let is_valid_signature_result = is_valid_signature_result
-                    .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+                    .map_err(FacilitatorLocalError::contract_call)?;
+                let is_valid_signature_result =
+                    is_valid_signature_result.map_err(FacilitatorLocalError::contract_call)?;
                 if !is_valid_signature_result {
                     return Err(FacilitatorLocalError::InvalidSignature(
                         payer.into(),
                         "Incorrect signature".to_string(),
                     ));
                 }
-                transfer_result.map_err(|e| FacilitatorLocalError::ContractCall(format!("{e}")))?;
+                transfer_result.map_err(|failure| {
+                    FacilitatorLocalError::Reverted(
+                        Some(payer.into()),
+                        revert::decode(&failure.return_data),
+                    )
+                })?;
             }
             StructuredSignature::EIP1271(signature) => {
                 // It is EOA or EIP-1271 signature, which we can pass to the transfer simulation
-                let transfer_call =
-                    transferWithAuthorization_0(&contract, &payment, signature).await?;
-                transfer_call
-                    .tx
-                    .call()
-                    .into_future()
-                    .instrument(tracing::info_span!("call_transferWithAuthorization_0",
-                            from = %transfer_call.from,
-                            to = %transfer_call.to,
-                            value = %transfer_call.value,
-                            valid_after = %transfer_call.valid_after,
-                            valid_before = %transfer_call.valid_before,
-                            nonce = %transfer_call.nonce,
-                            signature = %transfer_call.signature,
-                            token_contract = %transfer_call.contract_address,
-                            otel.kind = "client",
-                    ))
-                    .await
-                    .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+                if settings().check_signer_kind {
+                    assert_signature_by_signer_kind(self.inner(), payer, hash, &signature).await?;
+                }
+                let transfer_call = authorization_call(*contract.address(), &payment, signature);
+                simulate_authorization_call(
+                    self.inner(),
+                    &transfer_call,
+                    self.signer_addresses().first().copied(),
+                    "EIP1271",
+                )
+                .await?;
             }
         }
 
+        self.verified_authorizations().record(
+            NonceKey {
+                chain_id: self.chain().chain_id,
+                token: *contract.address(),
+                authorizer: payment.from.0,
+                nonce: FixedBytes(payment.nonce.0),
+            },
+            eip712_domain,
+            payment.valid_before,
+        );
         Ok(VerifyResponse::valid(payer.into()))
     }
 
@@ -529,6 +1532,9 @@ where
     /// If the wallet is already deployed (or the signature is plain EIP-1271/EOA),
     /// we submit a single `transferWithAuthorization` transaction.
     ///
+    /// If `SETTLEMENT_MEMO_TAG` is set, the transaction calldata carries a memo suffix
+    /// identifying the payment (see [`settlement_memo`]).
+    ///
     /// # Returns
     /// A [`SettleResponse`] containing success flag and transaction hash.
     ///
@@ -538,11 +1544,60 @@ where
     async fn settle(&self, request: &SettleRequest) -> Result<SettleResponse, Self::Error> {
         let payload = &request.payment_payload;
         let requirements = &request.payment_requirements;
-        let (contract, payment, eip712_domain) =
-            assert_valid_payment(self.inner(), self.chain(), payload, requirements).await?;
+        self.tokens().assert_asset_allowed(requirements)?;
+        self.tokens().assert_scheme_offered(requirements)?;
+        self.tokens()
+            .assert_authorization_kind_offered(requirements)?;
+        let receiver = authorized_receiver(self, requirements);
+        let (contract, payment, eip712_domain) = assert_valid_payment(
+            self.inner(),
+            self.chain(),
+            receiver,
+            payload,
+            requirements,
+            self.tokens(),
+        )
+        .await?;
+        let splits = assert_valid_splits(self, &payment, requirements)?;
+        assert_expected_nonce(receiver, &payment, requirements)?;
+        assert_plausible_nonce(self, &payment)?;
+        if let Some(token_registry) = self.token_registry() {
+            token_registry
+                .assert_approved(self.inner(), payment.from, *contract.address())
+                .await?;
+        }
+        assert_same_implementation(self, &contract, &payment, requirements).await?;
+        assert_fresh_nonce(self, &contract, &payment).await?;
+        // Keep sweeps away from the payment until it is forwarded.
+        let in_transit = match receiver {
+            AuthorizedReceiver::Facilitator(_) => Some(self.intermediary_lock().read().await),
+            AuthorizedReceiver::PayTo => None,
+        };
 
         let signed_message = SignedMessage::extract(&payment, &eip712_domain)?;
         let payer = signed_message.address;
+        let token = self.tokens().get(&requirements.asset);
+        let gas_limit = token.and_then(|token| token.gas_limit);
+        let unwrap_native = token.is_some_and(|token| token.unwrap_native);
+        // A fee-on-transfer token leaves the intermediary with less than the authorized value.
+        let received = self
+            .tokens()
+            .net_amount(&requirements.asset, payment.value.into());
+        let legs = forward_legs_of(
+            &payment,
+            received,
+            *contract.address(),
+            requirements,
+            &splits,
+            unwrap_native,
+        )?;
+        let memo = self.memo_tag().map(|tag| settlement_memo(tag, request));
+        await_valid_after(payment.valid_after).await?;
+        assert_settle_deadline(
+            payment.from.into(),
+            payment.valid_before,
+            settings().settle_expiry_buffer,
+        )?;
         let transaction_receipt_fut = match signed_message.signature {
             StructuredSignature::EIP6492 {
                 factory,
@@ -551,28 +1606,21 @@ where
                 original: _,
             } => {
                 let is_contract_deployed = is_contract_deployed(self.inner(), &payer).await?;
-                let transfer_call = transferWithAuthorization_0(&contract, &payment, inner).await?;
+                let transfer_call = authorization_call(*contract.address(), &payment, inner);
                 if is_contract_deployed {
                     // transferWithAuthorization with inner signature
                     self.send_transaction(MetaTransaction {
-                        to: transfer_call.tx.target(),
-                        calldata: transfer_call.tx.calldata().clone(),
+                        to: transfer_call.contract_address,
+                        calldata: append_memo(&transfer_call.calldata, memo.as_ref()),
+                        value: U256::ZERO,
+                        from: transfer_call.sender,
+                        gas_limit,
                         confirmations: 1,
+                        rebroadcast_on_reorg: true,
                     })
-                    .instrument(
-                        tracing::info_span!("call_transferWithAuthorization_0",
-                            from = %transfer_call.from,
-                            to = %transfer_call.to,
-                            value = %transfer_call.value,
-                            valid_after = %transfer_call.valid_after,
-                            valid_before = %transfer_call.valid_before,
-                            nonce = %transfer_call.nonce,
-                            signature = %transfer_call.signature,
-                            token_contract = %transfer_call.contract_address,
-                            sig_kind="EIP6492.deployed",
-                            otel.kind = "client",
-                        ),
-                    )
+                    .instrument(transfer_call.span("EIP6492.deployed"))
+                } else if payment.kind == AuthorizationKind::Receive {
+                    return Err(counterfactual_receive(payer));
                 } else {
                     // deploy the smart wallet, and transferWithAuthorization with inner signature
                     let deployment_call = IMulticall3::Call3 {
@@ -582,78 +1630,176 @@ where
                     };
                     let transfer_with_authorization_call = IMulticall3::Call3 {
                         allowFailure: false,
-                        target: transfer_call.tx.target(),
-                        callData: transfer_call.tx.calldata().clone(),
+                        target: transfer_call.contract_address,
+                        callData: transfer_call.calldata.clone(),
                     };
                     let aggregate_call = IMulticall3::aggregate3Call {
                         calls: vec![deployment_call, transfer_with_authorization_call],
                     };
                     self.send_transaction(MetaTransaction {
                         to: MULTICALL3_ADDRESS,
-                        calldata: aggregate_call.abi_encode().into(),
+                        calldata: append_memo(&aggregate_call.abi_encode().into(), memo.as_ref()),
+                        value: U256::ZERO,
+                        from: None,
+                        gas_limit,
                         confirmations: 1,
+                        rebroadcast_on_reorg: true,
                     })
-                    .instrument(
-                        tracing::info_span!("call_transferWithAuthorization_0",
-                            from = %transfer_call.from,
-                            to = %transfer_call.to,
-                            value = %transfer_call.value,
-                            valid_after = %transfer_call.valid_after,
-                            valid_before = %transfer_call.valid_before,
-                            nonce = %transfer_call.nonce,
-                            signature = %transfer_call.signature,
-                            token_contract = %transfer_call.contract_address,
-                            sig_kind="EIP6492.counterfactual",
-                            otel.kind = "client",
-                        ),
-                    )
+                    .instrument(transfer_call.span("EIP6492.counterfactual"))
                 }
             }
             StructuredSignature::EIP1271(eip1271_signature) => {
                 let transfer_call =
-                    transferWithAuthorization_0(&contract, &payment, eip1271_signature).await?;
-                // transferWithAuthorization with eip1271 signature
+                    authorization_call(*contract.address(), &payment, eip1271_signature);
+                // transferWithAuthorization (or receiveWithAuthorization) with eip1271 signature
                 self.send_transaction(MetaTransaction {
-                    to: transfer_call.tx.target(),
-                    calldata: transfer_call.tx.calldata().clone(),
+                    to: transfer_call.contract_address,
+                    calldata: append_memo(&transfer_call.calldata, memo.as_ref()),
+                    value: U256::ZERO,
+                    from: transfer_call.sender,
+                    gas_limit,
                     confirmations: 1,
+                    rebroadcast_on_reorg: true,
                 })
-                .instrument(
-                    tracing::info_span!("call_transferWithAuthorization_0",
-                        from = %transfer_call.from,
-                        to = %transfer_call.to,
-                        value = %transfer_call.value,
-                        valid_after = %transfer_call.valid_after,
-                        valid_before = %transfer_call.valid_before,
-                        nonce = %transfer_call.nonce,
-                        signature = %transfer_call.signature,
-                        token_contract = %transfer_call.contract_address,
-                        sig_kind="EIP1271",
-                        otel.kind = "client",
-                    ),
-                )
+                .instrument(transfer_call.span("EIP1271"))
+            }
+        };
+        let claim = claim_nonce(self, &contract, &payment).await?;
+        let receipt = transaction_receipt_fut
+            .await
+            .map_err(FacilitatorLocalError::from);
+        let receipt = match receipt {
+            Ok(receipt) => receipt,
+            Err(error) => {
+                release_nonce(self, claim).await;
+                return Err(error);
             }
         };
-        let receipt = transaction_receipt_fut.await?;
         let success = receipt.status();
-        if success {
-            tracing::event!(Level::INFO,
-                status = "ok",
-                tx = %receipt.transaction_hash,
-                "transferWithAuthorization_0 succeeded"
+        if !success {
+            release_nonce(self, claim).await;
+        }
+        if success && let Some(nonce_filter) = self.nonce_filter() {
+            nonce_filter.insert(
+                self.chain().chain_id,
+                *contract.address(),
+                payment.from.0,
+                FixedBytes(payment.nonce.0),
+            );
+        }
+        let forwarded = if success && !legs.is_empty() {
+            let intermediary: Address = payment.to.into();
+            let forward = async {
+                let forwarded = forward_legs(self, intermediary, &legs).await;
+                if let Err(failure) = &forwarded {
+                    tracing::event!(Level::ERROR,
+                        status = "failed",
+                        transfer_tx = %receipt.transaction_hash,
+                        intermediary = %intermediary,
+                        value = %payment.value,
+                        remaining = failure.remaining.len(),
+                        error = %failure.error,
+                        "payment received but not fully forwarded; retrying in the background"
+                    );
+                    forwarding::global()
+                        .record(PendingForward {
+                            network: payload.network,
+                            settlement: receipt.transaction_hash,
+                            intermediary,
+                            legs: failure.remaining.clone(),
+                            in_flight: failure.in_flight,
+                            updated_at: UnixTimestamp::try_now().unwrap_or(UnixTimestamp(0)),
+                        })
+                        .await;
+                }
+                forwarded
+            };
+            Some(forward_then_finalize(self, &receipt, in_transit, forward).await?)
+        } else {
+            drop(in_transit);
+            if success {
+                self.await_finality(&receipt).await?;
+            }
+            None
+        };
+        match forwarded {
+            Some(Ok(forward_receipts)) => {
+                // An unwrapped payment reaches `payTo` in the native transfer, the last transaction.
+                let delivery = if unwrap_native {
+                    forward_receipts.last().unwrap_or(&receipt)
+                } else {
+                    &receipt
+                };
+                tracing::event!(Level::INFO,
+                    status = "ok",
+                    tx = %delivery.transaction_hash,
+                    transfer_tx = %receipt.transaction_hash,
+                    signer = %receipt.from,
+                    legs = legs.len(),
+                    "forwarded payment succeeded"
+                );
+                return Ok(SettleResponse {
+                    success: true,
+                    error_reason: None,
+                    payer: payment.from.into(),
+                    transaction: Some(TransactionHash::Evm(delivery.transaction_hash.0)),
+                    block_number: delivery.block_number,
+                    status: None,
+                    network: payload.network,
+                    payment_id: None,
+                    cost: settlement_cost(
+                        payload.network,
+                        std::iter::once(&receipt).chain(&forward_receipts),
+                        Some(facilitator_fee(*contract.address(), received, &legs)),
+                    ),
+                    extensions: None,
+                });
+            }
+            Some(Err(failure)) => {
+                return Ok(SettleResponse {
+                    success: false,
+                    error_reason: Some(FacilitatorErrorReason::UnexpectedSettleError),
+                    payer: payment.from.into(),
+                    transaction: Some(TransactionHash::Evm(receipt.transaction_hash.0)),
+                    block_number: receipt.block_number,
+                    status: None,
+                    network: payload.network,
+                    payment_id: None,
+                    cost: settlement_cost(
+                        payload.network,
+                        std::iter::once(&receipt).chain(&failure.receipts),
+                        None,
+                    ),
+                    extensions: None,
+                });
+            }
+            None => {}
+        }
+        if success {
+            tracing::event!(Level::INFO,
+                status = "ok",
+                tx = %receipt.transaction_hash,
+                signer = %receipt.from,
+                "transferWithAuthorization_0 succeeded"
             );
             Ok(SettleResponse {
                 success: true,
                 error_reason: None,
                 payer: payment.from.into(),
                 transaction: Some(TransactionHash::Evm(receipt.transaction_hash.0)),
+                block_number: receipt.block_number,
+                status: None,
                 network: payload.network,
+                payment_id: None,
+                cost: settlement_cost(payload.network, [&receipt], None),
+                extensions: None,
             })
         } else {
             tracing::event!(
                 Level::WARN,
                 status = "failed",
                 tx = %receipt.transaction_hash,
+                signer = %receipt.from,
                 "transferWithAuthorization_0 failed"
             );
             Ok(SettleResponse {
@@ -661,32 +1807,364 @@ where
                 error_reason: Some(FacilitatorErrorReason::InvalidScheme),
                 payer: payment.from.into(),
                 transaction: Some(TransactionHash::Evm(receipt.transaction_hash.0)),
+                block_number: receipt.block_number,
+                status: None,
                 network: payload.network,
+                payment_id: None,
+                cost: settlement_cost(payload.network, [&receipt], None),
+                extensions: None,
             })
         }
     }
 
-    /// Report payment kinds supported by this provider on its current network.
+    /// Report payment kinds supported by this provider on its current network,
+    /// leaving out schemes that no known token offers (see [`TokenConfigs::network_offers_scheme`]).
+    ///
+    /// With a [`TokenList`], each kind lists the enumerated tokens it accepts as `assets`: those offering
+    /// its scheme, on the allowlist and, with a [`TokenRegistry`], approved by it.
     async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
-        let kinds = vec![SupportedPaymentKind {
-            network: self.chain().network().to_string(),
-            x402_version: X402Version::V1,
-            scheme: Scheme::Exact,
-            extra: None,
-        }];
+        let network = self.chain().network();
+        let mut listed = match self.token_list() {
+            Some(token_list) => Some(token_list.tokens(self.inner()).await),
+            None => None,
+        };
+        if let Some(listed) = listed.as_mut()
+            && let Some(token_registry) = self.token_registry()
+        {
+            let mut approved = Vec::with_capacity(listed.len());
+            for asset in listed.drain(..) {
+                let Ok(address) = Address::try_from(asset.address.clone()) else {
+                    continue;
+                };
+                // Payments are rejected while the registry cannot be read, so the token is left out too.
+                if let Ok(true) = token_registry.is_approved(self.inner(), address).await {
+                    approved.push(asset);
+                }
+            }
+            *listed = approved;
+        }
+        let kinds = [Scheme::Exact, Scheme::Upto]
+            .into_iter()
+            .filter(|scheme| self.tokens().network_offers_scheme(network, *scheme))
+            .map(|scheme| SupportedPaymentKind {
+                network: network.to_string(),
+                x402_version: X402Version::V1,
+                scheme,
+                extra: None,
+                assets: listed.as_ref().map(|listed| {
+                    listed
+                        .iter()
+                        .filter(|asset| {
+                            self.tokens().allows_asset(&asset.address)
+                                && self.tokens().offers_scheme(&asset.address, scheme)
+                        })
+                        .cloned()
+                        .collect()
+                }),
+                allowed_assets: self.tokens().allowed_assets(),
+                authorization_kinds: Some(self.tokens().authorization_kinds(network, scheme)),
+                amount_bounds: self.tokens().amount_bounds(scheme),
+            })
+            .collect();
         Ok(SupportedPaymentKindsResponse { kinds })
     }
+
+    /// Quotes the settlement, see [`quote_settlement`].
+    async fn quote(&self, request: &QuoteRequest) -> Option<Result<QuoteResponse, Self::Error>> {
+        Some(quote_settlement(self, request).await)
+    }
+
+    /// Submits `cancelAuthorization`, see [`cancel_authorization`].
+    async fn cancel(&self, request: &CancelRequest) -> Option<Result<CancelResponse, Self::Error>> {
+        Some(cancel_authorization(self, request).await)
+    }
+}
+
+/// Estimates the gas of settling the payment, at the current gas price.
+///
+/// With a payload, `eth_estimateGas` is run against the `transferWithAuthorization` or `receiveWithAuthorization`
+/// settlement would send, from the signer settlement would send it from. Without one, or if the estimate fails,
+/// e.g. for a counterfactual wallet's signature, [`DEFAULT_TRANSFER_GAS`] is quoted. Either way, the token's
+/// `gasLimit` floor applies. The transactions forwarding the payment from a facilitator signer are added
+/// at a default each, see [`forwarding_gas`].
+///
+/// # Errors
+/// - [`FacilitatorLocalError::NetworkMismatch`] if the requirements are for another network.
+/// - [`FacilitatorLocalError::UnsupportedAsset`] if the token is not on the network's allowlist.
+/// - [`FacilitatorLocalError::ContractCall`] if the gas price cannot be read.
+async fn quote_settlement<P>(
+    provider: &P,
+    request: &QuoteRequest,
+) -> Result<QuoteResponse, FacilitatorLocalError>
+where
+    P: MetaEvmProvider + Sync,
+    FacilitatorLocalError: From<P::Error>,
+{
+    let requirements = &request.payment_requirements;
+    let network = provider.chain().network;
+    if requirements.network != network {
+        return Err(FacilitatorLocalError::NetworkMismatch(
+            None,
+            network,
+            requirements.network,
+        ));
+    }
+    provider.tokens().assert_asset_allowed(requirements)?;
+    let asset: EvmAddress = requirements
+        .asset
+        .clone()
+        .try_into()
+        .map_err(|e| FacilitatorLocalError::InvalidAddress(format!("{e:?}")))?;
+    let estimate = match &request.payment_payload {
+        Some(PaymentPayload {
+            payload: ExactPaymentPayload::Evm(payload),
+            ..
+        }) => {
+            let authorization = &payload.authorization;
+            let payment = ExactEvmPayment {
+                chain: *provider.chain(),
+                from: authorization.from,
+                to: authorization.to,
+                value: authorization.value,
+                valid_after: authorization.valid_after,
+                valid_before: authorization.valid_before,
+                nonce: authorization.nonce,
+                signature: payload.signature.clone(),
+                kind: requirements.authorization_kind.unwrap_or_default(),
+            };
+            let call = authorization_call(asset.0, &payment, payload.signature.0.clone().into());
+            let tx = simulation_request(&call, provider.signer_addresses().first().copied());
+            let estimate = provider
+                .inner()
+                .estimate_gas(tx)
+                .into_future()
+                .instrument(tracing::info_span!(
+                    "estimate_settlement_gas",
+                    otel.kind = "client"
+                ))
+                .await;
+            match estimate {
+                Ok(gas) => Some(gas),
+                Err(error) => {
+                    tracing::debug!(%error, "Settlement gas estimate failed, quoting the default");
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    let token = provider.tokens().get(&requirements.asset);
+    let gas_floor = token.and_then(|token| token.gas_limit).unwrap_or_default();
+    let unwrap_native = token.is_some_and(|token| token.unwrap_native);
+    let forwarding_gas = forwarding_gas(requirements, unwrap_native);
+    let gas = estimate.unwrap_or(DEFAULT_TRANSFER_GAS).max(gas_floor) + forwarding_gas;
+    let gas_price = provider
+        .inner()
+        .get_gas_price()
+        .instrument(tracing::info_span!("get_gas_price"))
+        .await
+        .map_err(FacilitatorLocalError::contract_call)?;
+    let total_cost = U256::from(gas) * U256::from(gas_price);
+    Ok(QuoteResponse {
+        network,
+        gas,
+        gas_estimated: estimate.is_some(),
+        forwarding_gas,
+        gas_price: TokenAmount(U256::from(gas_price)),
+        total_cost: TokenAmount(total_cost),
+        native_decimals: 18,
+        total_cost_usd: native_cost_usd(network, total_cost, 18),
+    })
+}
+
+/// Gas of the transactions forwarding a payment from a facilitator signer, see [`forward_legs_of`]:
+/// one transfer per split share, an unwrap and a native transfer for `unwrapNative` tokens, and a
+/// transfer for other payments the facilitator receives.
+fn forwarding_gas(requirements: &PaymentRequirements, unwrap_native: bool) -> u64 {
+    match &requirements.splits {
+        Some(splits) => splits.len() as u64 * DEFAULT_FORWARD_TRANSFER_GAS,
+        None if unwrap_native => DEFAULT_UNWRAP_GAS + NATIVE_TRANSFER_GAS,
+        None if requirements.authorization_kind == Some(AuthorizationKind::Receive) => {
+            DEFAULT_FORWARD_TRANSFER_GAS
+        }
+        None => 0,
+    }
+}
+
+/// Submits `cancelAuthorization` for an authorization that is not used yet.
+///
+/// Only authorizations this provider has verified are canceled, and only if the cancellation is signed
+/// by the authorizer against the domain they were verified with, see [`verified_authorizations`]: the
+/// facilitator pays the gas, so it does not submit cancellations of payments it has never seen.
+///
+/// With replay protection, the authorization is claimed while the cancellation is in flight, so it
+/// can not be settled meanwhile, and stays claimed for [`CANCELED_CLAIM_SECS`] once canceled: `/verify`
+/// rejects it as `nonce_reused`. A canceled nonce is also added to the [`NonceFilter`], so it is
+/// checked against `authorizationState` from then on.
+///
+/// # Errors
+/// - [`FacilitatorLocalError::NetworkMismatch`] if the request is for another network.
+/// - [`FacilitatorLocalError::UnsupportedAsset`] if the token is not on the network's allowlist.
+/// - [`FacilitatorLocalError::UnknownAuthorization`] if the authorization was not verified here, or expired.
+/// - [`FacilitatorLocalError::InvalidSignature`] if the cancellation is not signed by the authorizer.
+/// - [`FacilitatorLocalError::AuthorizationUsed`] if the authorization is already used or canceled.
+/// - [`FacilitatorLocalError::NonceReused`] if the authorization is being settled.
+/// - [`FacilitatorLocalError::Reverted`] if the token rejects the cancellation, e.g. for an invalid signature.
+async fn cancel_authorization<P>(
+    provider: &P,
+    request: &CancelRequest,
+) -> Result<CancelResponse, FacilitatorLocalError>
+where
+    P: MetaEvmProvider + Sync,
+    FacilitatorLocalError: From<P::Error>,
+{
+    let network = provider.chain().network;
+    let authorizer = request.authorizer;
+    if request.network != network {
+        return Err(FacilitatorLocalError::NetworkMismatch(
+            Some(authorizer.into()),
+            network,
+            request.network,
+        ));
+    }
+    if !provider.tokens().allows_asset(&request.asset) {
+        return Err(FacilitatorLocalError::UnsupportedAsset(
+            Some(authorizer.into()),
+            request.asset.clone(),
+        ));
+    }
+    let asset: EvmAddress = request
+        .asset
+        .clone()
+        .try_into()
+        .map_err(|e| FacilitatorLocalError::InvalidAddress(format!("{e:?}")))?;
+    let nonce = FixedBytes(request.nonce.0);
+    let key = NonceKey {
+        chain_id: provider.chain().chain_id,
+        token: asset.0,
+        authorizer: authorizer.0,
+        nonce,
+    };
+    let domain = provider
+        .verified_authorizations()
+        .domain(&key)
+        .ok_or_else(|| FacilitatorLocalError::UnknownAuthorization(authorizer.into()))?;
+    verified_authorizations::assert_cancel_signed(&key, &domain, &request.signature.0)?;
+    let contract = USDC::new(asset.0, provider.inner());
+    let used = contract
+        .authorizationState(authorizer.0, nonce)
+        .call()
+        .into_future()
+        .instrument(tracing::info_span!(
+            "fetch_authorization_state",
+            token_contract = %asset,
+            authorizer = %authorizer,
+            otel.kind = "client"
+        ))
+        .await
+        .map_err(FacilitatorLocalError::contract_call)?;
+    if used {
+        return Err(FacilitatorLocalError::AuthorizationUsed(authorizer.into()));
+    }
+    let claim = match provider.nonce_store() {
+        Some(nonce_store) => {
+            let now = UnixTimestamp::try_now().map_err(FacilitatorLocalError::ClockError)?;
+            let claimed = nonce_store
+                .claim(&key, UnixTimestamp(now.0 + CANCELED_CLAIM_SECS))
+                .await;
+            if !claimed {
+                return Err(FacilitatorLocalError::NonceReused(authorizer.into()));
+            }
+            Some(key)
+        }
+        None => None,
+    };
+    let cancel_call = USDC::cancelAuthorization_1Call {
+        authorizer: authorizer.0,
+        nonce,
+        signature: Bytes::from(request.signature.0.clone()),
+    };
+    let receipt = provider
+        .send_transaction(MetaTransaction {
+            to: asset.0,
+            calldata: cancel_call.abi_encode().into(),
+            value: U256::ZERO,
+            from: None,
+            gas_limit: None,
+            confirmations: 1,
+            rebroadcast_on_reorg: true,
+        })
+        .instrument(tracing::info_span!("call_cancelAuthorization",
+            authorizer = %authorizer,
+            nonce = %nonce,
+            token_contract = %asset,
+            otel.kind = "client",
+        ))
+        .await
+        .map_err(FacilitatorLocalError::from);
+    let receipt = match receipt {
+        Ok(receipt) => receipt,
+        Err(error) => {
+            release_nonce(provider, claim).await;
+            return Err(error);
+        }
+    };
+    let success = receipt.status();
+    if success {
+        // The cancellation is mined: a finality error must not report it as failed.
+        if let Err(error) = provider.await_finality(&receipt).await {
+            let error = FacilitatorLocalError::from(error);
+            tracing::warn!(%error, tx = %receipt.transaction_hash, "cancelAuthorization not final");
+        }
+        if let Some(nonce_filter) = provider.nonce_filter() {
+            nonce_filter.insert(provider.chain().chain_id, asset.0, authorizer.0, nonce);
+        }
+    } else {
+        release_nonce(provider, claim).await;
+    }
+    tracing::info!(
+        success,
+        tx = %receipt.transaction_hash,
+        signer = %receipt.from,
+        authorizer = %authorizer,
+        "cancelAuthorization mined"
+    );
+    Ok(CancelResponse {
+        success,
+        authorizer,
+        transaction: Some(TransactionHash::Evm(receipt.transaction_hash.0)),
+        network,
+    })
 }
 
-/// A prepared call to `transferWithAuthorization` (ERC-3009) including all derived fields.
+/// How long a canceled authorization stays claimed in the nonce store, see [`Facilitator::cancel`].
+const CANCELED_CLAIM_SECS: u64 = 24 * 60 * 60;
+
+/// Gas quoted for a `transferWithAuthorization` that cannot be estimated, a little above what USDC uses.
+const DEFAULT_TRANSFER_GAS: u64 = 100_000;
+
+/// Gas quoted for forwarding a share of a payment with an ERC-20 `transfer`.
+const DEFAULT_FORWARD_TRANSFER_GAS: u64 = 65_000;
+
+/// Gas quoted for unwrapping a wrapped native token with `withdraw`.
+const DEFAULT_UNWRAP_GAS: u64 = 45_000;
+
+/// Gas of a plain native currency transfer.
+const NATIVE_TRANSFER_GAS: u64 = 21_000;
+
+/// A prepared call to `transferWithAuthorization` or `receiveWithAuthorization` (ERC-3009) including all derived fields.
 ///
-/// This struct wraps the assembled call builder, making it reusable across verification
-/// (`.call()`) and settlement (`.send()`) flows, along with context useful for tracing/logging.
+/// This struct wraps the encoded call, making it reusable across verification (`eth_call`) and
+/// settlement (sent as a transaction) flows, along with context useful for tracing/logging.
 ///
-/// This is created by [`EvmProvider::transferWithAuthorization_0`].
-pub struct TransferWithAuthorization0Call<P> {
-    /// The prepared call builder that can be `.call()`ed or `.send()`ed.
-    pub tx: SolCallBuilder<P, USDC::transferWithAuthorization_0Call>,
+/// This is created by [`authorization_call`].
+pub struct AuthorizationCall {
+    /// The encoded call to the token contract.
+    pub calldata: Bytes,
+    /// The signer the call must be sent from: the receiver for `receiveWithAuthorization`, any otherwise.
+    pub sender: Option<Address>,
+    /// The ERC-3009 function called.
+    pub kind: AuthorizationKind,
     /// The sender (`from`) address for the authorization.
     pub from: alloy::primitives::Address,
     /// The recipient (`to`) address for the authorization.
@@ -705,49 +2183,814 @@ pub struct TransferWithAuthorization0Call<P> {
     pub contract_address: alloy::primitives::Address,
 }
 
-/// Validates that the current time is within the `validAfter` and `validBefore` bounds.
+impl AuthorizationCall {
+    /// A client span for sending or simulating the call, named after the ERC-3009 function called.
+    ///
+    /// `sig_kind` tells how the payer signed: `EIP1271` for EOAs and deployed contract wallets, and
+    /// `EIP6492`, `EIP6492.deployed` or `EIP6492.counterfactual` for EIP-6492 wrapped signatures.
+    pub fn span(&self, sig_kind: &'static str) -> tracing::Span {
+        macro_rules! span {
+            ($name:literal) => {
+                tracing::info_span!($name,
+                    method = %self.kind,
+                    from = %self.from,
+                    to = %self.to,
+                    value = %self.value,
+                    valid_after = %self.valid_after,
+                    valid_before = %self.valid_before,
+                    nonce = %self.nonce,
+                    signature = %self.signature,
+                    token_contract = %self.contract_address,
+                    sig_kind,
+                    otel.kind = "client",
+                )
+            };
+        }
+        match self.kind {
+            AuthorizationKind::Transfer => span!("call_transferWithAuthorization_0"),
+            AuthorizationKind::Receive => span!("call_receiveWithAuthorization_0"),
+        }
+    }
+}
+
+/// Calldata suffix that tags a settlement transaction with its payment, for on-chain reconciliation.
+///
+/// Enabled by setting `SETTLEMENT_MEMO_TAG` to a hex string, see [`memo_tag_from_env`]: the suffix is
+/// that tag followed by the 32-byte [`crate::types::PaymentId`]. Solidity ignores trailing calldata, so
+/// the call itself is unaffected.
+fn settlement_memo(tag: &Bytes, request: &SettleRequest) -> Bytes {
+    [tag.as_ref(), &request.payment_id().0].concat().into()
+}
+
+/// The memo tag from `SETTLEMENT_MEMO_TAG`, `None` if it is unset or empty.
+///
+/// # Errors
+/// Returns an error if it is not valid hex.
+fn memo_tag_from_env() -> Result<Option<Bytes>, String> {
+    match std::env::var(from_env::ENV_SETTLEMENT_MEMO_TAG) {
+        Ok(tag) if !tag.trim().is_empty() => hex::decode(tag.trim())
+            .map(|tag| Some(tag.into()))
+            .map_err(|e| {
+                format!(
+                    "env {} is not valid hex: {e}",
+                    from_env::ENV_SETTLEMENT_MEMO_TAG
+                )
+            }),
+        _ => Ok(None),
+    }
+}
+
+/// Returns `calldata` with `memo` appended, if any.
+fn append_memo(calldata: &Bytes, memo: Option<&Bytes>) -> Bytes {
+    match memo {
+        Some(memo) => [calldata.as_ref(), memo.as_ref()].concat().into(),
+        None => calldata.clone(),
+    }
+}
+
+/// Who an authorization must name as its `to` address.
+#[derive(Debug, Clone, Copy)]
+enum AuthorizedReceiver<'a> {
+    /// The merchant's `payTo` address, paid directly by `transferWithAuthorization`.
+    PayTo,
+    /// One of the facilitator's signers, which forwards the funds to `payTo` after the transfer.
+    Facilitator(&'a [Address]),
+}
+
+/// Picks the [`AuthorizedReceiver`] for the token in `requirements`.
+///
+/// Tokens with `unwrapNative` enabled are paid to the facilitator, which unwraps them and
+/// forwards native currency to `payTo`. Split payments are paid to the facilitator too,
+/// which forwards each share, and so are `receiveWithAuthorization` payments, which only
+/// their receiver may submit.
+fn authorized_receiver<'a, P: MetaEvmProvider>(
+    provider: &'a P,
+    requirements: &PaymentRequirements,
+) -> AuthorizedReceiver<'a> {
+    let unwrap_native = provider
+        .tokens()
+        .get(&requirements.asset)
+        .is_some_and(|token| token.unwrap_native);
+    let receive = requirements.authorization_kind == Some(AuthorizationKind::Receive);
+    if unwrap_native || receive || requirements.splits.is_some() {
+        AuthorizedReceiver::Facilitator(provider.signer_addresses())
+    } else {
+        AuthorizedReceiver::PayTo
+    }
+}
+
+/// Validates `requirements.splits` against the authorized `payment`, returning the parsed shares.
+///
+/// Returns an empty list for payments without splits.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidSplits`] if the list is empty, a receiver is not an EVM address,
+/// the amounts do not sum to the authorized value, or the token also has `unwrapNative` enabled.
+fn assert_valid_splits<P: MetaEvmProvider>(
+    provider: &P,
+    payment: &ExactEvmPayment,
+    requirements: &PaymentRequirements,
+) -> Result<Vec<(Address, U256)>, FacilitatorLocalError> {
+    let Some(splits) = &requirements.splits else {
+        return Ok(Vec::new());
+    };
+    let payer: MixedAddress = payment.from.into();
+    let invalid = |reason: String| FacilitatorLocalError::InvalidSplits(payer.clone(), reason);
+    if splits.is_empty() {
+        return Err(invalid("no receivers".to_string()));
+    }
+    let unwrap_native = provider
+        .tokens()
+        .get(&requirements.asset)
+        .is_some_and(|token| token.unwrap_native);
+    if unwrap_native {
+        return Err(invalid("not available for unwrapNative tokens".to_string()));
+    }
+    let mut total = U256::ZERO;
+    let mut shares = Vec::with_capacity(splits.len());
+    for split in splits {
+        let receiver: EvmAddress = split
+            .pay_to
+            .clone()
+            .try_into()
+            .map_err(|_| invalid(format!("{} is not an EVM address", split.pay_to)))?;
+        total = total
+            .checked_add(split.amount.0)
+            .ok_or_else(|| invalid("amounts overflow".to_string()))?;
+        shares.push((receiver.0, split.amount.0));
+    }
+    let value: U256 = payment.value.into();
+    if total != value {
+        return Err(invalid(format!(
+            "amounts sum to {total}, authorized value is {value}"
+        )));
+    }
+    Ok(shares)
+}
+
+/// Forwards a payment mined into an intermediary signer with `forward`, releases `in_transit`,
+/// then waits for the payment to be final.
+///
+/// Forwarding comes first, so a finality error or timeout never leaves the payment with the signer,
+/// and sweeps are not held off for the whole wait.
+///
+/// # Errors
+/// Returns the error of [`MetaEvmProvider::await_finality`]; the payment is forwarded regardless.
+async fn forward_then_finalize<P, T>(
+    provider: &P,
+    receipt: &TransactionReceipt,
+    in_transit: Option<RwLockReadGuard<'_, ()>>,
+    forward: impl Future<Output = T>,
+) -> Result<T, P::Error>
+where
+    P: MetaEvmProvider,
+{
+    let forwarded = forward.await;
+    drop(in_transit);
+    provider.await_finality(receipt).await?;
+    Ok(forwarded)
+}
+
+/// The transactions forwarding `payment` from the intermediary signer to its receivers, in order.
+///
+/// Each share of a split payment is transferred; an `unwrapNative` payment is unwrapped, then sent
+/// to `payTo` as native currency; any other payment the facilitator receives is transferred to `payTo`.
+/// Returns no legs for a payment made out to `payTo` directly.
+///
+/// `received` is what the intermediary got from the payment, less than its value for fee-on-transfer
+/// tokens: that is forwarded, with split shares cut in proportion, see [`net_shares`].
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidAddress`] if `payTo` is not an EVM address.
+fn forward_legs_of(
+    payment: &ExactEvmPayment,
+    received: U256,
+    token: Address,
+    requirements: &PaymentRequirements,
+    splits: &[(Address, U256)],
+    unwrap_native: bool,
+) -> Result<Vec<ForwardLeg>, FacilitatorLocalError> {
+    let mut legs: Vec<ForwardLeg> = net_shares(splits, payment.value.into(), received)
+        .into_iter()
+        .map(|(to, amount)| ForwardLeg::Transfer { token, to, amount })
+        .collect();
+    if legs.is_empty() && (unwrap_native || payment.kind == AuthorizationKind::Receive) {
+        let pay_to: EvmAddress = requirements
+            .pay_to
+            .clone()
+            .try_into()
+            .map_err(|e| FacilitatorLocalError::InvalidAddress(format!("{e:?}")))?;
+        let amount = received;
+        if unwrap_native {
+            // Unwrap before the native transfer, so no external call happens while holding wrapped tokens.
+            legs.push(ForwardLeg::Unwrap { token, amount });
+            legs.push(ForwardLeg::Native {
+                to: pay_to.0,
+                amount,
+            });
+        } else {
+            // The facilitator receives the whole payment: forward it to `payTo` as a single share.
+            legs.push(ForwardLeg::Transfer {
+                token,
+                to: pay_to.0,
+                amount,
+            });
+        }
+    }
+    Ok(legs)
+}
+
+/// The `splits` of a payment of `value` cut in proportion to the `received` amount, rounded down,
+/// with the remainder added to the first share so they still sum to `received`.
+fn net_shares(splits: &[(Address, U256)], value: U256, received: U256) -> Vec<(Address, U256)> {
+    if received >= value || value.is_zero() {
+        return splits.to_vec();
+    }
+    let mut shares: Vec<(Address, U256)> = splits
+        .iter()
+        .map(|(to, amount)| (*to, amount.saturating_mul(received) / value))
+        .collect();
+    let total = shares
+        .iter()
+        .fold(U256::ZERO, |total, (_, amount)| total + amount);
+    if let Some((_, first)) = shares.first_mut() {
+        *first += received - total;
+    }
+    shares
+}
+
+/// Where forwarding stopped, see [`forward_legs`].
+struct ForwardFailure {
+    /// Receipts of the legs that were sent.
+    receipts: Vec<TransactionReceipt>,
+    /// The failed leg and those after it.
+    remaining: Vec<ForwardLeg>,
+    /// The transaction broadcast for the failed leg, if it may still be mined.
+    in_flight: Option<TxHash>,
+    error: FacilitatorLocalError,
+}
+
+/// Sends `legs` from the intermediary signer in order, stopping at the first that fails or reverts.
+///
+/// Returns the receipt of each leg.
+///
+/// # Errors
+/// Returns a [`ForwardFailure`] with the legs not sent, to be retried, see [`forwarding`].
+async fn forward_legs<P>(
+    provider: &P,
+    intermediary: Address,
+    legs: &[ForwardLeg],
+) -> Result<Vec<TransactionReceipt>, ForwardFailure>
+where
+    P: MetaEvmProvider,
+    FacilitatorLocalError: From<P::Error>,
+{
+    let mut receipts = Vec::with_capacity(legs.len());
+    for (index, leg) in legs.iter().enumerate() {
+        let (submitted, mut on_submitted) = oneshot::channel();
+        let sent = submission::track(submitted, send_leg(provider, intermediary, leg)).await;
+        let (error, in_flight) = match sent {
+            Ok(receipt) if receipt.status() => {
+                receipts.push(receipt);
+                continue;
+            }
+            Ok(receipt) => {
+                let error = FacilitatorLocalError::ContractCall(format!(
+                    "forwarding from {intermediary} reverted in {}",
+                    receipt.transaction_hash
+                ));
+                (error, None)
+            }
+            Err(error) => {
+                let in_flight = match on_submitted.try_recv() {
+                    Ok(TransactionHash::Evm(hash)) => Some(TxHash::from(hash)),
+                    _ => None,
+                };
+                (FacilitatorLocalError::from(error), in_flight)
+            }
+        };
+        return Err(ForwardFailure {
+            receipts,
+            remaining: legs[index..].to_vec(),
+            in_flight,
+            error,
+        });
+    }
+    Ok(receipts)
+}
+
+/// Sends the transaction of `leg` from `intermediary`.
+async fn send_leg<P: MetaEvmProvider>(
+    provider: &P,
+    intermediary: Address,
+    leg: &ForwardLeg,
+) -> Result<TransactionReceipt, P::Error> {
+    let (to, calldata, value, span) = match leg {
+        ForwardLeg::Transfer { token, to, amount } => (
+            *token,
+            USDC::transferCall {
+                to: *to,
+                value: *amount,
+            }
+            .abi_encode()
+            .into(),
+            U256::ZERO,
+            tracing::info_span!("call_transfer",
+                token_contract = %token,
+                from = %intermediary,
+                to = %to,
+                value = %amount,
+                otel.kind = "client",
+            ),
+        ),
+        ForwardLeg::Unwrap { token, amount } => (
+            *token,
+            IWETH::withdrawCall { wad: *amount }.abi_encode().into(),
+            U256::ZERO,
+            tracing::info_span!("call_withdraw",
+                token_contract = %token,
+                from = %intermediary,
+                value = %amount,
+                otel.kind = "client",
+            ),
+        ),
+        ForwardLeg::Native { to, amount } => (
+            *to,
+            Bytes::new(),
+            *amount,
+            tracing::info_span!("send_native",
+                from = %intermediary,
+                to = %to,
+                value = %amount,
+                otel.kind = "client",
+            ),
+        ),
+    };
+    provider
+        .send_transaction(MetaTransaction {
+            to,
+            calldata,
+            value,
+            from: Some(intermediary),
+            gas_limit: None,
+            confirmations: 1,
+            rebroadcast_on_reorg: false,
+        })
+        .instrument(span)
+        .await
+}
+
+/// Sends the legs of `pending` that are left, returning what is still pending afterwards.
+///
+/// A leg still in flight is only sent again once its transaction reverted or is no longer known
+/// to the node; if it was mined, the leg is done.
+async fn retry_forward<P>(provider: &P, mut pending: PendingForward) -> PendingForward
+where
+    P: MetaEvmProvider,
+    FacilitatorLocalError: From<P::Error>,
+{
+    let _in_transit = provider.intermediary_lock().read().await;
+    if let Some(hash) = pending.in_flight {
+        match provider.inner().get_transaction_receipt(hash).await {
+            Ok(Some(receipt)) if receipt.status() => {
+                pending.legs.remove(0);
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => match provider.inner().get_transaction_by_hash(hash).await {
+                Ok(None) => {}
+                _ => return pending,
+            },
+            Err(_) => return pending,
+        }
+        pending.in_flight = None;
+    }
+    match forward_legs(provider, pending.intermediary, &pending.legs).await {
+        Ok(_) => {
+            tracing::info!(
+                settlement = %pending.settlement,
+                intermediary = %pending.intermediary,
+                "Pending forward completed"
+            );
+            pending.legs.clear();
+        }
+        Err(failure) => {
+            tracing::warn!(
+                settlement = %pending.settlement,
+                intermediary = %pending.intermediary,
+                remaining = failure.remaining.len(),
+                error = %failure.error,
+                "Pending forward failed again"
+            );
+            pending.legs = failure.remaining;
+            pending.in_flight = failure.in_flight;
+        }
+    }
+    pending.updated_at = UnixTimestamp::try_now().unwrap_or(pending.updated_at);
+    pending
+}
+
+/// The [`cost_breakdown`] of `receipts` on `network`, if `SETTLE_COST_BREAKDOWN` is enabled.
+///
+/// The USD cost uses the price in `NATIVE_USD_PRICE_<NETWORK>`, see [`native_usd_price`].
+fn settlement_cost<'a>(
+    network: Network,
+    receipts: impl IntoIterator<Item = &'a TransactionReceipt>,
+    facilitator_fee: Option<U256>,
+) -> Option<SettlementCost> {
+    settings()
+        .settle_cost_breakdown
+        .then(|| cost_breakdown(receipts, facilitator_fee, native_usd_price(network)))
+}
+
+/// Sums gas and native cost over `receipts`, in USD too if the `native_usd_price` is known.
+///
+/// `facilitator_fee` is what the facilitator kept of a payment it forwarded, see [`facilitator_fee`].
+fn cost_breakdown<'a>(
+    receipts: impl IntoIterator<Item = &'a TransactionReceipt>,
+    facilitator_fee: Option<U256>,
+    native_usd_price: Option<f64>,
+) -> SettlementCost {
+    let (gas_used, total_cost) =
+        receipts
+            .into_iter()
+            .fold((0u64, U256::ZERO), |(gas_used, total_cost), receipt| {
+                let cost = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price);
+                (gas_used + receipt.gas_used, total_cost + cost)
+            });
+    let effective_gas_price = total_cost
+        .checked_div(U256::from(gas_used))
+        .unwrap_or_default();
+    SettlementCost {
+        gas_used,
+        effective_gas_price: TokenAmount(effective_gas_price),
+        total_cost: TokenAmount(total_cost),
+        total_cost_usd: native_usd_price.and_then(|price| usd_value(total_cost, 18, price)),
+        facilitator_fee: facilitator_fee.map(TokenAmount),
+    }
+}
+
+/// The amount of `token` the intermediary kept of the `received` payment once `legs` forwarded it.
+fn facilitator_fee(token: Address, received: U256, legs: &[ForwardLeg]) -> U256 {
+    let forwarded = legs.iter().fold(U256::ZERO, |total, leg| {
+        total.saturating_add(leg.reserves(token))
+    });
+    received.saturating_sub(forwarded)
+}
+
+/// Timing limits for the authorizations of one scheme.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimingRules {
+    /// Longest remaining validity, `validBefore - now`, in seconds. `None` accepts any.
+    pub max_validity: Option<u64>,
+    /// How far in the future `validAfter` may be, in seconds, to tolerate clock skew with the payer.
+    pub clock_skew: u64,
+}
+
+impl TimingRules {
+    /// Reads the rules for `scheme` from `EVM_MAX_VALIDITY_SECS_<SCHEME>` and `EVM_CLOCK_SKEW_SECS_<SCHEME>`,
+    /// e.g. `EVM_MAX_VALIDITY_SECS_EXACT`, with `var` looking the variables up by name. The clock skew
+    /// defaults to `EVM_CLOCK_SKEW_SECS` for every scheme.
+    ///
+    /// # Errors
+    /// Returns an error naming the first variable that is not a number of seconds.
+    fn from_vars(scheme: Scheme, var: &dyn Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let scheme_var = |prefix: &str| {
+            let name = format!(
+                "{prefix}_{}",
+                scheme.to_string().to_uppercase().replace('-', "_")
+            );
+            parse_var::<u64>(var, &name)
+        };
+        let clock_skew = match scheme_var(from_env::ENV_EVM_CLOCK_SKEW_SECS_PREFIX)? {
+            Some(clock_skew) => clock_skew,
+            None => parse_var(var, from_env::ENV_EVM_CLOCK_SKEW_SECS)?.unwrap_or(0),
+        };
+        Ok(Self {
+            max_validity: scheme_var(from_env::ENV_EVM_MAX_VALIDITY_SECS_PREFIX)?,
+            clock_skew,
+        })
+    }
+}
+
+/// Validates that the current time is within the `validAfter` and `validBefore` bounds,
+/// by the [`TimingRules`] of `scheme`.
 ///
-/// Adds a 6-second grace buffer when checking expiration to account for latency.
+/// Adds a 6-second grace buffer when checking expiration to account for latency. The clock skew
+/// tolerance only applies to `validAfter`: an expired `validBefore` is never accepted, as the
+/// transfer would revert on-chain.
 ///
 /// # Errors
-/// Returns [`FacilitatorLocalError::InvalidTiming`] if the authorization is not yet active or already expired.
+/// Returns [`FacilitatorLocalError::InvalidTiming`] if the authorization is not yet active, already expired,
+/// or valid for longer than the scheme allows.
 /// Returns [`FacilitatorLocalError::ClockError`] if the system clock cannot be read.
 #[instrument(skip_all, err)]
 fn assert_time(
     payer: MixedAddress,
+    scheme: Scheme,
     valid_after: UnixTimestamp,
     valid_before: UnixTimestamp,
 ) -> Result<(), FacilitatorLocalError> {
     let now = UnixTimestamp::try_now().map_err(FacilitatorLocalError::ClockError)?;
+    assert_time_at(
+        payer,
+        settings().timing_rules(scheme),
+        now,
+        valid_after,
+        valid_before,
+    )
+}
+
+/// [`assert_time`] at time `now`.
+fn assert_time_at(
+    payer: MixedAddress,
+    rules: TimingRules,
+    now: UnixTimestamp,
+    valid_after: UnixTimestamp,
+    valid_before: UnixTimestamp,
+) -> Result<(), FacilitatorLocalError> {
     if valid_before < now + 6 {
         return Err(FacilitatorLocalError::InvalidTiming(
             payer,
             format!("Expired: now {} > valid_before {}", now + 6, valid_before),
         ));
     }
-    if valid_after > now {
+    if valid_after > now + rules.clock_skew {
         return Err(FacilitatorLocalError::InvalidTiming(
             payer,
             format!("Not active yet: valid_after {valid_after} > now {now}",),
         ));
     }
+    if let Some(max_validity) = rules.max_validity
+        && valid_before > now + max_validity
+    {
+        return Err(FacilitatorLocalError::InvalidTiming(
+            payer,
+            format!("Valid for too long: valid_before {valid_before} > now {now} + {max_validity}"),
+        ));
+    }
     Ok(())
 }
 
-/// Checks if the payer has enough on-chain token balance to meet the `maxAmountRequired`.
+/// Waits, right before broadcast, until `valid_after` has passed.
 ///
-/// Performs an `ERC20.balanceOf()` call using the USDC contract instance.
+/// An authorization accepted within the clock skew tolerance of its `validAfter` would revert if mined
+/// before it; the wait is at most that tolerance, as verification rejects anything later.
+async fn await_valid_after(valid_after: UnixTimestamp) -> Result<(), FacilitatorLocalError> {
+    let now = UnixTimestamp::try_now().map_err(FacilitatorLocalError::ClockError)?;
+    if valid_after >= now {
+        let wait = Duration::from_secs(valid_after.0 - now.0 + 1);
+        tracing::info!(?wait, %valid_after, "Waiting for the authorization to become valid");
+        tokio::time::sleep(wait).await;
+    }
+    Ok(())
+}
+
+/// Checks, right before broadcast, that the authorization stays valid long enough for the
+/// settlement to be mined: an authorization that expires in the mempool reverts and wastes gas.
 ///
 /// # Errors
-/// Returns [`FacilitatorLocalError::InsufficientFunds`] if the balance is too low.
-/// Returns [`FacilitatorLocalError::ContractCall`] if the balance query fails.
-#[instrument(skip_all, err, fields(
-    sender = %sender,
+/// Returns [`FacilitatorLocalError::InvalidTiming`] if `valid_before` is less than `buffer` seconds
+/// away. Returns [`FacilitatorLocalError::ClockError`] if the system clock cannot be read.
+fn assert_settle_deadline(
+    payer: MixedAddress,
+    valid_before: UnixTimestamp,
+    buffer: u64,
+) -> Result<(), FacilitatorLocalError> {
+    if buffer == 0 {
+        return Ok(());
+    }
+    let now = UnixTimestamp::try_now().map_err(FacilitatorLocalError::ClockError)?;
+    if valid_before < now + buffer {
+        return Err(FacilitatorLocalError::InvalidTiming(
+            payer,
+            format!(
+                "Expires too soon to settle: valid_before {valid_before} is less than {buffer}s after now {now}"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// EVM settings shared by every network, set once at startup by [`settings_from_env`].
+static SETTINGS: OnceLock<EvmSettings> = OnceLock::new();
+
+/// Verification and settlement settings shared by every EVM network.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvmSettings {
+    /// Whether [`assert_signature_by_signer_kind`] is enabled, via `EVM_CHECK_SIGNER_KIND`.
+    pub check_signer_kind: bool,
+    /// Whether [`assert_block_time`] is enabled, via `EVM_CHECK_BLOCK_TIME`.
+    pub check_block_time: bool,
+    /// Whether [`assert_valid_payment`] checks values against the token's `totalSupply()`, via
+    /// `EVM_CHECK_TOTAL_SUPPLY`. Off by default, as it costs a call on every verification and settlement.
+    pub check_total_supply: bool,
+    /// Whether [`assert_domain`] prefers ERC-5267 domains, via `EVM_ERC5267_DOMAINS`.
+    pub erc5267_domains: bool,
+    /// Minimum remaining validity, in seconds, for an authorization to be broadcast,
+    /// via `EVM_SETTLE_EXPIRY_BUFFER_SECS` (default: `0`).
+    pub settle_expiry_buffer: u64,
+    /// Number of rebroadcasts after a reorg orphans a settlement, via `EVM_REORG_RETRIES` (default: `0`).
+    pub reorg_retries: usize,
+    /// Whether settlement responses include their [`SettlementCost`], via `SETTLE_COST_BREAKDOWN`.
+    pub settle_cost_breakdown: bool,
+    /// The [`TimingRules`] of each scheme.
+    pub timing: Vec<(Scheme, TimingRules)>,
+    /// How long to wait for a receipt, via `TX_RECEIPT_TIMEOUT_SECS` (default: `30`).
+    pub receipt_timeout_secs: u64,
+}
+
+impl EvmSettings {
+    /// Reads the settings with `var`, which looks a variable up by name.
+    ///
+    /// # Errors
+    /// Returns an error naming the first variable set to an invalid value.
+    fn from_vars(var: &dyn Fn(&str) -> Option<String>) -> Result<Self, String> {
+        Ok(Self {
+            check_signer_kind: parse_var(var, from_env::ENV_EVM_CHECK_SIGNER_KIND)?
+                .unwrap_or(false),
+            check_block_time: parse_var(var, from_env::ENV_EVM_CHECK_BLOCK_TIME)?.unwrap_or(false),
+            check_total_supply: parse_var(var, from_env::ENV_EVM_CHECK_TOTAL_SUPPLY)?
+                .unwrap_or(false),
+            erc5267_domains: parse_var(var, from_env::ENV_EVM_ERC5267_DOMAINS)?.unwrap_or(false),
+            settle_expiry_buffer: parse_var(var, from_env::ENV_EVM_SETTLE_EXPIRY_BUFFER_SECS)?
+                .unwrap_or(0),
+            reorg_retries: parse_var(var, from_env::ENV_EVM_REORG_RETRIES)?.unwrap_or(0),
+            settle_cost_breakdown: parse_var(var, from_env::ENV_SETTLE_COST_BREAKDOWN)?
+                .unwrap_or(false),
+            timing: Scheme::variants()
+                .iter()
+                .map(|scheme| Ok((*scheme, TimingRules::from_vars(*scheme, var)?)))
+                .collect::<Result<_, String>>()?,
+            receipt_timeout_secs: parse_var(var, from_env::ENV_TX_RECEIPT_TIMEOUT_SECS)?
+                .unwrap_or(30),
+        })
+    }
+
+    /// The [`TimingRules`] of `scheme`.
+    fn timing_rules(&self, scheme: Scheme) -> TimingRules {
+        self.timing
+            .iter()
+            .find(|(timed, _)| *timed == scheme)
+            .map(|(_, rules)| *rules)
+            .unwrap_or_default()
+    }
+}
+
+/// Parses variable `name`, looked up with `var`, or `None` if it is not set.
+fn parse_var<T>(var: &dyn Fn(&str) -> Option<String>, name: &str) -> Result<Option<T>, String>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    var(name)
+        .map(|raw| {
+            raw.trim()
+                .parse::<T>()
+                .map_err(|e| format!("env {name} is invalid: {e}"))
+        })
+        .transpose()
+}
+
+/// Reads the [`EvmSettings`] from the environment, once, before any payment is verified.
+///
+/// # Errors
+/// Returns an error if a variable is set to an invalid value, or if the settings were already read.
+pub fn settings_from_env() -> Result<(), String> {
+    let settings = EvmSettings::from_vars(&|name| std::env::var(name).ok())?;
+    SETTINGS
+        .set(settings)
+        .map_err(|_| "EVM settings are already set".to_string())
+}
+
+/// The settings read by [`settings_from_env`], or the defaults if it was not called.
+fn settings() -> &'static EvmSettings {
+    SETTINGS.get_or_init(EvmSettings::default)
+}
+
+/// Code prefix of an EOA delegated with EIP-7702, followed by the 20-byte delegate address.
+const EIP7702_DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+/// Whether an account with `code` is an EOA: it has no code, or only an EIP-7702 delegation,
+/// which leaves its key signing for it.
+fn is_eoa_code(code: &[u8]) -> bool {
+    code.is_empty() || (code.len() == 23 && code.starts_with(&EIP7702_DELEGATION_PREFIX))
+}
+
+/// Checks a plain (non-6492) signature with the method matching the kind of account `signer` is.
+///
+/// An EOA, see [`is_eoa_code`], must be the address the signature recovers to with `ecrecover`.
+/// An account with code is a contract wallet, so its EIP-1271 `isValidSignature` must accept it;
+/// recovering an address from a contract wallet's signature would not mean anything.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidSignature`] if the signature is malformed, recovers to
+/// another address, or is rejected by the contract wallet.
+/// Returns [`FacilitatorLocalError::ContractCall`] if the account cannot be read.
+async fn assert_signature_by_signer_kind<P: Provider>(
+    provider: &P,
+    signer: Address,
+    hash: FixedBytes<32>,
+    signature: &Bytes,
+) -> Result<(), FacilitatorLocalError> {
+    if is_eoa_code(&account_code(provider, &signer).await?) {
+        let recovered = ecdsa_signature(signature)
+            .ok_or_else(|| {
+                FacilitatorLocalError::InvalidSignature(
+                    signer.into(),
+                    "Malformed EOA signature".to_string(),
+                )
+            })?
+            .recover_address_from_prehash(&hash)
+            .map_err(|e| FacilitatorLocalError::InvalidSignature(signer.into(), format!("{e}")))?;
+        if recovered != signer {
+            return Err(FacilitatorLocalError::InvalidSignature(
+                signer.into(),
+                format!("Signature recovers to {recovered}"),
+            ));
+        }
+        return Ok(());
+    }
+    let magic_value = IERC1271::new(signer, provider)
+        .isValidSignature(hash, signature.clone())
+        .call()
+        .into_future()
+        .instrument(tracing::info_span!(
+            "call_isValidSignature",
+            signer = %signer,
+            otel.kind = "client"
+        ))
+        .await;
+    match magic_value {
+        Ok(magic_value) if magic_value == EIP1271_MAGIC_VALUE => Ok(()),
+        Err(e) if e.as_revert_data().is_none() => Err(FacilitatorLocalError::contract_call(e)),
+        _ => Err(FacilitatorLocalError::InvalidSignature(
+            signer.into(),
+            "Contract wallet rejected the signature".to_string(),
+        )),
+    }
+}
+
+/// Validates the authorization window against the latest block timestamp rather than the wall clock.
+///
+/// ERC-3009 checks `validAfter < block.timestamp < validBefore` on-chain, so an authorization that is
+/// already expired (or not yet active) at the chain head is guaranteed to revert, even if the server
+/// clock disagrees. Like [`assert_time`], `validAfter` may be up to `clock_skew` seconds ahead: the
+/// settlement waits for it, see [`await_valid_after`].
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidTiming`] if the window does not contain the latest block timestamp.
+/// Returns [`FacilitatorLocalError::ContractCall`] if the latest block cannot be fetched.
+#[instrument(skip_all, err)]
+async fn assert_block_time<P: Provider>(
+    provider: &P,
+    payer: MixedAddress,
+    clock_skew: u64,
+    valid_after: UnixTimestamp,
+    valid_before: UnixTimestamp,
+) -> Result<(), FacilitatorLocalError> {
+    let block = provider
+        .get_block_by_number(BlockNumberOrTag::Latest)
+        .into_future()
+        .instrument(tracing::info_span!(
+            "get_latest_block",
+            otel.kind = "client"
+        ))
+        .await
+        .map_err(FacilitatorLocalError::contract_call)?
+        .ok_or_else(|| FacilitatorLocalError::ContractCall("latest block not found".to_string()))?;
+    let block_time = UnixTimestamp(block.header.timestamp);
+    if valid_before <= block_time {
+        return Err(FacilitatorLocalError::InvalidTiming(
+            payer,
+            format!("Expired on-chain: block time {block_time} >= valid_before {valid_before}"),
+        ));
+    }
+    // The next block is later than the latest one, so an authorization valid after it can still be mined.
+    if valid_after > block_time + clock_skew {
+        return Err(FacilitatorLocalError::InvalidTiming(
+            payer,
+            format!("Not active on-chain yet: valid_after {valid_after} > block time {block_time}"),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks if the payer has enough on-chain token balance to meet the `maxAmountRequired`.
+///
+/// Performs an `ERC20.balanceOf()` call using the USDC contract instance, then converts the balance
+/// to transfer units with `value_model`.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InsufficientFunds`] if the balance is too low.
+/// Returns [`FacilitatorLocalError::ContractCall`] if the balance query or conversion fails.
+#[instrument(skip_all, err, fields(
+    sender = %sender,
     max_required = %max_amount_required,
     token_contract = %usdc_contract.address()
 ))]
 async fn assert_enough_balance<P: Provider>(
     usdc_contract: &USDC::USDCInstance<P>,
+    value_model: &dyn ValueModel,
     sender: &EvmAddress,
     max_amount_required: U256,
 ) -> Result<(), FacilitatorLocalError> {
@@ -762,7 +3005,10 @@ async fn assert_enough_balance<P: Provider>(
             otel.kind = "client"
         ))
         .await
-        .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+        .map_err(FacilitatorLocalError::contract_call)?;
+    let balance = value_model
+        .spendable(usdc_contract.provider(), *usdc_contract.address(), balance)
+        .await?;
 
     if balance < max_amount_required {
         Err(FacilitatorLocalError::InsufficientFunds((*sender).into()))
@@ -771,26 +3017,350 @@ async fn assert_enough_balance<P: Provider>(
     }
 }
 
-/// Verifies that the declared `value` in the payload is sufficient for the required amount.
+/// Nonce the merchant expects for `requirements`, if it binds the payment to an order.
+///
+/// Taken from `extra.expectedNonce` as a 32-byte hex string, or else derived as the keccak256 hash
+/// of `extra.orderId`. Returns `Ok(None)` when the requirements carry neither.
+fn expected_nonce(requirements: &PaymentRequirements) -> Result<Option<B256>, String> {
+    let Some(extra) = requirements.extra.as_ref() else {
+        return Ok(None);
+    };
+    if let Some(expected) = extra.get("expectedNonce") {
+        return expected
+            .as_str()
+            .and_then(|expected| expected.parse::<B256>().ok())
+            .map(Some)
+            .ok_or_else(|| "extra.expectedNonce is not a 32-byte hex string".to_string());
+    }
+    match extra.get("orderId") {
+        Some(serde_json::Value::String(order_id)) => Ok(Some(keccak256(order_id.as_bytes()))),
+        Some(_) => Err("extra.orderId is not a string".to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Nonce of a payment forwarded by a facilitator signer, committing to where it is forwarded.
+///
+/// The authorization only names the signer as its `to`, so the payer binds the receivers through
+/// the nonce instead: `keccak256(abi.encode(salt, payTo, splits))`, with `splits` as
+/// `(address payTo, uint256 amount)[]`, empty without splits. Requirements rewritten by whoever
+/// submits the payload then no longer match the signed nonce.
+///
+/// The salt tells apart payments to the same receivers: the nonce the merchant expects for an order,
+/// see [`expected_nonce`], or else `extra.routingSalt` as a 32-byte hex string.
+fn routing_nonce(requirements: &PaymentRequirements) -> Result<B256, String> {
+    use alloy::sol_types::SolValue;
+
+    let salt = match expected_nonce(requirements)? {
+        Some(salt) => salt,
+        None => requirements
+            .extra
+            .as_ref()
+            .and_then(|extra| extra.get("routingSalt"))
+            .ok_or_else(|| {
+                "extra.routingSalt is required for payments forwarded by the facilitator"
+                    .to_string()
+            })?
+            .as_str()
+            .and_then(|salt| salt.parse::<B256>().ok())
+            .ok_or_else(|| "extra.routingSalt is not a 32-byte hex string".to_string())?,
+    };
+    let address = |address: &MixedAddress| {
+        Address::try_from(address.clone()).map_err(|_| format!("{address} is not an EVM address"))
+    };
+    let pay_to = address(&requirements.pay_to)?;
+    let splits = requirements
+        .splits
+        .iter()
+        .flatten()
+        .map(|split| Ok((address(&split.pay_to)?, split.amount.0)))
+        .collect::<Result<Vec<(Address, U256)>, String>>()?;
+    Ok(keccak256((salt, pay_to, splits).abi_encode_params()))
+}
+
+/// Rejects an authorization whose nonce is not the one the merchant expects, see [`expected_nonce`].
+///
+/// This keeps a payer from reusing an authorization made for one order to pay for another.
+/// Payments forwarded by a facilitator signer `receiver` (splits, `unwrapNative`, `receiveWithAuthorization`)
+/// must instead carry their [`routing_nonce`], so their receivers can not be redirected.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::UnexpectedNonce`] if the nonce differs,
+/// and [`FacilitatorLocalError::DecodingError`] if the expected nonce is malformed.
+fn assert_expected_nonce(
+    receiver: AuthorizedReceiver<'_>,
+    payment: &ExactEvmPayment,
+    requirements: &PaymentRequirements,
+) -> Result<(), FacilitatorLocalError> {
+    let expected = if matches!(receiver, AuthorizedReceiver::Facilitator(_)) {
+        Some(routing_nonce(requirements).map_err(FacilitatorLocalError::DecodingError)?)
+    } else {
+        expected_nonce(requirements).map_err(FacilitatorLocalError::DecodingError)?
+    };
+    match expected {
+        Some(expected) if expected.0 != payment.nonce.0 => {
+            Err(FacilitatorLocalError::UnexpectedNonce(payment.from.into()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// EIP-1967 storage slot holding a proxy's implementation address.
+const EIP1967_IMPLEMENTATION_SLOT: B256 =
+    b256!("0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+
+/// Checks that the token's proxy implementation is still `extra.tokenImplementation`, for tokens
+/// with an `implementationCheck`.
+///
+/// Tokens without the setting, and requirements without `extra.tokenImplementation`, are not checked.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::ImplementationChanged`] if the implementation differs and the token
+/// rejects such payments; with `"warn"`, the change is only logged.
+/// Returns [`FacilitatorLocalError::ContractCall`] if the slot cannot be read,
+/// and [`FacilitatorLocalError::DecodingError`] if `extra.tokenImplementation` is not an address.
+async fn assert_same_implementation<P, I>(
+    provider: &P,
+    contract: &USDC::USDCInstance<I>,
+    payment: &ExactEvmPayment,
+    requirements: &PaymentRequirements,
+) -> Result<(), FacilitatorLocalError>
+where
+    P: MetaEvmProvider,
+    I: Provider,
+{
+    let Some(check) = provider
+        .tokens()
+        .get(&requirements.asset)
+        .and_then(|token| token.implementation_check)
+    else {
+        return Ok(());
+    };
+    let Some(expected) = requirements
+        .extra
+        .as_ref()
+        .and_then(|extra| extra.get("tokenImplementation"))
+    else {
+        return Ok(());
+    };
+    let expected: Address = expected
+        .as_str()
+        .and_then(|expected| expected.parse().ok())
+        .ok_or_else(|| {
+            FacilitatorLocalError::DecodingError(
+                "extra.tokenImplementation is not an address".to_string(),
+            )
+        })?;
+    let slot = provider
+        .inner()
+        .get_storage_at(*contract.address(), EIP1967_IMPLEMENTATION_SLOT.into())
+        .into_future()
+        .instrument(tracing::info_span!(
+            "fetch_token_implementation",
+            token_contract = %contract.address(),
+            otel.kind = "client"
+        ))
+        .await
+        .map_err(FacilitatorLocalError::contract_call)?;
+    let current = Address::from_word(slot.into());
+    if current == expected {
+        return Ok(());
+    }
+    let reason = format!("token implementation is {current}, requirements expect {expected}");
+    match check {
+        ImplementationCheck::Warn => {
+            tracing::warn!(token_contract = %contract.address(), %current, %expected, "Token implementation changed");
+            Ok(())
+        }
+        ImplementationCheck::Reject => Err(FacilitatorLocalError::ImplementationChanged(
+            payment.from.into(),
+            reason,
+        )),
+    }
+}
+
+/// Rejects an authorization whose nonce breaks one of the provider's [`NonceRules`], if configured.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::SuspiciousNonce`] with the broken rule.
+fn assert_plausible_nonce<P: MetaEvmProvider>(
+    provider: &P,
+    payment: &ExactEvmPayment,
+) -> Result<(), FacilitatorLocalError> {
+    let Some(nonce_rules) = provider.nonce_rules() else {
+        return Ok(());
+    };
+    match nonce_rules.violated_by(&B256::from(payment.nonce.0)) {
+        Some(rule) => Err(FacilitatorLocalError::SuspiciousNonce(
+            payment.from.into(),
+            rule.to_string(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Rejects an authorization whose nonce is already used, if the nonce filter is enabled.
+///
+/// `authorizationState` is only queried for nonces the [`NonceFilter`] may have seen settled;
+/// any other nonce is definitely fresh as far as this facilitator is concerned.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::AuthorizationUsed`] if the token reports the nonce as used.
+/// Returns [`FacilitatorLocalError::ContractCall`] if the query fails.
+async fn assert_fresh_nonce<P, I>(
+    provider: &P,
+    contract: &USDC::USDCInstance<I>,
+    payment: &ExactEvmPayment,
+) -> Result<(), FacilitatorLocalError>
+where
+    P: MetaEvmProvider,
+    I: Provider,
+{
+    let Some(nonce_filter) = provider.nonce_filter() else {
+        return Ok(());
+    };
+    let nonce = FixedBytes(payment.nonce.0);
+    let chain_id = provider.chain().chain_id;
+    if !nonce_filter.may_contain(chain_id, *contract.address(), payment.from.0, nonce) {
+        return Ok(());
+    }
+    let used = contract
+        .authorizationState(payment.from.0, nonce)
+        .call()
+        .into_future()
+        .instrument(tracing::info_span!(
+            "fetch_authorization_state",
+            token_contract = %contract.address(),
+            authorizer = %payment.from,
+            otel.kind = "client"
+        ))
+        .await
+        .map_err(FacilitatorLocalError::contract_call)?;
+    if used {
+        Err(FacilitatorLocalError::AuthorizationUsed(
+            payment.from.into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// The [`NonceKey`] of `payment`'s authorization.
+fn nonce_key<P: MetaEvmProvider, I: Provider>(
+    provider: &P,
+    contract: &USDC::USDCInstance<I>,
+    payment: &ExactEvmPayment,
+) -> NonceKey {
+    NonceKey {
+        chain_id: provider.chain().chain_id,
+        token: *contract.address(),
+        authorizer: payment.from.0,
+        nonce: FixedBytes(payment.nonce.0),
+    }
+}
+
+/// Rejects an authorization claimed by a settlement in flight, if replay protection is enabled.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::NonceReused`] if the authorization is claimed.
+async fn assert_unclaimed_nonce<P: MetaEvmProvider, I: Provider>(
+    provider: &P,
+    contract: &USDC::USDCInstance<I>,
+    payment: &ExactEvmPayment,
+) -> Result<(), FacilitatorLocalError> {
+    let Some(nonce_store) = provider.nonce_store() else {
+        return Ok(());
+    };
+    let claimed = nonce_store
+        .is_claimed(&nonce_key(provider, contract, payment))
+        .await;
+    if claimed {
+        Err(FacilitatorLocalError::NonceReused(payment.from.into()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Claims `payment`'s authorization until its `validBefore`, if replay protection is enabled.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::NonceReused`] if another settlement holds the claim.
+async fn claim_nonce<P: MetaEvmProvider, I: Provider>(
+    provider: &P,
+    contract: &USDC::USDCInstance<I>,
+    payment: &ExactEvmPayment,
+) -> Result<Option<NonceKey>, FacilitatorLocalError> {
+    let Some(nonce_store) = provider.nonce_store() else {
+        return Ok(None);
+    };
+    let key = nonce_key(provider, contract, payment);
+    let claimed = nonce_store.claim(&key, payment.valid_before).await;
+    if claimed {
+        Ok(Some(key))
+    } else {
+        Err(FacilitatorLocalError::NonceReused(payment.from.into()))
+    }
+}
+
+/// Releases a claim taken by [`claim_nonce`] for a settlement that did not go through.
+async fn release_nonce<P: MetaEvmProvider>(provider: &P, claim: Option<NonceKey>) {
+    if let (Some(nonce_store), Some(key)) = (provider.nonce_store(), claim) {
+        nonce_store.release(&key).await;
+    }
+}
+
+/// Verifies that the declared `value` in the payload is what `amount_scheme` allows for the required amount.
 ///
 /// This is a static check (not on-chain) that compares two numbers.
 ///
 /// # Errors
-/// Return [`FacilitatorLocalError::InsufficientValue`] if the payload's value is less than required.
+/// Return [`FacilitatorLocalError::InsufficientValue`] if the payload's value is less than an exact amount,
+/// and [`FacilitatorLocalError::ExcessValue`] if it is more than the scheme allows.
 #[instrument(skip_all, err, fields(
     sent = %sent,
     max_amount_required = %max_amount_required
 ))]
 fn assert_enough_value(
     payer: &EvmAddress,
+    amount_scheme: AmountScheme,
     sent: &U256,
     max_amount_required: &U256,
 ) -> Result<(), FacilitatorLocalError> {
-    if sent < max_amount_required {
-        Err(FacilitatorLocalError::InsufficientValue((*payer).into()))
-    } else {
-        Ok(())
+    match amount_scheme.compare(*sent, *max_amount_required) {
+        std::cmp::Ordering::Less => Err(FacilitatorLocalError::InsufficientValue((*payer).into())),
+        std::cmp::Ordering::Greater => Err(FacilitatorLocalError::ExcessValue((*payer).into())),
+        std::cmp::Ordering::Equal => Ok(()),
+    }
+}
+
+/// Rejects an authorized `value` above the token's `max_value` ceiling or its `total_supply`:
+/// no payer can hold that much, so the payload is bogus rather than underfunded.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::ValueOutOfRange`] naming the bound that was exceeded.
+fn assert_value_in_range(
+    payer: &EvmAddress,
+    value: U256,
+    max_value: Option<TokenAmount>,
+    total_supply: Option<U256>,
+) -> Result<(), FacilitatorLocalError> {
+    if let Some(max_value) = max_value
+        && value > max_value.0
+    {
+        return Err(FacilitatorLocalError::ValueOutOfRange(
+            (*payer).into(),
+            format!("value {value} exceeds the token's ceiling of {max_value}"),
+        ));
+    }
+    if let Some(total_supply) = total_supply
+        && value > total_supply
+    {
+        return Err(FacilitatorLocalError::ValueOutOfRange(
+            (*payer).into(),
+            format!("value {value} exceeds the token's total supply of {total_supply}"),
+        ));
     }
+    Ok(())
 }
 
 /// Check whether contract code is present at `address`.
@@ -805,7 +3375,18 @@ async fn is_contract_deployed<P: Provider>(
     provider: P,
     address: &Address,
 ) -> Result<bool, FacilitatorLocalError> {
-    let bytes = provider
+    Ok(!account_code(provider, address).await?.is_empty())
+}
+
+/// The code at `address`, read with `eth_getCode`.
+///
+/// # Errors
+/// Return [`FacilitatorLocalError::ContractCall`] if the RPC call fails.
+async fn account_code<P: Provider>(
+    provider: P,
+    address: &Address,
+) -> Result<Bytes, FacilitatorLocalError> {
+    provider
         .get_code_at(*address)
         .into_future()
         .instrument(tracing::info_span!("get_code_at",
@@ -813,15 +3394,18 @@ async fn is_contract_deployed<P: Provider>(
             otel.kind = "client",
         ))
         .await
-        .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
-    Ok(!bytes.is_empty())
+        .map_err(FacilitatorLocalError::contract_call)
 }
 
 /// Constructs the correct EIP-712 domain for signature verification.
 ///
-/// Resolves the `name` and `version` based on:
+/// If `EVM_ERC5267_DOMAINS` is enabled and the token implements ERC-5267, its `eip712Domain()` is used as is.
+/// Otherwise, resolves the `name` and `version` based on:
 /// - Static metadata from [`USDCDeployment`] (if available),
 /// - Or by calling `version()` on the token contract if not matched statically.
+///
+/// The domain's `verifyingContract` is the token, unless the token's `verifyingContract` setting
+/// overrides it, see [`with_verifying_contract`].
 #[instrument(skip_all, err, fields(
     network = %payload.network,
     asset = %asset_address
@@ -832,15 +3416,76 @@ async fn assert_domain<P: Provider>(
     payload: &PaymentPayload,
     asset_address: &Address,
     requirements: &PaymentRequirements,
+    verifying_contract: Option<EvmAddress>,
 ) -> Result<Eip712Domain, FacilitatorLocalError> {
-    let usdc = USDCDeployment::by_network(payload.network);
+    if settings().erc5267_domains
+        && let Some(domain) = erc5267_domain(chain, token_contract.provider(), asset_address).await
+    {
+        return Ok(with_verifying_contract(domain, verifying_contract));
+    }
+    match offline_domain(chain, asset_address, requirements, verifying_contract)? {
+        OfflineDomain::Resolved(domain) => Ok(domain),
+        OfflineDomain::MissingVersion(name) => {
+            let version = token_contract
+                .version()
+                .call()
+                .into_future()
+                .instrument(tracing::info_span!(
+                    "fetch_eip712_version",
+                    otel.kind = "client",
+                ))
+                .await
+                .map_err(FacilitatorLocalError::contract_call)?;
+            Ok(static_domain(
+                chain,
+                asset_address,
+                name,
+                version,
+                verifying_contract,
+            ))
+        }
+    }
+}
+
+/// The EIP-712 domain of a token as far as [`offline_domain`] can tell.
+enum OfflineDomain {
+    Resolved(Eip712Domain),
+    /// The domain name; the version must be read from the token.
+    MissingVersion(String),
+}
+
+/// The part of [`assert_domain`] that needs no chain access: the token's cached ERC-5267 domain, or
+/// the `name` and `version` from the requirements' `extra` or the known [`USDCDeployment`].
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::DecodingError`] if the domain name is unknown.
+fn offline_domain(
+    chain: &EvmChain,
+    asset_address: &Address,
+    requirements: &PaymentRequirements,
+    verifying_contract: Option<EvmAddress>,
+) -> Result<OfflineDomain, FacilitatorLocalError> {
+    if settings().erc5267_domains
+        && let Some(Some(domain)) = ERC5267_DOMAINS
+            .get(&(chain.chain_id, *asset_address))
+            .map(|cached| cached.clone())
+    {
+        return Ok(OfflineDomain::Resolved(with_verifying_contract(
+            domain,
+            verifying_contract,
+        )));
+    }
+    let usdc = USDCDeployment::by_network(chain.network);
     let name = requirements
         .extra
         .as_ref()
         .and_then(|e| e.get("name")?.as_str().map(str::to_string))
         .or_else(|| usdc.eip712.clone().map(|e| e.name))
-        .ok_or(FacilitatorLocalError::UnsupportedNetwork(None))?;
-    let chain_id = chain.chain_id;
+        .ok_or_else(|| {
+            FacilitatorLocalError::DecodingError(format!(
+                "EIP-712 domain name of {asset_address} is unknown; set `name` in the requirements' extra"
+            ))
+        })?;
     let version = requirements
         .extra
         .as_ref()
@@ -853,46 +3498,140 @@ async fn assert_domain<P: Provider>(
     } else {
         None
     };
-    let version = if let Some(version) = version {
-        version
-    } else {
-        token_contract
-            .version()
-            .call()
-            .into_future()
-            .instrument(tracing::info_span!(
-                "fetch_eip712_version",
-                otel.kind = "client",
-            ))
-            .await
-            .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?
-    };
+    Ok(match version {
+        Some(version) => OfflineDomain::Resolved(static_domain(
+            chain,
+            asset_address,
+            name,
+            version,
+            verifying_contract,
+        )),
+        None => OfflineDomain::MissingVersion(name),
+    })
+}
+
+fn static_domain(
+    chain: &EvmChain,
+    asset_address: &Address,
+    name: String,
+    version: String,
+    verifying_contract: Option<EvmAddress>,
+) -> Eip712Domain {
     let domain = eip712_domain! {
         name: name,
         version: version,
-        chain_id: chain_id,
+        chain_id: chain.chain_id,
         verifying_contract: *asset_address,
     };
-    Ok(domain)
+    with_verifying_contract(domain, verifying_contract)
 }
 
-/// Runs all preconditions needed for a successful payment:
-/// - Valid scheme, network, and receiver.
-/// - Valid time window (validAfter/validBefore).
-/// - Correct EIP-712 domain construction.
-/// - Sufficient on-chain balance.
-/// - Sufficient value in payload.
-#[instrument(skip_all, err)]
-async fn assert_valid_payment<P: Provider>(
-    provider: P,
+/// Replaces the `verifyingContract` of `domain` with `verifying_contract`, if set.
+///
+/// For tokens settled through a proxy or wrapper whose signatures are bound to another contract than
+/// the one `transferWithAuthorization` is called on.
+fn with_verifying_contract(
+    mut domain: Eip712Domain,
+    verifying_contract: Option<EvmAddress>,
+) -> Eip712Domain {
+    if let Some(verifying_contract) = verifying_contract {
+        domain.verifying_contract = Some(verifying_contract.0);
+    }
+    domain
+}
+
+/// Reads the token's ERC-5267 domain, cached per chain and token.
+///
+/// Returns `None` if the token does not implement ERC-5267. Only a definitive answer is cached, see
+/// [`lacks_erc5267`]; after any other failure, such as a timeout, the next payment asks again.
+async fn erc5267_domain<P: Provider>(
     chain: &EvmChain,
-    payload: &PaymentPayload,
-    requirements: &PaymentRequirements,
+    provider: &P,
+    token: &Address,
+) -> Option<Eip712Domain> {
+    let key = (chain.chain_id, *token);
+    if let Some(cached) = ERC5267_DOMAINS.get(&key) {
+        return cached.clone();
+    }
+    let result = IERC5267::new(*token, provider)
+        .eip712Domain()
+        .call()
+        .into_future()
+        .instrument(tracing::info_span!(
+            "fetch_eip712_domain",
+            otel.kind = "client"
+        ))
+        .await;
+    let domain = match result {
+        Ok(domain) => Some(domain_from_erc5267(
+            domain.fields.0[0],
+            domain.name,
+            domain.version,
+            domain.chainId,
+            domain.verifyingContract,
+            domain.salt,
+        )),
+        Err(error) if lacks_erc5267(&error) => None,
+        Err(error) => {
+            tracing::warn!(token = %token, %error, "failed to fetch ERC-5267 domain");
+            return None;
+        }
+    };
+    ERC5267_DOMAINS.insert(key, domain.clone());
+    domain
+}
+
+/// Whether a failed `eip712Domain()` call shows the token does not implement ERC-5267: the call reverted,
+/// returned nothing, or returned something other than an ERC-5267 domain.
+fn lacks_erc5267(error: &alloy::contract::Error) -> bool {
+    match error {
+        alloy::contract::Error::ZeroData(..) | alloy::contract::Error::AbiError(_) => true,
+        error => revert::reason(error).is_some(),
+    }
+}
+
+/// Builds an [`Eip712Domain`] from an ERC-5267 `eip712Domain()` result, keeping only the fields
+/// flagged in the `fields` bitmap: name, version, chainId, verifyingContract, salt (bits 0 to 4).
+fn domain_from_erc5267(
+    fields: u8,
+    name: String,
+    version: String,
+    chain_id: U256,
+    verifying_contract: Address,
+    salt: FixedBytes<32>,
+) -> Eip712Domain {
+    let has = |bit: u8| fields & (1 << bit) != 0;
+    Eip712Domain::new(
+        has(0).then_some(name.into()),
+        has(1).then_some(version.into()),
+        has(2).then_some(chain_id),
+        has(3).then_some(verifying_contract),
+        has(4).then_some(salt),
+    )
+}
+
+/// Runs all preconditions needed for a successful payment:
+/// - Valid scheme, network, and receiver (see [`AuthorizedReceiver`]).
+/// - Valid time window (validAfter/validBefore).
+/// - Correct EIP-712 domain construction.
+/// - Sufficient value in payload, within the token's `maxValue` setting and total supply.
+/// - Sufficient on-chain balance, as converted by the token's [`ValueModel`].
+#[instrument(skip_all, err)]
+async fn assert_valid_payment<P: Provider>(
+    provider: P,
+    chain: &EvmChain,
+    receiver: AuthorizedReceiver<'_>,
+    payload: &PaymentPayload,
+    requirements: &PaymentRequirements,
+    tokens: &TokenConfigs,
 ) -> Result<(USDC::USDCInstance<P>, ExactEvmPayment, Eip712Domain), FacilitatorLocalError> {
     let payment_payload = match &payload.payload {
         ExactPaymentPayload::Evm(payload) => payload,
         ExactPaymentPayload::Solana(_) => {
-            return Err(FacilitatorLocalError::UnsupportedNetwork(None));
+            return Err(FacilitatorLocalError::DecodingError(format!(
+                "payload for {} is a Solana transaction, not an EVM authorization",
+                chain.network
+            )));
         }
     };
     let payer = payment_payload.authorization.from;
@@ -923,16 +3662,48 @@ async fn assert_valid_payment<P: Provider>(
         .clone()
         .try_into()
         .map_err(|e| FacilitatorLocalError::InvalidAddress(format!("{e:?}")))?;
-    if payload_to != requirements_to {
-        return Err(FacilitatorLocalError::ReceiverMismatch(
-            payer.into(),
-            payload_to.to_string(),
-            requirements_to.to_string(),
-        ));
+    match receiver {
+        AuthorizedReceiver::PayTo => {
+            if payload_to != requirements_to {
+                return Err(FacilitatorLocalError::ReceiverMismatch(
+                    payer.into(),
+                    payload_to.to_string(),
+                    requirements_to.to_string(),
+                ));
+            }
+        }
+        // In verify-only mode no signer could ever receive, let alone forward, the payment.
+        AuthorizedReceiver::Facilitator([]) => {
+            return Err(FacilitatorLocalError::ReceiverMismatch(
+                payer.into(),
+                payload_to.to_string(),
+                "a facilitator signer, but none is configured".to_string(),
+            ));
+        }
+        AuthorizedReceiver::Facilitator(signers) => {
+            if !signers.contains(&payload_to.0) {
+                return Err(FacilitatorLocalError::ReceiverMismatch(
+                    payer.into(),
+                    payload_to.to_string(),
+                    format!("facilitator signer {signers:?}"),
+                ));
+            }
+        }
     }
     let valid_after = payment_payload.authorization.valid_after;
     let valid_before = payment_payload.authorization.valid_before;
-    assert_time(payer.into(), valid_after, valid_before)?;
+    assert_time(payer.into(), requirements.scheme, valid_after, valid_before)?;
+    if settings().check_block_time {
+        let clock_skew = settings().timing_rules(requirements.scheme).clock_skew;
+        assert_block_time(
+            &provider,
+            payer.into(),
+            clock_skew,
+            valid_after,
+            valid_before,
+        )
+        .await?;
+    }
     let asset_address = requirements
         .asset
         .clone()
@@ -940,17 +3711,54 @@ async fn assert_valid_payment<P: Provider>(
         .map_err(|e| FacilitatorLocalError::InvalidAddress(format!("{e:?}")))?;
     let contract = USDC::new(asset_address, provider);
 
-    let domain = assert_domain(chain, &contract, payload, &asset_address, requirements).await?;
+    let domain = assert_domain(
+        chain,
+        &contract,
+        payload,
+        &asset_address,
+        requirements,
+        tokens.verifying_contract(&requirements.asset),
+    )
+    .await?;
 
-    let amount_required = requirements.max_amount_required.0;
+    // For fee-on-transfer tokens, the payer must send enough for `payTo` to receive the required amount,
+    // after a second fee when the payment is forwarded by a facilitator signer.
+    let mut amount_required =
+        tokens.gross_amount(&requirements.asset, requirements.max_amount_required.0);
+    if matches!(receiver, AuthorizedReceiver::Facilitator(_)) {
+        amount_required = tokens.gross_amount(&requirements.asset, amount_required);
+    }
+    let value: U256 = payment_payload.authorization.value.into();
+    assert_enough_value(&payer, requirements.scheme.into(), &value, &amount_required)?;
+    tokens.assert_amount_in_bounds(payer.into(), &requirements.asset, TokenAmount(value))?;
+    let total_supply = if settings().check_total_supply {
+        let total_supply = contract
+            .totalSupply()
+            .call()
+            .into_future()
+            .instrument(tracing::info_span!(
+                "fetch_total_supply",
+                otel.kind = "client"
+            ))
+            .await
+            .map_err(FacilitatorLocalError::contract_call)?;
+        Some(total_supply)
+    } else {
+        None
+    };
+    assert_value_in_range(
+        &payer,
+        value,
+        tokens.max_value(&requirements.asset),
+        total_supply,
+    )?;
     assert_enough_balance(
         &contract,
+        tokens.value_model(&requirements.asset),
         &payment_payload.authorization.from,
-        amount_required,
+        value,
     )
     .await?;
-    let value: U256 = payment_payload.authorization.value.into();
-    assert_enough_value(&payer, &value, &amount_required)?;
 
     let payment = ExactEvmPayment {
         chain: *chain,
@@ -961,41 +3769,312 @@ async fn assert_valid_payment<P: Provider>(
         valid_before: payment_payload.authorization.valid_before,
         nonce: payment_payload.authorization.nonce,
         signature: payment_payload.signature.clone(),
+        kind: requirements.authorization_kind.unwrap_or_default(),
     };
 
     Ok((contract, payment, domain))
 }
 
-/// Constructs a full `transferWithAuthorization` call for a verified payment payload.
+/// Verifies an EVM payment without any RPC calls, against the EIP-712 domain in the request.
+///
+/// Checks scheme, network, receiver, timing (wall clock), value, and that the signature recovers
+/// to `from`. On-chain state — balance, nonce usage, contract wallets (EIP-1271/EIP-6492) — is not checked.
+///
+/// Returns the payer.
+///
+/// # Errors
+/// Returns the same [`FacilitatorLocalError`]s as verification, and
+/// [`FacilitatorLocalError::InvalidSignature`] for signatures that need chain access to validate.
+pub fn verify_offline(request: &OfflineVerifyRequest) -> Result<EvmAddress, FacilitatorLocalError> {
+    let payload = &request.payment_payload;
+    let requirements = &request.payment_requirements;
+    let payment_payload = match &payload.payload {
+        ExactPaymentPayload::Evm(payload) => payload,
+        ExactPaymentPayload::Solana(_) => {
+            return Err(FacilitatorLocalError::DecodingError(
+                "offline verification only supports EVM authorizations".to_string(),
+            ));
+        }
+    };
+    let authorization = &payment_payload.authorization;
+    let payer = authorization.from;
+    let chain = EvmChain::try_from(requirements.network)?;
+    if payload.network != chain.network {
+        return Err(FacilitatorLocalError::NetworkMismatch(
+            Some(payer.into()),
+            chain.network,
+            payload.network,
+        ));
+    }
+    if request.domain.chain_id != chain.chain_id {
+        return Err(FacilitatorLocalError::InvalidSignature(
+            payer.into(),
+            format!(
+                "domain chainId {} does not match {} ({})",
+                request.domain.chain_id, chain.network, chain.chain_id
+            ),
+        ));
+    }
+    if payload.scheme != requirements.scheme {
+        return Err(FacilitatorLocalError::SchemeMismatch(
+            Some(payer.into()),
+            requirements.scheme,
+            payload.scheme,
+        ));
+    }
+    let asset: EvmAddress = requirements
+        .asset
+        .clone()
+        .try_into()
+        .map_err(|e| FacilitatorLocalError::InvalidAddress(format!("{e:?}")))?;
+    if request.domain.verifying_contract != asset {
+        return Err(FacilitatorLocalError::InvalidSignature(
+            payer.into(),
+            format!(
+                "domain verifyingContract {} is not the asset {asset}",
+                request.domain.verifying_contract
+            ),
+        ));
+    }
+    let pay_to: EvmAddress = requirements
+        .pay_to
+        .clone()
+        .try_into()
+        .map_err(|e| FacilitatorLocalError::InvalidAddress(format!("{e:?}")))?;
+    if authorization.to != pay_to {
+        return Err(FacilitatorLocalError::ReceiverMismatch(
+            payer.into(),
+            authorization.to.to_string(),
+            pay_to.to_string(),
+        ));
+    }
+    assert_time(
+        payer.into(),
+        requirements.scheme,
+        authorization.valid_after,
+        authorization.valid_before,
+    )?;
+    let value: U256 = authorization.value.into();
+    assert_enough_value(
+        &payer,
+        requirements.scheme.into(),
+        &value,
+        &requirements.max_amount_required.0,
+    )?;
+
+    let domain = eip712_domain! {
+        name: request.domain.name.clone(),
+        version: request.domain.version.clone(),
+        chain_id: request.domain.chain_id,
+        verifying_contract: asset.0,
+    };
+    let payment = ExactEvmPayment {
+        chain,
+        from: authorization.from,
+        to: authorization.to,
+        value: authorization.value,
+        valid_after: authorization.valid_after,
+        valid_before: authorization.valid_before,
+        nonce: authorization.nonce,
+        signature: payment_payload.signature.clone(),
+        kind: requirements.authorization_kind.unwrap_or_default(),
+    };
+    let signed_message = SignedMessage::extract(&payment, &domain)?;
+    let signature = match &signed_message.signature {
+        StructuredSignature::EIP1271(signature) => ecdsa_signature(signature),
+        StructuredSignature::EIP6492 { .. } => None,
+    };
+    let Some(signature) = signature else {
+        return Err(FacilitatorLocalError::InvalidSignature(
+            payer.into(),
+            "Contract wallet signatures can not be verified offline".to_string(),
+        ));
+    };
+    let recovered = signature
+        .recover_address_from_prehash(&signed_message.hash)
+        .map_err(|e| FacilitatorLocalError::InvalidSignature(payer.into(), format!("{e}")))?;
+    if recovered != signed_message.address {
+        return Err(FacilitatorLocalError::InvalidSignature(
+            payer.into(),
+            format!("Signature recovers to {recovered}"),
+        ));
+    }
+    Ok(payer)
+}
+
+/// Parses an ECDSA signature: 65 bytes with any usual `v` encoding, or 64-byte EIP-2098 compact form.
+fn ecdsa_signature(bytes: &[u8]) -> Option<Signature> {
+    match bytes.len() {
+        65 => Signature::from_raw(bytes).ok(),
+        64 => Some(Signature::from_erc2098(bytes)),
+        _ => None,
+    }
+}
+
+/// The payer of an EVM payment, recovered from its ECDSA signature without chain access.
 ///
-/// This function prepares the transaction builder with gas pricing adapted to the network's
-/// capabilities (EIP-1559 or legacy) and packages it together with signature metadata
-/// into a [`TransferWithAuthorization0Call`] structure.
+/// The EIP-712 domain is resolved as in verification, as far as it can be without calling the token,
+/// see [`offline_domain`], with the `verifyingContract` setting of the tokens of the network served.
+/// Returns `None` if the domain is unknown, the signature is a contract wallet's, or it does not
+/// recover to the authorization's `from`.
+pub fn recover_payer(request: &VerifyRequest) -> Option<EvmAddress> {
+    let ExactPaymentPayload::Evm(payment_payload) = &request.payment_payload.payload else {
+        return None;
+    };
+    let authorization = &payment_payload.authorization;
+    let requirements = &request.payment_requirements;
+    let chain = EvmChain::try_from(request.payment_payload.network).ok()?;
+    let asset: EvmAddress = requirements.asset.clone().try_into().ok()?;
+    let verifying_contract = NETWORK_TOKENS
+        .get(&chain.network)
+        .and_then(|tokens| tokens.verifying_contract(&requirements.asset));
+    let OfflineDomain::Resolved(domain) =
+        offline_domain(&chain, &asset.0, requirements, verifying_contract).ok()?
+    else {
+        return None;
+    };
+    let payment = ExactEvmPayment {
+        chain,
+        from: authorization.from,
+        to: authorization.to,
+        value: authorization.value,
+        valid_after: authorization.valid_after,
+        valid_before: authorization.valid_before,
+        nonce: authorization.nonce,
+        signature: payment_payload.signature.clone(),
+        kind: requirements.authorization_kind.unwrap_or_default(),
+    };
+    let signed_message = SignedMessage::extract(&payment, &domain).ok()?;
+    let StructuredSignature::EIP1271(signature) = &signed_message.signature else {
+        return None;
+    };
+    let recovered = ecdsa_signature(signature)?
+        .recover_address_from_prehash(&signed_message.hash)
+        .ok()?;
+    (recovered == signed_message.address).then_some(authorization.from)
+}
+
+/// Rejects an ECDSA signature whose `s` is in the upper half of the curve order.
+///
+/// For every signature `(r, s)`, `(r, n - s)` with the other parity recovers to the same signer, so accepting
+/// both would let anyone derive a second valid-looking signature for an authorization. Only low-`s` signatures
+/// are canonical, as required by EIP-2. Bytes that are not an ECDSA signature are left to the signer to check.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidSignature`] for a high-`s` signature.
+fn assert_low_s(signer: EvmAddress, signature: &[u8]) -> Result<(), FacilitatorLocalError> {
+    let is_high_s =
+        ecdsa_signature(signature).is_some_and(|signature| signature.normalize_s().is_some());
+    if is_high_s {
+        return Err(FacilitatorLocalError::InvalidSignature(
+            signer.into(),
+            "Malleable signature: s is in the upper half of the curve order".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Recovers the signer of an EIP-191 message or EIP-712 typed data, see `POST /recover`.
+///
+/// ECDSA recovery needs no chain access. If the request names a `signer` that the signature does not
+/// recover to, it is checked as an EIP-1271 contract wallet with `isValidSignature` on `network`,
+/// using that network's `RPC_URL_*`.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::DecodingError`] if the request does not carry exactly one of
+/// `message` and `typedData`, or the typed data can not be hashed.
+/// Returns [`FacilitatorLocalError::UnsupportedNetwork`] if an EIP-1271 check is needed on a network
+/// without a configured RPC URL, and [`FacilitatorLocalError::ContractCall`] if the check fails.
+pub async fn recover(request: &RecoverRequest) -> Result<RecoverResponse, FacilitatorLocalError> {
+    let hash = match (&request.message, &request.typed_data) {
+        (Some(message), None) => alloy::primitives::eip191_hash_message(message),
+        (None, Some(typed_data)) => typed_data
+            .eip712_signing_hash()
+            .map_err(|e| FacilitatorLocalError::DecodingError(format!("{e}")))?,
+        _ => {
+            return Err(FacilitatorLocalError::DecodingError(
+                "expected exactly one of message and typedData".to_string(),
+            ));
+        }
+    };
+    let recovered = ecdsa_signature(&request.signature.0)
+        .and_then(|signature| signature.recover_address_from_prehash(&hash).ok())
+        .map(EvmAddress);
+    let valid = match request.signer {
+        None => None,
+        Some(signer) if recovered == Some(signer) => Some(true),
+        Some(signer) => {
+            let network = request
+                .network
+                .ok_or(FacilitatorLocalError::UnsupportedNetwork(None))?;
+            let provider =
+                recover_provider(network).ok_or(FacilitatorLocalError::UnsupportedNetwork(None))?;
+            let magic_value = IERC1271::new(signer.0, provider)
+                .isValidSignature(hash, request.signature.0.clone().into())
+                .call()
+                .into_future()
+                .instrument(tracing::info_span!(
+                    "call_isValidSignature",
+                    signer = %signer,
+                    otel.kind = "client"
+                ))
+                .await;
+            // Reverts and non-contract signers mean the signature is not valid for `signer`.
+            Some(magic_value.is_ok_and(|magic_value| magic_value == EIP1271_MAGIC_VALUE))
+        }
+    };
+    Ok(RecoverResponse {
+        hash,
+        recovered,
+        valid,
+    })
+}
+
+/// Constructs the `transferWithAuthorization` or `receiveWithAuthorization` call, by the payment's kind,
+/// for a verified payment payload, packaged with signature metadata into an [`AuthorizationCall`].
 ///
 /// This function does not perform any validation — it assumes inputs are already checked.
-#[allow(non_snake_case)]
-async fn transferWithAuthorization_0<'a, P: Provider>(
-    contract: &'a USDC::USDCInstance<P>,
+fn authorization_call(
+    contract_address: Address,
     payment: &ExactEvmPayment,
     signature: Bytes,
-) -> Result<TransferWithAuthorization0Call<&'a P>, FacilitatorLocalError> {
+) -> AuthorizationCall {
     let from: Address = payment.from.into();
     let to: Address = payment.to.into();
     let value: U256 = payment.value.into();
     let valid_after: U256 = payment.valid_after.into();
     let valid_before: U256 = payment.valid_before.into();
     let nonce = FixedBytes(payment.nonce.0);
-    let tx = contract.transferWithAuthorization_0(
-        from,
-        to,
-        value,
-        valid_after,
-        valid_before,
-        nonce,
-        signature.clone(),
-    );
-    Ok(TransferWithAuthorization0Call {
-        tx,
+    let (calldata, sender) = match payment.kind {
+        AuthorizationKind::Transfer => {
+            let call = USDC::transferWithAuthorization_0Call {
+                from,
+                to,
+                value,
+                validAfter: valid_after,
+                validBefore: valid_before,
+                nonce,
+                signature: signature.clone(),
+            };
+            (call.abi_encode().into(), None)
+        }
+        AuthorizationKind::Receive => {
+            let call = USDC::receiveWithAuthorization_0Call {
+                from,
+                to,
+                value,
+                validAfter: valid_after,
+                validBefore: valid_before,
+                nonce,
+                signature: signature.clone(),
+            };
+            (call.abi_encode().into(), Some(to))
+        }
+    };
+    AuthorizationCall {
+        calldata,
+        sender,
+        kind: payment.kind,
         from,
         to,
         value,
@@ -1003,8 +4082,52 @@ async fn transferWithAuthorization_0<'a, P: Provider>(
         valid_before,
         nonce,
         signature,
-        contract_address: *contract.address(),
-    })
+        contract_address,
+    }
+}
+
+/// Simulates `call` with `eth_call`, as settlement would send it: from the signer it must be sent from,
+/// if any, otherwise from `signer`, a facilitator signer. Checks that depend on the sender, such as a
+/// token blocklist, are therefore covered by verification.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::Reverted`] with the payer and the decoded revert reason if the call reverts.
+async fn simulate_authorization_call<P: Provider>(
+    provider: P,
+    call: &AuthorizationCall,
+    signer: Option<Address>,
+    sig_kind: &'static str,
+) -> Result<(), FacilitatorLocalError> {
+    provider
+        .call(simulation_request(call, signer))
+        .into_future()
+        .instrument(call.span(sig_kind))
+        .await
+        .map_err(|e| FacilitatorLocalError::contract_call(e).with_payer(call.from.into()))?;
+    Ok(())
+}
+
+/// The `eth_call` of [`simulate_authorization_call`].
+fn simulation_request(call: &AuthorizationCall, signer: Option<Address>) -> TransactionRequest {
+    let mut tx = TransactionRequest::default()
+        .with_to(call.contract_address)
+        .with_input(call.calldata.clone());
+    if let Some(sender) = call.sender.or(signer) {
+        tx.set_from(sender);
+    }
+    tx
+}
+
+/// The error for a `receiveWithAuthorization` payment from the undeployed EIP-6492 wallet `payer`.
+///
+/// A counterfactual wallet is deployed in the same Multicall3 batch as its transfer, but a
+/// `receiveWithAuthorization` must be sent by its receiver, not by Multicall3.
+fn counterfactual_receive(payer: Address) -> FacilitatorLocalError {
+    FacilitatorLocalError::InvalidSignature(
+        payer.into(),
+        "receiveWithAuthorization needs a deployed wallet, not a counterfactual EIP-6492 one"
+            .to_string(),
+    )
 }
 
 /// A structured representation of an Ethereum signature.
@@ -1048,13 +4171,13 @@ impl SignedMessage {
     /// corresponding [`Eip712Domain`].
     ///
     /// This helper ties together:
-    /// - The **payment intent** (an ERC-3009 `TransferWithAuthorization` struct),
+    /// - The **payment intent** (an ERC-3009 `TransferWithAuthorization` or `ReceiveWithAuthorization` struct),
     /// - The **EIP-712 domain** used for signing,
     /// - And the raw signature bytes attached to the payment.
     ///
     /// Steps performed:
-    /// 1. Build an in-memory [`TransferWithAuthorization`] struct from the
-    ///    `ExactEvmPayment` fields (`from`, `to`, `value`, validity window, `nonce`).
+    /// 1. Build an in-memory [`TransferWithAuthorization`] or [`ReceiveWithAuthorization`] struct, by the
+    ///    payment's kind, from the `ExactEvmPayment` fields (`from`, `to`, `value`, validity window, `nonce`).
     /// 2. Compute the **EIP-712 struct hash** for that transfer under the given
     ///    `domain`. This becomes the `hash` field of the signed message.
     /// 3. Parse the raw signature bytes into a [`StructuredSignature`], which
@@ -1067,21 +4190,37 @@ impl SignedMessage {
     ///
     /// Returns [`FacilitatorLocalError`] if:
     /// - The raw signature cannot be decoded as either EIP-1271 or EIP-6492.
+    /// - The signature, or the inner signature of an EIP-6492 wrapper, is a malleable high-`s` ECDSA signature.
     pub fn extract(
         payment: &ExactEvmPayment,
         domain: &Eip712Domain,
     ) -> Result<Self, FacilitatorLocalError> {
-        let transfer_with_authorization = TransferWithAuthorization {
-            from: payment.from.0,
-            to: payment.to.0,
-            value: payment.value.into(),
-            validAfter: payment.valid_after.into(),
-            validBefore: payment.valid_before.into(),
-            nonce: FixedBytes(payment.nonce.0),
+        let eip712_hash = match payment.kind {
+            AuthorizationKind::Transfer => TransferWithAuthorization {
+                from: payment.from.0,
+                to: payment.to.0,
+                value: payment.value.into(),
+                validAfter: payment.valid_after.into(),
+                validBefore: payment.valid_before.into(),
+                nonce: FixedBytes(payment.nonce.0),
+            }
+            .eip712_signing_hash(domain),
+            AuthorizationKind::Receive => ReceiveWithAuthorization {
+                from: payment.from.0,
+                to: payment.to.0,
+                value: payment.value.into(),
+                validAfter: payment.valid_after.into(),
+                validBefore: payment.valid_before.into(),
+                nonce: FixedBytes(payment.nonce.0),
+            }
+            .eip712_signing_hash(domain),
         };
-        let eip712_hash = transfer_with_authorization.eip712_signing_hash(domain);
         let expected_address = payment.from;
         let structured_signature: StructuredSignature = payment.signature.clone().try_into()?;
+        match &structured_signature {
+            StructuredSignature::EIP1271(signature) => assert_low_s(expected_address, signature)?,
+            StructuredSignature::EIP6492 { inner, .. } => assert_low_s(expected_address, inner)?,
+        }
         let signed_message = Self {
             address: expected_address.into(),
             hash: eip712_hash,
@@ -1233,9 +4372,1456 @@ impl PendingNonceManager {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use alloy::primitives::address;
+    use alloy::signers::SignerSync;
+    use alloy::signers::local::PrivateKeySigner;
+
+    use crate::types::{
+        Eip712DomainParams, ExactEvmPayload, ExactEvmPayloadAuthorization, PaymentSplit,
+    };
+
+    /// A request paying 1.5 USDC on Base Sepolia from `signer_of_record`, signed by `signer`.
+    pub(crate) fn offline_request(
+        signer: &PrivateKeySigner,
+        signer_of_record: Address,
+    ) -> OfflineVerifyRequest {
+        let asset = USDCDeployment::by_network(Network::BaseSepolia).address();
+        let asset_address: EvmAddress = asset.clone().try_into().unwrap();
+        let pay_to = EvmAddress(address!("0000000000000000000000000000000000000402"));
+        let now = UnixTimestamp::try_now().unwrap();
+        let authorization = ExactEvmPayloadAuthorization {
+            from: EvmAddress(signer_of_record),
+            to: pay_to,
+            value: TokenAmount(U256::from(1_500_000u64)),
+            valid_after: UnixTimestamp(now.0 - 60),
+            valid_before: UnixTimestamp(now.0 + 600),
+            nonce: HexEncodedNonce([7u8; 32]),
+        };
+        let domain = Eip712DomainParams {
+            name: "USDC".to_string(),
+            version: "2".to_string(),
+            chain_id: 84532,
+            verifying_contract: asset_address,
+        };
+        let hash = TransferWithAuthorization {
+            from: authorization.from.0,
+            to: authorization.to.0,
+            value: authorization.value.into(),
+            validAfter: authorization.valid_after.into(),
+            validBefore: authorization.valid_before.into(),
+            nonce: FixedBytes(authorization.nonce.0),
+        }
+        .eip712_signing_hash(&eip712_domain! {
+            name: domain.name.clone(),
+            version: domain.version.clone(),
+            chain_id: domain.chain_id,
+            verifying_contract: asset_address.0,
+        });
+        let signature = signer.sign_hash_sync(&hash).unwrap().as_bytes();
+        OfflineVerifyRequest {
+            x402_version: X402Version::V1,
+            payment_payload: PaymentPayload {
+                x402_version: X402Version::V1,
+                scheme: Scheme::Exact,
+                network: Network::BaseSepolia,
+                payload: ExactPaymentPayload::Evm(ExactEvmPayload {
+                    signature: EvmSignature::from(signature),
+                    authorization,
+                }),
+                extensions: None,
+            },
+            payment_requirements: PaymentRequirements {
+                scheme: Scheme::Exact,
+                network: Network::BaseSepolia,
+                max_amount_required: TokenAmount(U256::from(1_000_000u64)),
+                resource: "https://example.com/paid".parse().unwrap(),
+                description: String::new(),
+                mime_type: "application/json".to_string(),
+                output_schema: None,
+                pay_to: pay_to.into(),
+                max_timeout_seconds: 60,
+                asset,
+                extra: None,
+                splits: None,
+                authorization_kind: None,
+            },
+            domain,
+            decimals: 6,
+        }
+    }
+
+    /// A provider that records the transactions it is asked to send instead of sending them,
+    /// and fails sends from `revert_from` on and finality when `reorged`.
+    struct ScriptedProvider {
+        inner: RootProvider,
+        chain: EvmChain,
+        tokens: TokenConfigs,
+        intermediary_lock: RwLock<()>,
+        verified_authorizations: VerifiedAuthorizations,
+        sent: std::sync::Mutex<Vec<(Address, Bytes, U256)>>,
+        revert_from: usize,
+        reorged: bool,
+        token_registry: Option<TokenRegistry>,
+        token_list: Option<TokenList>,
+    }
+
+    impl ScriptedProvider {
+        fn new() -> Self {
+            Self {
+                inner: RootProvider::new_http("http://127.0.0.1:1".parse().unwrap()),
+                chain: EvmChain::new(Network::BaseSepolia, 84532),
+                tokens: TokenConfigs::default(),
+                intermediary_lock: RwLock::new(()),
+                verified_authorizations: VerifiedAuthorizations::default(),
+                sent: std::sync::Mutex::new(Vec::new()),
+                revert_from: usize::MAX,
+                reorged: false,
+                token_registry: None,
+                token_list: None,
+            }
+        }
+
+        fn sent(&self) -> Vec<(Address, Bytes, U256)> {
+            self.sent.lock().unwrap().clone()
+        }
+    }
+
+    fn receipt(index: usize, status: bool) -> TransactionReceipt {
+        serde_json::from_value(serde_json::json!({
+            "transactionHash": B256::with_last_byte(index as u8),
+            "transactionIndex": "0x0",
+            "blockHash": B256::repeat_byte(1),
+            "blockNumber": "0x1",
+            "from": Address::ZERO,
+            "to": Address::ZERO,
+            "gasUsed": "0x5208",
+            "effectiveGasPrice": "0x1",
+            "cumulativeGasUsed": "0x5208",
+            "contractAddress": null,
+            "logs": [],
+            "logsBloom": alloy::primitives::Bloom::ZERO,
+            "type": "0x2",
+            "status": if status { "0x1" } else { "0x0" },
+        }))
+        .unwrap()
+    }
+
+    impl MetaEvmProvider for ScriptedProvider {
+        type Error = FacilitatorLocalError;
+        type Inner = RootProvider;
+
+        fn inner(&self) -> &Self::Inner {
+            &self.inner
+        }
+        fn chain(&self) -> &EvmChain {
+            &self.chain
+        }
+        fn tokens(&self) -> &TokenConfigs {
+            &self.tokens
+        }
+        fn signer_addresses(&self) -> &[Address] {
+            &[]
+        }
+        fn intermediary_lock(&self) -> &RwLock<()> {
+            &self.intermediary_lock
+        }
+        fn nonce_filter(&self) -> Option<&NonceFilter> {
+            None
+        }
+        fn nonce_store(&self) -> Option<&dyn NonceStore> {
+            None
+        }
+        fn nonce_rules(&self) -> Option<&NonceRules> {
+            None
+        }
+        fn token_registry(&self) -> Option<&TokenRegistry> {
+            self.token_registry.as_ref()
+        }
+        fn token_list(&self) -> Option<&TokenList> {
+            self.token_list.as_ref()
+        }
+        fn memo_tag(&self) -> Option<&Bytes> {
+            None
+        }
+        fn verified_authorizations(&self) -> &VerifiedAuthorizations {
+            &self.verified_authorizations
+        }
+
+        async fn send_transaction(
+            &self,
+            tx: MetaTransaction,
+        ) -> Result<TransactionReceipt, Self::Error> {
+            let mut sent = self.sent.lock().unwrap();
+            sent.push((tx.to, tx.calldata, tx.value));
+            Ok(receipt(sent.len(), sent.len() <= self.revert_from))
+        }
+
+        async fn await_finality(&self, receipt: &TransactionReceipt) -> Result<(), Self::Error> {
+            if self.reorged {
+                return Err(FacilitatorLocalError::Reorged(format!(
+                    "transaction {} orphaned by reorg before finality",
+                    receipt.transaction_hash
+                )));
+            }
+            Ok(())
+        }
+    }
+
+    fn intermediary_payment(value: u64) -> ExactEvmPayment {
+        ExactEvmPayment {
+            chain: EvmChain::new(Network::BaseSepolia, 84532),
+            from: EvmAddress(Address::repeat_byte(0xaa)),
+            to: EvmAddress(Address::repeat_byte(0xfa)),
+            value: TokenAmount(U256::from(value)),
+            valid_after: UnixTimestamp(0),
+            valid_before: UnixTimestamp(u64::MAX),
+            nonce: HexEncodedNonce([7u8; 32]),
+            signature: EvmSignature(Vec::new()),
+            kind: AuthorizationKind::Receive,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfer_is_simulated_as_settlement_sends_it() {
+        use alloy::sol_types::SolError;
+
+        let token = Address::repeat_byte(1);
+        let signer = Address::repeat_byte(0x5e);
+        let receive = intermediary_payment(1);
+        let mut transfer = intermediary_payment(1);
+        transfer.kind = AuthorizationKind::Transfer;
+        let transfer_call = authorization_call(token, &transfer, Bytes::new());
+        assert_eq!(
+            simulation_request(&transfer_call, Some(signer)).from,
+            Some(signer)
+        );
+        assert_eq!(simulation_request(&transfer_call, None).from, None);
+        // Only the receiver may send a `receiveWithAuthorization`.
+        let receive_call = authorization_call(token, &receive, Bytes::new());
+        assert_eq!(
+            simulation_request(&receive_call, Some(signer)).from,
+            Some(receive.to.0)
+        );
+
+        let asserter = alloy::providers::mock::Asserter::new();
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .connect_mocked_client(asserter.clone());
+        let revert = alloy::sol_types::Revert::from("Blacklistable: account is blacklisted");
+        asserter.push_failure(alloy::rpc::json_rpc::ErrorPayload {
+            code: 3,
+            message: "execution reverted".into(),
+            data: Some(serde_json::value::to_raw_value(&Bytes::from(revert.abi_encode())).unwrap()),
+        });
+        let result =
+            simulate_authorization_call(&provider, &transfer_call, Some(signer), "EIP1271").await;
+        assert!(matches!(
+            result,
+            Err(FacilitatorLocalError::Reverted(Some(payer), reason))
+                if payer == transfer.from.into() && reason.contains("blacklisted")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_gas_limit_floor_raises_but_never_lowers_the_estimate() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .connect_mocked_client(asserter.clone());
+        let mut txr = TransactionRequest::default();
+        apply_gas_floor(&provider, &mut txr, None).await.unwrap();
+        assert_eq!(txr.gas, None);
+
+        asserter.push_success(&alloy::primitives::U64::from(21_000));
+        apply_gas_floor(&provider, &mut txr, Some(250_000))
+            .await
+            .unwrap();
+        assert_eq!(txr.gas, Some(250_000));
+        asserter.push_success(&alloy::primitives::U64::from(300_000));
+        apply_gas_floor(&provider, &mut txr, Some(250_000))
+            .await
+            .unwrap();
+        assert_eq!(txr.gas, Some(300_000));
+    }
+
+    #[tokio::test]
+    async fn test_orphaned_receipts_are_not_canonical_until_re_included() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .connect_mocked_client(asserter.clone());
+        let settled = receipt(1, true);
+        asserter.push_success(&settled);
+        assert!(is_canonical(&provider, &settled).await.unwrap());
+        let mut reorged = settled.clone();
+        reorged.block_hash = Some(B256::repeat_byte(2));
+        asserter.push_success(&reorged);
+        assert!(!is_canonical(&provider, &settled).await.unwrap());
+        asserter.push_success(&None::<TransactionReceipt>);
+        assert!(!is_canonical(&provider, &settled).await.unwrap());
+
+        let orphaned = [B256::with_last_byte(1), B256::with_last_byte(2)];
+        asserter.push_success(&None::<TransactionReceipt>);
+        asserter.push_success(&None::<TransactionReceipt>);
+        let found = find_canonical(&provider, &orphaned).await.unwrap();
+        assert!(found.is_none());
+        asserter.push_success(&None::<TransactionReceipt>);
+        asserter.push_success(&receipt(2, true));
+        let resurfaced = find_canonical(&provider, &orphaned).await.unwrap().unwrap();
+        assert_eq!(resurfaced.transaction_hash, B256::with_last_byte(2));
+    }
+
+    #[tokio::test]
+    async fn test_upgraded_token_implementations_are_rejected_or_logged() {
+        let implementation = Address::repeat_byte(0x1e);
+        let mut requirements = requirements();
+        requirements.extra = Some(serde_json::json!({
+            "tokenImplementation": implementation.to_string(),
+        }));
+        let asserter = alloy::providers::mock::Asserter::new();
+        let with_check = |check: &str| ScriptedProvider {
+            inner: RootProvider::new(alloy::rpc::client::RpcClient::mocked(asserter.clone())),
+            tokens: serde_json::from_value::<Vec<crate::tokens::TokenConfig>>(serde_json::json!([{
+                "address": requirements.asset,
+                "implementationCheck": check,
+            }]))
+            .unwrap()
+            .into_iter()
+            .collect(),
+            ..ScriptedProvider::new()
+        };
+        let payment = intermediary_payment(1);
+        let slot = |address: Address| U256::from_be_bytes(address.into_word().0);
+
+        let rejecting = with_check("reject");
+        let contract = USDC::new(Address::repeat_byte(1), rejecting.inner());
+        asserter.push_success(&slot(implementation));
+        assert!(
+            assert_same_implementation(&rejecting, &contract, &payment, &requirements)
+                .await
+                .is_ok()
+        );
+        asserter.push_success(&slot(Address::repeat_byte(0x2e)));
+        assert!(matches!(
+            assert_same_implementation(&rejecting, &contract, &payment, &requirements).await,
+            Err(FacilitatorLocalError::ImplementationChanged(..))
+        ));
+
+        let warning = with_check("warn");
+        let contract = USDC::new(Address::repeat_byte(1), warning.inner());
+        asserter.push_success(&slot(Address::repeat_byte(0x2e)));
+        assert!(
+            assert_same_implementation(&warning, &contract, &payment, &requirements)
+                .await
+                .is_ok()
+        );
+        // Requirements that do not pin an implementation are not checked.
+        requirements.extra = None;
+        assert!(
+            assert_same_implementation(&rejecting, &contract, &payment, &requirements)
+                .await
+                .is_ok()
+        );
+        assert!(asserter.read_q().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_supported_lists_only_readable_and_approved_tokens() {
+        let [approved, unreadable, unapproved] = [1, 2, 3].map(Address::repeat_byte);
+        let asserter = alloy::providers::mock::Asserter::new();
+        let provider = ScriptedProvider {
+            inner: RootProvider::new(alloy::rpc::client::RpcClient::mocked(asserter.clone())),
+            token_registry: Some(TokenRegistry::new(
+                Address::repeat_byte(0xee),
+                Duration::from_secs(60),
+            )),
+            token_list: Some(TokenList::new(
+                Address::repeat_byte(0xdd),
+                Duration::from_secs(60),
+            )),
+            ..ScriptedProvider::new()
+        };
+        let word = |value: u64| Bytes::from(U256::from(value).to_be_bytes::<32>());
+        let listed = vec![approved, unreadable, unapproved];
+        asserter.push_success(&Bytes::from(
+            crate::chain::token_list::ITokenList::getTokensCall::abi_encode_returns(&listed),
+        ));
+        asserter.push_success(&word(6));
+        asserter.push_failure_msg("execution reverted");
+        asserter.push_success(&word(18));
+        asserter.push_success(&word(1));
+        asserter.push_success(&word(0));
+
+        let supported = provider.supported().await.unwrap();
+        let exact = supported
+            .kinds
+            .iter()
+            .find(|kind| kind.scheme == Scheme::Exact)
+            .unwrap();
+        let assets = exact.assets.as_ref().unwrap();
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].address, EvmAddress(approved).into());
+        assert_eq!(assets[0].decimals, 6);
+        assert!(asserter.read_q().is_empty());
+    }
+
+    #[test]
+    fn test_settlements_about_to_expire_are_not_broadcast() {
+        let payer: MixedAddress = EvmAddress(Address::repeat_byte(0xaa)).into();
+        let now = UnixTimestamp::try_now().unwrap();
+        let soon = UnixTimestamp(now.0 + 30);
+        assert!(assert_settle_deadline(payer.clone(), soon, 0).is_ok());
+
+        assert!(matches!(
+            assert_settle_deadline(payer.clone(), soon, 60),
+            Err(FacilitatorLocalError::InvalidTiming(..))
+        ));
+        let later = UnixTimestamp(now.0 + 120);
+        assert!(assert_settle_deadline(payer, later, 60).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_delegated_eoas_are_checked_with_ecrecover() {
+        let delegation = [
+            EIP7702_DELEGATION_PREFIX.as_slice(),
+            Address::repeat_byte(0xde).as_slice(),
+        ]
+        .concat();
+        assert!(is_eoa_code(&[]));
+        assert!(is_eoa_code(&delegation));
+        assert!(!is_eoa_code(&delegation[..22]));
+        assert!(!is_eoa_code(&[0x60, 0x80, 0x60, 0x40]));
+
+        let signer = PrivateKeySigner::random();
+        let hash = FixedBytes::repeat_byte(9);
+        let signature = Bytes::from(signer.sign_hash_sync(&hash).unwrap().as_bytes());
+        let asserter = alloy::providers::mock::Asserter::new();
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .connect_mocked_client(asserter.clone());
+        asserter.push_success(&Bytes::from(delegation.clone()));
+        assert!(
+            assert_signature_by_signer_kind(&provider, signer.address(), hash, &signature)
+                .await
+                .is_ok()
+        );
+        asserter.push_success(&Bytes::from(delegation));
+        let other = PrivateKeySigner::random().address();
+        assert!(matches!(
+            assert_signature_by_signer_kind(&provider, other, hash, &signature).await,
+            Err(FacilitatorLocalError::InvalidSignature(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_provider_without_signers_only_verifies() {
+        let provider = EvmProvider::try_new(
+            EthereumWallet::default(),
+            "http://127.0.0.1:1",
+            true,
+            Network::BaseSepolia,
+        )
+        .await
+        .unwrap();
+        assert!(provider.signer_address().is_none());
+        assert!(provider.signer_addresses().is_empty());
+        let sent = provider
+            .send_transaction(MetaTransaction {
+                to: Address::repeat_byte(1),
+                calldata: Bytes::new(),
+                value: U256::ZERO,
+                from: None,
+                gas_limit: None,
+                confirmations: 1,
+                rebroadcast_on_reorg: false,
+            })
+            .await;
+        assert!(matches!(
+            sent,
+            Err(FacilitatorLocalError::SettlementDisabled)
+        ));
+
+        // No signer could receive a payment that must be made out to one, whatever it names.
+        let request = offline_request(&PrivateKeySigner::random(), Address::repeat_byte(0xaa));
+        let mut payment_requirements = request.payment_requirements;
+        payment_requirements.authorization_kind = Some(AuthorizationKind::Receive);
+        let verified = provider
+            .verify(&VerifyRequest {
+                x402_version: X402Version::V1,
+                payment_payload: request.payment_payload,
+                payment_requirements,
+            })
+            .await;
+        assert!(matches!(
+            verified,
+            Err(FacilitatorLocalError::ReceiverMismatch(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_payment_is_forwarded_before_finality_fails() {
+        let provider = ScriptedProvider {
+            reorged: true,
+            ..ScriptedProvider::new()
+        };
+        let token = Address::repeat_byte(1);
+        let payment = intermediary_payment(100);
+        let splits = [
+            (Address::repeat_byte(2), U256::from(60)),
+            (Address::repeat_byte(3), U256::from(40)),
+        ];
+        let in_transit = provider.intermediary_lock().read().await;
+        let legs = forward_legs_of(
+            &payment,
+            payment.value.into(),
+            token,
+            &requirements(),
+            &splits,
+            false,
+        )
+        .unwrap();
+        let forward = forward_legs(&provider, payment.to.0, &legs);
+        let result =
+            forward_then_finalize(&provider, &receipt(0, true), Some(in_transit), forward).await;
+
+        assert!(matches!(result, Err(FacilitatorLocalError::Reorged(_))));
+        let sent = provider.sent();
+        assert_eq!(sent.len(), 2);
+        assert!(sent.iter().all(|(to, _, _)| *to == token));
+        // Sweeps are not held off while waiting for finality.
+        assert!(provider.intermediary_lock().try_write().is_ok());
+    }
+
+    fn requirements() -> PaymentRequirements {
+        offline_request(&PrivateKeySigner::random(), Address::ZERO).payment_requirements
+    }
+
+    #[test]
+    fn test_forward_legs_of_each_kind_of_payment() {
+        let token = Address::repeat_byte(1);
+        let pay_to: EvmAddress = requirements().pay_to.try_into().unwrap();
+        let transfer = |to: Address, amount: u64| ForwardLeg::Transfer {
+            token,
+            to,
+            amount: U256::from(amount),
+        };
+        let mut payment = intermediary_payment(100);
+        let splits = [
+            (Address::repeat_byte(2), U256::from(60)),
+            (Address::repeat_byte(3), U256::from(40)),
+        ];
+        assert_eq!(
+            forward_legs_of(
+                &payment,
+                payment.value.into(),
+                token,
+                &requirements(),
+                &splits,
+                false
+            )
+            .unwrap(),
+            vec![
+                transfer(Address::repeat_byte(2), 60),
+                transfer(Address::repeat_byte(3), 40)
+            ]
+        );
+        assert_eq!(
+            forward_legs_of(
+                &payment,
+                payment.value.into(),
+                token,
+                &requirements(),
+                &[],
+                false
+            )
+            .unwrap(),
+            vec![transfer(pay_to.0, 100)]
+        );
+        assert_eq!(
+            forward_legs_of(
+                &payment,
+                payment.value.into(),
+                token,
+                &requirements(),
+                &[],
+                true
+            )
+            .unwrap(),
+            vec![
+                ForwardLeg::Unwrap {
+                    token,
+                    amount: U256::from(100)
+                },
+                ForwardLeg::Native {
+                    to: pay_to.0,
+                    amount: U256::from(100)
+                },
+            ]
+        );
+        payment.kind = AuthorizationKind::Transfer;
+        assert!(
+            forward_legs_of(
+                &payment,
+                payment.value.into(),
+                token,
+                &requirements(),
+                &[],
+                false
+            )
+            .unwrap()
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_forward_legs_of_a_fee_on_transfer_payment_forward_what_was_received() {
+        let token = Address::repeat_byte(1);
+        let pay_to: EvmAddress = requirements().pay_to.try_into().unwrap();
+        let transfer = |to: Address, amount: u64| ForwardLeg::Transfer {
+            token,
+            to,
+            amount: U256::from(amount),
+        };
+        // 1% of a 1000 payment is taken on the way to the intermediary.
+        let payment = intermediary_payment(1000);
+        let received = U256::from(990);
+        assert_eq!(
+            forward_legs_of(&payment, received, token, &requirements(), &[], false).unwrap(),
+            vec![transfer(pay_to.0, 990)]
+        );
+        let splits = [
+            (Address::repeat_byte(2), U256::from(333)),
+            (Address::repeat_byte(3), U256::from(667)),
+        ];
+        assert_eq!(
+            forward_legs_of(&payment, received, token, &requirements(), &splits, false).unwrap(),
+            vec![
+                transfer(Address::repeat_byte(2), 330),
+                transfer(Address::repeat_byte(3), 660)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_partly_failed_forward_is_retried_from_the_failed_leg() {
+        let token = Address::repeat_byte(1);
+        let payment = intermediary_payment(100);
+        let splits = [
+            (Address::repeat_byte(2), U256::from(50)),
+            (Address::repeat_byte(3), U256::from(30)),
+            (Address::repeat_byte(4), U256::from(20)),
+        ];
+        let legs = forward_legs_of(
+            &payment,
+            payment.value.into(),
+            token,
+            &requirements(),
+            &splits,
+            false,
+        )
+        .unwrap();
+        let provider = ScriptedProvider {
+            revert_from: 1,
+            ..ScriptedProvider::new()
+        };
+        let Err(failure) = forward_legs(&provider, payment.to.0, &legs).await else {
+            panic!("the second leg reverts");
+        };
+        assert_eq!(failure.receipts.len(), 1);
+        assert_eq!(failure.remaining, legs[1..]);
+        assert_eq!(failure.in_flight, None);
+
+        let pending = PendingForward {
+            network: Network::BaseSepolia,
+            settlement: TxHash::repeat_byte(9),
+            intermediary: payment.to.0,
+            legs: failure.remaining,
+            in_flight: failure.in_flight,
+            updated_at: UnixTimestamp(0),
+        };
+        let provider = ScriptedProvider::new();
+        let remaining = retry_forward(&provider, pending).await;
+        assert!(remaining.legs.is_empty());
+        // Only the legs left are sent again.
+        assert_eq!(provider.sent().len(), 2);
+    }
+
+    #[test]
+    fn test_settlement_cost_adds_usd_and_the_facilitator_fee() {
+        let receipts = [receipt(0, true), receipt(1, true)];
+        let cost = cost_breakdown(&receipts, Some(U256::from(10)), Some(2000.0));
+        assert_eq!(cost.gas_used, 42_000);
+        assert_eq!(cost.effective_gas_price, TokenAmount::from(1u64));
+        assert_eq!(cost.total_cost, TokenAmount::from(42_000u64));
+        let usd = cost.total_cost_usd.unwrap();
+        assert!((usd - 42_000.0 / 1e18 * 2000.0).abs() < 1e-20);
+        assert_eq!(cost.facilitator_fee, Some(TokenAmount::from(10u64)));
+
+        let cost = cost_breakdown(&receipts, None, None);
+        assert!(cost.total_cost_usd.is_none() && cost.facilitator_fee.is_none());
+        // Left out unless enabled.
+        assert!(settlement_cost(Network::Optimism, &receipts, None).is_none());
+
+        let token = Address::repeat_byte(1);
+        let transfer = |amount: u64| ForwardLeg::Transfer {
+            token,
+            to: Address::repeat_byte(2),
+            amount: U256::from(amount),
+        };
+        let legs = [transfer(60), transfer(30)];
+        assert_eq!(
+            facilitator_fee(token, U256::from(100), &legs),
+            U256::from(10)
+        );
+        let unwrap = [
+            ForwardLeg::Unwrap {
+                token,
+                amount: U256::from(100),
+            },
+            ForwardLeg::Native {
+                to: Address::repeat_byte(2),
+                amount: U256::from(100),
+            },
+        ];
+        assert_eq!(facilitator_fee(token, U256::from(100), &unwrap), U256::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_unwrapped_payment_whose_native_transfer_fails_keeps_that_leg_pending() {
+        let token = Address::repeat_byte(1);
+        let payment = intermediary_payment(100);
+        let legs = forward_legs_of(
+            &payment,
+            payment.value.into(),
+            token,
+            &requirements(),
+            &[],
+            true,
+        )
+        .unwrap();
+        let provider = ScriptedProvider {
+            revert_from: 1,
+            ..ScriptedProvider::new()
+        };
+        let Err(failure) = forward_legs(&provider, payment.to.0, &legs).await else {
+            panic!("the native transfer reverts");
+        };
+        // The unwrap went through: only the native transfer is left, and no wrapped tokens are reserved.
+        assert_eq!(failure.receipts.len(), 1);
+        assert_eq!(failure.remaining, legs[1..]);
+        assert!(matches!(failure.remaining[0], ForwardLeg::Native { .. }));
+        let sent = provider.sent();
+        assert_eq!(sent[0].0, token);
+        assert_eq!(sent[1].2, U256::from(100));
+
+        let pending = PendingForward {
+            network: Network::BaseSepolia,
+            settlement: TxHash::repeat_byte(9),
+            intermediary: payment.to.0,
+            legs: failure.remaining,
+            in_flight: failure.in_flight,
+            updated_at: UnixTimestamp(0),
+        };
+        let forwards = forwarding::PendingForwards::default();
+        forwards.record(pending.clone()).await;
+        assert_eq!(
+            forwards.reserved(Network::BaseSepolia, payment.to.0, token),
+            U256::ZERO
+        );
+        let provider = ScriptedProvider::new();
+        let remaining = retry_forward(&provider, pending).await;
+        assert!(remaining.legs.is_empty());
+        let sent = provider.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].2, U256::from(100));
+    }
+
+    #[tokio::test]
+    async fn test_failed_receive_forward_is_kept_for_retry() {
+        let token = Address::repeat_byte(1);
+        let payment = intermediary_payment(100);
+        let legs = forward_legs_of(
+            &payment,
+            payment.value.into(),
+            token,
+            &requirements(),
+            &[],
+            false,
+        )
+        .unwrap();
+        let provider = ScriptedProvider {
+            revert_from: 0,
+            ..ScriptedProvider::new()
+        };
+        let Err(failure) = forward_legs(&provider, payment.to.0, &legs).await else {
+            panic!("the forward reverts");
+        };
+        // The whole payment is still to be forwarded to `payTo`.
+        assert!(failure.receipts.is_empty());
+        assert_eq!(failure.remaining, legs);
+        let forwards = forwarding::PendingForwards::default();
+        forwards
+            .record(PendingForward {
+                network: Network::BaseSepolia,
+                settlement: TxHash::repeat_byte(9),
+                intermediary: payment.to.0,
+                legs: failure.remaining,
+                in_flight: failure.in_flight,
+                updated_at: UnixTimestamp(0),
+            })
+            .await;
+        assert_eq!(
+            forwards.reserved(Network::BaseSepolia, payment.to.0, token),
+            U256::from(100)
+        );
+    }
+
+    #[test]
+    fn test_only_definitive_erc5267_failures_are_cached() {
+        use alloy::rpc::json_rpc::ErrorPayload;
+        use alloy::transports::{RpcError, TransportErrorKind};
+
+        let reverted = alloy::contract::Error::TransportError(RpcError::ErrorResp(ErrorPayload {
+            code: 3,
+            message: "execution reverted".into(),
+            data: Some(serde_json::value::to_raw_value(&Bytes::new()).unwrap()),
+        }));
+        assert!(lacks_erc5267(&reverted));
+        let empty = alloy::contract::Error::ZeroData(
+            "eip712Domain".to_string(),
+            alloy::dyn_abi::Error::SolTypes(alloy::sol_types::Error::Overrun),
+        );
+        assert!(lacks_erc5267(&empty));
+
+        let rate_limited =
+            alloy::contract::Error::TransportError(RpcError::ErrorResp(ErrorPayload {
+                code: 429,
+                message: "too many requests".into(),
+                data: None,
+            }));
+        assert!(!lacks_erc5267(&rate_limited));
+        let timeout = alloy::contract::Error::TransportError(TransportErrorKind::custom_str(
+            "request timed out",
+        ));
+        assert!(!lacks_erc5267(&timeout));
+    }
+
+    #[test]
+    fn test_erc5267_domain_matches_standard_domain() {
+        let token = address!("0x036CbD53842c5426634e7929541eC2318f3dCF7e");
+        let domain = domain_from_erc5267(
+            0x0f,
+            "USDC".to_string(),
+            "2".to_string(),
+            U256::from(84532u64),
+            token,
+            FixedBytes::ZERO,
+        );
+        let standard = eip712_domain! {
+            name: "USDC",
+            version: "2",
+            chain_id: 84532,
+            verifying_contract: token,
+        };
+        assert_eq!(domain.separator(), standard.separator());
+    }
+
+    #[test]
+    fn test_erc5267_domain_respects_fields() {
+        let token = address!("0x036CbD53842c5426634e7929541eC2318f3dCF7e");
+        let salt = FixedBytes([1u8; 32]);
+        // name, chainId, verifyingContract and salt; no version
+        let domain = domain_from_erc5267(
+            0x1d,
+            "Token".to_string(),
+            "ignored".to_string(),
+            U256::from(1u64),
+            token,
+            salt,
+        );
+        assert_eq!(domain.version, None);
+        assert_eq!(domain.salt, Some(salt));
+        let without_salt = domain_from_erc5267(
+            0x0d,
+            "Token".to_string(),
+            String::new(),
+            U256::from(1u64),
+            token,
+            salt,
+        );
+        assert_ne!(domain.separator(), without_salt.separator());
+    }
+
+    #[test]
+    fn test_verifying_contract_override_binds_signatures_to_proxy() {
+        let token = address!("0x036CbD53842c5426634e7929541eC2318f3dCF7e");
+        let proxy = address!("0000000000000000000000000000000000000bee");
+        let tokens: TokenConfigs = serde_json::from_value::<Vec<crate::tokens::TokenConfig>>(
+            serde_json::json!([{ "address": token.to_string(), "verifyingContract": proxy.to_string() }]),
+        )
+        .unwrap()
+        .into_iter()
+        .collect();
+        let override_address = tokens.verifying_contract(&EvmAddress(token).into());
+        assert_eq!(override_address, Some(EvmAddress(proxy)));
+        assert_eq!(tokens.verifying_contract(&EvmAddress(proxy).into()), None);
+
+        let token_domain = eip712_domain! {
+            name: "USDC",
+            version: "2",
+            chain_id: 84532,
+            verifying_contract: token,
+        };
+        let proxy_domain = eip712_domain! {
+            name: "USDC",
+            version: "2",
+            chain_id: 84532,
+            verifying_contract: proxy,
+        };
+        let signer = PrivateKeySigner::random();
+        let transfer = TransferWithAuthorization {
+            from: signer.address(),
+            to: address!("0000000000000000000000000000000000000402"),
+            value: U256::from(1_000_000u64),
+            validAfter: U256::ZERO,
+            validBefore: U256::MAX,
+            nonce: FixedBytes([9u8; 32]),
+        };
+        let signature = signer
+            .sign_hash_sync(&transfer.eip712_signing_hash(&proxy_domain))
+            .unwrap();
+
+        let domain = with_verifying_contract(token_domain.clone(), override_address);
+        assert_eq!(domain.separator(), proxy_domain.separator());
+        let recovered = signature
+            .recover_address_from_prehash(&transfer.eip712_signing_hash(&domain))
+            .unwrap();
+        assert_eq!(recovered, signer.address());
+
+        let domain = with_verifying_contract(token_domain.clone(), None);
+        assert_eq!(domain.separator(), token_domain.separator());
+        let recovered = signature
+            .recover_address_from_prehash(&transfer.eip712_signing_hash(&domain))
+            .unwrap();
+        assert_ne!(recovered, signer.address());
+    }
+
+    /// [`offline_request`], which authorizes more than it requires, with the whole value required.
+    fn paid_in_full(mut request: OfflineVerifyRequest) -> OfflineVerifyRequest {
+        let ExactPaymentPayload::Evm(payload) = &request.payment_payload.payload else {
+            unreachable!()
+        };
+        request.payment_requirements.max_amount_required = payload.authorization.value;
+        request
+    }
+
+    #[test]
+    fn test_verify_offline_recovers_signer() {
+        let signer = PrivateKeySigner::random();
+        let request = paid_in_full(offline_request(&signer, signer.address()));
+        let payer = verify_offline(&request).unwrap();
+        assert_eq!(payer.0, signer.address());
+
+        let impostor = address!("0000000000000000000000000000000000000bad");
+        let request = paid_in_full(offline_request(&signer, impostor));
+        assert!(matches!(
+            verify_offline(&request),
+            Err(FacilitatorLocalError::InvalidSignature(..))
+        ));
+    }
+
+    #[test]
+    fn test_recover_payer_uses_the_verifiers_domain() {
+        let signer = PrivateKeySigner::random();
+        let request = |of_record: Address| {
+            let offline = offline_request(&signer, of_record);
+            VerifyRequest {
+                x402_version: offline.x402_version,
+                payment_payload: offline.payment_payload,
+                payment_requirements: offline.payment_requirements,
+            }
+        };
+        assert_eq!(
+            recover_payer(&request(signer.address())),
+            Some(EvmAddress(signer.address()))
+        );
+        assert_eq!(recover_payer(&request(Address::repeat_byte(0xba))), None);
+
+        // A `verifyingContract` override of the token changes the domain signatures are checked against.
+        let request = request(signer.address());
+        NETWORK_TOKENS.insert(
+            Network::BaseSepolia,
+            [
+                serde_json::from_value::<crate::tokens::TokenConfig>(serde_json::json!({
+                    "address": request.payment_requirements.asset,
+                    "verifyingContract": Address::repeat_byte(0x77),
+                }))
+                .unwrap(),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let recovered = recover_payer(&request);
+        NETWORK_TOKENS.remove(&Network::BaseSepolia);
+        assert_eq!(recovered, None);
+    }
+
+    #[test]
+    fn test_amounts_are_checked_against_the_scheme() {
+        let signer = PrivateKeySigner::random();
+        assert!(matches!(
+            verify_offline(&offline_request(&signer, signer.address())),
+            Err(FacilitatorLocalError::ExcessValue(..))
+        ));
+        let payer = EvmAddress(signer.address());
+        let required = U256::from(1_000_000u64);
+        assert!(
+            assert_enough_value(&payer, AmountScheme::UpTo, &U256::from(1u8), &required).is_ok()
+        );
+        assert!(matches!(
+            assert_enough_value(&payer, AmountScheme::UpTo, &U256::ZERO, &required),
+            Err(FacilitatorLocalError::InsufficientValue(..))
+        ));
+    }
+
+    #[test]
+    fn test_transfer_signature_does_not_authorize_receive() {
+        let signer = PrivateKeySigner::random();
+        let mut request = paid_in_full(offline_request(&signer, signer.address()));
+        request.payment_requirements.authorization_kind = Some(AuthorizationKind::Receive);
+        assert!(matches!(
+            verify_offline(&request),
+            Err(FacilitatorLocalError::InvalidSignature(..))
+        ));
+    }
+
+    #[test]
+    fn test_high_s_signatures_are_rejected() {
+        let secp256k1_order = U256::from_str_radix(
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+            16,
+        )
+        .unwrap();
+        let signer = PrivateKeySigner::random();
+        let request = paid_in_full(offline_request(&signer, signer.address()));
+        assert_eq!(verify_offline(&request).unwrap().0, signer.address());
+
+        let ExactPaymentPayload::Evm(payload) = &request.payment_payload.payload else {
+            unreachable!()
+        };
+        let canonical = Signature::from_raw(&payload.signature.0).unwrap();
+        let malleable = Signature::new(
+            canonical.r(),
+            secp256k1_order - canonical.s(),
+            !canonical.v(),
+        );
+        assert!(canonical.normalize_s().is_none());
+        assert!(malleable.normalize_s().is_some());
+
+        let mut malleated = request.clone();
+        if let ExactPaymentPayload::Evm(payload) = &mut malleated.payment_payload.payload {
+            payload.signature = EvmSignature::from(malleable.as_bytes());
+        }
+        assert!(matches!(
+            verify_offline(&malleated),
+            Err(FacilitatorLocalError::InvalidSignature(_, reason)) if reason.starts_with("Malleable")
+        ));
+    }
+
+    #[test]
+    fn test_timing_rules() {
+        let payer: MixedAddress = EvmAddress(Address::ZERO).into();
+        let now = UnixTimestamp(1_000_000);
+        let check = |rules, valid_after: u64, valid_before: u64| {
+            assert_time_at(
+                payer.clone(),
+                rules,
+                now,
+                UnixTimestamp(valid_after),
+                UnixTimestamp(valid_before),
+            )
+            .is_ok()
+        };
+        let default = TimingRules::default();
+        assert!(check(default, 0, 1_000_060));
+        assert!(!check(default, 1_000_005, 1_000_060));
+        assert!(!check(default, 0, 1_000_003));
+        let rules = TimingRules {
+            max_validity: Some(300),
+            clock_skew: 10,
+        };
+        assert!(check(rules, 1_000_005, 1_000_300));
+        assert!(!check(rules, 1_000_011, 1_000_300));
+        assert!(!check(rules, 0, 1_000_301));
+        // The skew tolerance never extends an expiring `validBefore`.
+        assert!(!check(rules, 0, 1_000_003));
+    }
+
+    #[test]
+    fn test_clock_skew_defaults_to_the_global_setting() {
+        let rules = |vars: &[(&str, &str)]| {
+            let vars: std::collections::HashMap<&str, &str> = vars.iter().copied().collect();
+            TimingRules::from_vars(Scheme::Upto, &|name| {
+                vars.get(name).map(|value| value.to_string())
+            })
+            .unwrap()
+        };
+        let global = (from_env::ENV_EVM_CLOCK_SKEW_SECS, "15");
+        assert_eq!(rules(&[global]).clock_skew, 15);
+        let upto = ("EVM_CLOCK_SKEW_SECS_UPTO", "3");
+        assert_eq!(rules(&[global, upto]).clock_skew, 3);
+        assert_eq!(rules(&[("EVM_CLOCK_SKEW_SECS_EXACT", "3")]).clock_skew, 0);
+        assert_eq!(rules(&[]), TimingRules::default());
+    }
+
+    #[tokio::test]
+    async fn test_quote_adds_the_forwarding_transactions() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        let mut provider = ScriptedProvider::new();
+        provider.inner = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .connect_mocked_client(asserter.clone());
+        let signer = PrivateKeySigner::random();
+        let request = offline_request(&signer, signer.address());
+        let mut quote_request = QuoteRequest {
+            payment_requirements: request.payment_requirements,
+            payment_payload: Some(request.payment_payload),
+        };
+
+        asserter.push_success(&"0x1d4c0");
+        asserter.push_success(&"0xa");
+        let quote = quote_settlement(&provider, &quote_request).await.unwrap();
+        assert!(quote.gas_estimated);
+        assert_eq!((quote.gas, quote.forwarding_gas), (120_000, 0));
+        assert_eq!(quote.total_cost.0, U256::from(1_200_000));
+
+        quote_request.payment_requirements.authorization_kind = Some(AuthorizationKind::Receive);
+        asserter.push_success(&"0x1d4c0");
+        asserter.push_success(&"0xa");
+        let quote = quote_settlement(&provider, &quote_request).await.unwrap();
+        assert_eq!(quote.forwarding_gas, DEFAULT_FORWARD_TRANSFER_GAS);
+        assert_eq!(quote.gas, 120_000 + DEFAULT_FORWARD_TRANSFER_GAS);
+
+        let share = |amount: u64| PaymentSplit {
+            pay_to: EvmAddress(Address::repeat_byte(1)).into(),
+            amount: TokenAmount(U256::from(amount)),
+        };
+        quote_request.payment_requirements.splits = Some(vec![share(1_000_000), share(500_000)]);
+        quote_request.payment_payload = None;
+        asserter.push_success(&"0xa");
+        let quote = quote_settlement(&provider, &quote_request).await.unwrap();
+        assert!(!quote.gas_estimated);
+        assert_eq!(
+            quote.gas,
+            DEFAULT_TRANSFER_GAS + 2 * DEFAULT_FORWARD_TRANSFER_GAS
+        );
+        assert_eq!(
+            forwarding_gas(
+                &PaymentRequirements {
+                    splits: None,
+                    authorization_kind: None,
+                    ..quote_request.payment_requirements.clone()
+                },
+                true
+            ),
+            DEFAULT_UNWRAP_GAS + NATIVE_TRANSFER_GAS
+        );
+    }
+
+    #[test]
+    fn test_recover_provider_is_built_once_per_network() {
+        let rpc_env = from_env::rpc_env_name_from_network(Network::Celo);
+        assert!(recover_provider(Network::Celo).is_none());
+        unsafe { std::env::set_var(rpc_env, "http://127.0.0.1:1") };
+        assert!(recover_provider(Network::Celo).is_some());
+        // Later calls reuse the provider instead of reading the URL again.
+        unsafe { std::env::remove_var(rpc_env) };
+        assert!(recover_provider(Network::Celo).is_some());
+        assert!(RECOVER_PROVIDERS.contains_key(&Network::Celo));
+    }
+
+    /// Settings read from `vars` instead of the environment.
+    fn settings_from(vars: &[(&str, &str)]) -> Result<EvmSettings, String> {
+        let vars: std::collections::HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        EvmSettings::from_vars(&|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_settings_refuse_invalid_values() {
+        let timing = Scheme::variants()
+            .iter()
+            .map(|scheme| (*scheme, TimingRules::default()))
+            .collect();
+        let defaults = EvmSettings {
+            timing,
+            receipt_timeout_secs: 30,
+            ..EvmSettings::default()
+        };
+        assert_eq!(settings_from(&[]), Ok(defaults));
+        let settings = settings_from(&[(from_env::ENV_EVM_CHECK_SIGNER_KIND, " true")]).unwrap();
+        assert!(settings.check_signer_kind);
+        let error = settings_from(&[(from_env::ENV_EVM_CHECK_SIGNER_KIND, "yes")]).unwrap_err();
+        assert!(error.contains(from_env::ENV_EVM_CHECK_SIGNER_KIND));
+        assert!(settings_from(&[(from_env::ENV_EVM_CHECK_BLOCK_TIME, "1")]).is_err());
+        assert!(settings_from(&[(from_env::ENV_EVM_CHECK_TOTAL_SUPPLY, "on")]).is_err());
+        assert!(settings_from(&[(from_env::ENV_EVM_ERC5267_DOMAINS, "")]).is_err());
+        let settings =
+            settings_from(&[(from_env::ENV_EVM_SETTLE_EXPIRY_BUFFER_SECS, "60")]).unwrap();
+        assert_eq!(settings.settle_expiry_buffer, 60);
+        assert!(settings_from(&[(from_env::ENV_EVM_SETTLE_EXPIRY_BUFFER_SECS, "1m")]).is_err());
+        assert!(settings_from(&[(from_env::ENV_EVM_REORG_RETRIES, "-1")]).is_err());
+        assert!(settings_from(&[(from_env::ENV_SETTLE_COST_BREAKDOWN, "True")]).is_err());
+        let settings = settings_from(&[("EVM_MAX_VALIDITY_SECS_EXACT", "600")]).unwrap();
+        assert_eq!(settings.timing_rules(Scheme::Exact).max_validity, Some(600));
+        assert_eq!(settings.timing_rules(Scheme::Upto), TimingRules::default());
+        assert!(settings_from(&[(from_env::ENV_EVM_CLOCK_SKEW_SECS, "10s")]).is_err());
+        assert!(settings_from(&[("EVM_CLOCK_SKEW_SECS_UPTO", "-5")]).is_err());
+        assert!(settings_from(&[(from_env::ENV_TX_RECEIPT_TIMEOUT_SECS, "30s")]).is_err());
+    }
+
+    #[test]
+    fn test_memo_tag_must_be_hex_and_prefixes_the_payment_id() {
+        unsafe { std::env::set_var(from_env::ENV_SETTLEMENT_MEMO_TAG, "0x78343032") };
+        let tag = memo_tag_from_env().unwrap().unwrap();
+        unsafe { std::env::set_var(from_env::ENV_SETTLEMENT_MEMO_TAG, "x402") };
+        assert!(memo_tag_from_env().is_err());
+        unsafe { std::env::set_var(from_env::ENV_SETTLEMENT_MEMO_TAG, " ") };
+        assert_eq!(memo_tag_from_env(), Ok(None));
+        unsafe { std::env::remove_var(from_env::ENV_SETTLEMENT_MEMO_TAG) };
+        assert_eq!(memo_tag_from_env(), Ok(None));
+
+        let request = offline_request(&PrivateKeySigner::random(), Address::ZERO);
+        let request = SettleRequest {
+            x402_version: X402Version::V1,
+            payment_payload: request.payment_payload,
+            payment_requirements: request.payment_requirements,
+        };
+        let memo = settlement_memo(&tag, &request);
+        assert_eq!(&memo[..4], b"x402");
+        assert_eq!(&memo[4..], request.payment_id().0.as_slice());
+    }
+
+    #[tokio::test]
+    async fn test_block_time_is_averaged_over_the_sample() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .connect_mocked_client(asserter.clone());
+        let push_block = |number: u64, timestamp: u64| {
+            let mut header = alloy::rpc::types::Header::<alloy::consensus::Header>::default();
+            header.inner.number = number;
+            header.inner.timestamp = timestamp;
+            asserter.push_success(
+                &alloy::rpc::types::Block::<alloy::rpc::types::Transaction>::empty(header),
+            );
+        };
+        push_block(1_000, 10_250);
+        push_block(1_000 - BLOCK_TIME_SAMPLE, 10_000);
+        assert_eq!(
+            measure_block_time(&provider).await,
+            Some(Duration::from_millis(2_500))
+        );
+        // A chain younger than the sample is not measured.
+        push_block(BLOCK_TIME_SAMPLE - 1, 10_000);
+        assert_eq!(measure_block_time(&provider).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_block_time_check_accepts_the_next_block_and_the_skew() {
+        let asserter = alloy::providers::mock::Asserter::new();
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .connect_mocked_client(asserter.clone());
+        let check = |clock_skew, valid_after, valid_before| {
+            let mut header = alloy::rpc::types::Header::<alloy::consensus::Header>::default();
+            header.inner.timestamp = 1_000;
+            asserter.push_success(
+                &alloy::rpc::types::Block::<alloy::rpc::types::Transaction>::empty(header),
+            );
+            let payer: MixedAddress = EvmAddress(Address::ZERO).into();
+            assert_block_time(
+                &provider,
+                payer,
+                clock_skew,
+                UnixTimestamp(valid_after),
+                UnixTimestamp(valid_before),
+            )
+        };
+        assert!(check(0, 999, 1_001).await.is_ok());
+        assert!(check(0, 1_000, 1_001).await.is_ok());
+        assert!(check(0, 1_001, 2_000).await.is_err());
+        assert!(check(5, 1_005, 2_000).await.is_ok());
+        assert!(check(5, 1_006, 2_000).await.is_err());
+        // The skew never extends `validBefore`.
+        assert!(check(5, 0, 1_000).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_settlement_waits_until_valid_after_has_passed() {
+        let started = Instant::now();
+        await_valid_after(UnixTimestamp(0)).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        let now = UnixTimestamp::try_now().unwrap();
+        await_valid_after(now).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert!(UnixTimestamp::try_now().unwrap() > now);
+    }
+
+    #[test]
+    fn test_value_range_rejects_max_uint256_and_values_above_total_supply() {
+        let payer = EvmAddress(Address::ZERO);
+        let max_uint256: TokenAmount = serde_json::from_value(serde_json::json!(
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+        ))
+        .unwrap();
+        assert_eq!(max_uint256.0, U256::MAX);
+        let ceiling = Some(TokenAmount(U256::from(1_000_000_000u64)));
+        let total_supply = Some(U256::from(5_000_000_000u64));
+
+        let out_of_range = |value: U256, max_value, total_supply| {
+            matches!(
+                assert_value_in_range(&payer, value, max_value, total_supply),
+                Err(FacilitatorLocalError::ValueOutOfRange(..))
+            )
+        };
+        assert!(out_of_range(max_uint256.0, ceiling, total_supply));
+        assert!(out_of_range(max_uint256.0, None, total_supply));
+        assert!(out_of_range(
+            U256::from(5_000_000_001u64),
+            None,
+            total_supply
+        ));
+        assert!(out_of_range(U256::from(1_000_000_001u64), ceiling, None));
+        assert!(
+            assert_value_in_range(&payer, U256::from(5_000_000_000u64), None, total_supply).is_ok()
+        );
+        assert!(assert_value_in_range(&payer, max_uint256.0, None, None).is_ok());
+    }
+
+    #[test]
+    fn test_expected_nonce_from_order_id() {
+        let signer = PrivateKeySigner::random();
+        let mut requirements = offline_request(&signer, signer.address()).payment_requirements;
+        assert_eq!(expected_nonce(&requirements), Ok(None));
+        requirements.extra = Some(serde_json::json!({ "orderId": "order-42" }));
+        assert_eq!(
+            expected_nonce(&requirements),
+            Ok(Some(keccak256("order-42")))
+        );
+        let explicit = B256::repeat_byte(7);
+        requirements.extra = Some(serde_json::json!({ "expectedNonce": explicit.to_string() }));
+        assert_eq!(expected_nonce(&requirements), Ok(Some(explicit)));
+        requirements.extra = Some(serde_json::json!({ "expectedNonce": "0x1234" }));
+        assert!(expected_nonce(&requirements).is_err());
+    }
+
+    #[test]
+    fn test_split_payments_must_commit_to_their_shares() {
+        use alloy::sol_types::SolValue;
+
+        let share = |byte: u8, amount: u64| crate::types::PaymentSplit {
+            pay_to: EvmAddress(Address::repeat_byte(byte)).into(),
+            amount: TokenAmount(U256::from(amount)),
+        };
+        let split = AuthorizedReceiver::Facilitator(&[]);
+        let mut requirements = requirements();
+        requirements.splits = Some(vec![share(2, 60), share(3, 40)]);
+        let mut payment = intermediary_payment(100);
+        assert!(matches!(
+            assert_expected_nonce(split, &payment, &requirements),
+            Err(FacilitatorLocalError::DecodingError(_))
+        ));
+
+        let salt = B256::repeat_byte(9);
+        requirements.extra = Some(serde_json::json!({ "routingSalt": salt.to_string() }));
+        let pay_to: Address = requirements.pay_to.clone().try_into().unwrap();
+        let shares = vec![
+            (Address::repeat_byte(2), U256::from(60)),
+            (Address::repeat_byte(3), U256::from(40)),
+        ];
+        let committed = keccak256((salt, pay_to, shares).abi_encode_params());
+        assert_eq!(routing_nonce(&requirements), Ok(committed));
+        assert!(matches!(
+            assert_expected_nonce(split, &payment, &requirements),
+            Err(FacilitatorLocalError::UnexpectedNonce(_))
+        ));
+        payment.nonce = HexEncodedNonce(committed.0);
+        assert!(assert_expected_nonce(split, &payment, &requirements).is_ok());
+
+        // Redirecting a share breaks the commitment.
+        requirements.splits = Some(vec![share(4, 60), share(3, 40)]);
+        assert!(matches!(
+            assert_expected_nonce(split, &payment, &requirements),
+            Err(FacilitatorLocalError::UnexpectedNonce(_))
+        ));
+
+        // An order's nonce salts the commitment in place of `routingSalt`.
+        requirements.splits = Some(vec![share(2, 60), share(3, 40)]);
+        requirements.extra = Some(serde_json::json!({ "orderId": "order-42" }));
+        let shares = vec![
+            (Address::repeat_byte(2), U256::from(60)),
+            (Address::repeat_byte(3), U256::from(40)),
+        ];
+        assert_eq!(
+            routing_nonce(&requirements),
+            Ok(keccak256(
+                (keccak256("order-42"), pay_to, shares).abi_encode_params()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_unwrap_and_receive_payments_must_commit_to_pay_to() {
+        use alloy::sol_types::SolValue;
+
+        let unwrap = AuthorizedReceiver::Facilitator(&[]);
+        let mut requirements = requirements();
+        let salt = B256::repeat_byte(9);
+        requirements.extra = Some(serde_json::json!({ "routingSalt": salt.to_string() }));
+        let mut payment = intermediary_payment(100);
+        // Paid to `payTo` directly, the nonce is free.
+        assert!(assert_expected_nonce(AuthorizedReceiver::PayTo, &payment, &requirements).is_ok());
+        assert!(matches!(
+            assert_expected_nonce(unwrap, &payment, &requirements),
+            Err(FacilitatorLocalError::UnexpectedNonce(_))
+        ));
+
+        let pay_to: Address = requirements.pay_to.clone().try_into().unwrap();
+        let no_splits: Vec<(Address, U256)> = Vec::new();
+        payment.nonce = HexEncodedNonce(keccak256((salt, pay_to, no_splits).abi_encode_params()).0);
+        assert!(assert_expected_nonce(unwrap, &payment, &requirements).is_ok());
+        requirements.pay_to = EvmAddress(Address::repeat_byte(0xbb)).into();
+        assert!(matches!(
+            assert_expected_nonce(unwrap, &payment, &requirements),
+            Err(FacilitatorLocalError::UnexpectedNonce(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_recover_personal_message() {
+        let signer = PrivateKeySigner::random();
+        let signature = signer.sign_message_sync(b"hello x402").unwrap();
+        let mut request = RecoverRequest {
+            message: Some("hello x402".to_string()),
+            typed_data: None,
+            signature: EvmSignature(signature.as_bytes().to_vec()),
+            signer: Some(EvmAddress(signer.address())),
+            network: None,
+        };
+        let response = recover(&request).await.unwrap();
+        assert_eq!(response.recovered, Some(EvmAddress(signer.address())));
+        assert_eq!(response.valid, Some(true));
+
+        // EIP-2098 compact signatures recover to the same signer.
+        request.signature = EvmSignature(signature.as_erc2098().to_vec());
+        let response = recover(&request).await.unwrap();
+        assert_eq!(response.recovered, Some(EvmAddress(signer.address())));
+    }
 
     #[tokio::test]
     async fn test_reset_nonce_clears_cache() {
@@ -0,0 +1,250 @@
+//! Per-network notion of when a settlement is final.
+//!
+//! Networks differ in how finality is reached: some finalize a block as soon as it is produced,
+//! others need many confirmations, and some expose a `finalized` block tag. A [`FinalityStrategy`]
+//! is chosen per network, and settlements only succeed once their transaction is final by it.
+//! A transaction reorged out while waiting fails the settlement with
+//! [`FacilitatorLocalError::Reorged`](crate::chain::FacilitatorLocalError::Reorged) instead.
+//!
+//! Environment variables used:
+//! - `EVM_FINALITY_<NETWORK>` — one of `confirmations:<n>` (default: `confirmations:1`),
+//!   `finalized` (the block is at or below the `finalized` tag) or `seconds:<n>` (the chain has
+//!   advanced `n` seconds of block time past the block),
+//! - `EVM_CONFIRMATIONS_<NETWORK>` — number of confirmations a settlement waits for, the same as
+//!   `EVM_FINALITY_<NETWORK>=confirmations:<n>`; setting both is an error,
+//! - `EVM_FINALITY_TIMEOUT_SECS` — how long a settlement waits for finality (default: `900`).
+//!
+//! While a network reorgs often, confirmation counts are raised, see [`ReorgMonitor`]:
+//! - `EVM_REORG_RATE_THRESHOLD` — number of reorgs within the window that raises the depth
+//!   (default: `0`, disabled),
+//! - `EVM_REORG_RATE_WINDOW_SECS` — the window reorgs are counted over (default: `600`),
+//! - `EVM_REORG_EXTRA_CONFIRMATIONS` — confirmations added while the rate is high (default: `5`).
+
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::from_env;
+use crate::network::Network;
+
+/// When a transaction included in a block counts as final.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinalityStrategy {
+    /// The block has this many confirmations, counting itself.
+    Confirmations(u64),
+    /// The block is at or below the node's `finalized` block.
+    Finalized,
+    /// The latest block is at least this much younger than the block, by block timestamps.
+    BlockTime(Duration),
+}
+
+impl Default for FinalityStrategy {
+    fn default() -> Self {
+        FinalityStrategy::Confirmations(1)
+    }
+}
+
+impl FromStr for FinalityStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "finalized" {
+            return Ok(FinalityStrategy::Finalized);
+        }
+        let (kind, value) = s
+            .split_once(':')
+            .ok_or_else(|| format!("unknown finality strategy {s}"))?;
+        let value: u64 = value
+            .trim()
+            .parse()
+            .map_err(|e| format!("finality strategy {s} is invalid: {e}"))?;
+        match kind {
+            "confirmations" => Ok(FinalityStrategy::Confirmations(value.max(1))),
+            "seconds" => Ok(FinalityStrategy::BlockTime(Duration::from_secs(value))),
+            _ => Err(format!("unknown finality strategy {s}")),
+        }
+    }
+}
+
+impl FinalityStrategy {
+    /// Reads the strategy for `network` from `EVM_FINALITY_<NETWORK>` or `EVM_CONFIRMATIONS_<NETWORK>`,
+    /// defaulting to one confirmation.
+    pub fn from_env(network: Network) -> Result<Self, Box<dyn std::error::Error>> {
+        let finality_var =
+            from_env::env_name_for_network(from_env::ENV_EVM_FINALITY_PREFIX, network);
+        let confirmations_var =
+            from_env::env_name_for_network(from_env::ENV_EVM_CONFIRMATIONS_PREFIX, network);
+        let finality = std::env::var(&finality_var).ok();
+        let confirmations = std::env::var(&confirmations_var).ok();
+        Self::from_settings(finality.as_deref(), confirmations.as_deref()).map_err(|e| {
+            format!("env {finality_var} or {confirmations_var} is invalid: {e}").into()
+        })
+    }
+
+    /// The strategy set by a finality strategy or a number of confirmations, one confirmation if neither is set.
+    fn from_settings(finality: Option<&str>, confirmations: Option<&str>) -> Result<Self, String> {
+        match (finality, confirmations) {
+            (Some(_), Some(_)) => Err("set a finality strategy or confirmations, not both".into()),
+            (Some(finality), None) => finality.parse(),
+            (None, Some(confirmations)) => {
+                let confirmations: u64 = confirmations
+                    .trim()
+                    .parse()
+                    .map_err(|e| format!("confirmations {confirmations} is invalid: {e}"))?;
+                if confirmations == 0 {
+                    return Err("confirmations must be at least 1".into());
+                }
+                Ok(FinalityStrategy::Confirmations(confirmations))
+            }
+            (None, None) => Ok(Self::default()),
+        }
+    }
+
+    /// This strategy while the network reorgs often: confirmation counts are raised by `extra`.
+    ///
+    /// The `finalized` tag and block time strategies already account for reorgs and are unchanged.
+    pub fn deepened(self, extra: u64) -> Self {
+        match self {
+            FinalityStrategy::Confirmations(n) => {
+                FinalityStrategy::Confirmations(n.saturating_add(extra))
+            }
+            strategy => strategy,
+        }
+    }
+
+    /// Whether the receipt wait already makes a transaction final, with no further waiting.
+    pub fn is_immediate(&self) -> bool {
+        matches!(self, FinalityStrategy::Confirmations(n) if *n <= 1)
+    }
+
+    /// How long a settlement waits for finality, from `EVM_FINALITY_TIMEOUT_SECS`.
+    pub fn timeout() -> Duration {
+        let secs = std::env::var(from_env::ENV_EVM_FINALITY_TIMEOUT_SECS)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(900);
+        Duration::from_secs(secs)
+    }
+}
+
+/// Recent reorgs observed on a network, raising confirmation depth while they are frequent.
+#[derive(Debug)]
+pub struct ReorgMonitor {
+    threshold: usize,
+    window: Duration,
+    extra_confirmations: u64,
+    reorgs: Mutex<VecDeque<Instant>>,
+}
+
+impl ReorgMonitor {
+    /// Raises confirmations by `extra_confirmations` while at least `threshold` reorgs happened within `window`.
+    ///
+    /// A `threshold` of zero disables the monitor.
+    pub fn new(threshold: usize, window: Duration, extra_confirmations: u64) -> Self {
+        Self {
+            threshold,
+            window,
+            extra_confirmations,
+            reorgs: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Reads the monitor settings from `EVM_REORG_RATE_*` and `EVM_REORG_EXTRA_CONFIRMATIONS`.
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+        Self::new(
+            var(from_env::ENV_EVM_REORG_RATE_THRESHOLD, 0) as usize,
+            Duration::from_secs(var(from_env::ENV_EVM_REORG_RATE_WINDOW_SECS, 600)),
+            var(from_env::ENV_EVM_REORG_EXTRA_CONFIRMATIONS, 5),
+        )
+    }
+
+    /// Records a reorg observed now.
+    pub fn record(&self) {
+        if self.threshold == 0 {
+            return;
+        }
+        if let Ok(mut reorgs) = self.reorgs.lock() {
+            reorgs.push_back(Instant::now());
+            if reorgs.len() > self.threshold {
+                reorgs.pop_front();
+            }
+        }
+    }
+
+    /// Whether at least `threshold` reorgs happened within the window.
+    pub fn is_elevated(&self) -> bool {
+        if self.threshold == 0 {
+            return false;
+        }
+        let Ok(mut reorgs) = self.reorgs.lock() else {
+            return false;
+        };
+        while reorgs
+            .front()
+            .is_some_and(|reorg| reorg.elapsed() > self.window)
+        {
+            reorgs.pop_front();
+        }
+        reorgs.len() >= self.threshold
+    }
+
+    /// `strategy`, deepened if the reorg rate is currently high.
+    pub fn effective(&self, strategy: FinalityStrategy) -> FinalityStrategy {
+        if self.is_elevated() {
+            strategy.deepened(self.extra_confirmations)
+        } else {
+            strategy
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirmations_setting() {
+        assert_eq!(
+            FinalityStrategy::from_settings(None, None),
+            Ok(FinalityStrategy::Confirmations(1))
+        );
+        assert_eq!(
+            FinalityStrategy::from_settings(None, Some("12")),
+            Ok(FinalityStrategy::Confirmations(12))
+        );
+        assert_eq!(
+            FinalityStrategy::from_settings(Some("finalized"), None),
+            Ok(FinalityStrategy::Finalized)
+        );
+        assert!(!FinalityStrategy::Confirmations(12).is_immediate());
+        assert!(FinalityStrategy::from_settings(None, Some("0")).is_err());
+        assert!(FinalityStrategy::from_settings(None, Some("many")).is_err());
+        assert!(FinalityStrategy::from_settings(Some("confirmations:3"), Some("3")).is_err());
+    }
+
+    #[test]
+    fn test_reorg_monitor_deepens_confirmations() {
+        let monitor = ReorgMonitor::new(2, Duration::from_secs(60), 5);
+        let strategy = FinalityStrategy::default();
+        monitor.record();
+        assert_eq!(monitor.effective(strategy), strategy);
+        monitor.record();
+        assert_eq!(
+            monitor.effective(strategy),
+            FinalityStrategy::Confirmations(6)
+        );
+        assert_eq!(
+            monitor.effective(FinalityStrategy::Finalized),
+            FinalityStrategy::Finalized
+        );
+        assert!(!ReorgMonitor::new(2, Duration::ZERO, 5).is_elevated());
+    }
+}
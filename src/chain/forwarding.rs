@@ -0,0 +1,308 @@
+//! Payments still to be forwarded from a facilitator signer, and their retries.
+//!
+//! Split payments, `receiveWithAuthorization` payments and `unwrapNative` tokens are paid to a
+//! facilitator signer first, then forwarded in separate transactions, see [`EvmProvider`]. They can
+//! not be sent in the same transaction through Multicall3: the payment would then have to be made
+//! out to Multicall3, which lets anyone take the tokens it holds. So when forwarding fails partway,
+//! the legs not yet sent are recorded here as a [`PendingForward`], and retried in the background
+//! until they are sent, see [`retry`]. Sweeps leave the amounts of pending forwards to the signer.
+//!
+//! A leg whose transaction was broadcast but not confirmed is only sent again once that transaction
+//! reverted or is gone from the node, so a slow transaction is never paid twice.
+//!
+//! Environment variables used:
+//! - `FORWARD_JOURNAL_PATH` — file pending forwards are kept in, so they survive a restart
+//!   (default: unset, kept in memory only),
+//! - `FORWARD_RETRY_INTERVAL_SECS` — how often pending forwards are retried (default: `60`).
+//!
+//! [`EvmProvider`]: crate::chain::evm::EvmProvider
+
+use alloy::primitives::{Address, TxHash, U256};
+use dashmap::DashMap;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::chain::{NetworkProvider, NetworkProviderOps};
+use crate::facilitator_local::FacilitatorLocal;
+use crate::from_env::{ENV_FORWARD_JOURNAL_PATH, ENV_FORWARD_RETRY_INTERVAL_SECS};
+use crate::network::Network;
+use crate::provider_cache::ProviderMap;
+use crate::timestamp::UnixTimestamp;
+
+/// A transaction forwarding (part of) a payment from the intermediary signer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ForwardLeg {
+    /// Transfers `amount` of `token` to `to`.
+    Transfer {
+        token: Address,
+        to: Address,
+        amount: U256,
+    },
+    /// Unwraps `amount` of the wrapped native `token`.
+    Unwrap { token: Address, amount: U256 },
+    /// Sends `amount` of native currency to `to`.
+    Native { to: Address, amount: U256 },
+}
+
+impl ForwardLeg {
+    /// The amount of `token` this leg still needs from the signer.
+    pub fn reserves(&self, token: Address) -> U256 {
+        match self {
+            ForwardLeg::Transfer {
+                token: leg_token,
+                amount,
+                ..
+            }
+            | ForwardLeg::Unwrap {
+                token: leg_token,
+                amount,
+            } if *leg_token == token => *amount,
+            _ => U256::ZERO,
+        }
+    }
+}
+
+/// The legs of a payment's forwarding that are not sent yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingForward {
+    pub network: Network,
+    /// The settlement transaction that paid the intermediary.
+    pub settlement: TxHash,
+    /// The signer holding the payment.
+    pub intermediary: Address,
+    /// Legs still to be sent, in order. Empty once forwarding is done.
+    pub legs: Vec<ForwardLeg>,
+    /// The last transaction broadcast for the first leg, if its outcome is not known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_flight: Option<TxHash>,
+    pub updated_at: UnixTimestamp,
+}
+
+/// Pending forwards by settlement transaction, optionally kept in a JSON Lines file.
+///
+/// Every record appends a line; when the file is opened, the last line of each settlement wins,
+/// and the file is rewritten with the forwards still pending.
+#[derive(Debug, Default)]
+pub struct PendingForwards {
+    entries: DashMap<TxHash, PendingForward>,
+    file: Option<Mutex<tokio::fs::File>>,
+}
+
+impl PendingForwards {
+    /// Opens the pending forwards kept at `path`, creating the file if needed.
+    pub async fn open(path: PathBuf) -> Result<Self, std::io::Error> {
+        let entries = DashMap::new();
+        match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => {
+                for (index, line) in contents.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str::<PendingForward>(line) {
+                        Ok(pending) if pending.legs.is_empty() => {
+                            entries.remove(&pending.settlement);
+                        }
+                        Ok(pending) => {
+                            entries.insert(pending.settlement, pending);
+                        }
+                        // A crash may leave the last line cut short.
+                        Err(error) => tracing::warn!(
+                            path = %path.display(),
+                            line = index + 1,
+                            %error,
+                            "Skipping unreadable pending forward line"
+                        ),
+                    }
+                }
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+        let mut compacted = Vec::new();
+        for pending in entries.iter() {
+            compacted.extend(Self::line(pending.value())?);
+        }
+        tokio::fs::write(&path, &compacted).await?;
+        let file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .await?;
+        Ok(Self {
+            entries,
+            file: Some(Mutex::new(file)),
+        })
+    }
+
+    /// Opens the pending forwards at `FORWARD_JOURNAL_PATH`, or keeps them in memory if it is not set.
+    pub async fn from_env() -> Result<Self, std::io::Error> {
+        match std::env::var(ENV_FORWARD_JOURNAL_PATH) {
+            Ok(path) if !path.trim().is_empty() => Self::open(path.into()).await,
+            _ => Ok(Self::default()),
+        }
+    }
+
+    fn line(pending: &PendingForward) -> Result<Vec<u8>, std::io::Error> {
+        let mut line = serde_json::to_vec(pending)?;
+        line.push(b'\n');
+        Ok(line)
+    }
+
+    /// Records `pending`, replacing the entry of the same settlement; forwards with no legs left are removed.
+    pub async fn record(&self, pending: PendingForward) {
+        if let Some(file) = &self.file {
+            let written = async {
+                let line = Self::line(&pending)?;
+                let mut file = file.lock().await;
+                file.write_all(&line).await?;
+                file.flush().await
+            };
+            if let Err(error) = written.await {
+                tracing::error!(%error, settlement = %pending.settlement, "Failed to journal pending forward");
+            }
+        }
+        if pending.legs.is_empty() {
+            self.entries.remove(&pending.settlement);
+        } else {
+            self.entries.insert(pending.settlement, pending);
+        }
+    }
+
+    /// Every pending forward of `network`.
+    pub fn pending(&self, network: Network) -> Vec<PendingForward> {
+        self.entries
+            .iter()
+            .filter(|pending| pending.network == network)
+            .map(|pending| pending.clone())
+            .collect()
+    }
+
+    /// The amount of `token` that `signer` holds for pending forwards on `network`.
+    pub fn reserved(&self, network: Network, signer: Address, token: Address) -> U256 {
+        self.entries
+            .iter()
+            .filter(|pending| pending.network == network && pending.intermediary == signer)
+            .map(|pending| {
+                pending.legs.iter().fold(U256::ZERO, |total, leg| {
+                    total.saturating_add(leg.reserves(token))
+                })
+            })
+            .fold(U256::ZERO, U256::saturating_add)
+    }
+}
+
+static PENDING_FORWARDS: OnceCell<Arc<PendingForwards>> = OnceCell::new();
+
+/// Makes `pending_forwards` the process-wide store. Only a call before the first [`global`] has an effect.
+pub fn install(pending_forwards: Arc<PendingForwards>) {
+    let _ = PENDING_FORWARDS.set(pending_forwards);
+}
+
+/// The process-wide pending forwards, kept in memory unless [`install`]ed otherwise.
+pub fn global() -> &'static Arc<PendingForwards> {
+    PENDING_FORWARDS.get_or_init(Default::default)
+}
+
+/// Retries the pending forwards of every EVM network of `facilitator` until `cancellation_token` is cancelled.
+pub async fn retry<A>(facilitator: Arc<FacilitatorLocal<A>>, cancellation_token: CancellationToken)
+where
+    A: ProviderMap<Value = NetworkProvider>,
+{
+    let interval = std::env::var(ENV_FORWARD_RETRY_INTERVAL_SECS)
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(60);
+    let mut interval = tokio::time::interval(Duration::from_secs(interval));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = cancellation_token.cancelled() => break,
+        }
+        for provider in facilitator.provider_map().values() {
+            let NetworkProvider::Evm(provider) = provider else {
+                continue;
+            };
+            for pending in global().pending(provider.network()) {
+                let remaining = provider.retry_forward(pending).await;
+                global().record(remaining).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(settlement: u8, legs: Vec<ForwardLeg>) -> PendingForward {
+        PendingForward {
+            network: Network::BaseSepolia,
+            settlement: TxHash::repeat_byte(settlement),
+            intermediary: Address::repeat_byte(0xfa),
+            legs,
+            in_flight: None,
+            updated_at: UnixTimestamp(0),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pending_forwards_survive_reopening_and_reserve_their_amounts() {
+        let path = std::env::temp_dir().join(format!(
+            "x402-forwards-{}.jsonl",
+            UnixTimestamp::try_now().unwrap().0 ^ u64::from(std::process::id())
+        ));
+        let token = Address::repeat_byte(1);
+        let transfer = ForwardLeg::Transfer {
+            token,
+            to: Address::repeat_byte(2),
+            amount: U256::from(40),
+        };
+        let forwards = PendingForwards::open(path.clone()).await.unwrap();
+        forwards.record(pending(1, vec![transfer.clone()])).await;
+        forwards
+            .record(pending(
+                2,
+                vec![
+                    ForwardLeg::Unwrap {
+                        token,
+                        amount: U256::from(5),
+                    },
+                    ForwardLeg::Native {
+                        to: Address::repeat_byte(3),
+                        amount: U256::from(5),
+                    },
+                ],
+            ))
+            .await;
+        forwards.record(pending(2, Vec::new())).await;
+        drop(forwards);
+
+        let reopened = PendingForwards::open(path.clone()).await.unwrap();
+        assert_eq!(
+            reopened.pending(Network::BaseSepolia),
+            vec![pending(1, vec![transfer])]
+        );
+        assert!(reopened.pending(Network::Base).is_empty());
+        let intermediary = Address::repeat_byte(0xfa);
+        assert_eq!(
+            reopened.reserved(Network::BaseSepolia, intermediary, token),
+            U256::from(40)
+        );
+        assert_eq!(
+            reopened.reserved(Network::BaseSepolia, Address::ZERO, token),
+            U256::ZERO
+        );
+        // Reopening compacts the file to the forwards still pending.
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 1);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,402 @@
+//! Per-network pricing of settlement transactions, and fee bumping of stuck ones.
+//!
+//! A [`GasStrategy`] prices every transaction the facilitator sends: `legacy` networks get a single
+//! gas price, the node's `eth_gasPrice` times a multiplier; `eip1559` networks get a max fee of the
+//! latest base fee times a multiplier plus the priority fee, either configured or the node's
+//! suggestion. Without a multiplier or priority fee, EIP-1559 fees are the node's fee history estimate.
+//!
+//! A transaction still pending after a while can be replaced by the same one, with the same nonce and
+//! fees raised by a [`FeeBump`], so a spike in gas prices does not leave a settlement stuck until its
+//! receipt wait times out.
+//!
+//! Environment variables used:
+//! - `EVM_GAS_STRATEGY_<NETWORK>` — `legacy` or `eip1559` (default: `eip1559` where the network supports it),
+//! - `EVM_GAS_PRICE_MULTIPLIER_<NETWORK>` — multiplier of `eth_gasPrice` for `legacy` (default: `1`),
+//! - `EVM_MAX_PRIORITY_FEE_<NETWORK>` — `maxPriorityFeePerGas` in wei for `eip1559` (default: the node's suggestion),
+//! - `EVM_BASE_FEE_MULTIPLIER_<NETWORK>` — multiplier of the base fee in the max fee for `eip1559` (default: `2`),
+//! - `EVM_FEE_BUMP_AFTER_SECS_<NETWORK>` — how long a transaction may be pending before its fees are bumped
+//!   (default: `0`, never),
+//! - `EVM_FEE_BUMP_PERCENT` — how much each bump raises the fees, at least the `10` nodes require (default: `20`),
+//! - `EVM_FEE_BUMP_MAX` — bumps of one transaction before waiting it out (default: `3`).
+
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::TransactionBuilder;
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::Instrument;
+
+use crate::chain::FacilitatorLocalError;
+use crate::from_env;
+use crate::network::Network;
+
+/// How the fees of a transaction are set, see the [module docs](self).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GasStrategy {
+    /// A single gas price: `eth_gasPrice` times `multiplier`.
+    Legacy { multiplier: f64 },
+    /// EIP-1559 fees: the max fee is the base fee times `base_fee_multiplier` plus the priority fee.
+    Eip1559 {
+        max_priority_fee_per_gas: Option<u128>,
+        base_fee_multiplier: Option<f64>,
+    },
+}
+
+/// Base fee multiplier used when only the priority fee is configured, as in the node's estimate.
+const DEFAULT_BASE_FEE_MULTIPLIER: f64 = 2.0;
+
+impl GasStrategy {
+    /// The default strategy of a network, by whether it supports EIP-1559.
+    pub fn for_eip1559(eip1559: bool) -> Self {
+        if eip1559 {
+            GasStrategy::Eip1559 {
+                max_priority_fee_per_gas: None,
+                base_fee_multiplier: None,
+            }
+        } else {
+            GasStrategy::Legacy { multiplier: 1.0 }
+        }
+    }
+
+    /// Reads the strategy of `network` from `EVM_GAS_STRATEGY_<NETWORK>` and its settings.
+    pub fn from_env(network: Network, eip1559: bool) -> Result<Self, Box<dyn std::error::Error>> {
+        let var = |prefix: &str| {
+            let env_var = from_env::env_name_for_network(prefix, network);
+            std::env::var(&env_var).ok().map(|raw| (env_var, raw))
+        };
+        let strategy = match var(from_env::ENV_EVM_GAS_STRATEGY_PREFIX) {
+            Some((env_var, raw)) => match raw.trim() {
+                "legacy" => false,
+                "eip1559" => true,
+                other => {
+                    return Err(
+                        format!("env {env_var} is invalid: unknown strategy {other}").into(),
+                    );
+                }
+            },
+            None => eip1559,
+        };
+        let strategy = if strategy {
+            GasStrategy::Eip1559 {
+                max_priority_fee_per_gas: var(from_env::ENV_EVM_MAX_PRIORITY_FEE_PREFIX)
+                    .map(|(env_var, raw)| parse(&env_var, &raw))
+                    .transpose()?,
+                base_fee_multiplier: var(from_env::ENV_EVM_BASE_FEE_MULTIPLIER_PREFIX)
+                    .map(|(env_var, raw)| parse_multiplier(&env_var, &raw))
+                    .transpose()?,
+            }
+        } else {
+            GasStrategy::Legacy {
+                multiplier: var(from_env::ENV_EVM_GAS_PRICE_MULTIPLIER_PREFIX)
+                    .map(|(env_var, raw)| parse_multiplier(&env_var, &raw))
+                    .transpose()?
+                    .unwrap_or(1.0),
+            }
+        };
+        Ok(strategy)
+    }
+
+    /// Sets the fees of `txr` by this strategy, none lower than `min_gas_price` if set.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::ContractCall`] if the gas price or fees can not be fetched.
+    pub async fn apply<P: Provider>(
+        &self,
+        provider: &P,
+        min_gas_price: Option<u128>,
+        txr: &mut TransactionRequest,
+    ) -> Result<(), FacilitatorLocalError> {
+        let floor = min_gas_price.unwrap_or_default();
+        match *self {
+            GasStrategy::Legacy { multiplier } => {
+                let gas_price = provider
+                    .get_gas_price()
+                    .instrument(tracing::info_span!("get_gas_price"))
+                    .await
+                    .map_err(FacilitatorLocalError::contract_call)?;
+                txr.set_gas_price(scale(gas_price, multiplier).max(floor));
+            }
+            GasStrategy::Eip1559 {
+                max_priority_fee_per_gas: None,
+                base_fee_multiplier: None,
+            } => {
+                let fees = provider
+                    .estimate_eip1559_fees()
+                    .instrument(tracing::info_span!("estimate_eip1559_fees"))
+                    .await
+                    .map_err(FacilitatorLocalError::contract_call)?;
+                txr.set_max_fee_per_gas(fees.max_fee_per_gas.max(floor));
+                txr.set_max_priority_fee_per_gas(fees.max_priority_fee_per_gas.max(floor));
+            }
+            GasStrategy::Eip1559 {
+                max_priority_fee_per_gas,
+                base_fee_multiplier,
+            } => {
+                let priority_fee = match max_priority_fee_per_gas {
+                    Some(priority_fee) => priority_fee,
+                    None => provider
+                        .get_max_priority_fee_per_gas()
+                        .instrument(tracing::info_span!("get_max_priority_fee_per_gas"))
+                        .await
+                        .map_err(FacilitatorLocalError::contract_call)?,
+                };
+                let base_fee = provider
+                    .get_block_by_number(BlockNumberOrTag::Latest)
+                    .into_future()
+                    .instrument(tracing::info_span!("get_base_fee"))
+                    .await
+                    .map_err(FacilitatorLocalError::contract_call)?
+                    .and_then(|block| block.header.base_fee_per_gas)
+                    .ok_or_else(|| {
+                        FacilitatorLocalError::ContractCall(
+                            "latest block has no base fee: is the network EIP-1559?".to_string(),
+                        )
+                    })?;
+                let base_fee_multiplier =
+                    base_fee_multiplier.unwrap_or(DEFAULT_BASE_FEE_MULTIPLIER);
+                let max_fee =
+                    scale(base_fee.into(), base_fee_multiplier).saturating_add(priority_fee);
+                txr.set_max_fee_per_gas(max_fee.max(floor));
+                txr.set_max_priority_fee_per_gas(priority_fee.max(floor));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replacement of pending transactions at higher fees, see the [module docs](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeBump {
+    /// How long a transaction may be pending before it is replaced.
+    pub after: Duration,
+    /// How much each replacement raises the fees, in percent.
+    pub percent: u64,
+    /// Replacements of one transaction, after which the last one is waited for.
+    pub max_bumps: u32,
+}
+
+impl FeeBump {
+    /// Reads `EVM_FEE_BUMP_AFTER_SECS_<NETWORK>`, `EVM_FEE_BUMP_PERCENT` and `EVM_FEE_BUMP_MAX`;
+    /// `None` if bumping is disabled on `network`.
+    pub fn from_env(network: Network) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let env_var =
+            from_env::env_name_for_network(from_env::ENV_EVM_FEE_BUMP_AFTER_SECS_PREFIX, network);
+        let after: u64 = match std::env::var(&env_var) {
+            Ok(raw) => parse(&env_var, &raw)?,
+            Err(_) => 0,
+        };
+        let percent: u64 = match std::env::var(from_env::ENV_EVM_FEE_BUMP_PERCENT) {
+            Ok(raw) => parse(from_env::ENV_EVM_FEE_BUMP_PERCENT, &raw)?,
+            Err(_) => 20,
+        };
+        let max_bumps: u32 = match std::env::var(from_env::ENV_EVM_FEE_BUMP_MAX) {
+            Ok(raw) => parse(from_env::ENV_EVM_FEE_BUMP_MAX, &raw)?,
+            Err(_) => 3,
+        };
+        Ok((after > 0 && max_bumps > 0).then_some(Self {
+            after: Duration::from_secs(after),
+            percent: percent.max(10),
+            max_bumps,
+        }))
+    }
+
+    /// How long to wait for the receipt of a transaction replaced `bumps` times, with `remaining` left
+    /// of the receipt timeout, and whether to replace it again if it is still pending by then.
+    pub fn next_wait(&self, bumps: u32, remaining: Duration) -> (Duration, bool) {
+        if bumps < self.max_bumps && self.after < remaining {
+            (self.after, true)
+        } else {
+            (remaining, false)
+        }
+    }
+
+    /// Raises the fees already set on `txr` by [`FeeBump::percent`], rounding up.
+    pub fn bump(&self, txr: &mut TransactionRequest) {
+        let raise = |fee: u128| {
+            fee.saturating_mul(100 + u128::from(self.percent))
+                .div_ceil(100)
+                .max(fee + 1)
+        };
+        if let Some(gas_price) = txr.gas_price {
+            txr.set_gas_price(raise(gas_price));
+        }
+        if let Some(max_fee) = txr.max_fee_per_gas {
+            txr.set_max_fee_per_gas(raise(max_fee));
+        }
+        if let Some(priority_fee) = txr.max_priority_fee_per_gas {
+            txr.set_max_priority_fee_per_gas(raise(priority_fee));
+        }
+    }
+}
+
+/// `value` times `multiplier`, in thousandths.
+fn scale(value: u128, multiplier: f64) -> u128 {
+    let permille = (multiplier * 1000.0).round() as u128;
+    value.saturating_mul(permille) / 1000
+}
+
+fn parse<T: FromStr>(env_var: &str, raw: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    raw.trim()
+        .parse()
+        .map_err(|e| format!("env {env_var} is invalid: {e}"))
+}
+
+fn parse_multiplier(env_var: &str, raw: &str) -> Result<f64, String> {
+    let multiplier: f64 = parse(env_var, raw)?;
+    if multiplier.is_finite() && multiplier > 0.0 {
+        Ok(multiplier)
+    } else {
+        Err(format!(
+            "env {env_var} is invalid: must be a positive number"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::providers::ProviderBuilder;
+    use alloy::providers::mock::Asserter;
+    use alloy::rpc::types::{Block, Header};
+
+    #[test]
+    fn test_fee_bump_settings_must_parse() {
+        let after_env = from_env::env_name_for_network(
+            from_env::ENV_EVM_FEE_BUMP_AFTER_SECS_PREFIX,
+            Network::BaseSepolia,
+        );
+        unsafe { std::env::set_var(&after_env, "30") };
+        unsafe { std::env::set_var(from_env::ENV_EVM_FEE_BUMP_PERCENT, "5") };
+        let fee_bump = FeeBump::from_env(Network::BaseSepolia).unwrap().unwrap();
+        assert_eq!(fee_bump.after, Duration::from_secs(30));
+        assert_eq!(fee_bump.percent, 10);
+        assert_eq!(fee_bump.max_bumps, 3);
+        unsafe { std::env::set_var(from_env::ENV_EVM_FEE_BUMP_PERCENT, "twenty") };
+        assert!(FeeBump::from_env(Network::BaseSepolia).is_err());
+        unsafe { std::env::remove_var(from_env::ENV_EVM_FEE_BUMP_PERCENT) };
+        unsafe { std::env::set_var(from_env::ENV_EVM_FEE_BUMP_MAX, "-1") };
+        assert!(FeeBump::from_env(Network::BaseSepolia).is_err());
+        unsafe { std::env::remove_var(from_env::ENV_EVM_FEE_BUMP_MAX) };
+        unsafe { std::env::remove_var(&after_env) };
+        assert_eq!(FeeBump::from_env(Network::BaseSepolia).unwrap(), None);
+    }
+
+    #[test]
+    fn test_fee_bumps_stop_at_the_limit_or_the_timeout() {
+        let bump = FeeBump {
+            after: Duration::from_secs(30),
+            percent: 20,
+            max_bumps: 2,
+        };
+        let minute = Duration::from_secs(60);
+        assert_eq!(bump.next_wait(0, minute), (bump.after, true));
+        assert_eq!(bump.next_wait(1, minute), (bump.after, true));
+        // Out of bumps, the last replacement is waited for until the timeout.
+        assert_eq!(bump.next_wait(2, minute), (minute, false));
+        // A bump that could not be waited for is not sent.
+        let soon = Duration::from_secs(10);
+        assert_eq!(bump.next_wait(0, soon), (soon, false));
+    }
+
+    #[tokio::test]
+    async fn test_apply_prices_by_strategy_and_floor() {
+        let asserter = Asserter::new();
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .connect_mocked_client(asserter.clone());
+
+        asserter.push_success(&"0x3e8");
+        let mut legacy = TransactionRequest::default();
+        GasStrategy::Legacy { multiplier: 1.5 }
+            .apply(&provider, None, &mut legacy)
+            .await
+            .unwrap();
+        assert_eq!(legacy.gas_price, Some(1_500));
+        assert_eq!(legacy.max_fee_per_gas, None);
+
+        asserter.push_success(&"0x3e8");
+        let mut floored = TransactionRequest::default();
+        GasStrategy::Legacy { multiplier: 1.0 }
+            .apply(&provider, Some(5_000), &mut floored)
+            .await
+            .unwrap();
+        assert_eq!(floored.gas_price, Some(5_000));
+
+        let mut header = Header::<alloy::consensus::Header>::default();
+        header.inner.base_fee_per_gas = Some(100);
+        asserter.push_success(&Block::<alloy::rpc::types::Transaction>::empty(header));
+        let mut eip1559 = TransactionRequest::default();
+        GasStrategy::Eip1559 {
+            max_priority_fee_per_gas: Some(7),
+            base_fee_multiplier: None,
+        }
+        .apply(&provider, None, &mut eip1559)
+        .await
+        .unwrap();
+        assert_eq!(eip1559.max_fee_per_gas, Some(207));
+        assert_eq!(eip1559.max_priority_fee_per_gas, Some(7));
+        assert_eq!(eip1559.gas_price, None);
+    }
+
+    #[tokio::test]
+    async fn test_min_gas_price_floors_both_eip1559_fees() {
+        let asserter = Asserter::new();
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .connect_mocked_client(asserter.clone());
+        let strategy = GasStrategy::Eip1559 {
+            max_priority_fee_per_gas: Some(7),
+            base_fee_multiplier: None,
+        };
+        let latest = || {
+            let mut header = Header::<alloy::consensus::Header>::default();
+            header.inner.base_fee_per_gas = Some(100);
+            Block::<alloy::rpc::types::Transaction>::empty(header)
+        };
+
+        asserter.push_success(&latest());
+        let mut floored = TransactionRequest::default();
+        strategy
+            .apply(&provider, Some(500), &mut floored)
+            .await
+            .unwrap();
+        assert_eq!(floored.max_fee_per_gas, Some(500));
+        assert_eq!(floored.max_priority_fee_per_gas, Some(500));
+
+        // A floor below the fees leaves them as they are.
+        asserter.push_success(&latest());
+        let mut above = TransactionRequest::default();
+        strategy
+            .apply(&provider, Some(5), &mut above)
+            .await
+            .unwrap();
+        assert_eq!(above.max_fee_per_gas, Some(207));
+        assert_eq!(above.max_priority_fee_per_gas, Some(7));
+    }
+
+    #[test]
+    fn test_bump_raises_every_fee_set() {
+        let bump = FeeBump {
+            after: Duration::from_secs(30),
+            percent: 20,
+            max_bumps: 3,
+        };
+        let mut legacy = TransactionRequest::default().with_gas_price(1_000);
+        bump.bump(&mut legacy);
+        assert_eq!(legacy.gas_price, Some(1_200));
+        assert_eq!(legacy.max_fee_per_gas, None);
+
+        let mut eip1559 = TransactionRequest::default()
+            .with_max_fee_per_gas(2_001)
+            .with_max_priority_fee_per_gas(1);
+        bump.bump(&mut eip1559);
+        assert_eq!(eip1559.max_fee_per_gas, Some(2_402));
+        assert_eq!(eip1559.max_priority_fee_per_gas, Some(2));
+
+        assert_eq!(scale(1_000_000, 1.25), 1_250_000);
+    }
+}
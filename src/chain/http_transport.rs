@@ -0,0 +1,260 @@
+//! HTTP JSON-RPC transport that caps response sizes and tells timeouts apart.
+//!
+//! A malicious or buggy RPC provider could answer with an enormous body, e.g. a huge trace,
+//! and exhaust the facilitator's memory. [`LimitedHttp`] reads response bodies chunk by chunk
+//! and aborts with an error once they exceed a configured size, instead of buffering them whole.
+//! Normal JSON-RPC responses are far below any reasonable limit.
+//!
+//! Requests are also bounded by separate connect, read and total timeouts, see [`RpcTimeouts`].
+//! When one fires, the error names it as an [`RpcTimeout`], so a DNS or connection problem can be
+//! told apart from a slow node.
+
+use alloy::rpc::json_rpc::{RequestPacket, ResponsePacket};
+use alloy::transports::http::reqwest;
+use alloy::transports::{TransportError, TransportErrorKind, TransportFut, TransportResult};
+use std::task;
+use std::time::Duration;
+use tower::Service;
+use tracing::Instrument;
+use url::Url;
+
+use crate::from_env;
+use crate::network::Network;
+
+/// Default cap on RPC response bodies: 10 MiB.
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 10 * 1024 * 1024;
+
+/// The RPC timeout that fired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum RpcTimeout {
+    /// No connection to the node could be established in time, e.g. slow DNS or an unreachable host.
+    #[error("RPC connect timeout after {0:?}")]
+    Connect(Duration),
+    /// The node stopped sending data for too long.
+    #[error("RPC read timeout after {0:?}")]
+    Read(Duration),
+    /// The whole request took too long.
+    #[error("RPC request timeout after {0:?}")]
+    Total(Duration),
+}
+
+/// Connect, read and total timeouts of RPC requests. `None` leaves a timeout unbounded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RpcTimeouts {
+    pub connect: Option<Duration>,
+    pub read: Option<Duration>,
+    pub total: Option<Duration>,
+}
+
+impl RpcTimeouts {
+    /// Reads the timeouts for `network` from `RPC_CONNECT_TIMEOUT_MS_<NETWORK>`,
+    /// `RPC_READ_TIMEOUT_MS_<NETWORK>` and `RPC_TIMEOUT_MS_<NETWORK>`.
+    pub fn from_env(network: Network) -> Result<Self, Box<dyn std::error::Error>> {
+        let read = |prefix: &str| -> Result<Option<Duration>, Box<dyn std::error::Error>> {
+            let env_var = from_env::env_name_for_network(prefix, network);
+            match std::env::var(&env_var) {
+                Ok(raw) => {
+                    let millis: u64 = raw
+                        .trim()
+                        .parse()
+                        .map_err(|e| format!("env {env_var} is invalid: {e}"))?;
+                    Ok((millis > 0).then(|| Duration::from_millis(millis)))
+                }
+                Err(_) => Ok(None),
+            }
+        };
+        Ok(Self {
+            connect: read(from_env::ENV_RPC_CONNECT_TIMEOUT_MS_PREFIX)?,
+            read: read(from_env::ENV_RPC_READ_TIMEOUT_MS_PREFIX)?,
+            total: read(from_env::ENV_RPC_TIMEOUT_MS_PREFIX)?,
+        })
+    }
+}
+
+/// HTTP transport rejecting responses larger than `max_response_bytes`, with [`RpcTimeouts`].
+#[derive(Debug, Clone)]
+pub struct LimitedHttp {
+    client: reqwest::Client,
+    url: Url,
+    max_response_bytes: usize,
+    timeouts: RpcTimeouts,
+}
+
+impl LimitedHttp {
+    /// Creates a transport for `url` that reads at most `max_response_bytes` of each response.
+    pub fn new(
+        url: Url,
+        max_response_bytes: usize,
+        timeouts: RpcTimeouts,
+    ) -> Result<Self, reqwest::Error> {
+        let mut client = reqwest::Client::builder();
+        if let Some(connect) = timeouts.connect {
+            client = client.connect_timeout(connect);
+        }
+        if let Some(read) = timeouts.read {
+            client = client.read_timeout(read);
+        }
+        Ok(Self {
+            client: client.build()?,
+            url,
+            max_response_bytes,
+            timeouts,
+        })
+    }
+
+    /// Maps a reqwest error to a transport error, naming the timeout if one fired.
+    fn transport_error(&self, error: reqwest::Error) -> TransportError {
+        if error.is_timeout() {
+            let timeout = match (
+                error.is_connect(),
+                self.timeouts.connect,
+                self.timeouts.read,
+            ) {
+                (true, Some(connect), _) => Some(RpcTimeout::Connect(connect)),
+                (false, _, Some(read)) => Some(RpcTimeout::Read(read)),
+                _ => None,
+            };
+            if let Some(timeout) = timeout {
+                return TransportErrorKind::custom(timeout);
+            }
+        }
+        TransportErrorKind::custom(error)
+    }
+
+    async fn request(self, request: RequestPacket) -> TransportResult<ResponsePacket> {
+        match self.timeouts.total {
+            Some(total) => tokio::time::timeout(total, self.clone().request_once(request))
+                .await
+                .unwrap_or_else(|_| Err(TransportErrorKind::custom(RpcTimeout::Total(total)))),
+            None => self.request_once(request).await,
+        }
+    }
+
+    async fn request_once(self, request: RequestPacket) -> TransportResult<ResponsePacket> {
+        let too_large = |size: String| {
+            TransportErrorKind::custom_str(&format!(
+                "RPC response of {size} bytes exceeds the limit of {} bytes",
+                self.max_response_bytes
+            ))
+        };
+        let mut response = self
+            .client
+            .post(self.url.clone())
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| self.transport_error(e))?;
+        let status = response.status();
+        if let Some(length) = response.content_length()
+            && length > self.max_response_bytes as u64
+        {
+            return Err(too_large(length.to_string()));
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| self.transport_error(e))?
+        {
+            if body.len() + chunk.len() > self.max_response_bytes {
+                return Err(too_large(format!("more than {}", body.len() + chunk.len())));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        if !status.is_success() {
+            return Err(TransportErrorKind::http_error(
+                status.as_u16(),
+                String::from_utf8_lossy(&body).into_owned(),
+            ));
+        }
+        serde_json::from_slice(&body)
+            .map_err(|err| TransportError::deser_err(err, String::from_utf8_lossy(&body)))
+    }
+}
+
+impl Service<RequestPacket> for LimitedHttp {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut task::Context<'_>) -> task::Poll<Result<(), Self::Error>> {
+        task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let this = self.clone();
+        let span = tracing::debug_span!("LimitedHttp", url = %this.url);
+        Box::pin(this.request(request).instrument(span))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::FacilitatorLocalError;
+    use alloy::primitives::U64;
+    use alloy::rpc::client::RpcClient;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answers each connection with the next of `responses`, then closes it.
+    async fn serve(responses: Vec<String>) -> Url {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 4096];
+                let _ = socket.read(&mut request).await;
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        url.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_responses_over_the_limit_are_refused() {
+        let small = r#"{"jsonrpc":"2.0","id":0,"result":"0x2105"}"#;
+        let large = format!(
+            r#"{{"jsonrpc":"2.0","id":0,"result":"0x{}"}}"#,
+            "0".repeat(256)
+        );
+        let url = serve(vec![
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{small}",
+                small.len()
+            ),
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{large}",
+                large.len()
+            ),
+            format!("HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n{large}"),
+        ])
+        .await;
+        let transport = LimitedHttp::new(url, 128, RpcTimeouts::default()).unwrap();
+        let client = RpcClient::new(transport, true);
+
+        let chain_id: U64 = client.request_noparams("eth_chainId").await.unwrap();
+        assert_eq!(chain_id, U64::from(8453));
+        // Refused by its declared length, and by what arrived of it when no length is declared.
+        for _ in 0..2 {
+            let error = client
+                .request_noparams::<U64>("eth_chainId")
+                .await
+                .unwrap_err();
+            assert!(
+                error.to_string().contains("exceeds the limit of 128 bytes"),
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rpc_timeout_survives_error_wrapping() {
+        let timeout = RpcTimeout::Read(Duration::from_secs(5));
+        let error = alloy::contract::Error::TransportError(TransportErrorKind::custom(timeout));
+        assert!(matches!(
+            FacilitatorLocalError::contract_call(error),
+            FacilitatorLocalError::RpcTimeout(message) if message == "RPC read timeout after 5s"
+        ));
+    }
+}
@@ -1,19 +1,39 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
 use std::time::SystemTimeError;
 
 use crate::chain::evm::EvmProvider;
 use crate::chain::solana::SolanaProvider;
 use crate::facilitator::Facilitator;
+use crate::from_env;
+use crate::health::HealthReport;
 use crate::network::{Network, NetworkFamily};
 use crate::types::{
-    MixedAddress, Scheme, SettleRequest, SettleResponse, SupportedPaymentKindsResponse,
+    AuthorizationKind, CancelRequest, CancelResponse, MixedAddress, QuoteRequest, QuoteResponse,
+    Scheme, SettleRequest, SettleResponse, SupportedPaymentKindsResponse, TransactionHash,
     VerifyRequest, VerifyResponse,
 };
 
 pub mod evm;
+pub mod finality;
+pub mod forwarding;
+pub mod gas_strategy;
+pub mod http_transport;
+pub mod nonce_filter;
+pub mod nonce_rules;
+pub mod nonce_store;
+pub mod revert;
+pub mod rpc_retry;
 pub mod solana;
+pub mod submission;
+pub mod sweep;
+pub mod token_list;
+pub mod token_registry;
+pub mod value_model;
+pub mod verified_authorizations;
 
 pub enum NetworkProvider {
-    Evm(EvmProvider),
+    Evm(Box<EvmProvider>),
     Solana(SolanaProvider),
 }
 
@@ -29,7 +49,7 @@ impl FromEnvByNetworkBuild for NetworkProvider {
         let provider = match family {
             NetworkFamily::Evm => {
                 let provider = EvmProvider::from_env(network).await?;
-                provider.map(NetworkProvider::Evm)
+                provider.map(|provider| NetworkProvider::Evm(Box::new(provider)))
             }
             NetworkFamily::Solana => {
                 let provider = SolanaProvider::from_env(network).await?;
@@ -41,12 +61,84 @@ impl FromEnvByNetworkBuild for NetworkProvider {
 }
 
 pub trait NetworkProviderOps {
-    fn signer_address(&self) -> MixedAddress;
+    /// The address transactions are sent from, or `None` for a verify-only provider.
+    fn signer_address(&self) -> Option<MixedAddress>;
     fn network(&self) -> Network;
 }
 
+impl NetworkProvider {
+    /// Whether `transaction` succeeded, or `None` if it is not mined.
+    pub async fn transaction_status(
+        &self,
+        transaction: &TransactionHash,
+    ) -> Result<Option<bool>, FacilitatorLocalError> {
+        match (self, transaction) {
+            (NetworkProvider::Evm(provider), TransactionHash::Evm(hash)) => {
+                provider.transaction_status(*hash).await
+            }
+            (NetworkProvider::Solana(provider), TransactionHash::Solana(signature)) => {
+                provider.transaction_status(*signature).await
+            }
+            _ => Err(FacilitatorLocalError::DecodingError(format!(
+                "{transaction} is not a transaction of {}",
+                self.network()
+            ))),
+        }
+    }
+}
+
+/// USD prices of the networks' native currencies, set once at startup by [`native_usd_prices_from_env`].
+static NATIVE_USD_PRICES: OnceLock<HashMap<Network, f64>> = OnceLock::new();
+
+/// Reads the native currency prices from `NATIVE_USD_PRICE_<NETWORK>`, for [`native_usd_price`].
+///
+/// # Errors
+/// Returns an error if a price is not a non-negative number, or if the prices were already read.
+pub fn native_usd_prices_from_env() -> Result<(), String> {
+    let mut prices = HashMap::new();
+    for network in Network::variants() {
+        let env_var =
+            from_env::env_name_for_network(from_env::ENV_NATIVE_USD_PRICE_PREFIX, *network);
+        let Ok(raw) = std::env::var(&env_var) else {
+            continue;
+        };
+        let price = raw
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| format!("env {env_var} is invalid: {e}"))?;
+        if !price.is_finite() || price < 0.0 {
+            return Err(format!("env {env_var} is not a non-negative price: {raw}"));
+        }
+        prices.insert(*network, price);
+    }
+    NATIVE_USD_PRICES
+        .set(prices)
+        .map_err(|_| "native currency prices are already set".to_string())
+}
+
+/// The USD price of the native currency of `network` read by [`native_usd_prices_from_env`], if set.
+pub fn native_usd_price(network: Network) -> Option<f64> {
+    NATIVE_USD_PRICES.get()?.get(&network).copied()
+}
+
+/// `total_cost`, in the smallest unit of a native currency with `decimals`, converted to USD
+/// at the price in `NATIVE_USD_PRICE_<NETWORK>`, if set.
+pub fn native_cost_usd(
+    network: Network,
+    total_cost: alloy::primitives::U256,
+    decimals: u8,
+) -> Option<f64> {
+    usd_value(total_cost, decimals, native_usd_price(network)?)
+}
+
+/// `amount`, in the smallest unit of a currency with `decimals`, converted to USD at `price`.
+pub fn usd_value(amount: alloy::primitives::U256, decimals: u8, price: f64) -> Option<f64> {
+    let amount: f64 = amount.to_string().parse().ok()?;
+    Some(amount / 10f64.powi(decimals.into()) * price)
+}
+
 impl NetworkProviderOps for NetworkProvider {
-    fn signer_address(&self) -> MixedAddress {
+    fn signer_address(&self) -> Option<MixedAddress> {
         match self {
             NetworkProvider::Evm(provider) => provider.signer_address(),
             NetworkProvider::Solana(provider) => provider.signer_address(),
@@ -84,9 +176,43 @@ impl Facilitator for NetworkProvider {
             NetworkProvider::Solana(provider) => provider.supported().await,
         }
     }
+
+    async fn quote(&self, request: &QuoteRequest) -> Option<Result<QuoteResponse, Self::Error>> {
+        match self {
+            NetworkProvider::Evm(provider) => provider.quote(request).await,
+            NetworkProvider::Solana(provider) => provider.quote(request).await,
+        }
+    }
+
+    async fn cancel(&self, request: &CancelRequest) -> Option<Result<CancelResponse, Self::Error>> {
+        match self {
+            NetworkProvider::Evm(provider) => provider.cancel(request).await,
+            NetworkProvider::Solana(provider) => provider.cancel(request).await,
+        }
+    }
+
+    /// Looks `transaction` up on this provider's network, see [`NetworkProvider::transaction_status`].
+    async fn settlement_status(
+        &self,
+        _network: Network,
+        transaction: &TransactionHash,
+    ) -> Option<Result<Option<bool>, Self::Error>> {
+        Some(self.transaction_status(transaction).await)
+    }
+
+    async fn health(&self) -> HealthReport {
+        match self {
+            NetworkProvider::Evm(provider) => {
+                HealthReport::probe(provider.network(), provider.latest_block()).await
+            }
+            NetworkProvider::Solana(provider) => {
+                HealthReport::probe(provider.network(), provider.latest_slot()).await
+            }
+        }
+    }
 }
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum FacilitatorLocalError {
     /// The network is not supported by this facilitator.
     #[error("Unsupported network")]
@@ -97,6 +223,14 @@ pub enum FacilitatorLocalError {
     /// Scheme mismatch.
     #[error("Scheme mismatch: expected {1}, actual {2}")]
     SchemeMismatch(Option<MixedAddress>, Scheme, Scheme),
+    /// The scheme is not offered for this token on this network.
+    #[error("Scheme {1} is not offered for token {2}")]
+    UnsupportedScheme(Option<MixedAddress>, Scheme, MixedAddress),
+    /// The token is not approved for settlement by this facilitator.
+    #[error("{1} is not offered for token {2}")]
+    UnsupportedAuthorizationKind(Option<MixedAddress>, AuthorizationKind, MixedAddress),
+    #[error("Token {1} is not approved")]
+    UnsupportedAsset(Option<MixedAddress>, MixedAddress),
     /// Invalid address.
     #[error("Invalid address: {0}")]
     InvalidAddress(String),
@@ -112,6 +246,9 @@ pub enum FacilitatorLocalError {
     /// Low-level contract interaction failure (e.g. call failed, method not found).
     #[error("Invalid contract call: {0}")]
     ContractCall(String),
+    /// A contract call reverted; carries the payer, if known, and the decoded revert reason, see [`revert`].
+    #[error("Transaction reverted: {1}")]
+    Reverted(Option<MixedAddress>, String),
     /// EIP-712 signature is invalid or mismatched.
     #[error("Invalid signature: {1}")]
     InvalidSignature(MixedAddress, String),
@@ -121,7 +258,91 @@ pub enum FacilitatorLocalError {
     /// The payload's `value` is not enough to meet the requirements.
     #[error("Insufficient value")]
     InsufficientValue(MixedAddress),
+    /// The payload's `value` exceeds what the requirements' amount scheme allows.
+    #[error("Excess value")]
+    ExcessValue(MixedAddress),
+    /// The payload's `value` exceeds the token's configured ceiling or total supply.
+    #[error("Value out of range: {1}")]
+    ValueOutOfRange(MixedAddress, String),
+    /// A cancellation was requested for an authorization this facilitator has not verified.
+    #[error("Authorization not verified by this facilitator")]
+    UnknownAuthorization(MixedAddress),
+    /// The payment's amount is below the token's configured `minAmount`.
+    #[error("Below minimum: {1}")]
+    BelowMinimum(MixedAddress, String),
+    /// The payment's amount is above the token's configured `maxAmount`.
+    #[error("Above maximum: {1}")]
+    AboveMaximum(MixedAddress, String),
+    /// The requirements' payment splits are malformed or do not add up to the authorized value.
+    #[error("Invalid payment splits: {1}")]
+    InvalidSplits(MixedAddress, String),
     /// The payload decoding failed.
     #[error("Decoding error: {0}")]
     DecodingError(String),
+    /// The authorization nonce was already used on-chain.
+    #[error("Authorization already used")]
+    AuthorizationUsed(MixedAddress),
+    /// The authorization is already being settled by this facilitator.
+    #[error("Authorization already submitted for settlement")]
+    NonceReused(MixedAddress),
+    /// The authorization nonce is not the one the merchant expects for the order, or, for a payment
+    /// forwarded by a facilitator signer, does not commit to its receivers.
+    #[error("Nonce does not match the order or the receivers it is forwarded to")]
+    UnexpectedNonce(MixedAddress),
+    /// The authorization nonce breaks a configured nonce rule.
+    #[error("Suspicious nonce: {1}")]
+    SuspiciousNonce(MixedAddress, String),
+    /// The token's proxy implementation is not the one the requirements were issued against.
+    #[error("Token implementation changed: {1}")]
+    ImplementationChanged(MixedAddress, String),
+    /// The requirements are not signed by the registered merchant key for their `payTo`.
+    #[error("Invalid merchant intent: {0}")]
+    InvalidMerchantIntent(String),
+    /// The requirements' `resource` is not allowed for their `payTo`.
+    #[error("Resource not allowed: {0}")]
+    ResourceNotAllowed(String),
+    /// A receiver of the requirements is not on the network's receiver allowlist.
+    #[error("Receiver not allowed: {0}")]
+    ReceiverNotAllowed(String),
+    /// An RPC request to the node timed out; names the timeout that fired.
+    #[error("{0}")]
+    RpcTimeout(String),
+    /// The settlement transaction was reorged out before reaching the required depth.
+    #[error("Transaction reorged out: {0}")]
+    Reorged(String),
+    /// The facilitator runs without a signer and only verifies payments.
+    #[error("Settlement is not enabled on this facilitator")]
+    SettlementDisabled,
+    /// The facilitator is in maintenance mode and refuses settlements.
+    #[error("Settlement is paused for maintenance")]
+    Maintenance,
+}
+
+impl FacilitatorLocalError {
+    /// Wraps a failed RPC or contract call, as [`FacilitatorLocalError::RpcTimeout`] if an
+    /// [`RpcTimeout`](http_transport::RpcTimeout) caused it, as [`FacilitatorLocalError::Reverted`] if
+    /// the call reverted, otherwise as [`FacilitatorLocalError::ContractCall`].
+    pub fn contract_call<E: std::error::Error + 'static>(error: E) -> Self {
+        if let Some(reason) = revert::reason(&error) {
+            return FacilitatorLocalError::Reverted(None, reason);
+        }
+        let mut cause: Option<&(dyn std::error::Error + 'static)> = Some(&error);
+        while let Some(current) = cause {
+            if let Some(timeout) = current.downcast_ref::<http_transport::RpcTimeout>() {
+                return FacilitatorLocalError::RpcTimeout(timeout.to_string());
+            }
+            cause = current.source();
+        }
+        FacilitatorLocalError::ContractCall(format!("{error:?}"))
+    }
+
+    /// This error, naming `payer` if it is a [`FacilitatorLocalError::Reverted`] without one.
+    pub fn with_payer(self, payer: MixedAddress) -> Self {
+        match self {
+            FacilitatorLocalError::Reverted(None, reason) => {
+                FacilitatorLocalError::Reverted(Some(payer), reason)
+            }
+            error => error,
+        }
+    }
 }
@@ -0,0 +1,89 @@
+//! Bloom filter of ERC-3009 authorization nonces this facilitator has settled.
+//!
+//! Checking `authorizationState` for every payment costs an RPC call. The filter answers
+//! "definitely never settled here" for almost every fresh nonce without one, and only the
+//! possible duplicates are checked on-chain. False positives only cost that extra call;
+//! there are no false negatives for nonces settled by this process.
+//!
+//! The filter lives in memory: after a restart it is empty, and replays are left to the
+//! transfer simulation done on every verification, as without the filter.
+
+use alloy::primitives::{Address, B256, keccak256};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Fixed-size, lock-free Bloom filter of `(chain, token, payer, nonce)` authorizations.
+#[derive(Debug)]
+pub struct NonceFilter {
+    words: Vec<AtomicU64>,
+    hashes: u32,
+}
+
+impl NonceFilter {
+    /// Creates a filter with at least `bits` bits, setting `hashes` bits per nonce.
+    pub fn new(bits: usize, hashes: u32) -> Self {
+        let words = bits.div_ceil(64).max(1);
+        Self {
+            words: (0..words).map(|_| AtomicU64::new(0)).collect(),
+            hashes: hashes.max(1),
+        }
+    }
+
+    fn key(chain_id: u64, token: Address, payer: Address, nonce: B256) -> B256 {
+        let mut preimage = Vec::with_capacity(8 + 20 + 20 + 32);
+        preimage.extend_from_slice(&chain_id.to_be_bytes());
+        preimage.extend_from_slice(token.as_slice());
+        preimage.extend_from_slice(payer.as_slice());
+        preimage.extend_from_slice(nonce.as_slice());
+        keccak256(preimage)
+    }
+
+    /// Bit positions for `key`, by double hashing two halves of the digest.
+    fn positions(&self, key: B256) -> impl Iterator<Item = usize> + '_ {
+        let h1 = u64::from_be_bytes(key[0..8].try_into().unwrap());
+        let h2 = u64::from_be_bytes(key[8..16].try_into().unwrap()) | 1;
+        let bits = (self.words.len() * 64) as u64;
+        (0..u64::from(self.hashes))
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bits) as usize)
+    }
+
+    /// Records a settled authorization.
+    pub fn insert(&self, chain_id: u64, token: Address, payer: Address, nonce: B256) {
+        for position in self.positions(Self::key(chain_id, token, payer, nonce)) {
+            self.words[position / 64].fetch_or(1 << (position % 64), Ordering::Relaxed);
+        }
+    }
+
+    /// Whether the authorization may have been settled. `false` means it definitely was not.
+    pub fn may_contain(&self, chain_id: u64, token: Address, payer: Address, nonce: B256) -> bool {
+        self.positions(Self::key(chain_id, token, payer, nonce))
+            .all(|position| {
+                self.words[position / 64].load(Ordering::Relaxed) & (1 << (position % 64)) != 0
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonce_filter_has_no_false_negatives() {
+        let filter = NonceFilter::new(4096, 4);
+        let token = Address::repeat_byte(0x11);
+        let payer = Address::repeat_byte(0x22);
+        let nonces: Vec<B256> = (0u64..100).map(|i| keccak256(i.to_be_bytes())).collect();
+        assert!(!filter.may_contain(8453, token, payer, nonces[0]));
+        for nonce in &nonces {
+            filter.insert(8453, token, payer, *nonce);
+        }
+        assert!(
+            nonces
+                .iter()
+                .all(|nonce| filter.may_contain(8453, token, payer, *nonce))
+        );
+        let fresh = (1000u64..1100)
+            .filter(|i| filter.may_contain(8453, token, payer, keccak256(i.to_be_bytes())))
+            .count();
+        assert!(fresh < 10, "too many false positives: {fresh}");
+    }
+}
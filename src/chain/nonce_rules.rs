@@ -0,0 +1,151 @@
+//! Heuristic rules rejecting suspicious ERC-3009 authorization nonces.
+//!
+//! Nonces are meant to be random 32-byte values. A nonce that is all zeros, one repeated byte,
+//! a small counter, or inside a range known to be abused points at a crafted replay attempt
+//! or a buggy client. When rules are configured, such authorizations are rejected at
+//! verification with the name of the rule they broke.
+//!
+//! Environment variables used:
+//! - `EVM_NONCE_RULES` — comma-separated rules, disabled when unset:
+//!   - `zero`: the nonce is all zeros,
+//!   - `repeated`: all 32 bytes of the nonce are the same,
+//!   - `small`: the nonce is below 2^64, as sequential counters are,
+//!   - `range:<from>-<to>`: the nonce is within the inclusive range, given as hex or decimal integers.
+
+use alloy::primitives::{B256, U256};
+use std::str::FromStr;
+
+use crate::from_env::ENV_EVM_NONCE_RULES;
+
+/// A single nonce rule, see the [module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NonceRule {
+    Zero,
+    Repeated,
+    Small,
+    Range(U256, U256),
+}
+
+impl NonceRule {
+    /// Returns whether `nonce` breaks this rule.
+    pub fn matches(&self, nonce: &B256) -> bool {
+        match self {
+            NonceRule::Zero => nonce.is_zero(),
+            NonceRule::Repeated => nonce.iter().all(|byte| *byte == nonce[0]),
+            NonceRule::Small => nonce[..24].iter().all(|byte| *byte == 0),
+            NonceRule::Range(from, to) => {
+                let value = U256::from_be_bytes(nonce.0);
+                *from <= value && value <= *to
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for NonceRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NonceRule::Zero => write!(f, "zero"),
+            NonceRule::Repeated => write!(f, "repeated"),
+            NonceRule::Small => write!(f, "small"),
+            NonceRule::Range(from, to) => write!(f, "range:{from:#x}-{to:#x}"),
+        }
+    }
+}
+
+impl FromStr for NonceRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zero" => Ok(NonceRule::Zero),
+            "repeated" => Ok(NonceRule::Repeated),
+            "small" => Ok(NonceRule::Small),
+            _ => {
+                let range = s
+                    .strip_prefix("range:")
+                    .ok_or_else(|| format!("unknown nonce rule {s}"))?;
+                let (from, to) = range.split_once('-').ok_or_else(|| {
+                    format!("nonce rule {s} is not of the form range:<from>-<to>")
+                })?;
+                let parse = |bound: &str| {
+                    U256::from_str(bound.trim())
+                        .map_err(|e| format!("nonce rule {s} has an invalid bound: {e}"))
+                };
+                Ok(NonceRule::Range(parse(from)?, parse(to)?))
+            }
+        }
+    }
+}
+
+/// Configured set of [`NonceRule`]s.
+#[derive(Debug, Clone, Default)]
+pub struct NonceRules {
+    rules: Vec<NonceRule>,
+}
+
+impl NonceRules {
+    pub fn new(rules: Vec<NonceRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Reads the rules from `EVM_NONCE_RULES`, or returns `None` if not set.
+    pub fn from_env() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let Ok(raw) = std::env::var(ENV_EVM_NONCE_RULES) else {
+            return Ok(None);
+        };
+        let rules = raw
+            .split(',')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(NonceRule::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("env {ENV_EVM_NONCE_RULES} is invalid: {e}"))?;
+        Ok(Some(Self::new(rules)))
+    }
+
+    /// Returns the first rule `nonce` breaks, if any.
+    pub fn violated_by(&self, nonce: &B256) -> Option<&NonceRule> {
+        self.rules.iter().find(|rule| rule.matches(nonce))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonce_rules() {
+        let rules = NonceRules::new(
+            [
+                "zero",
+                "repeated",
+                "small",
+                "range:0x1000000000000000000-0x2000000000000000000",
+            ]
+            .into_iter()
+            .map(|rule| rule.parse().unwrap())
+            .collect(),
+        );
+        assert_eq!(rules.violated_by(&B256::ZERO), Some(&NonceRule::Zero));
+        assert_eq!(
+            rules.violated_by(&B256::repeat_byte(0xff)),
+            Some(&NonceRule::Repeated)
+        );
+        assert_eq!(
+            rules.violated_by(&B256::from(U256::from(42u64))),
+            Some(&NonceRule::Small)
+        );
+        let abused = B256::from(U256::from(0x1800000000000000000u128));
+        assert!(matches!(
+            rules.violated_by(&abused),
+            Some(NonceRule::Range(..))
+        ));
+        let random = B256::from([
+            0x3a, 0x91, 0x07, 0xc4, 0x5e, 0x22, 0xd8, 0x6b, 0x10, 0xf3, 0x8c, 0x47, 0x99, 0x0e,
+            0xa5, 0x71, 0x2d, 0xbe, 0x64, 0x03, 0xcf, 0x58, 0x1a, 0xe7, 0x36, 0x84, 0xfb, 0x29,
+            0x5d, 0xc0, 0x12, 0x9f,
+        ]);
+        assert_eq!(rules.violated_by(&random), None);
+        assert!("sequential".parse::<NonceRule>().is_err());
+    }
+}
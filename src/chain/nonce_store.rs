@@ -0,0 +1,124 @@
+//! Replay protection for ERC-3009 authorizations in flight.
+//!
+//! An authorization can be submitted to `/settle` several times before its first settlement lands
+//! on-chain, when `authorizationState` still reports the nonce as unused. To refuse such replays,
+//! `/settle` claims the authorization in a [`NonceStore`] before sending the transaction, and
+//! `/verify` rejects authorizations that are claimed. A claim is released if the transaction is
+//! not sent or reverts, and otherwise expires with the authorization's `validBefore`, after which
+//! it cannot be settled anyway.
+//!
+//! [`InMemoryNonceStore`] protects a single facilitator instance. Instances sharing traffic need a
+//! shared backend, such as Redis, implementing [`NonceStore`].
+//!
+//! Environment variables used:
+//! - `EVM_REPLAY_PROTECTION` — set to `false` to disable claims (default: `true`).
+
+use alloy::primitives::{Address, B256};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::from_env;
+use crate::timestamp::UnixTimestamp;
+
+/// An authorization: `nonce` of `authorizer` for `token` on chain `chain_id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NonceKey {
+    pub chain_id: u64,
+    pub token: Address,
+    pub authorizer: Address,
+    pub nonce: B256,
+}
+
+/// Record of claimed authorizations, see the [module docs](self).
+///
+/// Implementations must make [`NonceStore::claim`] atomic: of concurrent claims for the same key,
+/// only one may succeed.
+#[async_trait]
+pub trait NonceStore: Send + Sync + std::fmt::Debug {
+    /// Claims `key` until `expires_at`. Returns `false` if it is already claimed.
+    async fn claim(&self, key: &NonceKey, expires_at: UnixTimestamp) -> bool;
+    /// Whether `key` is claimed.
+    async fn is_claimed(&self, key: &NonceKey) -> bool;
+    /// Releases a claim on `key`, so the authorization can be settled again.
+    async fn release(&self, key: &NonceKey);
+}
+
+/// Number of claims between sweeps of expired entries from an [`InMemoryNonceStore`].
+const PRUNE_EVERY: usize = 1024;
+
+/// [`NonceStore`] local to this process.
+#[derive(Debug, Default)]
+pub struct InMemoryNonceStore {
+    claims: DashMap<NonceKey, UnixTimestamp>,
+    claims_since_prune: AtomicUsize,
+}
+
+impl InMemoryNonceStore {
+    fn is_live(expires_at: UnixTimestamp) -> bool {
+        UnixTimestamp::try_now().is_ok_and(|now| now.0 < expires_at.0)
+    }
+}
+
+#[async_trait]
+impl NonceStore for InMemoryNonceStore {
+    async fn claim(&self, key: &NonceKey, expires_at: UnixTimestamp) -> bool {
+        if self.claims_since_prune.fetch_add(1, Ordering::Relaxed) >= PRUNE_EVERY {
+            self.claims_since_prune.store(0, Ordering::Relaxed);
+            self.claims
+                .retain(|_, expires_at| Self::is_live(*expires_at));
+        }
+        let mut entry = self.claims.entry(*key).or_insert(UnixTimestamp(0));
+        if Self::is_live(*entry) {
+            return false;
+        }
+        *entry = expires_at;
+        true
+    }
+
+    async fn is_claimed(&self, key: &NonceKey) -> bool {
+        self.claims
+            .get(key)
+            .is_some_and(|expires_at| Self::is_live(*expires_at))
+    }
+
+    async fn release(&self, key: &NonceKey) {
+        self.claims.remove(key);
+    }
+}
+
+/// The nonce store configured by `EVM_REPLAY_PROTECTION`: in memory unless disabled.
+pub fn store_from_env() -> Option<Arc<dyn NonceStore>> {
+    let enabled = std::env::var(from_env::ENV_EVM_REPLAY_PROTECTION)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(true);
+    enabled.then(|| Arc::new(InMemoryNonceStore::default()) as Arc<dyn NonceStore>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_claim_is_exclusive_until_released_or_expired() {
+        let store = InMemoryNonceStore::default();
+        let key = NonceKey {
+            chain_id: 8453,
+            token: Address::repeat_byte(1),
+            authorizer: Address::repeat_byte(2),
+            nonce: B256::repeat_byte(3),
+        };
+        let later = UnixTimestamp(UnixTimestamp::try_now().unwrap().0 + 600);
+        assert!(store.claim(&key, later).await);
+        assert!(store.is_claimed(&key).await);
+        assert!(!store.claim(&key, later).await);
+        store.release(&key).await;
+        assert!(!store.is_claimed(&key).await);
+
+        assert!(store.claim(&key, UnixTimestamp(1)).await);
+        assert!(!store.is_claimed(&key).await);
+        assert!(store.claim(&key, later).await);
+    }
+}
@@ -0,0 +1,142 @@
+//! Decoding of revert reasons from failed EVM calls.
+//!
+//! A reverted call is answered by the node with the revert data, which is meaningless to a client as-is.
+//! [`reason`] finds it in an error and decodes it: `Error(string)`, as in
+//! `FiatTokenV2: authorization is used or canceled`, `Panic(uint256)`, and the custom errors of
+//! OpenZeppelin ERC-20 and ERC-3009 tokens, see [`TokenErrors`]. Other custom errors are named by their
+//! selector.
+
+use alloy::primitives::hex;
+use alloy::sol;
+use alloy::sol_types::{SolInterface, decode_revert_reason};
+use alloy::transports::{RpcError, TransportError};
+
+sol! {
+    /// Custom errors of common ERC-20 and ERC-3009 token implementations.
+    #[derive(Debug)]
+    interface TokenErrors {
+        error ERC20InsufficientBalance(address sender, uint256 balance, uint256 needed);
+        error ERC20InvalidSender(address sender);
+        error ERC20InvalidReceiver(address receiver);
+        error ERC20InsufficientAllowance(address spender, uint256 allowance, uint256 needed);
+        error AuthorizationAlreadyUsed(address authorizer, bytes32 nonce);
+        error AuthorizationExpired();
+        error AuthorizationNotYetValid();
+        error InvalidSignature();
+        error EnforcedPause();
+    }
+}
+
+/// The decoded revert reason of a failed call, if `error` was caused by a revert.
+///
+/// Errors unrelated to the call itself, such as a dropped connection or insufficient funds for gas,
+/// have no revert reason.
+pub fn reason(error: &(dyn std::error::Error + 'static)) -> Option<String> {
+    let mut cause = Some(error);
+    while let Some(current) = cause {
+        if let Some(alloy::contract::Error::TransportError(error)) =
+            current.downcast_ref::<alloy::contract::Error>()
+        {
+            return transport_reason(error);
+        }
+        if let Some(error) = current.downcast_ref::<TransportError>() {
+            return transport_reason(error);
+        }
+        cause = current.source();
+    }
+    None
+}
+
+fn transport_reason(error: &TransportError) -> Option<String> {
+    let RpcError::ErrorResp(payload) = error else {
+        return None;
+    };
+    match payload.as_revert_data() {
+        Some(data) => Some(decode(&data)),
+        None if payload.message.contains("revert") => Some(payload.message.to_string()),
+        None => None,
+    }
+}
+
+/// Decodes the revert data of a call.
+pub fn decode(data: &[u8]) -> String {
+    if data.is_empty() {
+        return "reverted without a reason".to_string();
+    }
+    if let Ok(error) = TokenErrors::TokenErrorsErrors::abi_decode(data) {
+        return token_error(error);
+    }
+    if let Some(reason) = decode_revert_reason(data) {
+        return reason;
+    }
+    match data.get(..4) {
+        Some(selector) => format!("custom error 0x{}", hex::encode(selector)),
+        None => format!("malformed revert data 0x{}", hex::encode(data)),
+    }
+}
+
+fn token_error(error: TokenErrors::TokenErrorsErrors) -> String {
+    use TokenErrors::TokenErrorsErrors as E;
+    match error {
+        E::ERC20InsufficientBalance(e) => format!(
+            "insufficient balance: {} has {}, needs {}",
+            e.sender, e.balance, e.needed
+        ),
+        E::ERC20InvalidSender(e) => format!("invalid sender {}", e.sender),
+        E::ERC20InvalidReceiver(e) => format!("invalid receiver {}", e.receiver),
+        E::ERC20InsufficientAllowance(e) => format!(
+            "insufficient allowance: {} has {}, needs {}",
+            e.spender, e.allowance, e.needed
+        ),
+        E::AuthorizationAlreadyUsed(e) => format!(
+            "authorization {} of {} is used or canceled",
+            e.nonce, e.authorizer
+        ),
+        E::AuthorizationExpired(_) => "authorization is expired".to_string(),
+        E::AuthorizationNotYetValid(_) => "authorization is not yet valid".to_string(),
+        E::InvalidSignature(_) => "invalid signature".to_string(),
+        E::EnforcedPause(_) => "token is paused".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{U256, address};
+    use alloy::rpc::json_rpc::ErrorPayload;
+    use alloy::sol_types::{Panic, PanicKind, Revert, SolError};
+
+    #[test]
+    fn test_standard_and_token_reverts_are_decoded() {
+        let revert = Revert::from("FiatTokenV2: authorization is used or canceled").abi_encode();
+        assert_eq!(
+            decode(&revert),
+            "revert: FiatTokenV2: authorization is used or canceled"
+        );
+        let panic = Panic::from(PanicKind::UnderOverflow).abi_encode();
+        assert!(decode(&panic).contains("overflow"));
+        let balance = TokenErrors::ERC20InsufficientBalance {
+            sender: address!("0x0000000000000000000000000000000000000001"),
+            balance: U256::from(1),
+            needed: U256::from(2),
+        }
+        .abi_encode();
+        assert!(decode(&balance).starts_with("insufficient balance"));
+        assert_eq!(decode(&[0xde, 0xad, 0xbe, 0xef]), "custom error 0xdeadbeef");
+
+        let error: TransportError = RpcError::ErrorResp(ErrorPayload {
+            code: 3,
+            message: "execution reverted".into(),
+            data: Some(
+                serde_json::value::to_raw_value(&alloy::primitives::Bytes::from(revert)).unwrap(),
+            ),
+        });
+        assert!(reason(&error).is_some_and(|reason| reason.contains("FiatTokenV2")));
+        let gas: TransportError = RpcError::ErrorResp(ErrorPayload {
+            code: -32000,
+            message: "insufficient funds for gas * price + value".into(),
+            data: None,
+        });
+        assert_eq!(reason(&gas), None);
+    }
+}
@@ -0,0 +1,129 @@
+//! Retries of transient RPC failures while submitting settlement transactions.
+//!
+//! RPC providers fail requests now and then for reasons unrelated to the transaction: rate limits,
+//! a `502` from an overloaded gateway, a timeout. [`is_transient`] tells those apart from permanent
+//! failures, such as a revert or insufficient funds for gas, which are never retried. Transient ones
+//! are retried with exponential backoff, see [`RetryPolicy`].
+//!
+//! A submission that failed on the way back may still have reached the mempool. The transaction is
+//! therefore signed once, and before each retry the node is asked whether it already knows its hash:
+//! if so, the submission is considered done, so a transaction is never broadcast twice.
+//!
+//! Environment variables used:
+//! - `EVM_RPC_RETRY_ATTEMPTS` — attempts per RPC call, including the first (default: `3`; `1` disables retries),
+//! - `EVM_RPC_RETRY_BASE_DELAY_MS` — delay before the first retry, doubled for each further one (default: `250`).
+
+use alloy::transports::{RpcError, TransportError, TransportErrorKind};
+use std::time::Duration;
+
+use crate::chain::http_transport::RpcTimeout;
+use crate::from_env;
+
+/// Longest delay between two attempts, however many retries came before.
+const MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// How often, and how patiently, transient RPC failures are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts per call, including the first.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(250),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Reads `EVM_RPC_RETRY_ATTEMPTS` and `EVM_RPC_RETRY_BASE_DELAY_MS`.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let max_attempts = std::env::var(from_env::ENV_EVM_RPC_RETRY_ATTEMPTS)
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(default.max_attempts)
+            .max(1);
+        let base_delay = std::env::var(from_env::ENV_EVM_RPC_RETRY_BASE_DELAY_MS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map_or(default.base_delay, Duration::from_millis);
+        Self {
+            max_attempts,
+            base_delay,
+        }
+    }
+
+    /// Whether another attempt may follow the failed `attempt`, counted from 1.
+    pub fn should_retry(&self, attempt: u32, error: &TransportError) -> bool {
+        attempt < self.max_attempts && is_transient(error)
+    }
+
+    /// Delay after the failed `attempt`, counted from 1: the base delay, doubled per earlier retry, capped.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32 << attempt.saturating_sub(1).min(16);
+        self.base_delay.saturating_mul(factor).min(MAX_DELAY)
+    }
+}
+
+/// Whether `error` is likely to go away on retry: rate limits, gateway errors, timeouts and dropped connections.
+///
+/// Errors answered by the node about the request itself, e.g. a revert or a nonce too low, are permanent.
+pub fn is_transient(error: &TransportError) -> bool {
+    match error {
+        RpcError::Transport(TransportErrorKind::HttpError(http)) => {
+            matches!(http.status, 408 | 429 | 502 | 503 | 504)
+        }
+        RpcError::Transport(TransportErrorKind::Custom(custom)) => {
+            if custom.downcast_ref::<RpcTimeout>().is_some() {
+                return true;
+            }
+            if let Some(error) = custom.downcast_ref::<alloy::transports::http::reqwest::Error>() {
+                return error.is_timeout() || error.is_connect() || error.is_request();
+            }
+            custom.to_string().contains("429 Too Many Requests")
+        }
+        RpcError::Transport(TransportErrorKind::MissingBatchResponse(_))
+        | RpcError::Transport(TransportErrorKind::BackendGone) => true,
+        RpcError::ErrorResp(payload) => payload.is_retry_err(),
+        RpcError::NullResp => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::rpc::json_rpc::ErrorPayload;
+
+    #[test]
+    fn test_only_transient_errors_are_retried_with_backoff() {
+        let policy = RetryPolicy::default();
+        let gateway = TransportErrorKind::http_error(502, String::new());
+        let timeout = TransportErrorKind::custom(RpcTimeout::Read(Duration::from_secs(5)));
+        let revert = RpcError::ErrorResp(ErrorPayload {
+            code: -32000,
+            message: "execution reverted".into(),
+            data: None,
+        });
+        let insufficient_funds = RpcError::ErrorResp(ErrorPayload {
+            code: -32000,
+            message: "insufficient funds for gas * price + value".into(),
+            data: None,
+        });
+        assert!(policy.should_retry(1, &gateway));
+        assert!(policy.should_retry(2, &timeout));
+        assert!(!policy.should_retry(3, &gateway));
+        assert!(!policy.should_retry(1, &revert));
+        assert!(!policy.should_retry(1, &insufficient_funds));
+
+        assert_eq!(policy.delay(1), Duration::from_millis(250));
+        assert_eq!(policy.delay(3), Duration::from_millis(1000));
+        assert_eq!(policy.delay(30), MAX_DELAY);
+    }
+}
@@ -12,14 +12,18 @@ use std::sync::Arc;
 use std::time::Duration;
 use tracing_core::Level;
 
-use crate::chain::{FacilitatorLocalError, FromEnvByNetworkBuild, NetworkProviderOps};
+use crate::chain::{
+    FacilitatorLocalError, FromEnvByNetworkBuild, NetworkProviderOps, native_cost_usd,
+};
 use crate::facilitator::Facilitator;
 use crate::from_env;
 use crate::network::Network;
+use crate::tokens::TokenConfigs;
 use crate::types::{
-    Base64Bytes, ExactPaymentPayload, FacilitatorErrorReason, MixedAddress, PaymentRequirements,
-    SettleRequest, SettleResponse, SupportedPaymentKind, SupportedPaymentKindExtra,
-    SupportedPaymentKindsResponse, TokenAmount, TransactionHash, VerifyRequest, VerifyResponse,
+    AmountScheme, Base64Bytes, ExactPaymentPayload, FacilitatorErrorReason, MixedAddress,
+    PaymentRequirements, QuoteRequest, QuoteResponse, SettleRequest, SettleResponse,
+    SupportedPaymentKind, SupportedPaymentKindExtra, SupportedPaymentKindsResponse, TokenAmount,
+    TransactionHash, VerifyRequest, VerifyResponse,
 };
 use crate::types::{Scheme, X402Version};
 
@@ -48,6 +52,8 @@ impl TryFrom<Network> for SolanaChain {
             Network::Celo => Err(FacilitatorLocalError::UnsupportedNetwork(None)),
             Network::Sei => Err(FacilitatorLocalError::UnsupportedNetwork(None)),
             Network::SeiTestnet => Err(FacilitatorLocalError::UnsupportedNetwork(None)),
+            Network::Arbitrum => Err(FacilitatorLocalError::UnsupportedNetwork(None)),
+            Network::Optimism => Err(FacilitatorLocalError::UnsupportedNetwork(None)),
         }
     }
 }
@@ -93,15 +99,20 @@ impl From<SolanaAddress> for MixedAddress {
 
 #[derive(Clone)]
 pub struct SolanaProvider {
-    keypair: Arc<Keypair>,
+    /// The fee payer signing settlements, or `None` for a verify-only provider.
+    keypair: Option<Arc<Keypair>>,
     chain: SolanaChain,
     rpc_client: Arc<RpcClient>,
+    tokens: TokenConfigs,
 }
 
 impl Debug for SolanaProvider {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("SolanaProvider")
-            .field("pubkey", &self.keypair.pubkey())
+            .field(
+                "pubkey",
+                &self.keypair.as_ref().map(|keypair| keypair.pubkey()),
+            )
             .field("chain", &self.chain)
             .field("rpc_url", &self.rpc_client.url())
             .finish()
@@ -109,24 +120,53 @@ impl Debug for SolanaProvider {
 }
 
 impl SolanaProvider {
+    /// Builds a provider settling with `keypair` as the fee payer, or a verify-only one without it.
     pub fn try_new(
-        keypair: Keypair,
+        keypair: Option<Keypair>,
         rpc_url: String,
         network: Network,
     ) -> Result<Self, FacilitatorLocalError> {
         let chain = SolanaChain::try_from(network)?;
         {
-            let signer_addresses = vec![keypair.pubkey()];
+            let signer_addresses: Vec<Pubkey> = keypair.iter().map(Keypair::pubkey).collect();
             tracing::info!(network=%network, rpc=rpc_url, signers=?signer_addresses, "Initialized provider");
         }
         let rpc_client = RpcClient::new(rpc_url);
         Ok(Self {
-            keypair: Arc::new(keypair),
+            keypair: keypair.map(Arc::new),
             chain,
             rpc_client: Arc::new(rpc_client),
+            tokens: TokenConfigs::default(),
         })
     }
 
+    /// Latest slot, a cheap check that the RPC endpoint answers.
+    pub async fn latest_slot(&self) -> Result<u64, FacilitatorLocalError> {
+        self.rpc_client
+            .get_slot()
+            .await
+            .map_err(|e| FacilitatorLocalError::ContractCall(e.to_string()))
+    }
+
+    /// Whether the transaction with `signature` succeeded, or `None` if it is not processed.
+    pub async fn transaction_status(
+        &self,
+        signature: [u8; 64],
+    ) -> Result<Option<bool>, FacilitatorLocalError> {
+        let status = self
+            .rpc_client
+            .get_signature_status(&Signature::from(signature))
+            .await
+            .map_err(|e| FacilitatorLocalError::ContractCall(e.to_string()))?;
+        Ok(status.map(|result| result.is_ok()))
+    }
+
+    /// Use per-token settings from `tokens` for verification and settlement.
+    pub fn with_tokens(mut self, tokens: TokenConfigs) -> Self {
+        self.tokens = tokens;
+        self
+    }
+
     pub fn verify_compute_limit_instruction(
         &self,
         transaction: &VersionedTransaction,
@@ -163,7 +203,7 @@ impl SolanaProvider {
         &self,
         transaction: &VersionedTransaction,
         instruction_index: usize,
-    ) -> Result<(), FacilitatorLocalError> {
+    ) -> Result<u64, FacilitatorLocalError> {
         let instructions = transaction.message.instructions();
         let instruction =
             instructions
@@ -190,6 +230,38 @@ impl SolanaProvider {
         if microlamports > 5 * 1_000_000 {
             return Err(FacilitatorLocalError::DecodingError("invalid_exact_svm_payload_transaction_instructions_compute_price_instruction_too_high".to_string()));
         }
+        Ok(microlamports)
+    }
+
+    /// Checks that `authority`, the payer, signed the transaction with its ed25519 key.
+    ///
+    /// The facilitator signs as fee payer only at settlement, so its own signature is not checked here.
+    pub fn verify_payer_signature(
+        &self,
+        transaction: &VersionedTransaction,
+        authority: &Pubkey,
+    ) -> Result<(), FacilitatorLocalError> {
+        let required_signatures = transaction.message.header().num_required_signatures as usize;
+        let signer_index = transaction
+            .message
+            .static_account_keys()
+            .iter()
+            .take(required_signatures)
+            .position(|key| key == authority)
+            .ok_or(FacilitatorLocalError::DecodingError(
+                "invalid_exact_svm_payload_transaction_authority_not_a_signer".to_string(),
+            ))?;
+        let message = transaction.message.serialize();
+        let signed = transaction
+            .signatures
+            .get(signer_index)
+            .is_some_and(|signature| signature.verify(authority.as_ref(), &message));
+        if !signed {
+            return Err(FacilitatorLocalError::InvalidSignature(
+                SolanaAddress::from(*authority).into(),
+                "invalid_exact_svm_payload_transaction_authority_signature".to_string(),
+            ));
+        }
         Ok(())
     }
 
@@ -351,7 +423,7 @@ impl SolanaProvider {
         };
 
         // Verify that the fee payer is not transferring funds (not the authority)
-        let fee_payer_pubkey = self.keypair.pubkey();
+        let fee_payer_pubkey = self.fee_payer_of(transaction)?;
         if transfer_checked_instruction.authority == fee_payer_pubkey {
             return Err(FacilitatorLocalError::DecodingError(
                 "invalid_exact_svm_payload_transaction_fee_payer_transferring_funds".to_string(),
@@ -394,7 +466,10 @@ impl SolanaProvider {
         }
         let instruction_amount: TokenAmount = transfer_checked_instruction.amount.into();
         let requirements_amount: TokenAmount = requirements.max_amount_required;
-        if instruction_amount != requirements_amount {
+        if AmountScheme::from(requirements.scheme)
+            .compare(instruction_amount.0, requirements_amount.0)
+            != std::cmp::Ordering::Equal
+        {
             return Err(FacilitatorLocalError::DecodingError(
                 "invalid_exact_svm_payload_transaction_amount_mismatch".to_string(),
             ));
@@ -408,11 +483,25 @@ impl SolanaProvider {
     ) -> Result<VerifyTransferResult, FacilitatorLocalError> {
         let payload = &request.payment_payload;
         let requirements = &request.payment_requirements;
+        self.tokens.assert_asset_allowed(requirements)?;
+        self.tokens.assert_scheme_offered(requirements)?;
+        if requirements.splits.is_some() {
+            return Err(FacilitatorLocalError::DecodingError(
+                "payment splits are not supported on Solana".to_string(),
+            ));
+        }
+        if requirements.authorization_kind.is_some() {
+            return Err(FacilitatorLocalError::DecodingError(
+                "authorization kinds are not supported on Solana".to_string(),
+            ));
+        }
 
         // Assert valid payment START
         let payment_payload = match &payload.payload {
             ExactPaymentPayload::Evm(..) => {
-                return Err(FacilitatorLocalError::UnsupportedNetwork(None));
+                return Err(FacilitatorLocalError::DecodingError(
+                    "invalid_exact_svm_payload_not_a_transaction".to_string(),
+                ));
             }
             ExactPaymentPayload::Solana(payload) => payload,
         };
@@ -465,11 +554,18 @@ impl SolanaProvider {
                 "invalid_exact_svm_payload_transaction_instructions_count".to_string(),
             ));
         };
+        self.verify_payer_signature(&transaction, &transfer_instruction.authority)?;
+        let payer: SolanaAddress = transfer_instruction.authority.into();
+        self.tokens.assert_amount_in_bounds(
+            payer.clone().into(),
+            &requirements.asset,
+            transfer_instruction.amount.into(),
+        )?;
 
         // Rule 2: Fee payer safety check
         // Verify that the fee payer is not included in any instruction's accounts
         // This single check covers all cases: authority, source, or any other role
-        let fee_payer_pubkey = self.keypair.pubkey();
+        let fee_payer_pubkey = self.fee_payer_of(&transaction)?;
         for instruction in transaction.message.instructions().iter() {
             for account_idx in instruction.accounts.iter() {
                 let account = transaction
@@ -488,7 +584,12 @@ impl SolanaProvider {
             }
         }
 
-        let tx = TransactionInt::new(transaction.clone()).sign(&self.keypair)?;
+        // Signing also checks that the transaction names this facilitator as its fee payer.
+        // In verify-only mode it is simulated unsigned, which `sig_verify: false` allows.
+        let tx = match &self.keypair {
+            Some(keypair) => TransactionInt::new(transaction.clone()).sign(keypair)?,
+            None => TransactionInt::new(transaction.clone()),
+        };
         let cfg = RpcSimulateTransactionConfig {
             sig_verify: false,
             replace_recent_blockhash: false,
@@ -508,13 +609,33 @@ impl SolanaProvider {
                 "invalid_exact_svm_payload_transaction_simulation_failed".to_string(),
             ));
         }
-        let payer: SolanaAddress = transfer_instruction.authority.into();
         Ok(VerifyTransferResult { payer, transaction })
     }
 
-    pub fn fee_payer(&self) -> MixedAddress {
-        let pubkey = self.keypair.pubkey();
-        MixedAddress::Solana(pubkey)
+    /// The facilitator's fee payer, or `None` in verify-only mode.
+    pub fn fee_payer(&self) -> Option<MixedAddress> {
+        self.keypair
+            .as_ref()
+            .map(|keypair| MixedAddress::Solana(keypair.pubkey()))
+    }
+
+    /// The fee payer the fee payer safety rules apply to: the facilitator's, or in verify-only mode the
+    /// one `transaction` names, since there is no facilitator key to compare it with.
+    fn fee_payer_of(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<Pubkey, FacilitatorLocalError> {
+        match &self.keypair {
+            Some(keypair) => Ok(keypair.pubkey()),
+            None => transaction
+                .message
+                .static_account_keys()
+                .first()
+                .copied()
+                .ok_or(FacilitatorLocalError::DecodingError(
+                    "invalid_exact_svm_payload_transaction_fee_payer_missing".to_string(),
+                )),
+        }
     }
 }
 
@@ -528,12 +649,54 @@ impl FromEnvByNetworkBuild for SolanaProvider {
                 return Ok(None);
             }
         };
-        let keypair = from_env::SignerType::from_env()?.make_solana_wallet()?;
-        let provider = SolanaProvider::try_new(keypair, rpc_url, network)?;
+        // Without a signer, the provider only verifies payments.
+        let keypair = match from_env::SignerType::from_env_optional()? {
+            Some(signer_type) => Some(signer_type.make_solana_wallet()?),
+            None => None,
+        };
+        let tokens = TokenConfigs::from_env(network)?;
+        let provider = SolanaProvider::try_new(keypair, rpc_url, network)?.with_tokens(tokens);
         Ok(Some(provider))
     }
 }
 
+/// What identifies the payment of a base64 `transaction`, for [`crate::types::PaymentId`]: the mint,
+/// source, destination and amount of its final `TransferChecked` instruction, then its recent blockhash.
+///
+/// Unlike the transaction bytes, these do not change when the same payment is signed again. `None` if
+/// the transaction does not decode or does not end with a token transfer.
+pub fn transfer_preimage(transaction: &str) -> Option<Vec<u8>> {
+    let bytes = Base64Bytes::from(transaction.as_bytes()).decode().ok()?;
+    let transaction = bincode::deserialize::<VersionedTransaction>(&bytes).ok()?;
+    let recent_blockhash = *transaction.message.recent_blockhash();
+    let index = transaction.message.instructions().len().checked_sub(1)?;
+    let instruction = TransactionInt::new(transaction).instruction(index).ok()?;
+    let program_id = instruction.program_id();
+    let amount = if spl_token::ID.eq(&program_id) {
+        match spl_token::instruction::TokenInstruction::unpack(instruction.data_slice()).ok()? {
+            spl_token::instruction::TokenInstruction::TransferChecked { amount, .. } => amount,
+            _ => return None,
+        }
+    } else if spl_token_2022::ID.eq(&program_id) {
+        match spl_token_2022::instruction::TokenInstruction::unpack(instruction.data_slice())
+            .ok()?
+        {
+            spl_token_2022::instruction::TokenInstruction::TransferChecked { amount, .. } => amount,
+            _ => return None,
+        }
+    } else {
+        return None;
+    };
+    let mut preimage = Vec::new();
+    // Mint = 1, Source = 0, Destination = 2
+    for account in [1, 0, 2] {
+        preimage.extend_from_slice(instruction.account(account).ok()?.as_ref());
+    }
+    preimage.extend_from_slice(&amount.to_le_bytes());
+    preimage.extend_from_slice(recent_blockhash.as_ref());
+    Some(preimage)
+}
+
 pub struct VerifyTransferResult {
     pub payer: SolanaAddress,
     pub transaction: VersionedTransaction,
@@ -552,7 +715,7 @@ pub struct TransferCheckedInstruction {
 }
 
 impl NetworkProviderOps for SolanaProvider {
-    fn signer_address(&self) -> MixedAddress {
+    fn signer_address(&self) -> Option<MixedAddress> {
         self.fee_payer()
     }
 
@@ -570,8 +733,11 @@ impl Facilitator for SolanaProvider {
     }
 
     async fn settle(&self, request: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+        let Some(keypair) = &self.keypair else {
+            return Err(FacilitatorLocalError::SettlementDisabled);
+        };
         let verification = self.verify_transfer(request).await?;
-        let tx = TransactionInt::new(verification.transaction).sign(&self.keypair)?;
+        let tx = TransactionInt::new(verification.transaction).sign(keypair)?;
         // Verify if fully signed
         if !tx.is_fully_signed() {
             tracing::event!(Level::WARN, status = "failed", "undersigned transaction");
@@ -580,7 +746,12 @@ impl Facilitator for SolanaProvider {
                 error_reason: Some(FacilitatorErrorReason::UnexpectedSettleError),
                 payer: verification.payer.into(),
                 transaction: None,
+                block_number: None,
+                status: None,
                 network: self.network(),
+                payment_id: None,
+                cost: None,
+                extensions: None,
             });
         }
         let tx_sig = tx
@@ -591,24 +762,123 @@ impl Facilitator for SolanaProvider {
             error_reason: None,
             payer: verification.payer.into(),
             transaction: Some(TransactionHash::Solana(*tx_sig.as_array())),
+            block_number: None,
+            status: None,
             network: self.network(),
+            payment_id: None,
+            cost: None,
+            extensions: None,
         };
         Ok(settle_response)
     }
 
     async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
-        let kinds = vec![SupportedPaymentKind {
-            network: self.network().to_string(),
-            scheme: Scheme::Exact,
-            x402_version: X402Version::V1,
-            extra: Some(SupportedPaymentKindExtra {
-                fee_payer: self.signer_address(),
-            }),
-        }];
+        let network = self.network();
+        let kinds = [Scheme::Exact, Scheme::Upto]
+            .into_iter()
+            .filter(|scheme| self.tokens.network_offers_scheme(network, *scheme))
+            .map(|scheme| SupportedPaymentKind {
+                network: network.to_string(),
+                scheme,
+                x402_version: X402Version::V1,
+                // Clients name the fee payer in the transaction; a verify-only provider has none to offer.
+                extra: self
+                    .signer_address()
+                    .map(|fee_payer| SupportedPaymentKindExtra { fee_payer }),
+                assets: None,
+                allowed_assets: self.tokens.allowed_assets(),
+                authorization_kinds: None,
+                amount_bounds: self.tokens.amount_bounds(scheme),
+            })
+            .collect();
         Ok(SupportedPaymentKindsResponse { kinds })
     }
+
+    /// Quotes the fee paid as fee payer, see [`SolanaProvider::quote_fee`].
+    async fn quote(&self, request: &QuoteRequest) -> Option<Result<QuoteResponse, Self::Error>> {
+        Some(self.quote_fee(request).await)
+    }
+}
+
+impl SolanaProvider {
+    /// Estimates the fee paid by the facilitator as fee payer: the signature fees, plus the priority fee of
+    /// the compute budget. With a payload, its compute unit limit and price are quoted; otherwise
+    /// [`DEFAULT_COMPUTE_UNITS`] at the median recent prioritization fee.
+    ///
+    /// # Errors
+    /// - [`FacilitatorLocalError::NetworkMismatch`] if the requirements are for another network.
+    /// - [`FacilitatorLocalError::UnsupportedAsset`] if the token is not on the network's allowlist.
+    /// - [`FacilitatorLocalError::DecodingError`] if the payload's transaction lacks a valid compute budget.
+    /// - [`FacilitatorLocalError::ContractCall`] if recent prioritization fees cannot be read.
+    async fn quote_fee(
+        &self,
+        request: &QuoteRequest,
+    ) -> Result<QuoteResponse, FacilitatorLocalError> {
+        let requirements = &request.payment_requirements;
+        let network = self.network();
+        if requirements.network != network {
+            return Err(FacilitatorLocalError::NetworkMismatch(
+                None,
+                network,
+                requirements.network,
+            ));
+        }
+        self.tokens.assert_asset_allowed(requirements)?;
+        let (compute_units, compute_unit_price, signatures, estimated) = match request
+            .payment_payload
+            .as_ref()
+            .map(|payload| &payload.payload)
+        {
+            Some(ExactPaymentPayload::Solana(payload)) => {
+                let bytes = Base64Bytes::from(payload.transaction.as_bytes())
+                    .decode()
+                    .map_err(|e| FacilitatorLocalError::DecodingError(format!("{e}")))?;
+                let transaction = bincode::deserialize::<VersionedTransaction>(&bytes)
+                    .map_err(|e| FacilitatorLocalError::DecodingError(format!("{e}")))?;
+                let compute_units = self.verify_compute_limit_instruction(&transaction, 0)?;
+                let compute_unit_price = self.verify_compute_price_instruction(&transaction, 1)?;
+                let signatures = transaction.signatures.len() as u64;
+                (compute_units.into(), compute_unit_price, signatures, true)
+            }
+            _ => {
+                let mut fees: Vec<u64> = self
+                    .rpc_client
+                    .get_recent_prioritization_fees(&[])
+                    .await
+                    .map_err(|e| FacilitatorLocalError::ContractCall(e.to_string()))?
+                    .into_iter()
+                    .map(|fee| fee.prioritization_fee)
+                    .collect();
+                fees.sort_unstable();
+                let median = fees.get(fees.len() / 2).copied().unwrap_or_default();
+                // The payer's and the fee payer's signatures.
+                (DEFAULT_COMPUTE_UNITS, median, 2, false)
+            }
+        };
+        let priority_fee =
+            (u128::from(compute_units) * u128::from(compute_unit_price)).div_ceil(1_000_000);
+        let total_cost = alloy::primitives::U256::from(
+            u128::from(signatures * LAMPORTS_PER_SIGNATURE) + priority_fee,
+        );
+        Ok(QuoteResponse {
+            network,
+            gas: compute_units,
+            gas_estimated: estimated,
+            forwarding_gas: 0,
+            gas_price: TokenAmount::from(compute_unit_price),
+            total_cost: TokenAmount(total_cost),
+            native_decimals: 9,
+            total_cost_usd: native_cost_usd(network, total_cost, 9),
+        })
+    }
 }
 
+/// Base fee charged per transaction signature, in lamports.
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// Compute units quoted without a payload: the default limit of a transaction without a compute budget.
+const DEFAULT_COMPUTE_UNITS: u64 = 200_000;
+
 pub struct InstructionInt {
     instruction: CompiledInstruction,
     account_keys: Vec<Pubkey>,
@@ -767,3 +1037,107 @@ impl TransactionInt {
         Ok(string)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::compute_budget::ComputeBudgetInstruction;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::message::{Message, VersionedMessage};
+
+    fn transaction(compute_unit_price: u64, amount: u64, blockhash: Hash) -> String {
+        let payer = Pubkey::new_from_array([1; 32]);
+        let fee_payer = Pubkey::new_from_array([2; 32]);
+        let transfer = spl_token::instruction::transfer_checked(
+            &spl_token::ID,
+            &Pubkey::new_from_array([3; 32]),
+            &Pubkey::new_from_array([4; 32]),
+            &Pubkey::new_from_array([5; 32]),
+            &payer,
+            &[],
+            amount,
+            6,
+        )
+        .unwrap();
+        let message = Message::new_with_blockhash(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+                ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
+                transfer,
+            ],
+            Some(&fee_payer),
+            &blockhash,
+        );
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::default(); 2],
+            message: VersionedMessage::Legacy(message),
+        };
+        TransactionInt::new(transaction).as_base64().unwrap()
+    }
+
+    #[test]
+    fn test_transfer_preimage_ignores_everything_but_the_transfer_and_blockhash() {
+        let blockhash = Hash::new_from_array([7; 32]);
+        let preimage = transfer_preimage(&transaction(1, 10_000, blockhash)).unwrap();
+        assert_eq!(
+            transfer_preimage(&transaction(5, 10_000, blockhash)),
+            Some(preimage.clone())
+        );
+        assert_ne!(
+            transfer_preimage(&transaction(1, 10_001, blockhash)),
+            Some(preimage.clone())
+        );
+        assert_ne!(
+            transfer_preimage(&transaction(1, 10_000, Hash::new_from_array([8; 32]))),
+            Some(preimage)
+        );
+        assert_eq!(transfer_preimage("not a transaction"), None);
+    }
+
+    #[test]
+    fn test_only_transactions_signed_by_the_payer_pass() {
+        let provider = SolanaProvider::try_new(
+            None,
+            "http://127.0.0.1:1".to_string(),
+            Network::SolanaDevnet,
+        )
+        .unwrap();
+        let payer = Keypair::new();
+        let fee_payer = Pubkey::new_from_array([2; 32]);
+        let transfer = spl_token::instruction::transfer_checked(
+            &spl_token::ID,
+            &Pubkey::new_from_array([3; 32]),
+            &Pubkey::new_from_array([4; 32]),
+            &Pubkey::new_from_array([5; 32]),
+            &payer.pubkey(),
+            &[],
+            10_000,
+            6,
+        )
+        .unwrap();
+        let message = VersionedMessage::Legacy(Message::new_with_blockhash(
+            &[transfer],
+            Some(&fee_payer),
+            &Hash::new_from_array([7; 32]),
+        ));
+        let mut transaction = VersionedTransaction {
+            signatures: vec![Signature::default(); 2],
+            message,
+        };
+        assert!(matches!(
+            provider.verify_payer_signature(&transaction, &payer.pubkey()),
+            Err(FacilitatorLocalError::InvalidSignature(..))
+        ));
+
+        transaction.signatures[1] = payer.sign_message(&transaction.message.serialize());
+        assert!(
+            provider
+                .verify_payer_signature(&transaction, &payer.pubkey())
+                .is_ok()
+        );
+        assert!(matches!(
+            provider.verify_payer_signature(&transaction, &Pubkey::new_from_array([5; 32])),
+            Err(FacilitatorLocalError::DecodingError(..))
+        ));
+    }
+}
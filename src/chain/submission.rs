@@ -0,0 +1,253 @@
+//! Early notification that a settlement transaction was broadcast.
+//!
+//! `POST /settle?wait=false` answers as soon as the settlement transaction is in the mempool,
+//! while the settlement carries on in the background. The handler runs the settlement inside
+//! [`watch`], and the chain provider calls [`notify`] right after broadcasting. Only the first
+//! broadcast of a settlement is reported: that is the transfer from the payer, and any later
+//! transaction, such as forwarding a split, depends on it.
+//!
+//! `GET /ws/settle` follows the settlement further: run inside [`stream`], the broadcast and every
+//! confirmation count of that first transaction seen while waiting for finality, see
+//! [`notify_confirmations`], are sent as [`Progress`].
+//!
+//! The settlement journal runs every settlement inside [`track`], to record its first broadcast
+//! whether or not a client is watching, and settlements run inside [`detect`], so a failed one is
+//! only forwarded to a [fallback facilitator](crate::fallback) if it broadcast nothing.
+//!
+//! A transaction replaced at higher fees, see [`FeeBump`](crate::chain::gas_strategy::FeeBump), is
+//! reported with [`notify_replaced`]: [`stream`] then follows the replacement, and settlements run
+//! inside [`track_replacements`] learn its hash. A transaction orphaned by a reorg is reported with
+//! [`notify_reorged`], to settlements run inside [`track_reorgs`].
+//!
+//! Outside of [`watch`], [`stream`], [`track`], [`track_replacements`], [`track_reorgs`] and
+//! [`detect`], [`notify`], [`notify_replaced`], [`notify_reorged`] and [`notify_confirmations`] do
+//! nothing.
+
+use std::future::Future;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::types::TransactionHash;
+
+/// A step of a settlement run inside [`stream`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Progress {
+    /// The settlement transaction was broadcast.
+    Submitted(TransactionHash),
+    /// The settlement transaction was replaced by one with the same nonce and higher fees.
+    Replaced {
+        transaction: TransactionHash,
+        replacement: TransactionHash,
+    },
+    /// The settlement transaction is included in a block with this many confirmations, not yet final.
+    Pending {
+        transaction: TransactionHash,
+        confirmations: u64,
+    },
+}
+
+struct Subscriber {
+    progress: mpsc::UnboundedSender<Progress>,
+    transaction: Option<TransactionHash>,
+}
+
+tokio::task_local! {
+    static SUBMITTED: Mutex<Option<oneshot::Sender<TransactionHash>>>;
+    static SUBSCRIBER: Mutex<Subscriber>;
+    static TRACKED: Mutex<Option<oneshot::Sender<TransactionHash>>>;
+    static REPLACED: mpsc::UnboundedSender<(TransactionHash, TransactionHash)>;
+    static REORGED: mpsc::UnboundedSender<TransactionHash>;
+    static DETECTED: AtomicBool;
+}
+
+/// Runs `future`, sending the first transaction it broadcasts to `submitted`.
+///
+/// `submitted` is dropped unsent if `future` completes without broadcasting anything.
+pub async fn watch<F: Future>(submitted: oneshot::Sender<TransactionHash>, future: F) -> F::Output {
+    SUBMITTED.scope(Mutex::new(Some(submitted)), future).await
+}
+
+/// Runs `future`, sending the first transaction it broadcasts to `submitted`, like [`watch`].
+///
+/// Independent of [`watch`] and [`stream`]: a settlement may run inside all of them.
+pub async fn track<F: Future>(submitted: oneshot::Sender<TransactionHash>, future: F) -> F::Output {
+    TRACKED.scope(Mutex::new(Some(submitted)), future).await
+}
+
+/// Runs `future`, sending every `(transaction, replacement)` reported by [`notify_replaced`] to `replaced`.
+pub async fn track_replacements<F: Future>(
+    replaced: mpsc::UnboundedSender<(TransactionHash, TransactionHash)>,
+    future: F,
+) -> F::Output {
+    REPLACED.scope(replaced, future).await
+}
+
+/// Runs `future`, sending every transaction reported by [`notify_reorged`] to `reorged`.
+pub async fn track_reorgs<F: Future>(
+    reorged: mpsc::UnboundedSender<TransactionHash>,
+    future: F,
+) -> F::Output {
+    REORGED.scope(reorged, future).await
+}
+
+/// Runs `future`, also returning whether it broadcast any transaction.
+pub async fn detect<F: Future>(future: F) -> (F::Output, bool) {
+    let broadcast = AtomicBool::new(false);
+    DETECTED
+        .scope(broadcast, async {
+            let output = future.await;
+            let broadcast = DETECTED.with(|broadcast| broadcast.load(Ordering::Relaxed));
+            (output, broadcast)
+        })
+        .await
+}
+
+/// Runs `future`, sending the [`Progress`] of the first transaction it broadcasts to `progress`.
+pub async fn stream<F: Future>(progress: mpsc::UnboundedSender<Progress>, future: F) -> F::Output {
+    let subscriber = Subscriber {
+        progress,
+        transaction: None,
+    };
+    SUBSCRIBER.scope(Mutex::new(subscriber), future).await
+}
+
+/// Whether the current settlement runs inside [`stream`], i.e. confirmation counts are worth reading.
+pub fn is_streaming() -> bool {
+    SUBSCRIBER.try_with(|_| ()).is_ok()
+}
+
+/// Reports that `transaction` was broadcast, if running inside [`watch`], [`stream`] or [`track`]
+/// and nothing was reported yet, and to [`detect`].
+pub fn notify(transaction: TransactionHash) {
+    let _ = DETECTED.try_with(|broadcast| broadcast.store(true, Ordering::Relaxed));
+    for submitted in [&SUBMITTED, &TRACKED] {
+        let _ = submitted.try_with(|submitted| {
+            if let Some(sender) = submitted.lock().ok().and_then(|mut sender| sender.take()) {
+                let _ = sender.send(transaction.clone());
+            }
+        });
+    }
+    let _ = SUBSCRIBER.try_with(|subscriber| {
+        if let Ok(mut subscriber) = subscriber.lock()
+            && subscriber.transaction.is_none()
+        {
+            subscriber.transaction = Some(transaction.clone());
+            let _ = subscriber.progress.send(Progress::Submitted(transaction));
+        }
+    });
+}
+
+/// Reports that `transaction` was replaced by `replacement`, to [`track_replacements`], and to [`stream`]
+/// if `transaction` is the one it follows.
+pub fn notify_replaced(transaction: TransactionHash, replacement: TransactionHash) {
+    let _ = REPLACED.try_with(|replaced| {
+        let _ = replaced.send((transaction.clone(), replacement.clone()));
+    });
+    let _ = SUBSCRIBER.try_with(|subscriber| {
+        if let Ok(mut subscriber) = subscriber.lock()
+            && subscriber.transaction.as_ref() == Some(&transaction)
+        {
+            subscriber.transaction = Some(replacement.clone());
+            let _ = subscriber.progress.send(Progress::Replaced {
+                transaction,
+                replacement,
+            });
+        }
+    });
+}
+
+/// Reports that `transaction` was orphaned by a reorg, to [`track_reorgs`].
+pub fn notify_reorged(transaction: TransactionHash) {
+    let _ = REORGED.try_with(|reorged| {
+        let _ = reorged.send(transaction);
+    });
+}
+
+/// Reports that `transaction` has `confirmations` confirmations, if it is the one reported to [`stream`].
+pub fn notify_confirmations(transaction: TransactionHash, confirmations: u64) {
+    let _ = SUBSCRIBER.try_with(|subscriber| {
+        if let Ok(subscriber) = subscriber.lock()
+            && subscriber.transaction.as_ref() == Some(&transaction)
+        {
+            let _ = subscriber.progress.send(Progress::Pending {
+                transaction,
+                confirmations,
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stream_reports_only_the_first_transaction() {
+        let (progress, mut updates) = mpsc::unbounded_channel();
+        let transfer = TransactionHash::Evm([1; 32]);
+        let forward = TransactionHash::Evm([2; 32]);
+        stream(progress, async {
+            notify(transfer.clone());
+            notify_confirmations(transfer.clone(), 2);
+            notify(forward.clone());
+            notify_confirmations(forward.clone(), 1);
+        })
+        .await;
+        assert_eq!(
+            updates.recv().await,
+            Some(Progress::Submitted(transfer.clone()))
+        );
+        assert_eq!(
+            updates.recv().await,
+            Some(Progress::Pending {
+                transaction: transfer,
+                confirmations: 2
+            })
+        );
+        assert_eq!(updates.recv().await, None);
+        assert!(!is_streaming());
+    }
+
+    #[tokio::test]
+    async fn test_replacements_are_followed() {
+        let (progress, mut updates) = mpsc::unbounded_channel();
+        let (replaced, mut replacements) = mpsc::unbounded_channel();
+        let transfer = TransactionHash::Evm([1; 32]);
+        let replacement = TransactionHash::Evm([2; 32]);
+        let unrelated = TransactionHash::Evm([3; 32]);
+        let settlement = async {
+            notify(transfer.clone());
+            notify_replaced(transfer.clone(), replacement.clone());
+            notify_replaced(unrelated.clone(), TransactionHash::Evm([4; 32]));
+            notify_confirmations(transfer.clone(), 1);
+            notify_confirmations(replacement.clone(), 1);
+        };
+        stream(progress, track_replacements(replaced, settlement)).await;
+        assert_eq!(
+            updates.recv().await,
+            Some(Progress::Submitted(transfer.clone()))
+        );
+        assert_eq!(
+            updates.recv().await,
+            Some(Progress::Replaced {
+                transaction: transfer.clone(),
+                replacement: replacement.clone()
+            })
+        );
+        assert_eq!(
+            updates.recv().await,
+            Some(Progress::Pending {
+                transaction: replacement.clone(),
+                confirmations: 1
+            })
+        );
+        assert_eq!(updates.recv().await, None);
+        assert_eq!(replacements.recv().await, Some((transfer, replacement)));
+        assert_eq!(
+            replacements.recv().await.map(|(replaced, _)| replaced),
+            Some(unrelated)
+        );
+        assert_eq!(replacements.recv().await, None);
+    }
+}
@@ -0,0 +1,90 @@
+//! Background sweeping of signer balances to a treasury.
+//!
+//! Signers that receive payments as an intermediary (`unwrapNative` tokens, split payments) can
+//! accumulate leftovers, e.g. from forwarding failures. When a treasury is configured, a background
+//! task periodically transfers the balance of every token with a `sweepThreshold` (see [`crate::tokens`])
+//! to the treasury, once a signer holds more than the threshold.
+//!
+//! Sweeps never race settlements: they wait for payments in transit to be forwarded
+//! (see [`EvmProvider::sweep`]), and take nonces from the same nonce manager. Shutdown waits for
+//! a running sweep, see [`crate::inflight`], and no sweep starts once it has begun.
+//!
+//! Environment variables used:
+//! - `SWEEP_TREASURY` — EVM address to sweep to; enables sweeping when set,
+//! - `SWEEP_INTERVAL_SECS` — how often to check signer balances (default: `300`).
+
+use alloy::primitives::Address;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::chain::NetworkProvider;
+use crate::chain::NetworkProviderOps;
+use crate::chain::evm::EvmProvider;
+use crate::facilitator_local::FacilitatorLocal;
+use crate::from_env::{ENV_SWEEP_INTERVAL_SECS, ENV_SWEEP_TREASURY};
+use crate::inflight::InFlight;
+use crate::provider_cache::ProviderMap;
+
+/// Periodically sweeps signer balances, see the [module docs](self).
+pub struct Sweeper {
+    treasury: Address,
+    interval: Duration,
+}
+
+impl Sweeper {
+    /// Reads the sweep configuration, or returns `None` if `SWEEP_TREASURY` is not set.
+    pub fn from_env() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let Ok(treasury) = env::var(ENV_SWEEP_TREASURY) else {
+            return Ok(None);
+        };
+        let treasury: Address = treasury
+            .trim()
+            .parse()
+            .map_err(|e| format!("env {ENV_SWEEP_TREASURY} is invalid: {e}"))?;
+        let interval = env::var(ENV_SWEEP_INTERVAL_SECS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(300);
+        Ok(Some(Self {
+            treasury,
+            interval: Duration::from_secs(interval),
+        }))
+    }
+
+    /// Sweeps every EVM network of `facilitator` until `cancellation_token` is cancelled.
+    pub async fn watch<A>(
+        self,
+        facilitator: Arc<FacilitatorLocal<A>>,
+        cancellation_token: CancellationToken,
+    ) where
+        A: ProviderMap<Value = NetworkProvider>,
+    {
+        tracing::info!(treasury = %self.treasury, "Sweeping signer balances");
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = cancellation_token.cancelled() => break,
+            }
+            for provider in facilitator.provider_map().values() {
+                // A sweep started while draining could be cut short by the exit.
+                if InFlight::global().is_draining() {
+                    return;
+                }
+                if let NetworkProvider::Evm(provider) = provider {
+                    self.sweep(provider).await;
+                }
+            }
+        }
+    }
+
+    async fn sweep(&self, provider: &EvmProvider) {
+        let _in_flight = InFlight::global().sweep();
+        if let Err(error) = provider.sweep(self.treasury).await {
+            tracing::warn!(network = %provider.network(), %error, "Failed to sweep signer balances");
+        }
+    }
+}
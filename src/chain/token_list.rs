@@ -0,0 +1,141 @@
+//! On-chain enumeration of accepted tokens.
+//!
+//! Deployments accepting many tokens from a known factory or registry would otherwise have to list
+//! each of them in `TOKENS_<NETWORK>` to advertise them. Instead, an operator can point the facilitator
+//! at a contract exposing `getTokens() returns (address[])`, e.g. a factory keeping track of its
+//! deployments. The listed tokens, with their `decimals()` read on-chain, are advertised as `assets`
+//! of the network's payment kinds in `/supported`, as far as the facilitator accepts them. A token
+//! whose `decimals()` cannot be read is left out.
+//!
+//! The list is read with the first `/supported` refresh and again once it is older than the refresh
+//! interval. If it cannot be read, the previous list is kept.
+//!
+//! Environment variables used:
+//! - `TOKEN_LIST_<NETWORK>` — address of the contract enumerating tokens on that network, e.g. `TOKEN_LIST_BASE`,
+//! - `TOKEN_LIST_REFRESH_SECS` — how long an enumeration is reused (default: `3600`).
+
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use alloy::sol;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::Instrument;
+
+use crate::from_env;
+use crate::network::Network;
+use crate::types::{EvmAddress, SupportedAsset};
+
+sol! {
+    /// Contract enumerating accepted tokens.
+    #[sol(rpc)]
+    interface ITokenList {
+        function getTokens() external view returns (address[]);
+    }
+
+    /// Decimals of an ERC-20 token.
+    #[sol(rpc)]
+    interface IERC20Decimals {
+        function decimals() external view returns (uint8);
+    }
+}
+
+/// Cached enumeration of a token list contract, see the [module docs](self).
+#[derive(Debug)]
+pub struct TokenList {
+    contract: Address,
+    refresh: Duration,
+    listed: Mutex<Option<(Vec<SupportedAsset>, Instant)>>,
+}
+
+impl TokenList {
+    /// Creates a view of the token list at `contract`, enumerating it again every `refresh`.
+    pub fn new(contract: Address, refresh: Duration) -> Self {
+        Self {
+            contract,
+            refresh,
+            listed: Mutex::new(None),
+        }
+    }
+
+    /// Reads the token list for `network` from `TOKEN_LIST_<NETWORK>`, or returns `None` if not set.
+    pub fn from_env(network: Network) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let env_var = from_env::env_name_for_network(from_env::ENV_TOKEN_LIST_PREFIX, network);
+        let Ok(contract) = std::env::var(&env_var) else {
+            return Ok(None);
+        };
+        let contract: Address = contract
+            .trim()
+            .parse()
+            .map_err(|e| format!("env {env_var} is invalid: {e}"))?;
+        let refresh = std::env::var(from_env::ENV_TOKEN_LIST_REFRESH_SECS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(3600);
+        Ok(Some(Self::new(contract, Duration::from_secs(refresh))))
+    }
+
+    /// The listed tokens, enumerating the contract again if the cached list is stale.
+    ///
+    /// Concurrent callers wait for a single enumeration. If it fails, the previous list is returned,
+    /// or an empty one if the contract was never read.
+    pub async fn tokens<P: Provider>(&self, provider: &P) -> Vec<SupportedAsset> {
+        let mut listed = self.listed.lock().await;
+        if let Some((tokens, fetched_at)) = listed.as_ref()
+            && fetched_at.elapsed() < self.refresh
+        {
+            return tokens.clone();
+        }
+        match self
+            .enumerate(provider)
+            .instrument(tracing::info_span!(
+                "enumerate_token_list",
+                token_list = %self.contract,
+                otel.kind = "client"
+            ))
+            .await
+        {
+            Ok(tokens) => {
+                tracing::info!(token_list = %self.contract, count = tokens.len(), "Token list enumerated");
+                *listed = Some((tokens.clone(), Instant::now()));
+                tokens
+            }
+            Err(error) => {
+                tracing::warn!(token_list = %self.contract, %error, "Failed to enumerate token list, keeping previous list");
+                listed
+                    .as_ref()
+                    .map(|(tokens, _)| tokens.clone())
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    /// Reads the listed addresses and the decimals of each, skipping tokens whose decimals cannot be read.
+    async fn enumerate<P: Provider>(
+        &self,
+        provider: &P,
+    ) -> Result<Vec<SupportedAsset>, alloy::contract::Error> {
+        let addresses = ITokenList::new(self.contract, provider)
+            .getTokens()
+            .call()
+            .await?;
+        let mut tokens = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let decimals = match IERC20Decimals::new(address, provider)
+                .decimals()
+                .call()
+                .await
+            {
+                Ok(decimals) => decimals,
+                Err(error) => {
+                    tracing::warn!(token_list = %self.contract, token = %address, %error, "Failed to read decimals of listed token, skipping it");
+                    continue;
+                }
+            };
+            tokens.push(SupportedAsset {
+                address: EvmAddress(address).into(),
+                decimals,
+            });
+        }
+        Ok(tokens)
+    }
+}
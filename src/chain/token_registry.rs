@@ -0,0 +1,124 @@
+//! On-chain registry of tokens approved for settlement.
+//!
+//! For compliance, an operator can require every EVM payment's token to be approved in a registry
+//! contract they control, exposing `isApproved(address token) returns (bool)`. Approvals are updated
+//! on-chain, without redeploying the facilitator. Lookups are cached for a configurable time.
+//!
+//! If the registry cannot be read, payments are rejected rather than settled unchecked.
+//!
+//! Environment variables used:
+//! - `TOKEN_REGISTRY_<NETWORK>` — registry contract address on that network, e.g. `TOKEN_REGISTRY_BASE`,
+//! - `TOKEN_REGISTRY_CACHE_SECS` — how long a lookup is cached (default: `300`).
+
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use alloy::sol;
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+use crate::chain::FacilitatorLocalError;
+use crate::from_env;
+use crate::network::Network;
+use crate::types::{EvmAddress, MixedAddress};
+
+sol! {
+    /// Operator-controlled list of approved tokens.
+    #[sol(rpc)]
+    interface ITokenRegistry {
+        function isApproved(address token) external view returns (bool);
+    }
+}
+
+/// Cached view of an approved-token registry contract, see the [module docs](self).
+#[derive(Debug)]
+pub struct TokenRegistry {
+    contract: Address,
+    ttl: Duration,
+    approvals: DashMap<Address, (bool, Instant)>,
+}
+
+impl TokenRegistry {
+    /// Creates a view of the registry at `contract`, caching lookups for `ttl`.
+    pub fn new(contract: Address, ttl: Duration) -> Self {
+        Self {
+            contract,
+            ttl,
+            approvals: DashMap::new(),
+        }
+    }
+
+    /// Reads the registry for `network` from `TOKEN_REGISTRY_<NETWORK>`, or returns `None` if not set.
+    pub fn from_env(network: Network) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let env_var = from_env::env_name_for_network(from_env::ENV_TOKEN_REGISTRY_PREFIX, network);
+        let Ok(contract) = std::env::var(&env_var) else {
+            return Ok(None);
+        };
+        let contract: Address = contract
+            .trim()
+            .parse()
+            .map_err(|e| format!("env {env_var} is invalid: {e}"))?;
+        let ttl = std::env::var(from_env::ENV_TOKEN_REGISTRY_CACHE_SECS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(300);
+        Ok(Some(Self::new(contract, Duration::from_secs(ttl))))
+    }
+
+    /// Whether `token` is approved, reading the registry unless a fresh cached answer exists.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::ContractCall`] if the registry cannot be read.
+    pub async fn is_approved<P: Provider>(
+        &self,
+        provider: &P,
+        token: Address,
+    ) -> Result<bool, FacilitatorLocalError> {
+        let cached = self
+            .approvals
+            .get(&token)
+            .filter(|entry| entry.1.elapsed() < self.ttl)
+            .map(|entry| entry.0);
+        let approved = match cached {
+            Some(approved) => approved,
+            None => {
+                let approved = ITokenRegistry::new(self.contract, provider)
+                    .isApproved(token)
+                    .call()
+                    .into_future()
+                    .instrument(tracing::info_span!(
+                        "fetch_token_approval",
+                        registry = %self.contract,
+                        token_contract = %token,
+                        otel.kind = "client"
+                    ))
+                    .await
+                    .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+                self.approvals.insert(token, (approved, Instant::now()));
+                approved
+            }
+        };
+        Ok(approved)
+    }
+
+    /// Checks that `token` is approved, see [`TokenRegistry::is_approved`].
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::UnsupportedAsset`] if the registry does not approve the token.
+    /// Returns [`FacilitatorLocalError::ContractCall`] if the registry cannot be read.
+    pub async fn assert_approved<P: Provider>(
+        &self,
+        provider: &P,
+        payer: EvmAddress,
+        token: Address,
+    ) -> Result<(), FacilitatorLocalError> {
+        if self.is_approved(provider, token).await? {
+            Ok(())
+        } else {
+            Err(FacilitatorLocalError::UnsupportedAsset(
+                Some(payer.into()),
+                MixedAddress::Evm(EvmAddress(token)),
+            ))
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! How token balances compare to authorized transfer values.
+//!
+//! For ordinary tokens, `balanceOf` and `transfer` use the same base units, and a payer can
+//! spend what `balanceOf` reports. Rebasing and share-based tokens break that: `balanceOf` may
+//! report shares while authorizations move assets. A [`ValueModel`] converts a balance reading
+//! into spendable transfer units before the sufficiency check. It is chosen per token with the
+//! `valueModel` setting of [`crate::tokens::TokenConfig`].
+
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::fmt::Debug;
+
+use crate::chain::FacilitatorLocalError;
+
+sol! {
+    /// Share-to-asset conversion, as in ERC-4626 vaults.
+    interface IConvertToAssets {
+        function convertToAssets(uint256 shares) external view returns (uint256);
+    }
+}
+
+/// Converts a token's `balanceOf` reading into the units of authorization values.
+#[async_trait]
+pub trait ValueModel: Debug + Send + Sync {
+    /// Returns how much of `token` a holder with `balance` can transfer.
+    async fn spendable(
+        &self,
+        provider: &dyn Provider,
+        token: Address,
+        balance: U256,
+    ) -> Result<U256, FacilitatorLocalError>;
+}
+
+/// Balances and transfers use the same base units.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Standard;
+
+#[async_trait]
+impl ValueModel for Standard {
+    async fn spendable(
+        &self,
+        _provider: &dyn Provider,
+        _token: Address,
+        balance: U256,
+    ) -> Result<U256, FacilitatorLocalError> {
+        Ok(balance)
+    }
+}
+
+/// Balances are shares; the token's `convertToAssets(shares)` gives the transferable amount.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConvertToAssets;
+
+#[async_trait]
+impl ValueModel for ConvertToAssets {
+    async fn spendable(
+        &self,
+        provider: &dyn Provider,
+        token: Address,
+        balance: U256,
+    ) -> Result<U256, FacilitatorLocalError> {
+        let call = IConvertToAssets::convertToAssetsCall { shares: balance };
+        let tx = TransactionRequest::default()
+            .with_to(token)
+            .with_input(call.abi_encode());
+        let output = provider
+            .call(tx)
+            .await
+            .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))?;
+        IConvertToAssets::convertToAssetsCall::abi_decode_returns(&output)
+            .map_err(|e| FacilitatorLocalError::ContractCall(format!("{e:?}")))
+    }
+}
+
+/// Value models selectable from token configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ValueModelKind {
+    /// See [`Standard`].
+    #[default]
+    Standard,
+    /// See [`ConvertToAssets`].
+    ConvertToAssets,
+}
+
+impl ValueModelKind {
+    /// Returns the [`ValueModel`] implementation.
+    pub fn model(self) -> &'static dyn ValueModel {
+        match self {
+            ValueModelKind::Standard => &Standard,
+            ValueModelKind::ConvertToAssets => &ConvertToAssets,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::Bytes;
+    use alloy::providers::ProviderBuilder;
+    use alloy::providers::mock::Asserter;
+    use alloy::sol_types::SolValue;
+
+    #[tokio::test]
+    async fn test_share_balances_are_converted_to_assets() {
+        let asserter = Asserter::new();
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .connect_mocked_client(asserter.clone());
+        let token = Address::repeat_byte(1);
+        let shares = U256::from(1_000u64);
+
+        let standard: ValueModelKind = serde_json::from_str(r#""standard""#).unwrap();
+        let spendable = standard.model().spendable(&provider, token, shares).await;
+        assert_eq!(spendable.unwrap(), shares);
+
+        let shares_model: ValueModelKind = serde_json::from_str(r#""convertToAssets""#).unwrap();
+        asserter.push_success(&Bytes::from(U256::from(1_050u64).abi_encode()));
+        let spendable = shares_model
+            .model()
+            .spendable(&provider, token, shares)
+            .await;
+        assert_eq!(spendable.unwrap(), U256::from(1_050u64));
+        asserter.push_failure_msg("execution reverted");
+        let spendable = shares_model
+            .model()
+            .spendable(&provider, token, shares)
+            .await;
+        assert!(matches!(
+            spendable,
+            Err(FacilitatorLocalError::ContractCall(_))
+        ));
+    }
+}
@@ -0,0 +1,150 @@
+//! Authorizations this facilitator has verified, the only ones it will cancel.
+//!
+//! `POST /cancel` has the facilitator pay the gas of the payer's `cancelAuthorization`. To keep that
+//! from being used for free transactions, a cancellation is only submitted for an authorization that
+//! passed `/verify` here and has not expired, and only once its signature checks out against the
+//! EIP-712 domain the authorization was verified with, see [`assert_cancel_signed`].
+//!
+//! Entries expire with the authorization's `validBefore`; at most [`MAX_ENTRIES`] are kept, and
+//! authorizations verified while full can not be canceled through this facilitator. Neither can those of
+//! contract wallets, whose signatures can only be checked on-chain.
+
+use alloy::primitives::{Address, Signature};
+use alloy::sol;
+use alloy::sol_types::{Eip712Domain, SolStruct};
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::chain::FacilitatorLocalError;
+use crate::chain::nonce_store::NonceKey;
+use crate::timestamp::UnixTimestamp;
+use crate::types::EvmAddress;
+
+/// Largest number of verified authorizations remembered.
+pub const MAX_ENTRIES: usize = 100_000;
+
+/// Number of records between sweeps of expired entries.
+const PRUNE_EVERY: usize = 1024;
+
+sol! {
+    /// ERC-3009 `cancelAuthorization` message.
+    #[derive(Debug)]
+    struct CancelAuthorization {
+        address authorizer;
+        bytes32 nonce;
+    }
+}
+
+/// Verified authorizations with the EIP-712 domain they were signed against, see the [module docs](self).
+#[derive(Debug, Default)]
+pub struct VerifiedAuthorizations {
+    entries: DashMap<NonceKey, (Eip712Domain, UnixTimestamp)>,
+    records_since_prune: AtomicUsize,
+}
+
+impl VerifiedAuthorizations {
+    fn is_live(valid_before: UnixTimestamp) -> bool {
+        UnixTimestamp::try_now().is_ok_and(|now| now.0 < valid_before.0)
+    }
+
+    /// Remembers that `key` was verified against `domain`, until `valid_before`.
+    pub fn record(&self, key: NonceKey, domain: Eip712Domain, valid_before: UnixTimestamp) {
+        if self.records_since_prune.fetch_add(1, Ordering::Relaxed) >= PRUNE_EVERY {
+            self.records_since_prune.store(0, Ordering::Relaxed);
+            self.entries
+                .retain(|_, (_, valid_before)| Self::is_live(*valid_before));
+        }
+        if self.entries.len() >= MAX_ENTRIES && !self.entries.contains_key(&key) {
+            return;
+        }
+        self.entries.insert(key, (domain, valid_before));
+    }
+
+    /// The domain `key` was verified against, if it was verified and has not expired.
+    pub fn domain(&self, key: &NonceKey) -> Option<Eip712Domain> {
+        self.entries
+            .get(key)
+            .filter(|entry| Self::is_live(entry.1))
+            .map(|entry| entry.0.clone())
+    }
+}
+
+/// Checks that `signature` is the authorizer's ECDSA signature of the `cancelAuthorization` of `key`.
+///
+/// # Errors
+/// Returns [`FacilitatorLocalError::InvalidSignature`] if it is malformed or signed by someone else.
+pub fn assert_cancel_signed(
+    key: &NonceKey,
+    domain: &Eip712Domain,
+    signature: &[u8],
+) -> Result<(), FacilitatorLocalError> {
+    let invalid = |reason: &str| {
+        FacilitatorLocalError::InvalidSignature(
+            EvmAddress(key.authorizer).into(),
+            reason.to_string(),
+        )
+    };
+    let signature =
+        Signature::from_raw(signature).map_err(|_| invalid("malformed cancel signature"))?;
+    let message = CancelAuthorization {
+        authorizer: key.authorizer,
+        nonce: key.nonce,
+    };
+    let signer: Address = signature
+        .recover_address_from_prehash(&message.eip712_signing_hash(domain))
+        .map_err(|_| invalid("malformed cancel signature"))?;
+    if signer != key.authorizer {
+        return Err(invalid("cancellation is not signed by the authorizer"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::B256;
+    use alloy::signers::SignerSync;
+    use alloy::signers::local::PrivateKeySigner;
+    use alloy::sol_types::eip712_domain;
+
+    #[test]
+    fn test_only_verified_authorizations_signed_by_the_authorizer_are_cancelable() {
+        let payer = PrivateKeySigner::random();
+        let domain = eip712_domain! {
+            name: "USD Coin",
+            version: "2",
+            chain_id: 84532,
+            verifying_contract: Address::repeat_byte(1),
+        };
+        let key = NonceKey {
+            chain_id: 84532,
+            token: Address::repeat_byte(1),
+            authorizer: payer.address(),
+            nonce: B256::ZERO,
+        };
+        let verified = VerifiedAuthorizations::default();
+        assert!(verified.domain(&key).is_none());
+
+        let later = UnixTimestamp(UnixTimestamp::try_now().unwrap().0 + 600);
+        verified.record(key, domain.clone(), later);
+        let domain = verified.domain(&key).unwrap();
+        let other = NonceKey {
+            nonce: B256::repeat_byte(9),
+            ..key
+        };
+        assert!(verified.domain(&other).is_none());
+        verified.record(other, domain.clone(), UnixTimestamp(1));
+        assert!(verified.domain(&other).is_none());
+
+        let message = CancelAuthorization {
+            authorizer: key.authorizer,
+            nonce: key.nonce,
+        };
+        let hash = message.eip712_signing_hash(&domain);
+        let signature = payer.sign_hash_sync(&hash).unwrap();
+        assert!(assert_cancel_signed(&key, &domain, &signature.as_bytes()).is_ok());
+        let forged = PrivateKeySigner::random().sign_hash_sync(&hash).unwrap();
+        assert!(assert_cancel_signed(&key, &domain, &forged.as_bytes()).is_err());
+        assert!(assert_cancel_signed(&key, &domain, &[0u8; 12]).is_err());
+    }
+}
@@ -0,0 +1,685 @@
+//! Content negotiation for facilitator request and response bodies.
+//!
+//! JSON is always supported. With the `msgpack` feature enabled, clients may also:
+//! - send request bodies as MessagePack with `Content-Type: application/msgpack`,
+//! - receive `/verify` and `/settle` responses as MessagePack with `Accept: application/msgpack`.
+//!
+//! Responses are JSON unless MessagePack is accepted. With `STRICT_ACCEPT` enabled, a request whose `Accept`
+//! header rules out every supported media type is rejected with `406 Not Acceptable`, see [`Format`].
+//!
+//! MessagePack payloads use the same field names as JSON (maps, not positional arrays),
+//! so the wire schema is identical apart from the encoding.
+//!
+//! With `AUTHORIZATION_PAYLOAD` enabled, `/verify` and `/settle` also accept the payment payload
+//! base64-encoded in an `Authorization: X402 <payload>` header, as sent in the x402 challenge flow.
+//! The body then only needs `paymentRequirements`, see [`PaymentBody`].
+//!
+//! Request bodies are limited to `MAX_BODY_BYTES` (default: 2 MiB); larger ones are rejected with
+//! `413 Payload Too Large` before being buffered in full, see [`max_body_bytes`]. Bodies that do not
+//! decode are rejected with `400 Bad Request` and an [`ErrorResponse`] naming the path of the field
+//! that failed, e.g. `paymentPayload.payload.authorization.value`, or the position of a syntax error.
+//!
+//! Bodies that fail to decode because of an unsupported `x402Version`, at the top level or in the
+//! `paymentPayload` of a request or of any request in a batch, are rejected with `400 Bad Request`
+//! and an [`UnsupportedVersionResponse`] naming the supported versions, see [`X402Version::SUPPORTED`].
+//! Likewise, bodies naming a `scheme` other than one of [`Scheme::variants`], in a payload or in
+//! requirements, are rejected with `400 Bad Request` and an [`UnknownSchemeResponse`] with the
+//! `invalid_scheme` reason, rather than a generic decoding error.
+
+use axum::Json;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::from_env::{ENV_AUTHORIZATION_PAYLOAD, ENV_MAX_BODY_BYTES, ENV_STRICT_ACCEPT};
+use crate::types::{
+    Base64Bytes, ErrorResponse, NotAcceptableResponse, PaymentPayload, PaymentRequirements, Scheme,
+    UnknownSchemeResponse, UnsupportedVersionResponse, VerifyRequest, X402Version,
+};
+
+/// MIME type for MessagePack bodies.
+#[cfg(feature = "msgpack")]
+pub const MSGPACK_MIME: &str = "application/msgpack";
+
+/// Body encoding selected for a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+}
+
+impl Format {
+    /// Media types responses can be encoded in.
+    pub fn media_types() -> Vec<&'static str> {
+        vec![
+            "application/json",
+            #[cfg(feature = "msgpack")]
+            MSGPACK_MIME,
+        ]
+    }
+
+    /// The response encoding acceptable per the request's `Accept` header, or `None` if it rules out
+    /// all of [`Format::media_types`]. Without an `Accept` header, JSON is picked.
+    pub fn negotiate(headers: &HeaderMap) -> Option<Self> {
+        let ranges: Vec<(String, f32)> = headers
+            .get_all(axum::http::header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|part| {
+                let mut params = part.split(';');
+                let range = params.next()?.trim().to_ascii_lowercase();
+                let quality = params
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .and_then(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                (!range.is_empty()).then_some((range, quality))
+            })
+            .collect();
+        if ranges.is_empty() {
+            return Some(Format::Json);
+        }
+        let ranges: Vec<String> = ranges
+            .into_iter()
+            .filter(|(_, quality)| *quality > 0.0)
+            .map(|(range, _)| range)
+            .collect();
+        #[cfg(feature = "msgpack")]
+        if ranges.iter().any(|range| range == MSGPACK_MIME) {
+            return Some(Format::MessagePack);
+        }
+        ranges
+            .iter()
+            .any(|range| matches!(range.as_str(), "application/json" | "application/*" | "*/*"))
+            .then_some(Format::Json)
+    }
+
+    /// Serializes `body` in this format.
+    pub fn respond<T: Serialize>(self, status: StatusCode, body: &T) -> Response {
+        match self {
+            Format::Json => (status, Json(body)).into_response(),
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => match rmp_serde::to_vec_named(body) {
+                Ok(bytes) => (
+                    status,
+                    [(axum::http::header::CONTENT_TYPE, MSGPACK_MIME)],
+                    bytes,
+                )
+                    .into_response(),
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to encode MessagePack response");
+                    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                }
+            },
+        }
+    }
+}
+
+/// Response encoding negotiated from the `Accept` header.
+///
+/// If the header rules out every supported encoding, the request is answered with JSON, or, with
+/// `STRICT_ACCEPT` enabled, rejected with `406 Not Acceptable` and a [`NotAcceptableResponse`].
+impl<S> FromRequestParts<S> for Format
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(format) = Format::negotiate(&parts.headers) {
+            return Ok(format);
+        }
+        let strict = std::env::var(ENV_STRICT_ACCEPT)
+            .ok()
+            .and_then(|s| s.parse::<bool>().ok())
+            .unwrap_or(false);
+        if !strict {
+            return Ok(Format::Json);
+        }
+        let supported_media_types = Format::media_types();
+        Err((
+            StatusCode::NOT_ACCEPTABLE,
+            Json(NotAcceptableResponse {
+                error: format!(
+                    "None of the accepted media types is supported, expected one of: {}",
+                    supported_media_types.join(", ")
+                ),
+                supported_media_types: supported_media_types
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            }),
+        )
+            .into_response())
+    }
+}
+
+#[cfg(feature = "msgpack")]
+fn header_has_mime(headers: &HeaderMap, name: axum::http::HeaderName, mime: &str) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|part| {
+            part.split(';')
+                .next()
+                .is_some_and(|m| m.trim().eq_ignore_ascii_case(mime))
+        })
+}
+
+/// Largest request body accepted, in bytes, from `MAX_BODY_BYTES`; axum's default of 2 MiB if not set.
+pub fn max_body_bytes() -> usize {
+    std::env::var(ENV_MAX_BODY_BYTES)
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|bytes| *bytes > 0)
+        .unwrap_or(2 * 1024 * 1024)
+}
+
+/// The `413 Payload Too Large` answer to a body over [`max_body_bytes`].
+pub fn body_too_large() -> Response {
+    error_response(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        format!(
+            "Request body is larger than the limit of {} bytes",
+            max_body_bytes()
+        ),
+    )
+}
+
+fn error_response(status: StatusCode, error: String) -> Response {
+    (status, Json(ErrorResponse { error })).into_response()
+}
+
+/// Whether the request declares a JSON body: `application/json` or `application/*+json`.
+fn has_json_content_type(headers: &HeaderMap) -> bool {
+    headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|mime| mime.trim().to_ascii_lowercase())
+        .is_some_and(|mime| {
+            mime == "application/json"
+                || (mime.starts_with("application/") && mime.ends_with("+json"))
+        })
+}
+
+/// The `400 Bad Request` answer to a body that does not decode, naming where it failed.
+fn invalid_body<E: std::fmt::Display>(
+    format: &str,
+    error: serde_path_to_error::Error<E>,
+) -> Response {
+    let path = error.path().to_string();
+    let message = if path == "." {
+        format!("Malformed {format} body: {}", error.inner())
+    } else {
+        format!("Invalid {format} body at {path}: {}", error.inner())
+    };
+    error_response(StatusCode::BAD_REQUEST, message)
+}
+
+/// Request body extractor accepting JSON, or MessagePack when the `msgpack` feature is enabled.
+///
+/// Like [`Json`], but every rejection is an [`ErrorResponse`], see the [module docs](self).
+pub struct Body<T>(pub T);
+
+impl<T, S> FromRequest<S> for Body<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, _state: &S) -> Result<Self, Self::Rejection> {
+        let (parts, body) = req.into_parts();
+        let bytes = axum::body::to_bytes(body, max_body_bytes())
+            .await
+            .map_err(|_| body_too_large())?;
+        #[cfg(feature = "msgpack")]
+        if header_has_mime(
+            &parts.headers,
+            axum::http::header::CONTENT_TYPE,
+            MSGPACK_MIME,
+        ) {
+            return decode(&bytes, Format::MessagePack).map(Body);
+        }
+        if !has_json_content_type(&parts.headers) {
+            return Err(error_response(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "Expected request with `Content-Type: application/json`".to_string(),
+            ));
+        }
+        decode(&bytes, Format::Json).map(Body)
+    }
+}
+
+/// Decodes `bytes` in `format`, rejected as a [`Body`] would be, e.g. for a WebSocket message.
+#[allow(clippy::result_large_err)] // The rejection is the answer, as an extractor's is.
+pub fn decode<T: DeserializeOwned>(bytes: &[u8], format: Format) -> Result<T, Response> {
+    match format {
+        #[cfg(feature = "msgpack")]
+        Format::MessagePack => {
+            let deserializer = &mut rmp_serde::Deserializer::new(bytes);
+            serde_path_to_error::deserialize(deserializer).map_err(|e| {
+                decode_rejection(
+                    rmp_serde::from_slice(bytes).ok(),
+                    rmp_serde::from_slice(bytes).ok(),
+                )
+                .unwrap_or_else(|| invalid_body("MessagePack", e))
+            })
+        }
+        Format::Json => {
+            let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+            let value = serde_path_to_error::deserialize(&mut deserializer).map_err(|e| {
+                decode_rejection(
+                    serde_json::from_slice(bytes).ok(),
+                    serde_json::from_slice(bytes).ok(),
+                )
+                .unwrap_or_else(|| {
+                    if e.inner().is_syntax() || e.inner().is_eof() {
+                        let message = format!("Malformed JSON body: {}", e.inner());
+                        error_response(StatusCode::BAD_REQUEST, message)
+                    } else {
+                        invalid_body("JSON", e)
+                    }
+                })
+            })?;
+            deserializer.end().map_err(|e| {
+                error_response(StatusCode::BAD_REQUEST, format!("Malformed JSON body: {e}"))
+            })?;
+            Ok(value)
+        }
+    }
+}
+
+/// The `x402Version` fields of a request body, read when it fails to decode.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VersionProbe {
+    One(RequestVersions),
+    Batch(Vec<RequestVersions>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RequestVersions {
+    x402_version: Option<u8>,
+    payment_payload: Option<PayloadVersion>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PayloadVersion {
+    x402_version: Option<u8>,
+}
+
+impl VersionProbe {
+    /// Every version found, in body order.
+    fn versions(self) -> Vec<u8> {
+        let requests = match self {
+            VersionProbe::One(request) => vec![request],
+            VersionProbe::Batch(requests) => requests,
+        };
+        requests
+            .into_iter()
+            .flat_map(|request| {
+                let payload_version = request.payment_payload.and_then(|p| p.x402_version);
+                request.x402_version.into_iter().chain(payload_version)
+            })
+            .collect()
+    }
+}
+
+/// The `scheme` fields of a request body, read when it fails to decode.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SchemeProbe {
+    One(RequestSchemes),
+    Batch(Vec<RequestSchemes>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RequestSchemes {
+    payment_payload: Option<SchemeField>,
+    payment_requirements: Option<SchemeField>,
+}
+
+#[derive(Deserialize)]
+struct SchemeField {
+    scheme: Option<String>,
+}
+
+impl SchemeProbe {
+    /// Every scheme found, in body order.
+    fn schemes(self) -> Vec<String> {
+        let requests = match self {
+            SchemeProbe::One(request) => vec![request],
+            SchemeProbe::Batch(requests) => requests,
+        };
+        requests
+            .into_iter()
+            .flat_map(|request| [request.payment_payload, request.payment_requirements])
+            .flatten()
+            .filter_map(|field| field.scheme)
+            .collect()
+    }
+}
+
+/// The rejection of a body that failed to decode, if caused by an unsupported version or an unknown scheme.
+fn decode_rejection(
+    versions: Option<VersionProbe>,
+    schemes: Option<SchemeProbe>,
+) -> Option<Response> {
+    unsupported_version(versions.map(VersionProbe::versions).unwrap_or_default())
+        .or_else(|| unknown_scheme(schemes.map(SchemeProbe::schemes).unwrap_or_default()))
+}
+
+/// The rejection for the first unknown scheme among `schemes`, if any.
+fn unknown_scheme(schemes: impl IntoIterator<Item = String>) -> Option<Response> {
+    let scheme = schemes.into_iter().find(|scheme| {
+        !Scheme::variants()
+            .iter()
+            .any(|known| known.to_string() == *scheme)
+    })?;
+    Some(
+        (
+            StatusCode::BAD_REQUEST,
+            Json(UnknownSchemeResponse::new(scheme)),
+        )
+            .into_response(),
+    )
+}
+
+/// The rejection for the first unsupported version among `versions`, if any.
+fn unsupported_version(versions: impl IntoIterator<Item = u8>) -> Option<Response> {
+    let error = versions
+        .into_iter()
+        .find_map(|version| X402Version::try_from(version).err())?;
+    Some(
+        (
+            StatusCode::BAD_REQUEST,
+            Json(UnsupportedVersionResponse::from(&error)),
+        )
+            .into_response(),
+    )
+}
+
+/// Scheme of an `Authorization` header carrying a base64-encoded [`PaymentPayload`].
+pub const AUTHORIZATION_SCHEME: &str = "X402";
+
+/// Whether payloads are accepted in the `Authorization` header, set by [`authorization_payload_from_env`].
+static AUTHORIZATION_PAYLOAD: AtomicBool = AtomicBool::new(false);
+
+/// Reads `AUTHORIZATION_PAYLOAD`, enabling payloads in the `Authorization` header if it is `true`.
+///
+/// # Errors
+/// Returns an error if it is set to something other than `true` or `false`.
+pub fn authorization_payload_from_env() -> Result<(), String> {
+    let enabled = match std::env::var(ENV_AUTHORIZATION_PAYLOAD) {
+        Ok(raw) => raw
+            .trim()
+            .parse::<bool>()
+            .map_err(|e| format!("env {ENV_AUTHORIZATION_PAYLOAD} is invalid: {e}"))?,
+        Err(_) => false,
+    };
+    AUTHORIZATION_PAYLOAD.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+/// A `/verify` or `/settle` request, taken from the [`Body`] or, if enabled by `AUTHORIZATION_PAYLOAD`,
+/// with its payload from an `Authorization: X402 <payload>` header.
+///
+/// When the header is used, `x402Version` and `paymentPayload` come from the decoded header and only
+/// `paymentRequirements` is read from the body; any other body fields are ignored.
+pub struct PaymentBody(pub VerifyRequest);
+
+/// Body accompanying a payload sent in the `Authorization` header.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RequirementsBody {
+    payment_requirements: PaymentRequirements,
+}
+
+impl<S> FromRequest<S> for PaymentBody
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Some(encoded) = authorization_payload(req.headers()) else {
+            let Body(request) = Body::<VerifyRequest>::from_request(req, state).await?;
+            return Ok(PaymentBody(request));
+        };
+        let encoded = Base64Bytes::from(encoded.as_bytes());
+        let payment_payload = PaymentPayload::try_from(encoded.clone()).map_err(|e| {
+            let decoded = encoded.decode().unwrap_or_default();
+            let version = serde_json::from_slice::<PayloadVersion>(&decoded)
+                .ok()
+                .and_then(|payload| payload.x402_version);
+            let scheme = serde_json::from_slice::<SchemeField>(&decoded)
+                .ok()
+                .and_then(|payload| payload.scheme);
+            unsupported_version(version)
+                .or_else(|| unknown_scheme(scheme))
+                .unwrap_or_else(|| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!(
+                            "Failed to decode the {AUTHORIZATION_SCHEME} authorization payload: {e}"
+                        ),
+                    )
+                        .into_response()
+                })
+        })?;
+        let Body(body) = Body::<RequirementsBody>::from_request(req, state).await?;
+        Ok(PaymentBody(VerifyRequest {
+            x402_version: payment_payload.x402_version,
+            payment_payload,
+            payment_requirements: body.payment_requirements,
+        }))
+    }
+}
+
+/// The base64 payload of an `Authorization: X402 <payload>` header, if accepted and present.
+fn authorization_payload(headers: &HeaderMap) -> Option<String> {
+    if !AUTHORIZATION_PAYLOAD.load(Ordering::Relaxed) {
+        return None;
+    }
+    let value = headers
+        .get(axum::http::header::AUTHORIZATION)?
+        .to_str()
+        .ok()?;
+    let (scheme, payload) = value.trim().split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case(AUTHORIZATION_SCHEME)
+        .then(|| payload.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_finds_unsupported_versions_in_batches_and_payloads() {
+        let body = br#"[
+            {"x402Version": 1, "paymentPayload": {"x402Version": 1}},
+            {"x402Version": 1, "paymentPayload": {"x402Version": 7, "scheme": "exact"}}
+        ]"#;
+        let versions = serde_json::from_slice::<VersionProbe>(body)
+            .unwrap()
+            .versions();
+        assert_eq!(versions, vec![1, 1, 1, 7]);
+        let response = unsupported_version(versions).unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(unsupported_version([1]).is_none());
+    }
+
+    #[test]
+    fn test_unknown_schemes_are_rejected_as_invalid_scheme() {
+        let body = br#"{
+            "x402Version": 1,
+            "paymentPayload": {"x402Version": 1, "scheme": "exacct", "network": "base"},
+            "paymentRequirements": {"scheme": "exact"}
+        }"#;
+        let schemes = serde_json::from_slice::<SchemeProbe>(body)
+            .unwrap()
+            .schemes();
+        assert_eq!(schemes, vec!["exacct".to_string(), "exact".to_string()]);
+        let response = decode_rejection(None, serde_json::from_slice(body).ok()).unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(unknown_scheme(["exact".to_string(), "upto".to_string()]).is_none());
+
+        let rejection = serde_json::to_value(UnknownSchemeResponse::new("exacct".into())).unwrap();
+        assert_eq!(rejection["invalidReason"], "invalid_scheme");
+        assert_eq!(
+            rejection["supportedSchemes"],
+            serde_json::json!(["exact", "upto"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_body_rejections_are_error_responses() {
+        async fn reject(body: Vec<u8>) -> (StatusCode, String) {
+            let request = Request::builder()
+                .header(axum::http::header::CONTENT_TYPE, "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap();
+            let response = Body::<VerifyRequest>::from_request(request, &())
+                .await
+                .err()
+                .unwrap();
+            let status = response.status();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let error: ErrorResponse = serde_json::from_slice(&bytes).unwrap();
+            (status, error.error)
+        }
+
+        let (status, error) = reject(br#"{"x402Version": 1, "paymentPayload": 5}"#.to_vec()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(
+            error.starts_with("Invalid JSON body at paymentPayload:"),
+            "{error}"
+        );
+        let (status, error) = reject(br#"{"x402Version": 1,"#.to_vec()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error.starts_with("Malformed JSON body:"), "{error}");
+        let (status, _) = reject(vec![b' '; max_body_bytes() + 1]).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_decoded_messages_are_rejected_as_bodies_are() {
+        let rejection = decode::<VerifyRequest>(br#"{"x402Version": 1,"#, Format::Json)
+            .err()
+            .unwrap();
+        assert_eq!(rejection.status(), StatusCode::BAD_REQUEST);
+        let bytes = axum::body::to_bytes(rejection.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: ErrorResponse = serde_json::from_slice(&bytes).unwrap();
+        assert!(
+            error.error.starts_with("Malformed JSON body:"),
+            "{}",
+            error.error
+        );
+        let rejection = decode::<VerifyRequest>(br#"{"x402Version": 9}"#, Format::Json)
+            .err()
+            .unwrap();
+        assert_eq!(rejection.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_authorization_payload_is_read_once_and_must_be_a_bool() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::AUTHORIZATION,
+            "x402 eyJ4NDAyVmVyc2lvbiI6MX0=".parse().unwrap(),
+        );
+        unsafe { std::env::set_var(ENV_AUTHORIZATION_PAYLOAD, "yes") };
+        assert!(authorization_payload_from_env().is_err());
+        assert_eq!(authorization_payload(&headers), None);
+
+        unsafe { std::env::set_var(ENV_AUTHORIZATION_PAYLOAD, "true") };
+        authorization_payload_from_env().unwrap();
+        unsafe { std::env::remove_var(ENV_AUTHORIZATION_PAYLOAD) };
+        assert_eq!(
+            authorization_payload(&headers).as_deref(),
+            Some("eyJ4NDAyVmVyc2lvbiI6MX0=")
+        );
+
+        authorization_payload_from_env().unwrap();
+        assert_eq!(authorization_payload(&headers), None);
+    }
+
+    #[test]
+    fn test_negotiate_honours_accept_ranges() {
+        let negotiate = |accept: Option<&str>| {
+            let mut headers = HeaderMap::new();
+            if let Some(accept) = accept {
+                headers.insert(axum::http::header::ACCEPT, accept.parse().unwrap());
+            }
+            Format::negotiate(&headers)
+        };
+        assert_eq!(negotiate(None), Some(Format::Json));
+        assert_eq!(negotiate(Some("text/html, */*;q=0.8")), Some(Format::Json));
+        assert_eq!(negotiate(Some("Application/JSON")), Some(Format::Json));
+        assert_eq!(negotiate(Some("text/html")), None);
+        assert_eq!(negotiate(Some("application/json;q=0")), None);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn test_msgpack_requests_decode_and_accepted_msgpack_responses_encode() {
+        let offline = crate::chain::evm::tests::offline_request(
+            &alloy::signers::local::PrivateKeySigner::random(),
+            alloy::primitives::Address::ZERO,
+        );
+        let verify = VerifyRequest {
+            x402_version: X402Version::V1,
+            payment_payload: offline.payment_payload,
+            payment_requirements: offline.payment_requirements,
+        };
+        let request = Request::builder()
+            .header(axum::http::header::CONTENT_TYPE, MSGPACK_MIME)
+            .body(axum::body::Body::from(
+                rmp_serde::to_vec_named(&verify).unwrap(),
+            ))
+            .unwrap();
+        let Body(decoded) = Body::<VerifyRequest>::from_request(request, &())
+            .await
+            .ok()
+            .unwrap();
+        assert_eq!(decoded.payment_id(), verify.payment_id());
+
+        let accept = "application/json;q=0.5, application/msgpack";
+        let mut headers = HeaderMap::new();
+        headers.insert(axum::http::header::ACCEPT, accept.parse().unwrap());
+        let format = Format::negotiate(&headers).unwrap();
+        assert_eq!(format, Format::MessagePack);
+        let response = format.respond(
+            StatusCode::OK,
+            &ErrorResponse {
+                error: "oops".to_string(),
+            },
+        );
+        assert_eq!(
+            response.headers()[axum::http::header::CONTENT_TYPE],
+            MSGPACK_MIME
+        );
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: ErrorResponse = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(error.error, "oops");
+    }
+}
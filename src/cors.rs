@@ -0,0 +1,160 @@
+//! Cross-origin access for browser clients.
+//!
+//! A dApp calling `/verify` or `/settle` from the browser needs the facilitator to answer CORS preflight
+//! `OPTIONS` requests. By default any origin is allowed, with the methods and request headers x402
+//! clients use; in production, `CORS_ALLOWED_ORIGINS` locks access down to the listed origins.
+//!
+//! Environment variables used:
+//! - `CORS_ALLOWED_ORIGINS` — comma-separated origins, e.g. `https://app.example.com`, or `*` for any (default: `*`),
+//! - `CORS_ALLOWED_METHODS` — comma-separated methods (default: `GET,POST`),
+//! - `CORS_ALLOWED_HEADERS` — comma-separated request headers, or `*` for any (default: [`DEFAULT_ALLOWED_HEADERS`]).
+
+use axum::http::{HeaderName, HeaderValue, Method, header};
+use tower_http::cors::{AllowHeaders, AllowOrigin, Any, CorsLayer};
+
+use crate::from_env::{
+    ENV_CORS_ALLOWED_HEADERS, ENV_CORS_ALLOWED_METHODS, ENV_CORS_ALLOWED_ORIGINS,
+};
+
+/// Request headers allowed unless `CORS_ALLOWED_HEADERS` is set: the body and response types, the
+/// `X-PAYMENT` and `Authorization: X402` payloads, `Idempotency-Key` and trace context.
+pub const DEFAULT_ALLOWED_HEADERS: &[&str] = &[
+    "content-type",
+    "accept",
+    "authorization",
+    "x-payment",
+    "idempotency-key",
+    "traceparent",
+    "tracestate",
+];
+
+/// Builds the CORS layer from `CORS_ALLOWED_ORIGINS`, `CORS_ALLOWED_METHODS` and `CORS_ALLOWED_HEADERS`.
+pub fn layer_from_env() -> Result<CorsLayer, Box<dyn std::error::Error>> {
+    let var = |name: &str| std::env::var(name).ok().filter(|s| !s.trim().is_empty());
+    layer(
+        var(ENV_CORS_ALLOWED_ORIGINS).as_deref(),
+        var(ENV_CORS_ALLOWED_METHODS).as_deref(),
+        var(ENV_CORS_ALLOWED_HEADERS).as_deref(),
+    )
+}
+
+/// Builds the CORS layer from comma-separated lists, `None` meaning the default.
+fn layer(
+    origins: Option<&str>,
+    methods: Option<&str>,
+    headers: Option<&str>,
+) -> Result<CorsLayer, Box<dyn std::error::Error>> {
+    let origins = match origins.map(str::trim) {
+        None | Some("*") => AllowOrigin::from(Any),
+        Some(origins) => AllowOrigin::list(
+            list(origins)
+                .map(|origin| {
+                    HeaderValue::from_str(origin)
+                        .map_err(|e| format!("env {ENV_CORS_ALLOWED_ORIGINS} is invalid: {e}"))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+    };
+    let methods = match methods {
+        None => vec![Method::GET, Method::POST],
+        Some(methods) => list(methods)
+            .map(|method| {
+                Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                    .map_err(|e| format!("env {ENV_CORS_ALLOWED_METHODS} is invalid: {e}"))
+            })
+            .collect::<Result<_, _>>()?,
+    };
+    let headers = match headers.map(str::trim) {
+        Some("*") => AllowHeaders::from(Any),
+        None => AllowHeaders::list(
+            DEFAULT_ALLOWED_HEADERS
+                .iter()
+                .map(|name| HeaderName::from_static(name)),
+        ),
+        Some(headers) => AllowHeaders::list(
+            list(headers)
+                .map(|name| {
+                    HeaderName::from_bytes(name.as_bytes())
+                        .map_err(|e| format!("env {ENV_CORS_ALLOWED_HEADERS} is invalid: {e}"))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+    };
+    Ok(CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(methods)
+        .allow_headers(headers)
+        .expose_headers([header::RETRY_AFTER]))
+}
+
+fn list(raw: &str) -> impl Iterator<Item = &str> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_preflight_is_answered_for_listed_origins_only() {
+        let router = Router::new()
+            .route("/verify", post(|| async { "ok" }))
+            .layer(
+                layer(
+                    Some("https://app.example.com, https://admin.example.com"),
+                    None,
+                    None,
+                )
+                .unwrap(),
+            );
+        let preflight = |origin: &str| {
+            Request::builder()
+                .method(Method::OPTIONS)
+                .uri("/verify")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                .header(
+                    header::ACCESS_CONTROL_REQUEST_HEADERS,
+                    "content-type,x-payment",
+                )
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let allowed = router
+            .clone()
+            .oneshot(preflight("https://app.example.com"))
+            .await
+            .unwrap();
+        assert_eq!(allowed.status(), StatusCode::OK);
+        assert_eq!(
+            allowed.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+        assert!(
+            allowed.headers()[header::ACCESS_CONTROL_ALLOW_HEADERS]
+                .to_str()
+                .unwrap()
+                .contains("x-payment")
+        );
+
+        let refused = router
+            .oneshot(preflight("https://evil.example.com"))
+            .await
+            .unwrap();
+        assert!(
+            !refused
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        );
+
+        assert!(layer(None, Some("GET,NOT A METHOD"), None).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! Settlement event export for streaming pipelines.
+//!
+//! This module defines a pluggable [`SettlementEventSink`] that receives a structured
+//! [`SettlementEvent`] every time a settlement reaches a final status, including settlements that
+//! fail with an error before or after their transaction is sent. Events are published
+//! off the request path, so a slow or unavailable message bus never delays `/settle`.
+//!
+//! Available sinks:
+//! - [`NatsEventSink`] — publishes JSON-encoded events to a NATS subject (requires the `nats` feature).
+//!
+//! Environment variables used:
+//! - `EVENTS_NATS_URL` — NATS server URL, enables the NATS sink when set,
+//! - `EVENTS_NATS_SUBJECT` — subject to publish to (default: `x402.settlements`).
+
+use async_trait::async_trait;
+use serde::Serialize;
+use std::env;
+use std::sync::Arc;
+
+use crate::from_env::{ENV_EVENTS_NATS_SUBJECT, ENV_EVENTS_NATS_URL};
+use crate::network::Network;
+use crate::timestamp::UnixTimestamp;
+use crate::types::{
+    ExactPaymentPayload, Extensions, MixedAddress, PaymentId, SettleRequest, SettleResponse,
+    TokenAmount, TransactionHash,
+};
+
+/// Default NATS subject used when `EVENTS_NATS_SUBJECT` is not set.
+pub const DEFAULT_NATS_SUBJECT: &str = "x402.settlements";
+
+/// Final status of a settlement attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettlementStatus {
+    /// The transfer was executed on-chain.
+    Settled,
+    /// The transfer was attempted but did not succeed.
+    Failed,
+}
+
+/// Structured record of a settlement, published to every configured [`SettlementEventSink`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettlementEvent {
+    pub payment_id: PaymentId,
+    pub status: SettlementStatus,
+    pub network: Network,
+    /// Unknown for Solana payments that failed before their transaction was decoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payer: Option<MixedAddress>,
+    pub receiver: MixedAddress,
+    pub token: MixedAddress,
+    pub amount: TokenAmount,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<UnixTimestamp>,
+    /// Why the settlement failed, for settlements that ended with an error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The payload's [`Extensions`], as sent by the client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Extensions>,
+}
+
+impl SettlementEvent {
+    /// Builds an event from the original settlement request and the facilitator's response.
+    ///
+    /// For EVM payments the amount is the authorized `value`; otherwise the required amount is reported.
+    pub fn new(request: &SettleRequest, response: &SettleResponse) -> Self {
+        let status = if response.success {
+            SettlementStatus::Settled
+        } else {
+            SettlementStatus::Failed
+        };
+        Self {
+            status,
+            network: response.network,
+            payer: Some(response.payer.clone()),
+            transaction: response.transaction.clone(),
+            ..Self::failed(request, None)
+        }
+    }
+
+    /// Builds a [`SettlementStatus::Failed`] event for a settlement of `request` that ended with `error`.
+    pub fn failed(request: &SettleRequest, error: Option<String>) -> Self {
+        let requirements = &request.payment_requirements;
+        let amount = match &request.payment_payload.payload {
+            ExactPaymentPayload::Evm(payload) => payload.authorization.value,
+            ExactPaymentPayload::Solana(_) => requirements.max_amount_required,
+        };
+        Self {
+            payment_id: request.payment_id(),
+            status: SettlementStatus::Failed,
+            network: request.network(),
+            payer: request.payer(),
+            receiver: requirements.pay_to.clone(),
+            token: requirements.asset.clone(),
+            amount,
+            transaction: None,
+            timestamp: UnixTimestamp::try_now().ok(),
+            error,
+            extensions: request.payment_payload.extensions.clone(),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EventSinkError {
+    #[error("Failed to encode settlement event: {0}")]
+    Encoding(#[from] serde_json::Error),
+    #[error("Failed to publish settlement event: {0}")]
+    #[cfg_attr(not(feature = "nats"), allow(dead_code))]
+    Transport(String),
+}
+
+/// Destination for [`SettlementEvent`]s.
+///
+/// Implementations must be cheap to share across tasks; the facilitator publishes each event
+/// from a spawned task and only logs failures.
+#[async_trait]
+pub trait SettlementEventSink: Send + Sync {
+    async fn publish(&self, event: &SettlementEvent) -> Result<(), EventSinkError>;
+}
+
+/// Publishes settlement events as JSON messages to a NATS subject.
+#[cfg(feature = "nats")]
+pub struct NatsEventSink {
+    client: async_nats::Client,
+    subject: String,
+}
+
+#[cfg(feature = "nats")]
+impl NatsEventSink {
+    /// Connects to the NATS server at `url`, publishing subsequent events to `subject`.
+    pub async fn connect(url: &str, subject: String) -> Result<Self, async_nats::ConnectError> {
+        let client = async_nats::connect(url).await?;
+        Ok(Self { client, subject })
+    }
+}
+
+#[cfg(feature = "nats")]
+#[async_trait]
+impl SettlementEventSink for NatsEventSink {
+    async fn publish(&self, event: &SettlementEvent) -> Result<(), EventSinkError> {
+        let payload = serde_json::to_vec(event)?;
+        self.client
+            .publish(self.subject.clone(), payload.into())
+            .await
+            .map_err(|e| EventSinkError::Transport(e.to_string()))
+    }
+}
+
+/// Constructs the settlement event sink configured through environment variables, if any.
+///
+/// Returns an error if a sink is configured but its feature is not compiled in,
+/// or if the connection to the message bus cannot be established.
+pub async fn sink_from_env()
+-> Result<Option<Arc<dyn SettlementEventSink>>, Box<dyn std::error::Error>> {
+    let Ok(url) = env::var(ENV_EVENTS_NATS_URL) else {
+        return Ok(None);
+    };
+    let subject =
+        env::var(ENV_EVENTS_NATS_SUBJECT).unwrap_or_else(|_| DEFAULT_NATS_SUBJECT.to_string());
+    #[cfg(feature = "nats")]
+    {
+        let sink = NatsEventSink::connect(&url, subject.clone()).await?;
+        tracing::info!(url, subject, "Publishing settlement events to NATS");
+        Ok(Some(Arc::new(sink)))
+    }
+    #[cfg(not(feature = "nats"))]
+    {
+        let _ = subject;
+        Err(format!("env {ENV_EVENTS_NATS_URL} is set to {url}, but x402-rs is built without the `nats` feature").into())
+    }
+}
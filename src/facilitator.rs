@@ -3,8 +3,11 @@
 //! Implementors of this trait are responsible for validating incoming payment payloads
 //! against specified requirements [`Facilitator::verify`] and executing on-chain transfers [`Facilitator::settle`].
 
+use crate::health::HealthReport;
+use crate::network::Network;
 use crate::types::{
-    SettleRequest, SettleResponse, SupportedPaymentKindsResponse, VerifyRequest, VerifyResponse,
+    CancelRequest, CancelResponse, QuoteRequest, QuoteResponse, SettleRequest, SettleResponse,
+    SupportedPaymentKindsResponse, TransactionHash, VerifyRequest, VerifyResponse,
 };
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
@@ -56,6 +59,76 @@ pub trait Facilitator {
     fn supported(
         &self,
     ) -> impl Future<Output = Result<SupportedPaymentKindsResponse, Self::Error>> + Send;
+
+    /// Estimates what settling the payment of a [`QuoteRequest`] would cost the facilitator.
+    ///
+    /// Nothing is sent on-chain. Returns `None` by default, for facilitators that do not quote.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the network is not supported or the estimate cannot be made.
+    fn quote(
+        &self,
+        request: &QuoteRequest,
+    ) -> impl Future<Output = Option<Result<QuoteResponse, Self::Error>>> + Send {
+        let _ = request;
+        async { None }
+    }
+
+    /// Submits the ERC-3009 `cancelAuthorization` of a [`CancelRequest`], invalidating an unsettled authorization.
+    ///
+    /// Returns `None` by default, for facilitators that do not support cancellation.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the network does not support cancellation, the authorization is already
+    /// used or being settled, or the cancellation cannot be submitted.
+    fn cancel(
+        &self,
+        request: &CancelRequest,
+    ) -> impl Future<Output = Option<Result<CancelResponse, Self::Error>>> + Send {
+        let _ = request;
+        async { None }
+    }
+
+    /// Whether the settlement transaction `transaction` on `network` succeeded, `Ok(None)` if it is not mined.
+    ///
+    /// Returns `None` by default, for facilitators that cannot look transactions up.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the network is not supported or the transaction cannot be looked up.
+    fn settlement_status(
+        &self,
+        network: Network,
+        transaction: &TransactionHash,
+    ) -> impl Future<Output = Option<Result<Option<bool>, Self::Error>>> + Send {
+        let _ = (network, transaction);
+        async { None }
+    }
+
+    /// The payment kinds of [`Facilitator::supported`] that are on `network`.
+    fn supported_for(
+        &self,
+        network: Network,
+    ) -> impl Future<Output = Result<SupportedPaymentKindsResponse, Self::Error>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let network = network.to_string();
+            let mut supported = self.supported().await?;
+            supported.kinds.retain(|kind| kind.network == network);
+            Ok(supported)
+        }
+    }
+
+    /// Probes the RPC connectivity of the networks this facilitator serves, see [`crate::health`].
+    ///
+    /// Reports no networks by default.
+    fn health(&self) -> impl Future<Output = HealthReport> + Send {
+        async { HealthReport::default() }
+    }
 }
 
 impl<T: Facilitator> Facilitator for Arc<T> {
@@ -80,4 +153,30 @@ impl<T: Facilitator> Facilitator for Arc<T> {
     ) -> impl Future<Output = Result<SupportedPaymentKindsResponse, Self::Error>> + Send {
         self.as_ref().supported()
     }
+
+    fn quote(
+        &self,
+        request: &QuoteRequest,
+    ) -> impl Future<Output = Option<Result<QuoteResponse, Self::Error>>> + Send {
+        self.as_ref().quote(request)
+    }
+
+    fn cancel(
+        &self,
+        request: &CancelRequest,
+    ) -> impl Future<Output = Option<Result<CancelResponse, Self::Error>>> + Send {
+        self.as_ref().cancel(request)
+    }
+
+    fn settlement_status(
+        &self,
+        network: Network,
+        transaction: &TransactionHash,
+    ) -> impl Future<Output = Option<Result<Option<bool>, Self::Error>>> + Send {
+        self.as_ref().settlement_status(network, transaction)
+    }
+
+    fn health(&self) -> impl Future<Output = HealthReport> + Send {
+        self.as_ref().health()
+    }
 }
@@ -8,15 +8,49 @@
 //! - ERC-20 balance checks
 //! - Contract interaction using Alloy
 //! - Network-specific configuration via [`ProviderCache`] and [`USDCDeployment`]
+//!
+//! The result of [`Facilitator::supported`] is cached: `/supported` is always served from memory,
+//! and [`FacilitatorLocal::refresh_supported`] recomputes it, typically from a background task.
+//!
+//! Concurrent verifications of an identical request are deduplicated ("single-flight"): one of them
+//! does the RPC work, and all of them receive its result, including errors. With
+//! [`FacilitatorLocal::with_verify_cache`], results are also reused by later identical verifications.
+//!
+//! With [`FacilitatorLocal::with_merchant_intents`], payments for resources of registered merchants must carry
+//! a merchant-signed intent, see [`crate::merchant_intent`]. With [`FacilitatorLocal::with_resource_policy`],
+//! their `resource` must match the merchant's allowed patterns, see [`crate::resource_policy`].
+//! With [`FacilitatorLocal::with_receiver_allowlist`], only listed receivers can be paid, see [`crate::receiver_allowlist`].
+//! With [`FacilitatorLocal::with_fallback`], verifications and settlements failing on an RPC or signer
+//! error are forwarded to a backup facilitator, see [`crate::fallback`].
+//!
+//! Every verification and settlement is recorded in [`Metrics`].
 
+use alloy::primitives::{B256, keccak256};
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use tokio::sync::OnceCell;
 use tracing::instrument;
 
-use crate::chain::FacilitatorLocalError;
+use crate::chain::{FacilitatorLocalError, submission};
+use crate::events::{SettlementEvent, SettlementEventSink};
 use crate::facilitator::Facilitator;
+use crate::fallback::Fallback;
+use crate::health::HealthReport;
+use crate::journal;
+use crate::merchant_intent::MerchantIntents;
+use crate::metrics::Metrics;
+use crate::network::Network;
 use crate::provider_cache::ProviderMap;
+use crate::receiver_allowlist::ReceiverAllowlist;
+use crate::resource_policy::ResourcePolicy;
 use crate::types::{
-    SettleRequest, SettleResponse, SupportedPaymentKindsResponse, VerifyRequest, VerifyResponse,
+    CancelRequest, CancelResponse, PaymentRequirements, QuoteRequest, QuoteResponse, SettleRequest,
+    SettleResponse, SettleStatus, SupportedPaymentKindsResponse, TransactionHash, VerifyRequest,
+    VerifyResponse,
 };
+use crate::verify_cache::VerifyCache;
 
 /// A concrete [`Facilitator`] implementation that verifies and settles x402 payments
 /// using a network-aware provider cache.
@@ -25,6 +59,68 @@ use crate::types::{
 /// which enables testing or customization beyond the default [`ProviderCache`].
 pub struct FacilitatorLocal<A> {
     provider_map: A,
+    event_sink: Option<Arc<dyn SettlementEventSink>>,
+    supported_cache: RwLock<Option<SupportedPaymentKindsResponse>>,
+    settlement_enabled: bool,
+    /// Set while in maintenance mode, e.g. by the [`crate::kill_switch`]; settlements are refused.
+    maintenance: Arc<AtomicBool>,
+    /// In-flight verifications keyed by request hash; `None` disables deduplication.
+    inflight_verifies: Option<DashMap<B256, Arc<InflightVerify>>>,
+    merchant_intents: Option<MerchantIntents>,
+    resource_policy: Option<ResourcePolicy>,
+    receiver_allowlist: Option<ReceiverAllowlist>,
+    verify_cache: Option<VerifyCache>,
+    fallback: Option<Fallback>,
+}
+
+type InflightVerify = OnceCell<Result<VerifyResponse, FacilitatorLocalError>>;
+
+/// Runs `work` once for all concurrent callers with the same `key`, sharing its output.
+///
+/// If the caller doing the work is cancelled, the next waiter takes over. The entry of `key` is
+/// removed as soon as a caller is done or cancelled, so it is never left behind; callers arriving
+/// after that start over.
+async fn single_flight<T, F, Fut>(
+    inflight: &DashMap<B256, Arc<OnceCell<T>>>,
+    key: B256,
+    work: F,
+) -> T
+where
+    T: Clone,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = T>,
+{
+    /// Removes the entry of `key` when dropped, unless it was replaced since.
+    struct Entry<'a, T> {
+        inflight: &'a DashMap<B256, Arc<OnceCell<T>>>,
+        key: B256,
+        cell: Arc<OnceCell<T>>,
+    }
+
+    impl<T> Drop for Entry<'_, T> {
+        fn drop(&mut self) {
+            self.inflight
+                .remove_if(&self.key, |_, current| Arc::ptr_eq(current, &self.cell));
+        }
+    }
+
+    let cell = Arc::clone(
+        inflight
+            .entry(key)
+            .or_insert_with(|| Arc::new(OnceCell::new()))
+            .value(),
+    );
+    let entry = Entry {
+        inflight,
+        key,
+        cell,
+    };
+    entry.cell.get_or_init(work).await.clone()
+}
+
+/// Hash identifying identical requests, for deduplication and caching.
+fn request_key(request: &VerifyRequest) -> Option<B256> {
+    serde_json::to_vec(request).ok().map(keccak256)
 }
 
 impl<A> FacilitatorLocal<A> {
@@ -32,16 +128,187 @@ impl<A> FacilitatorLocal<A> {
     ///
     /// The provider cache is used to resolve the appropriate EVM provider for each payment's target network.
     pub fn new(provider_map: A) -> Self {
-        FacilitatorLocal { provider_map }
+        FacilitatorLocal {
+            provider_map,
+            event_sink: None,
+            supported_cache: RwLock::new(None),
+            settlement_enabled: true,
+            maintenance: Arc::new(AtomicBool::new(false)),
+            inflight_verifies: Some(DashMap::new()),
+            merchant_intents: None,
+            resource_policy: None,
+            receiver_allowlist: None,
+            verify_cache: None,
+            fallback: None,
+        }
+    }
+
+    /// Returns the providers this facilitator settles with.
+    pub fn provider_map(&self) -> &A {
+        &self.provider_map
+    }
+
+    /// Shared maintenance flag: while it is set, [`Facilitator::settle`] returns
+    /// [`FacilitatorLocalError::Maintenance`].
+    pub fn maintenance_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.maintenance)
+    }
+
+    /// Enables or disables single-flight deduplication of concurrent identical verifications (enabled by default).
+    pub fn with_single_flight_verify(mut self, enabled: bool) -> Self {
+        self.inflight_verifies = enabled.then(DashMap::new);
+        self
+    }
+
+    /// Answers repeated identical verifications from `verify_cache`, see [`crate::verify_cache`].
+    pub fn with_verify_cache(mut self, verify_cache: Option<VerifyCache>) -> Self {
+        self.verify_cache = verify_cache;
+        self
+    }
+
+    /// Enables or disables settlement. With settlement disabled, the facilitator only verifies payments,
+    /// and [`Facilitator::settle`] returns [`FacilitatorLocalError::SettlementDisabled`].
+    pub fn with_settlement(mut self, settlement_enabled: bool) -> Self {
+        self.settlement_enabled = settlement_enabled;
+        self
+    }
+
+    /// Requires payments to registered merchants to carry a signature by the merchant's key
+    /// over their payment requirements.
+    pub fn with_merchant_intents(mut self, merchant_intents: Option<MerchantIntents>) -> Self {
+        self.merchant_intents = merchant_intents;
+        self
+    }
+
+    /// Restricts the `resource` of payments to each merchant to the merchant's allowed patterns.
+    pub fn with_resource_policy(mut self, resource_policy: Option<ResourcePolicy>) -> Self {
+        self.resource_policy = resource_policy;
+        self
+    }
+
+    /// Restricts the receivers of payments per network to the listed addresses.
+    pub fn with_receiver_allowlist(
+        mut self,
+        receiver_allowlist: Option<ReceiverAllowlist>,
+    ) -> Self {
+        self.receiver_allowlist = receiver_allowlist;
+        self
+    }
+
+    /// Forwards verifications and settlements failing on an infrastructure error to a backup facilitator.
+    pub fn with_fallback(mut self, fallback: Option<Fallback>) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    fn assert_receiver_allowed(
+        &self,
+        requirements: &PaymentRequirements,
+    ) -> Result<(), FacilitatorLocalError> {
+        match &self.receiver_allowlist {
+            Some(receiver_allowlist) => receiver_allowlist.assert_allowed(requirements),
+            None => Ok(()),
+        }
+    }
+
+    fn assert_merchant_intent(
+        &self,
+        requirements: &PaymentRequirements,
+    ) -> Result<(), FacilitatorLocalError> {
+        if let Some(resource_policy) = &self.resource_policy {
+            resource_policy.assert_allowed(requirements)?;
+        }
+        match &self.merchant_intents {
+            Some(merchant_intents) => merchant_intents.assert_signed(requirements),
+            None => Ok(()),
+        }
+    }
+
+    /// Publishes a [`SettlementEvent`] to `event_sink` after every settlement, failed ones included.
+    ///
+    /// Publishing happens in the background; failures are logged and never affect the settlement response.
+    pub fn with_event_sink(mut self, event_sink: Option<Arc<dyn SettlementEventSink>>) -> Self {
+        self.event_sink = event_sink;
+        self
+    }
+
+    fn publish_settlement(&self, request: &SettleRequest, response: &SettleResponse) {
+        self.publish(|| SettlementEvent::new(request, response));
+    }
+
+    fn publish_failure(&self, request: &SettleRequest, error: &FacilitatorLocalError) {
+        self.publish(|| SettlementEvent::failed(request, Some(error.to_string())));
+    }
+
+    fn publish(&self, event: impl FnOnce() -> SettlementEvent) {
+        let Some(event_sink) = &self.event_sink else {
+            return;
+        };
+        let event_sink = Arc::clone(event_sink);
+        let event = event();
+        tokio::spawn(async move {
+            if let Err(error) = event_sink.publish(&event).await {
+                tracing::warn!(%error, "Failed to publish settlement event");
+            }
+        });
     }
 }
 
-impl<A, E> Facilitator for FacilitatorLocal<A>
+impl<A, E> FacilitatorLocal<A>
 where
     A: ProviderMap + Sync,
     A::Value: Facilitator<Error = E>,
     E: Send,
     FacilitatorLocalError: From<E>,
+{
+    /// Recomputes the supported payment kinds from every provider and replaces the cached value.
+    ///
+    /// Providers that fail to report are left out of the result.
+    pub async fn refresh_supported(&self) -> SupportedPaymentKindsResponse {
+        let mut kinds = vec![];
+        for provider in self.provider_map.values() {
+            let supported = provider.supported().await.ok();
+            let mut supported_kinds = supported.map(|k| k.kinds).unwrap_or_default();
+            kinds.append(&mut supported_kinds);
+        }
+        let supported = SupportedPaymentKindsResponse { kinds };
+        if let Ok(mut cache) = self.supported_cache.write() {
+            *cache = Some(supported.clone());
+        }
+        supported
+    }
+
+    fn cached_supported(&self) -> Option<SupportedPaymentKindsResponse> {
+        self.supported_cache.read().ok()?.clone()
+    }
+
+    /// Verifies `request` with the provider for its network, without deduplication.
+    async fn verify_once(
+        &self,
+        request: &VerifyRequest,
+    ) -> Result<VerifyResponse, FacilitatorLocalError> {
+        self.assert_receiver_allowed(&request.payment_requirements)?;
+        self.assert_merchant_intent(&request.payment_requirements)?;
+        let network = request.network();
+        let provider = self
+            .provider_map
+            .by_network(network)
+            .ok_or(FacilitatorLocalError::UnsupportedNetwork(None))?;
+        let verify_response = provider
+            .verify(request)
+            .await?
+            .with_payment_id(request.payment_id())
+            .with_extensions(request.payment_payload.extensions.clone());
+        Ok(verify_response)
+    }
+}
+
+impl<A, E> Facilitator for FacilitatorLocal<A>
+where
+    A: ProviderMap + Sync,
+    A::Value: Facilitator<Error = E>,
+    E: Send + std::fmt::Display,
+    FacilitatorLocalError: From<E>,
 {
     type Error = FacilitatorLocalError;
 
@@ -60,16 +327,23 @@ where
     /// - invalid signature,
     /// - expired or future-dated timing,
     /// - insufficient funds,
-    /// - unsupported network.
+    /// - unsupported network,
+    /// - a missing or invalid merchant intent, if the receiver is a registered merchant,
+    /// - a `resource` not allowed for the receiver.
     #[instrument(skip_all, err, fields(network = %request.payment_payload.network))]
     async fn verify(&self, request: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
-        let network = request.network();
-        let provider = self
-            .provider_map
-            .by_network(network)
-            .ok_or(FacilitatorLocalError::UnsupportedNetwork(None))?;
-        let verify_response = provider.verify(request).await?;
-        Ok(verify_response)
+        let started = Instant::now();
+        let mut result = self.verify_deduplicated(request).await;
+        if let (Some(fallback), Err(error)) = (&self.fallback, &result)
+            && let Some(backup) = fallback.backup_for(request.network(), error)
+        {
+            result = fallback.verify(backup, request).await;
+        }
+        Metrics::global().observe_verify(request.network(), started.elapsed(), &result);
+        if let Ok(VerifyResponse::Valid { .. }) = &result {
+            journal::record_verified(request);
+        }
+        result
     }
 
     /// Executes an x402 payment on-chain using ERC-3009 `transferWithAuthorization`.
@@ -83,24 +357,293 @@ where
     ///
     /// Returns [`FacilitatorLocalError`] if validation or contract call fails. Transaction receipt is included
     /// in the response on success or failure.
+    /// Returns [`FacilitatorLocalError::SettlementDisabled`] in verify-only mode,
+    /// and [`FacilitatorLocalError::Maintenance`] in maintenance mode.
     #[instrument(skip_all, err, fields(network = %request.payment_payload.network))]
     async fn settle(&self, request: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+        let started = Instant::now();
+        let (mut result, broadcast) = submission::detect(self.settle_once(request)).await;
+        if let (Some(fallback), Err(error), false) = (&self.fallback, &result, broadcast)
+            && let Some(backup) = fallback.backup_for(request.network(), error)
+        {
+            result = fallback.settle(backup, request).await;
+            if let Ok(response) = &result {
+                self.publish_settlement(request, response);
+                journal::record_outcome(request, response).await;
+            }
+        }
+        if let Err(error) = &result {
+            self.publish_failure(request, error);
+        }
+        // The authorization is spent or in flight: a cached verification of it is stale.
+        if let Some(verify_cache) = &self.verify_cache
+            && let Some(key) = request_key(request)
+        {
+            verify_cache.invalidate(&key);
+        }
+        Metrics::global().observe_settle(request.network(), started.elapsed(), &result);
+        result
+    }
+
+    /// Returns the cached supported payment kinds, computing them on first use.
+    async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
+        match self.cached_supported() {
+            Some(supported) => Ok(supported),
+            None => Ok(self.refresh_supported().await),
+        }
+    }
+
+    async fn quote(&self, request: &QuoteRequest) -> Option<Result<QuoteResponse, Self::Error>> {
+        let Some(provider) = self
+            .provider_map
+            .by_network(request.payment_requirements.network)
+        else {
+            return Some(Err(FacilitatorLocalError::UnsupportedNetwork(None)));
+        };
+        let result = provider.quote(request).await?;
+        Some(result.map_err(Into::into))
+    }
+
+    /// Submits the cancellation unless settlement is disabled or in maintenance, like [`Facilitator::settle`].
+    async fn cancel(&self, request: &CancelRequest) -> Option<Result<CancelResponse, Self::Error>> {
+        if !self.settlement_enabled {
+            return Some(Err(FacilitatorLocalError::SettlementDisabled));
+        }
+        if self.maintenance.load(Ordering::Relaxed) {
+            return Some(Err(FacilitatorLocalError::Maintenance));
+        }
+        let Some(provider) = self.provider_map.by_network(request.network) else {
+            return Some(Err(FacilitatorLocalError::UnsupportedNetwork(None)));
+        };
+        let result = provider.cancel(request).await?;
+        Some(result.map_err(Into::into))
+    }
+
+    async fn settlement_status(
+        &self,
+        network: Network,
+        transaction: &TransactionHash,
+    ) -> Option<Result<Option<bool>, Self::Error>> {
+        let Some(provider) = self.provider_map.by_network(network) else {
+            return Some(Err(FacilitatorLocalError::UnsupportedNetwork(None)));
+        };
+        let result = provider.settlement_status(network, transaction).await?;
+        Some(result.map_err(Into::into))
+    }
+
+    /// Probes every provider concurrently.
+    async fn health(&self) -> HealthReport {
+        futures::future::join_all(self.provider_map.values().map(|provider| provider.health()))
+            .await
+            .into_iter()
+            .fold(HealthReport::default(), HealthReport::merge)
+    }
+}
+
+impl<A, E> FacilitatorLocal<A>
+where
+    A: ProviderMap + Sync,
+    A::Value: Facilitator<Error = E>,
+    E: Send + std::fmt::Display,
+    FacilitatorLocalError: From<E>,
+{
+    /// Verifies `request`, sharing the work with identical verifications in flight.
+    ///
+    /// With a [`VerifyCache`], a live cached result is returned instead, and fresh results are cached.
+    async fn verify_deduplicated(
+        &self,
+        request: &VerifyRequest,
+    ) -> Result<VerifyResponse, FacilitatorLocalError> {
+        let Some(key) = request_key(request) else {
+            return self.verify_once(request).await;
+        };
+        if let Some(verify_cache) = &self.verify_cache
+            && let Some(response) = verify_cache.get(&key)
+        {
+            return Ok(response);
+        }
+        let result = self.verify_single_flight(key, request).await;
+        if let Some(verify_cache) = &self.verify_cache
+            && let Ok(response) = &result
+        {
+            verify_cache.insert(key, request, response.clone());
+        }
+        result
+    }
+
+    async fn verify_single_flight(
+        &self,
+        key: B256,
+        request: &VerifyRequest,
+    ) -> Result<VerifyResponse, FacilitatorLocalError> {
+        let Some(inflight_verifies) = &self.inflight_verifies else {
+            return self.verify_once(request).await;
+        };
+        single_flight(inflight_verifies, key, || self.verify_once(request)).await
+    }
+
+    async fn settle_once(
+        &self,
+        request: &SettleRequest,
+    ) -> Result<SettleResponse, FacilitatorLocalError> {
+        if !self.settlement_enabled {
+            return Err(FacilitatorLocalError::SettlementDisabled);
+        }
+        if self.maintenance.load(Ordering::Relaxed) {
+            return Err(FacilitatorLocalError::Maintenance);
+        }
+        self.assert_receiver_allowed(&request.payment_requirements)?;
+        self.assert_merchant_intent(&request.payment_requirements)?;
         let network = request.network();
         let provider = self
             .provider_map
             .by_network(network)
             .ok_or(FacilitatorLocalError::UnsupportedNetwork(None))?;
-        let settle_response = provider.settle(request).await?;
+        let mut settle_response = journal::track(request, provider.settle(request)).await?;
+        settle_response.payment_id = Some(request.payment_id());
+        settle_response.extensions = request.payment_payload.extensions.clone();
+        if settle_response.success {
+            settle_response.status = Some(SettleStatus::Confirmed);
+        }
+        self.publish_settlement(request, &settle_response);
+        journal::record_outcome(request, &settle_response).await;
         Ok(settle_response)
     }
+}
 
-    async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
-        let mut kinds = vec![];
-        for provider in self.provider_map.values() {
-            let supported = provider.supported().await.ok();
-            let mut supported_kinds = supported.map(|k| k.kinds).unwrap_or_default();
-            kinds.append(&mut supported_kinds);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::Address;
+    use alloy::signers::local::PrivateKeySigner;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    use crate::chain::evm::tests::offline_request;
+    use crate::events::{EventSinkError, SettlementStatus};
+    use crate::network::Network;
+    use crate::types::X402Version;
+
+    /// A provider whose settlements fail with an RPC error.
+    struct Unreachable;
+
+    impl Facilitator for Unreachable {
+        type Error = FacilitatorLocalError;
+
+        async fn verify(&self, _: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
+            Err(FacilitatorLocalError::ContractCall(
+                "node unavailable".to_string(),
+            ))
+        }
+
+        async fn settle(&self, _: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+            Err(FacilitatorLocalError::ContractCall(
+                "node unavailable".to_string(),
+            ))
         }
-        Ok(SupportedPaymentKindsResponse { kinds })
+
+        async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
+            Err(FacilitatorLocalError::UnsupportedNetwork(None))
+        }
+    }
+
+    /// A provider counting how often it is asked for its supported kinds.
+    #[derive(Default)]
+    struct CountingSupported(AtomicUsize);
+
+    impl Facilitator for CountingSupported {
+        type Error = FacilitatorLocalError;
+
+        async fn verify(&self, _: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
+            Err(FacilitatorLocalError::UnsupportedNetwork(None))
+        }
+
+        async fn settle(&self, _: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+            Err(FacilitatorLocalError::UnsupportedNetwork(None))
+        }
+
+        async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(SupportedPaymentKindsResponse { kinds: Vec::new() })
+        }
+    }
+
+    struct OnlyProvider<F>(F);
+
+    impl<F: Sync> ProviderMap for OnlyProvider<F> {
+        type Value = F;
+
+        fn by_network<N: std::borrow::Borrow<Network>>(&self, _: N) -> Option<&F> {
+            Some(&self.0)
+        }
+
+        fn values(&self) -> impl Iterator<Item = &F> + Send {
+            std::iter::once(&self.0)
+        }
+    }
+
+    struct RecordingSink(mpsc::UnboundedSender<SettlementEvent>);
+
+    #[async_trait::async_trait]
+    impl SettlementEventSink for RecordingSink {
+        async fn publish(&self, event: &SettlementEvent) -> Result<(), EventSinkError> {
+            let _ = self.0.send(event.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_settlements_ending_with_an_error_publish_a_failed_event() {
+        let (events, mut published) = mpsc::unbounded_channel();
+        let facilitator = FacilitatorLocal::new(OnlyProvider(Unreachable))
+            .with_event_sink(Some(Arc::new(RecordingSink(events))));
+        let request = offline_request(&PrivateKeySigner::random(), Address::ZERO);
+        let request = SettleRequest {
+            x402_version: X402Version::V1,
+            payment_payload: request.payment_payload,
+            payment_requirements: request.payment_requirements,
+        };
+        assert!(facilitator.settle(&request).await.is_err());
+
+        let event = tokio::time::timeout(Duration::from_secs(1), published.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.status, SettlementStatus::Failed);
+        assert_eq!(event.payment_id, request.payment_id());
+        assert_eq!(event.payer, request.payer());
+        assert!(event.transaction.is_none());
+        assert!(event.error.unwrap().contains("node unavailable"));
+    }
+
+    #[tokio::test]
+    async fn test_supported_is_served_from_the_cache_until_refreshed() {
+        let facilitator = FacilitatorLocal::new(OnlyProvider(CountingSupported::default()));
+        let calls = || facilitator.provider_map().0.0.load(Ordering::SeqCst);
+        facilitator.supported().await.unwrap();
+        assert_eq!(calls(), 1);
+        facilitator.supported().await.unwrap();
+        assert_eq!(calls(), 1);
+        facilitator.refresh_supported().await;
+        facilitator.supported().await.unwrap();
+        assert_eq!(calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_single_flight_leaves_no_entry_behind() {
+        let inflight: DashMap<B256, Arc<OnceCell<u32>>> = DashMap::new();
+        let key = B256::repeat_byte(1);
+        let cancelled = tokio::time::timeout(
+            Duration::from_millis(10),
+            single_flight(&inflight, key, std::future::pending::<u32>),
+        )
+        .await;
+        assert!(cancelled.is_err());
+        assert!(inflight.is_empty());
+
+        let result = single_flight(&inflight, key, || async { 7 }).await;
+        assert_eq!(result, 7);
+        assert!(inflight.is_empty());
     }
 }
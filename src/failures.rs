@@ -0,0 +1,161 @@
+//! Bounded in-memory log of recent failed payments, for debugging integrations.
+//!
+//! Failed `/verify` and `/settle` calls are recorded with their failure reason and request body,
+//! keeping only the most recent entries, and served by `GET /admin/failures`. Integrators can
+//! inspect what went wrong without access to the facilitator's logs.
+//!
+//! Fields named in `FAILURE_LOG_REDACT` are replaced with `"<redacted>"` wherever they appear
+//! in a recorded body.
+//!
+//! Environment variables used:
+//! - `FAILURE_LOG_SIZE` — number of failures kept (default: `0`, disabled),
+//! - `FAILURE_LOG_REDACT` — comma-separated field names to redact (default: `signature,transaction`,
+//!   the signature of EVM payments and the signed transaction of Solana payments),
+//! - `ADMIN_API_TOKEN` — bearer token required by `GET /admin/failures`; the endpoint is disabled without it.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::from_env::{ENV_FAILURE_LOG_REDACT, ENV_FAILURE_LOG_SIZE};
+use crate::timestamp::UnixTimestamp;
+
+/// Fields redacted unless `FAILURE_LOG_REDACT` is set.
+const DEFAULT_REDACT: &str = "signature,transaction";
+
+/// A recorded failure.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailureSample {
+    pub timestamp: UnixTimestamp,
+    /// Endpoint that failed, e.g. `/verify`.
+    pub endpoint: &'static str,
+    pub reason: String,
+    /// Request body, with redacted fields.
+    pub body: serde_json::Value,
+}
+
+/// Ring buffer of the most recent [`FailureSample`]s.
+#[derive(Debug)]
+pub struct FailureLog {
+    capacity: usize,
+    redact: Vec<String>,
+    samples: Mutex<VecDeque<FailureSample>>,
+}
+
+static FAILURES: Lazy<FailureLog> = Lazy::new(FailureLog::from_env);
+
+impl FailureLog {
+    /// Creates a log keeping `capacity` failures, redacting the `redact` fields.
+    pub fn new(capacity: usize, redact: Vec<String>) -> Self {
+        Self {
+            capacity,
+            redact,
+            samples: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    fn from_env() -> Self {
+        let capacity = std::env::var(ENV_FAILURE_LOG_SIZE)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        let redact =
+            std::env::var(ENV_FAILURE_LOG_REDACT).unwrap_or_else(|_| DEFAULT_REDACT.to_string());
+        Self::new(capacity, fields(&redact))
+    }
+
+    /// Process-wide log populated by the HTTP handlers.
+    pub fn global() -> &'static FailureLog {
+        &FAILURES
+    }
+
+    /// Records a failure, evicting the oldest one if the log is full.
+    pub fn record<T: Serialize>(&self, endpoint: &'static str, reason: String, body: &T) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut body = serde_json::to_value(body).unwrap_or(serde_json::Value::Null);
+        redact(&mut body, &self.redact);
+        let sample = FailureSample {
+            timestamp: UnixTimestamp::try_now().unwrap_or(UnixTimestamp(0)),
+            endpoint,
+            reason,
+            body,
+        };
+        let Ok(mut samples) = self.samples.lock() else {
+            return;
+        };
+        if samples.len() == self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    /// Returns the recorded failures, most recent first.
+    pub fn recent(&self) -> Vec<FailureSample> {
+        self.samples
+            .lock()
+            .map(|samples| samples.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+fn fields(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|field| field.trim().to_string())
+        .filter(|field| !field.is_empty())
+        .collect()
+}
+
+fn redact(value: &mut serde_json::Value, fields: &[String]) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if fields.iter().any(|field| field == key) {
+                    *value = serde_json::Value::String("<redacted>".to_string());
+                } else {
+                    redact(value, fields);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => {
+            values.iter_mut().for_each(|value| redact(value, fields));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_failure_log_is_bounded_and_redacted() {
+        let log = FailureLog::new(2, vec!["signature".to_string()]);
+        for i in 0..3 {
+            let body = json!({"id": i, "payload": {"signature": "0xdead", "from": "0xabc"}});
+            log.record("/verify", format!("failure {i}"), &body);
+        }
+        let recent = log.recent();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].reason, "failure 2");
+        assert_eq!(recent[1].reason, "failure 1");
+        assert_eq!(recent[0].body["payload"]["signature"], "<redacted>");
+        assert_eq!(recent[0].body["payload"]["from"], "0xabc");
+    }
+
+    #[test]
+    fn test_solana_transactions_are_redacted_by_default() {
+        let log = FailureLog::new(1, fields(DEFAULT_REDACT));
+        let body = json!({"paymentPayload": {"payload": {"transaction": "AQID"}}});
+        log.record("/settle", "failure".to_string(), &body);
+        let recent = log.recent();
+        assert_eq!(
+            recent[0].body["paymentPayload"]["payload"]["transaction"],
+            "<redacted>"
+        );
+    }
+}
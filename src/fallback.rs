@@ -0,0 +1,245 @@
+//! Forwarding of verifications and settlements to a backup facilitator.
+//!
+//! When a network's RPC or signer is unavailable, `/verify` and `/settle` can be answered by another
+//! facilitator instead of failing. Forwarding is opt-in per network and only happens on infrastructure
+//! errors, [`FacilitatorLocalError::ContractCall`] and [`FacilitatorLocalError::RpcTimeout`], never on a
+//! rejection of the payment itself such as a scheme mismatch. A settlement is only forwarded if it failed
+//! before broadcasting anything, so the same authorization is never submitted by both facilitators.
+//! A settlement the backup made is journaled and published to the event sink like a local one.
+//!
+//! The backup is called over the same HTTP API that `x402_axum::facilitator_client::FacilitatorClient`
+//! speaks. Forwarded requests carry [`FORWARDED_HEADER`], and requests carrying it are never forwarded
+//! again, see [`mark_forwarded`], so two facilitators configured as each other's backup do not ping-pong.
+//! A backup that does not connect within [`CONNECT_TIMEOUT`] or answer within [`REQUEST_TIMEOUT`]
+//! fails the request like an unreachable one.
+//!
+//! Environment variables used:
+//! - `FALLBACK_FACILITATOR_URL_<NETWORK>` — base URL of the backup facilitator for a network, e.g.
+//!   `FALLBACK_FACILITATOR_URL_BASE=https://backup.example.com/`.
+
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use reqwest::StatusCode;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::time::Duration;
+use url::Url;
+
+use crate::chain::FacilitatorLocalError;
+use crate::from_env::{self, ENV_FALLBACK_FACILITATOR_URL_PREFIX};
+use crate::network::Network;
+use crate::types::{SettleRequest, SettleResponse, VerifyRequest, VerifyResponse};
+
+/// Header marking a request forwarded by another facilitator.
+pub const FORWARDED_HEADER: &str = "x-facilitator-forwarded";
+
+/// How long to wait for a connection to a backup facilitator.
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long to wait for a backup facilitator's answer, long enough for it to wait for a receipt.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+tokio::task_local! {
+    static FORWARDED: bool;
+}
+
+/// Middleware running the request with [`is_forwarded`] telling whether it carries [`FORWARDED_HEADER`].
+pub async fn mark_forwarded(request: Request, next: Next) -> Response {
+    let forwarded = request.headers().contains_key(FORWARDED_HEADER);
+    FORWARDED.scope(forwarded, next.run(request)).await
+}
+
+/// Whether the current request was forwarded by another facilitator.
+pub fn is_forwarded() -> bool {
+    FORWARDED.try_with(|forwarded| *forwarded).unwrap_or(false)
+}
+
+/// Whether `error` is a failure of the facilitator's infrastructure rather than of the payment.
+pub fn is_infrastructure(error: &FacilitatorLocalError) -> bool {
+    matches!(
+        error,
+        FacilitatorLocalError::ContractCall(_) | FacilitatorLocalError::RpcTimeout(_)
+    )
+}
+
+/// Backup facilitators, keyed by network, see the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct Fallback {
+    backups: HashMap<Network, Url>,
+    client: reqwest::Client,
+}
+
+impl Fallback {
+    /// Reads `FALLBACK_FACILITATOR_URL_<NETWORK>` for every network, or returns `None` if none is set.
+    pub fn from_env() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let mut backups = HashMap::new();
+        for network in Network::variants() {
+            let env_var =
+                from_env::env_name_for_network(ENV_FALLBACK_FACILITATOR_URL_PREFIX, *network);
+            let Ok(raw) = std::env::var(&env_var) else {
+                continue;
+            };
+            let mut url: Url = raw
+                .trim()
+                .parse()
+                .map_err(|e| format!("env {env_var} is invalid: {e}"))?;
+            if !url.path().ends_with('/') {
+                url.set_path(&format!("{}/", url.path()));
+            }
+            backups.insert(*network, url);
+        }
+        if backups.is_empty() {
+            return Ok(None);
+        }
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        Ok(Some(Self { backups, client }))
+    }
+
+    /// The backup to forward a request on `network` to after `error`, if any.
+    ///
+    /// `None` if `error` is not an infrastructure error, the request was itself forwarded, or no
+    /// backup is configured for `network`.
+    pub fn backup_for(&self, network: Network, error: &FacilitatorLocalError) -> Option<&Url> {
+        if !is_infrastructure(error) || is_forwarded() {
+            return None;
+        }
+        let backup = self.backups.get(&network)?;
+        tracing::warn!(%network, %error, %backup, "Forwarding to fallback facilitator");
+        Some(backup)
+    }
+
+    /// Verifies `request` with the facilitator at `backup`.
+    pub async fn verify(
+        &self,
+        backup: &Url,
+        request: &VerifyRequest,
+    ) -> Result<VerifyResponse, FacilitatorLocalError> {
+        self.post(backup, "verify", request).await
+    }
+
+    /// Settles `request` with the facilitator at `backup`.
+    pub async fn settle(
+        &self,
+        backup: &Url,
+        request: &SettleRequest,
+    ) -> Result<SettleResponse, FacilitatorLocalError> {
+        self.post(backup, "settle", request).await
+    }
+
+    async fn post<T: Serialize, R: DeserializeOwned>(
+        &self,
+        backup: &Url,
+        endpoint: &str,
+        request: &T,
+    ) -> Result<R, FacilitatorLocalError> {
+        let failed = |error: String| {
+            FacilitatorLocalError::ContractCall(format!(
+                "fallback facilitator {backup} failed to {endpoint}: {error}"
+            ))
+        };
+        let url = backup.join(endpoint).map_err(|e| failed(e.to_string()))?;
+        let response = self
+            .client
+            .post(url)
+            .header(FORWARDED_HEADER, "true")
+            .json(request)
+            .send()
+            .await
+            .map_err(|e| failed(e.to_string()))?;
+        let status = response.status();
+        if status != StatusCode::OK {
+            let body = response.text().await.unwrap_or_default();
+            return Err(failed(format!("answered {status}: {body}")));
+        }
+        response.json().await.map_err(|e| failed(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Scheme;
+
+    #[tokio::test]
+    async fn test_only_infrastructure_errors_of_local_requests_are_forwarded() {
+        let fallback = Fallback {
+            backups: HashMap::from([(
+                Network::BaseSepolia,
+                "https://backup.example.com/".parse().unwrap(),
+            )]),
+            client: reqwest::Client::new(),
+        };
+        let rpc_down = FacilitatorLocalError::ContractCall("connection refused".to_string());
+        let mismatch = FacilitatorLocalError::SchemeMismatch(None, Scheme::Exact, Scheme::Upto);
+
+        assert!(
+            fallback
+                .backup_for(Network::BaseSepolia, &rpc_down)
+                .is_some()
+        );
+        assert!(fallback.backup_for(Network::Base, &rpc_down).is_none());
+        assert!(
+            fallback
+                .backup_for(Network::BaseSepolia, &mismatch)
+                .is_none()
+        );
+        let forwarded = FORWARDED
+            .scope(true, async {
+                fallback
+                    .backup_for(Network::BaseSepolia, &rpc_down)
+                    .cloned()
+            })
+            .await;
+        assert!(forwarded.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_post_marks_requests_forwarded_and_fails_on_errors() {
+        use axum::Json;
+        use axum::http::HeaderMap;
+        use axum::routing::post;
+
+        let app = axum::Router::new()
+            .route(
+                "/backup/verify",
+                post(
+                    |headers: HeaderMap, Json(body): Json<serde_json::Value>| async move {
+                        let forwarded = headers.contains_key(FORWARDED_HEADER);
+                        Json(serde_json::json!({ "forwarded": forwarded, "echo": body }))
+                    },
+                ),
+            )
+            .route(
+                "/backup/settle",
+                post(|| async { (StatusCode::BAD_GATEWAY, "rpc down") }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let backup: Url = format!("http://{address}/backup/").parse().unwrap();
+        let fallback = Fallback {
+            backups: HashMap::new(),
+            client: reqwest::Client::new(),
+        };
+
+        let answer: serde_json::Value = fallback
+            .post(&backup, "verify", &serde_json::json!({ "x": 1 }))
+            .await
+            .unwrap();
+        assert_eq!(answer["forwarded"], true);
+        assert_eq!(answer["echo"]["x"], 1);
+        let error = fallback
+            .post::<_, serde_json::Value>(&backup, "settle", &serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            &error,
+            FacilitatorLocalError::ContractCall(message) if message.contains("502") && message.contains("rpc down")
+        ));
+    }
+}
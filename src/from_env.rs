@@ -3,6 +3,7 @@ use alloy::network::EthereumWallet;
 use alloy::signers::local::PrivateKeySigner;
 use serde::Deserialize;
 use serde::Serialize;
+use serde::de::DeserializeOwned;
 use solana_sdk::signature::Keypair;
 use std::env;
 use std::str::FromStr;
@@ -24,6 +25,107 @@ pub const ENV_RPC_CELO_ALFAJORES: &str = "RPC_URL_CELO_ALFAJORES";
 pub const ENV_RPC_CELO: &str = "RPC_URL_CELO";
 pub const ENV_RPC_SEI: &str = "RPC_URL_SEI";
 pub const ENV_RPC_SEI_TESTNET: &str = "RPC_URL_SEI_TESTNET";
+pub const ENV_RPC_ARBITRUM: &str = "RPC_URL_ARBITRUM";
+pub const ENV_RPC_OPTIMISM: &str = "RPC_URL_OPTIMISM";
+
+pub const ENV_RPC_MAX_RESPONSE_BYTES: &str = "RPC_MAX_RESPONSE_BYTES";
+pub const ENV_RPC_CONNECT_TIMEOUT_MS_PREFIX: &str = "RPC_CONNECT_TIMEOUT_MS";
+pub const ENV_RPC_READ_TIMEOUT_MS_PREFIX: &str = "RPC_READ_TIMEOUT_MS";
+pub const ENV_RPC_TIMEOUT_MS_PREFIX: &str = "RPC_TIMEOUT_MS";
+
+pub const ENV_TOKENS_PREFIX: &str = "TOKENS";
+pub const ENV_NATIVE_USD_PRICE_PREFIX: &str = "NATIVE_USD_PRICE";
+pub const ENV_ASSET_ALLOWLIST_PREFIX: &str = "ASSET_ALLOWLIST";
+pub const ENV_RECEIVER_ALLOWLIST_PREFIX: &str = "RECEIVER_ALLOWLIST";
+pub const ENV_EVM_MIN_GAS_PRICE_PREFIX: &str = "EVM_MIN_GAS_PRICE";
+pub const ENV_EVM_GAS_STRATEGY_PREFIX: &str = "EVM_GAS_STRATEGY";
+pub const ENV_EVM_GAS_PRICE_MULTIPLIER_PREFIX: &str = "EVM_GAS_PRICE_MULTIPLIER";
+pub const ENV_EVM_MAX_PRIORITY_FEE_PREFIX: &str = "EVM_MAX_PRIORITY_FEE";
+pub const ENV_EVM_BASE_FEE_MULTIPLIER_PREFIX: &str = "EVM_BASE_FEE_MULTIPLIER";
+pub const ENV_EVM_FEE_BUMP_AFTER_SECS_PREFIX: &str = "EVM_FEE_BUMP_AFTER_SECS";
+pub const ENV_EVM_FEE_BUMP_PERCENT: &str = "EVM_FEE_BUMP_PERCENT";
+pub const ENV_EVM_FEE_BUMP_MAX: &str = "EVM_FEE_BUMP_MAX";
+pub const ENV_TX_RECEIPT_TIMEOUT_SECS: &str = "TX_RECEIPT_TIMEOUT_SECS";
+pub const ENV_TX_RECEIPT_TIMEOUT_BLOCKS_PREFIX: &str = "TX_RECEIPT_TIMEOUT_BLOCKS";
+pub const ENV_EVM_FINALITY_PREFIX: &str = "EVM_FINALITY";
+pub const ENV_FORWARD_JOURNAL_PATH: &str = "FORWARD_JOURNAL_PATH";
+pub const ENV_FORWARD_RETRY_INTERVAL_SECS: &str = "FORWARD_RETRY_INTERVAL_SECS";
+pub const ENV_EVM_CONFIRMATIONS_PREFIX: &str = "EVM_CONFIRMATIONS";
+pub const ENV_EVM_FINALITY_TIMEOUT_SECS: &str = "EVM_FINALITY_TIMEOUT_SECS";
+pub const ENV_EVM_REORG_RATE_THRESHOLD: &str = "EVM_REORG_RATE_THRESHOLD";
+pub const ENV_EVM_REORG_RATE_WINDOW_SECS: &str = "EVM_REORG_RATE_WINDOW_SECS";
+pub const ENV_EVM_REORG_EXTRA_CONFIRMATIONS: &str = "EVM_REORG_EXTRA_CONFIRMATIONS";
+
+pub const ENV_SUPPORTED_REFRESH_INTERVAL_SECS: &str = "SUPPORTED_REFRESH_INTERVAL_SECS";
+pub const ENV_LANDING_NETWORKS: &str = "LANDING_NETWORKS";
+
+pub const ENV_VERIFY_SINGLE_FLIGHT: &str = "VERIFY_SINGLE_FLIGHT";
+pub const ENV_VERIFY_BATCH_MAX_SIZE: &str = "VERIFY_BATCH_MAX_SIZE";
+pub const ENV_VERIFY_BATCH_CONCURRENCY: &str = "VERIFY_BATCH_CONCURRENCY";
+pub const ENV_VERIFY_CACHE_TTL_SECS: &str = "VERIFY_CACHE_TTL_SECS";
+pub const ENV_VERIFY_CACHE_SIZE: &str = "VERIFY_CACHE_SIZE";
+pub const ENV_SETTLE_BATCH_MAX_SIZE: &str = "SETTLE_BATCH_MAX_SIZE";
+pub const ENV_SETTLE_BATCH_CONCURRENCY: &str = "SETTLE_BATCH_CONCURRENCY";
+pub const ENV_BATCH_MAX_NETWORKS: &str = "BATCH_MAX_NETWORKS";
+pub const ENV_AUTHORIZATION_PAYLOAD: &str = "AUTHORIZATION_PAYLOAD";
+pub const ENV_STRICT_ACCEPT: &str = "STRICT_ACCEPT";
+pub const ENV_MAX_BODY_BYTES: &str = "MAX_BODY_BYTES";
+pub const ENV_FALLBACK_FACILITATOR_URL_PREFIX: &str = "FALLBACK_FACILITATOR_URL";
+pub const ENV_CORS_ALLOWED_ORIGINS: &str = "CORS_ALLOWED_ORIGINS";
+pub const ENV_CORS_ALLOWED_METHODS: &str = "CORS_ALLOWED_METHODS";
+pub const ENV_CORS_ALLOWED_HEADERS: &str = "CORS_ALLOWED_HEADERS";
+pub const ENV_HEALTH_PROBE_TIMEOUT_MS: &str = "HEALTH_PROBE_TIMEOUT_MS";
+pub const ENV_HEALTH_REQUIRED_NETWORKS: &str = "HEALTH_REQUIRED_NETWORKS";
+
+pub const ENV_EVM_ERC5267_DOMAINS: &str = "EVM_ERC5267_DOMAINS";
+pub const ENV_EVM_CHECK_BLOCK_TIME: &str = "EVM_CHECK_BLOCK_TIME";
+pub const ENV_EVM_CHECK_SIGNER_KIND: &str = "EVM_CHECK_SIGNER_KIND";
+pub const ENV_EVM_CHECK_TOTAL_SUPPLY: &str = "EVM_CHECK_TOTAL_SUPPLY";
+pub const ENV_EVM_REORG_RETRIES: &str = "EVM_REORG_RETRIES";
+pub const ENV_EVM_RPC_RETRY_ATTEMPTS: &str = "EVM_RPC_RETRY_ATTEMPTS";
+pub const ENV_EVM_RPC_RETRY_BASE_DELAY_MS: &str = "EVM_RPC_RETRY_BASE_DELAY_MS";
+pub const ENV_SETTLEMENT_MEMO_TAG: &str = "SETTLEMENT_MEMO_TAG";
+pub const ENV_SETTLEMENT_JOURNAL_PATH: &str = "SETTLEMENT_JOURNAL_PATH";
+pub const ENV_SETTLEMENT_STATUS_REFRESH_SECS: &str = "SETTLEMENT_STATUS_REFRESH_SECS";
+pub const ENV_SETTLE_QUEUE_WORKERS: &str = "SETTLE_QUEUE_WORKERS";
+
+pub const ENV_MERCHANT_INTENT_SIGNERS: &str = "MERCHANT_INTENT_SIGNERS";
+pub const ENV_RESOURCE_PATTERNS: &str = "RESOURCE_PATTERNS";
+pub const ENV_EVM_MAX_VALIDITY_SECS_PREFIX: &str = "EVM_MAX_VALIDITY_SECS";
+pub const ENV_EVM_CLOCK_SKEW_SECS: &str = "EVM_CLOCK_SKEW_SECS";
+pub const ENV_EVM_CLOCK_SKEW_SECS_PREFIX: &str = "EVM_CLOCK_SKEW_SECS";
+pub const ENV_EVM_SETTLE_EXPIRY_BUFFER_SECS: &str = "EVM_SETTLE_EXPIRY_BUFFER_SECS";
+pub const ENV_EVM_NONCE_FILTER_BITS: &str = "EVM_NONCE_FILTER_BITS";
+pub const ENV_EVM_NONCE_FILTER_HASHES: &str = "EVM_NONCE_FILTER_HASHES";
+pub const ENV_EVM_NONCE_RULES: &str = "EVM_NONCE_RULES";
+pub const ENV_EVM_REPLAY_PROTECTION: &str = "EVM_REPLAY_PROTECTION";
+pub const ENV_TOKEN_REGISTRY_PREFIX: &str = "TOKEN_REGISTRY";
+pub const ENV_TOKEN_REGISTRY_CACHE_SECS: &str = "TOKEN_REGISTRY_CACHE_SECS";
+pub const ENV_TOKEN_LIST_PREFIX: &str = "TOKEN_LIST";
+pub const ENV_TOKEN_LIST_REFRESH_SECS: &str = "TOKEN_LIST_REFRESH_SECS";
+pub const ENV_SETTLE_COST_BREAKDOWN: &str = "SETTLE_COST_BREAKDOWN";
+pub const ENV_SWEEP_TREASURY: &str = "SWEEP_TREASURY";
+pub const ENV_SWEEP_INTERVAL_SECS: &str = "SWEEP_INTERVAL_SECS";
+pub const ENV_FAILURE_LOG_SIZE: &str = "FAILURE_LOG_SIZE";
+pub const ENV_FAILURE_LOG_REDACT: &str = "FAILURE_LOG_REDACT";
+pub const ENV_IDEMPOTENCY_KEY_TTL_SECS: &str = "IDEMPOTENCY_KEY_TTL_SECS";
+pub const ENV_IDEMPOTENCY_KEY_MAX_ENTRIES: &str = "IDEMPOTENCY_KEY_MAX_ENTRIES";
+pub const ENV_RATE_LIMIT_PER_SECOND: &str = "RATE_LIMIT_PER_SECOND";
+pub const ENV_RATE_LIMIT_BURST: &str = "RATE_LIMIT_BURST";
+pub const ENV_RATE_LIMIT_TRUSTED_PROXIES: &str = "RATE_LIMIT_TRUSTED_PROXIES";
+pub const ENV_RATE_LIMIT_QUOTA_STATE: &str = "RATE_LIMIT_QUOTA_STATE";
+pub const ENV_LOG_FORMAT: &str = "LOG_FORMAT";
+pub const ENV_METRICS_EXEMPLARS: &str = "METRICS_EXEMPLARS";
+pub const ENV_ADMIN_API_TOKEN: &str = "ADMIN_API_TOKEN";
+pub const ENV_ADMIN_AUDIT_LOG_PATH: &str = "ADMIN_AUDIT_LOG_PATH";
+pub const ENV_SHUTDOWN_DRAIN_TIMEOUT_SECS: &str = "SHUTDOWN_DRAIN_TIMEOUT_SECS";
+pub const ENV_SHUTDOWN_DRAIN_LOG_INTERVAL_SECS: &str = "SHUTDOWN_DRAIN_LOG_INTERVAL_SECS";
+pub const ENV_KILL_SWITCH_CONTRACT: &str = "KILL_SWITCH_CONTRACT";
+pub const ENV_KILL_SWITCH_NETWORK: &str = "KILL_SWITCH_NETWORK";
+pub const ENV_KILL_SWITCH_INTERVAL_SECS: &str = "KILL_SWITCH_INTERVAL_SECS";
+
+pub const ENV_EVENTS_NATS_URL: &str = "EVENTS_NATS_URL";
+pub const ENV_EVENTS_NATS_SUBJECT: &str = "EVENTS_NATS_SUBJECT";
 
 pub fn rpc_env_name_from_network(network: Network) -> &'static str {
     match network {
@@ -40,9 +142,32 @@ pub fn rpc_env_name_from_network(network: Network) -> &'static str {
         Network::Celo => ENV_RPC_CELO,
         Network::Sei => ENV_RPC_SEI,
         Network::SeiTestnet => ENV_RPC_SEI_TESTNET,
+        Network::Arbitrum => ENV_RPC_ARBITRUM,
+        Network::Optimism => ENV_RPC_OPTIMISM,
     }
 }
 
+/// Name of a per-network environment variable: `prefix` followed by the network suffix
+/// used for its RPC URL, e.g. `TOKENS_BASE_SEPOLIA` for `TOKENS` on Base Sepolia.
+pub fn env_name_for_network(prefix: &str, network: Network) -> String {
+    let suffix = rpc_env_name_from_network(network).trim_start_matches("RPC_URL_");
+    format!("{prefix}_{suffix}")
+}
+
+/// The entries of a comma-separated variable value, each parsed from its JSON string form, e.g. a
+/// network name or an address. Blank entries are skipped; each entry comes with its parse result.
+pub fn list_entries<T: DeserializeOwned>(
+    raw: &str,
+) -> impl Iterator<Item = (&str, Result<T, serde_json::Error>)> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let parsed = serde_json::from_value(serde_json::Value::String(entry.to_string()));
+            (entry, parsed)
+        })
+}
+
 /// Supported methods for constructing an Ethereum wallet from environment variables.
 #[derive(Debug, Hash, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SignerType {
@@ -62,16 +187,48 @@ impl SignerType {
         }
     }
 
+    /// Parse the signer type from `SIGNER_TYPE`, or `None` if it is not set.
+    ///
+    /// Without a signer the facilitator runs in verify-only mode: `/settle` is disabled.
+    pub fn from_env_optional() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        if env::var(ENV_SIGNER_TYPE).is_err() {
+            return Ok(None);
+        }
+        Self::from_env().map(Some)
+    }
+
     /// Constructs an [`EthereumWallet`] based on the [`SignerType`] selected from environment.
     ///
     /// Currently only supports [`SignerType::PrivateKey`] variant, based on the following environment variables:
     /// - `SIGNER_TYPE` — currently only `"private-key"` is supported
     /// - `EVM_PRIVATE_KEY` — comma-separated list of private keys used to sign transactions
     pub fn make_evm_wallet(&self) -> Result<EthereumWallet, Box<dyn std::error::Error>> {
+        self.make_evm_wallet_from(ENV_EVM_PRIVATE_KEY)
+    }
+
+    /// Constructs the [`EthereumWallet`] signing on `network`.
+    ///
+    /// Reads the pool of keys from `EVM_PRIVATE_KEY_<NETWORK>`, falling back to `EVM_PRIVATE_KEY`
+    /// if it is not set, so each network can settle from its own accounts.
+    pub fn make_evm_wallet_for(
+        &self,
+        network: Network,
+    ) -> Result<EthereumWallet, Box<dyn std::error::Error>> {
+        let env_var = env_name_for_network(ENV_EVM_PRIVATE_KEY, network);
+        if env::var(&env_var).is_ok() {
+            self.make_evm_wallet_from(&env_var)
+        } else {
+            self.make_evm_wallet()
+        }
+    }
+
+    fn make_evm_wallet_from(
+        &self,
+        env_var: &str,
+    ) -> Result<EthereumWallet, Box<dyn std::error::Error>> {
         match self {
             SignerType::PrivateKey => {
-                let raw_keys = env::var(ENV_EVM_PRIVATE_KEY)
-                    .map_err(|_| format!("env {ENV_EVM_PRIVATE_KEY} not set"))?;
+                let raw_keys = env::var(env_var).map_err(|_| format!("env {env_var} not set"))?;
                 let signers = raw_keys
                     .split(',')
                     .map(str::trim)
@@ -80,7 +237,7 @@ impl SignerType {
                     .collect::<Result<Vec<_>, _>>()
                     .map_err(|err| -> Box<dyn std::error::Error> { Box::new(err) })?;
                 if signers.is_empty() {
-                    return Err("env EVM_PRIVATE_KEY did not contain any private keys".into());
+                    return Err(format!("env {env_var} did not contain any private keys").into());
                 }
 
                 let mut iter = signers.into_iter();
@@ -147,6 +304,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn list_entries_skip_blanks_and_keep_parse_errors() {
+        let entries: Vec<(&str, Result<Network, _>)> =
+            list_entries(" base, ,nowhere,solana-devnet,").collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].0, "base");
+        assert_eq!(entries[0].1.as_ref().unwrap(), &Network::Base);
+        assert_eq!(entries[1].0, "nowhere");
+        assert!(entries[1].1.is_err());
+        assert_eq!(entries[2].1.as_ref().unwrap(), &Network::SolanaDevnet);
+    }
+
     #[test]
     fn make_evm_wallet_supports_multiple_private_keys() {
         let _guard = ENV_LOCK.lock().expect("env lock poisoned");
@@ -181,4 +350,36 @@ mod tests {
         assert!(signers.contains(&expected_primary));
         assert!(signers.contains(&expected_secondary));
     }
+
+    #[test]
+    fn make_evm_wallet_for_prefers_the_network_pool() {
+        let _guard = ENV_LOCK.lock().expect("env lock poisoned");
+        let evm_keys_override = EnvOverride::new(ENV_EVM_PRIVATE_KEY);
+        let network_keys_override = EnvOverride::new("EVM_PRIVATE_KEY_BASE_SEPOLIA");
+
+        const SHARED: &str = "0xcafe000000000000000000000000000000000000000000000000000000000001";
+        const POOL_1: &str = "0xcafe000000000000000000000000000000000000000000000000000000000002";
+        const POOL_2: &str = "0xcafe000000000000000000000000000000000000000000000000000000000003";
+
+        evm_keys_override.set(SHARED);
+        network_keys_override.set(&format!("{POOL_1},{POOL_2}"));
+
+        let address = |key: &str| {
+            PrivateKeySigner::from_str(key)
+                .expect("key parses")
+                .address()
+        };
+        let signers = |network| -> Vec<_> {
+            let wallet = SignerType::PrivateKey
+                .make_evm_wallet_for(network)
+                .expect("wallet constructed from env");
+            NetworkWallet::<AlloyEthereum>::signer_addresses(&wallet).collect()
+        };
+
+        let pool = signers(Network::BaseSepolia);
+        assert_eq!(pool.len(), 2);
+        assert!(pool.contains(&address(POOL_1)));
+        assert!(pool.contains(&address(POOL_2)));
+        assert_eq!(signers(Network::Base), vec![address(SHARED)]);
+    }
 }
@@ -9,18 +9,48 @@
 //! Each endpoint consumes or produces structured JSON payloads defined in `x402-rs`,
 //! and is compatible with official x402 client SDKs.
 
-use axum::extract::State;
-use axum::http::{StatusCode, header};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, Path, Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
 use axum::response::Response;
 use axum::routing::{get, post};
 use axum::{Json, Router, response::IntoResponse};
+use futures::StreamExt;
+use once_cell::sync::OnceCell;
+use rust_decimal::Decimal;
+use serde::Deserialize;
 use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use subtle::ConstantTimeEq;
 use tracing::instrument;
 
+use crate::audit;
 use crate::chain::FacilitatorLocalError;
+use crate::chain::evm::{recover, recover_payer, verify_offline};
+use crate::chain::submission;
+use crate::codec::{self, Body, Format, PaymentBody};
 use crate::facilitator::Facilitator;
+use crate::failures::FailureLog;
+use crate::fallback;
+use crate::from_env::{
+    ENV_ADMIN_API_TOKEN, ENV_BATCH_MAX_NETWORKS, ENV_LANDING_NETWORKS,
+    ENV_SETTLE_BATCH_CONCURRENCY, ENV_SETTLE_BATCH_MAX_SIZE, ENV_VERIFY_BATCH_CONCURRENCY,
+    ENV_VERIFY_BATCH_MAX_SIZE,
+};
+use crate::health::ChainsResponse;
+use crate::idempotency::{Claim, IDEMPOTENT_REPLAYED_HEADER, IdempotencyStore};
+use crate::inflight::{self, InFlight};
+use crate::journal;
+use crate::metrics::{self, Metrics};
+use crate::network::Network;
+use crate::openapi;
+use crate::rate_limit;
+use crate::settle_queue;
 use crate::types::{
-    ErrorResponse, FacilitatorErrorReason, MixedAddress, SettleRequest, VerifyRequest,
+    CancelRequest, ErrorResponse, ExactPaymentPayload, FacilitatorErrorReason, MixedAddress,
+    OfflineVerifyRequest, OfflineVerifyResponse, PaymentId, QuoteRequest, RecoverRequest,
+    SettleEvent, SettleRequest, SettleResponse, SettleStatus, TransactionHash, VerifyRequest,
     VerifyResponse,
 };
 
@@ -38,6 +68,13 @@ pub async fn get_verify_info() -> impl IntoResponse {
         "body": {
             "paymentPayload": "PaymentPayload",
             "paymentRequirements": "PaymentRequirements",
+        },
+        "batch": {
+            "endpoint": "/verify/batch",
+            "description": "POST an array of verify bodies; responses are returned in the same order",
+            "body": ["VerifyRequest"],
+            "response": ["VerifyResponse | ErrorResponse"],
+            "maxSize": verify_batch_max_size(),
         }
     }))
 }
@@ -54,32 +91,217 @@ pub async fn get_settle_info() -> impl IntoResponse {
         "body": {
             "paymentPayload": "PaymentPayload",
             "paymentRequirements": "PaymentRequirements",
+        },
+        "batch": {
+            "endpoint": "/settle/batch",
+            "description": "POST an array of settle bodies; results are returned in the same order",
+            "body": ["SettleRequest"],
+            "response": ["SettleResponse | { success: false, error }"],
+            "maxSize": settle_batch_max_size(),
+        },
+        "query": {
+            "wait": "false to answer 202 with status \"submitted\" once the transaction is broadcast (EVM only, default: true)",
+        },
+        "websocket": {
+            "endpoint": "/ws/settle",
+            "description": "Send a settle body as a text message to receive submitted, pending, then confirmed or failed events",
+            "events": ["SettleEvent"],
         }
     }))
 }
 
+/// `GET /openapi.json`: OpenAPI 3.1 document of the facilitator API, see [`crate::openapi`].
+#[instrument(skip_all)]
+pub async fn get_openapi() -> impl IntoResponse {
+    Json(openapi::spec())
+}
+
 pub fn routes<A>() -> Router<A>
 where
     A: Facilitator + Clone + Send + Sync + 'static,
-    A::Error: IntoResponse,
+    A::Error: IntoResponse + Send + 'static,
 {
     use tower_http::services::ServeDir;
 
     Router::new()
-        .route("/", get(get_root))
+        .route("/", get(get_root::<A>))
         .route("/verify", get(get_verify_info))
-        .route("/verify", post(post_verify::<A>))
+        .route(
+            "/verify",
+            post(post_verify::<A>).layer(axum::middleware::from_fn(rate_limit::limit)),
+        )
+        .route(
+            "/verify/batch",
+            post(post_verify_batch::<A>).layer(axum::middleware::from_fn(rate_limit::limit_batch)),
+        )
+        .route(
+            "/verify/offline",
+            post(post_verify_offline).layer(axum::middleware::from_fn(rate_limit::limit)),
+        )
+        .route(
+            "/recover",
+            post(post_recover).layer(axum::middleware::from_fn(rate_limit::limit)),
+        )
+        .route(
+            "/quote",
+            post(post_quote::<A>).layer(axum::middleware::from_fn(rate_limit::limit)),
+        )
+        .route(
+            "/cancel",
+            post(post_cancel::<A>)
+                .layer(axum::middleware::from_fn(rate_limit::limit))
+                .layer(axum::middleware::from_fn(inflight::reject_while_draining)),
+        )
         .route("/settle", get(get_settle_info))
-        .route("/settle", post(post_settle::<A>))
-        .route("/health", get(get_health::<A>))
+        .route(
+            "/settle",
+            post(post_settle::<A>)
+                .layer(axum::middleware::from_fn(rate_limit::limit))
+                .layer(axum::middleware::from_fn(inflight::reject_while_draining)),
+        )
+        .route(
+            "/settle/batch",
+            post(post_settle_batch::<A>)
+                .layer(axum::middleware::from_fn(rate_limit::limit_batch))
+                .layer(axum::middleware::from_fn(inflight::reject_while_draining)),
+        )
+        .route(
+            "/ws/settle",
+            get(get_ws_settle::<A>)
+                .layer(axum::middleware::from_fn(inflight::reject_while_draining)),
+        )
+        .route("/health", get(get_ready::<A>))
+        .route("/ready", get(get_ready::<A>))
+        .route("/live", get(get_live))
+        .route("/chains", get(get_chains::<A>))
         .route("/supported", get(get_supported::<A>))
+        .route("/openapi.json", get(get_openapi))
+        .route("/supported/{network}", get(get_supported_for::<A>))
+        .route(
+            "/settlements/{tx_hash}",
+            get(get_settlement::<A>).layer(axum::middleware::from_fn(audit::audit)),
+        )
+        .route("/payments/{payment_id}", get(get_payment::<A>))
+        .route(
+            "/admin/failures",
+            get(get_admin_failures).layer(axum::middleware::from_fn(audit::audit)),
+        )
+        .route(
+            "/admin/inflight",
+            get(get_admin_inflight).layer(axum::middleware::from_fn(audit::audit)),
+        )
+        .route(
+            "/admin/audit",
+            get(get_admin_audit).layer(axum::middleware::from_fn(audit::audit)),
+        )
+        .route("/metrics", get(get_metrics))
+        .route_layer(axum::middleware::from_fn(metrics::count_requests))
+        .route_layer(axum::middleware::from_fn(fallback::mark_forwarded))
         .nest_service("/static", ServeDir::new("static"))
 }
 
 /// `GET /`: Returns the Stake Capital branded landing page.
+///
+/// The network grid lists the networks in [`Facilitator::supported`], see [`network_cards`].
 #[instrument(skip_all)]
-pub async fn get_root() -> impl IntoResponse {
-    let html = r#"<!DOCTYPE html>
+pub async fn get_root<A>(State(facilitator): State<A>) -> impl IntoResponse
+where
+    A: Facilitator,
+{
+    let networks: Vec<String> = match facilitator.supported().await {
+        Ok(supported) => supported
+            .kinds
+            .into_iter()
+            .map(|kind| kind.network)
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    let configured = LANDING_NETWORKS.get_or_init(HashMap::new);
+    let html = LANDING_PAGE.replace("{{NETWORK_CARDS}}", &network_cards(configured, &networks));
+
+    (StatusCode::OK, [(header::CONTENT_TYPE, "text/html; charset=utf-8")], html)
+}
+
+/// Display name and logo of a network on the landing page.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LandingNetwork {
+    pub name: String,
+    pub logo: String,
+}
+
+/// Landing page entries of `LANDING_NETWORKS`, read once by [`landing_networks_from_env`].
+static LANDING_NETWORKS: OnceCell<HashMap<String, LandingNetwork>> = OnceCell::new();
+
+/// Reads the landing page entries of `LANDING_NETWORKS`, see [`network_cards`]. Only the first call has an effect.
+///
+/// # Errors
+/// Returns an error if `LANDING_NETWORKS` is set but not a JSON object of `{"name", "logo"}` entries.
+pub fn landing_networks_from_env() -> Result<(), String> {
+    let configured = match std::env::var(ENV_LANDING_NETWORKS) {
+        Ok(raw) => serde_json::from_str(&raw)
+            .map_err(|e| format!("env {ENV_LANDING_NETWORKS} is invalid: {e}"))?,
+        Err(_) => HashMap::new(),
+    };
+    let _ = LANDING_NETWORKS.set(configured);
+    Ok(())
+}
+
+/// Built-in landing page entries; testnets share their mainnet's card.
+fn default_landing_network(network: &str) -> LandingNetwork {
+    let (name, logo) = match network {
+        "arbitrum" => ("Arbitrum", "/static/arbitrum.png"),
+        "avalanche" | "avalanche-fuji" => ("Avalanche", "/static/avalanche.png"),
+        "base" | "base-sepolia" => ("Base", "/static/base.png"),
+        "celo" | "celo-alfajores" => ("Celo", "/static/celo.png"),
+        "optimism" => ("Optimism", "/static/optimism.png"),
+        "polygon" | "polygon-amoy" => ("Polygon", "/static/polygon.png"),
+        "solana" | "solana-devnet" => ("Solana", "/static/solana.png"),
+        "sei" | "sei-testnet" => ("Sei", "/static/logo.png"),
+        "xdc" => ("XDC", "/static/logo.png"),
+        other => (other, "/static/logo.png"),
+    };
+    LandingNetwork {
+        name: name.to_string(),
+        logo: logo.to_string(),
+    }
+}
+
+/// Renders one card per distinct display name among `networks`, in order.
+///
+/// Names and logos come from `configured`, the `LANDING_NETWORKS` object mapping network names to
+/// `{"name", "logo"}`, falling back to built-in entries for networks not listed.
+fn network_cards(configured: &HashMap<String, LandingNetwork>, networks: &[String]) -> String {
+    let mut shown = HashSet::new();
+    let mut cards = String::new();
+    for network in networks {
+        let entry = configured
+            .get(network)
+            .cloned()
+            .unwrap_or_else(|| default_landing_network(network));
+        if !shown.insert(entry.name.clone()) {
+            continue;
+        }
+        let name = escape_html(&entry.name);
+        let logo = escape_html(&entry.logo);
+        cards.push_str(&format!(
+            r#"                <div class="network-card">
+                    <img src="{logo}" alt="{name}">
+                    <span class="network-name">{name}</span>
+                </div>
+"#
+        ));
+    }
+    cards.trim_end().to_string()
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const LANDING_PAGE: &str = r#"<!DOCTYPE html>
 <html lang="en">
 <head>
     <meta charset="UTF-8">
@@ -270,26 +492,7 @@ pub async fn get_root() -> impl IntoResponse {
         <div class="networks-section">
             <h2>SUPPORTED NETWORKS</h2>
             <div class="networks-grid">
-                <div class="network-card">
-                    <img src="/static/avalanche.png" alt="Avalanche">
-                    <span class="network-name">Avalanche</span>
-                </div>
-                <div class="network-card">
-                    <img src="/static/celo.png" alt="Celo">
-                    <span class="network-name">Celo</span>
-                </div>
-                <div class="network-card">
-                    <img src="/static/solana.png" alt="Solana">
-                    <span class="network-name">Solana</span>
-                </div>
-                <div class="network-card">
-                    <img src="/static/polygon.png" alt="Polygon">
-                    <span class="network-name">Polygon</span>
-                </div>
-                <div class="network-card">
-                    <img src="/static/base.png" alt="Base">
-                    <span class="network-name">Base</span>
-                </div>
+{{NETWORK_CARDS}}
             </div>
         </div>
 
@@ -298,10 +501,24 @@ pub async fn get_root() -> impl IntoResponse {
             <ul class="endpoint-list">
                 <li><span class="method">GET</span> <code>/verify</code> – Supported verification schema</li>
                 <li><span class="method">POST</span> <code>/verify</code> – Verify payment payload</li>
+                <li><span class="method">POST</span> <code>/verify/batch</code> – Verify several payment payloads at once</li>
+                <li><span class="method">POST</span> <code>/verify/offline</code> – Verify payment signature without chain access</li>
+                <li><span class="method">POST</span> <code>/recover</code> – Recover the signer of a message or typed data</li>
+                <li><span class="method">POST</span> <code>/quote</code> – Estimate what settling a payment costs the facilitator</li>
+                <li><span class="method">POST</span> <code>/cancel</code> – Cancel an unsettled EVM authorization on-chain</li>
                 <li><span class="method">GET</span> <code>/settle</code> – Supported settlement schema</li>
                 <li><span class="method">POST</span> <code>/settle</code> – Settle payment on-chain</li>
+                <li><span class="method">POST</span> <code>/settle/batch</code> – Settle several payments at once</li>
+                <li><span class="method">GET</span> <code>/ws/settle</code> – Settle over a WebSocket, streaming confirmations</li>
                 <li><span class="method">GET</span> <code>/supported</code> – List supported payment kinds</li>
-                <li><span class="method">GET</span> <code>/health</code> – Health check</li>
+                <li><span class="method">GET</span> <code>/supported/{network}</code> – List supported payment kinds on one network</li>
+                <li><span class="method">GET</span> <code>/settlements/{tx_hash}</code> – Look up a journaled settlement by transaction hash (admin)</li>
+                <li><span class="method">GET</span> <code>/payments/{payment_id}</code> – Lifecycle of a settled payment by payment id</li>
+                <li><span class="method">GET</span> <code>/health</code>, <code>/ready</code> – RPC connectivity of every network</li>
+                <li><span class="method">GET</span> <code>/live</code> – Liveness check</li>
+                <li><span class="method">GET</span> <code>/chains</code> – Chain IDs, token metadata and RPC status of every network</li>
+                <li><span class="method">GET</span> <code>/metrics</code> – Prometheus metrics</li>
+                <li><span class="method">GET</span> <code>/openapi.json</code> – OpenAPI 3.1 specification</li>
             </ul>
         </div>
 
@@ -312,9 +529,6 @@ pub async fn get_root() -> impl IntoResponse {
 </body>
 </html>"#;
 
-    (StatusCode::OK, [(header::CONTENT_TYPE, "text/html; charset=utf-8")], html)
-}
-
 /// `GET /supported`: Lists the x402 payment schemes and networks supported by this facilitator.
 ///
 /// Facilitators may expose this to help clients dynamically configure their payment requests
@@ -331,13 +545,203 @@ where
     }
 }
 
+/// `GET /supported/{network}`: Lists the supported payment kinds on a single network.
+///
+/// `network` is spelled as in [`PaymentRequirements`], e.g. `base-sepolia`. Answers
+/// `404 Not Found` if it does not name a known network.
+#[instrument(skip_all, fields(network = %network))]
+pub async fn get_supported_for<A>(
+    State(facilitator): State<A>,
+    Path(network): Path<String>,
+) -> impl IntoResponse
+where
+    A: Facilitator + Sync,
+    A::Error: IntoResponse,
+{
+    let Ok(network) = serde_json::from_value::<Network>(serde_json::Value::String(network)) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Unknown network".to_string(),
+            }),
+        )
+            .into_response();
+    };
+    match facilitator.supported_for(network).await {
+        Ok(supported) => (StatusCode::OK, Json(json!(supported))).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+/// `GET /settlements/{tx_hash}`: The journal entry of a settlement, see [`crate::journal`].
+///
+/// Answers the [`JournalEntry`](journal::JournalEntry), with the original request, the payer, the
+/// amount and the status, or `404 Not Found` if the transaction was not journaled or journaling is disabled.
+/// A `pending` entry is re-checked on-chain first if `SETTLEMENT_STATUS_REFRESH_SECS` is set, see
+/// [`journal::StatusRefresh`].
+///
+/// Requires `Authorization: Bearer <ADMIN_API_TOKEN>`, as the entry holds the payer's signed request.
+/// Answers `404 Not Found` if `ADMIN_API_TOKEN` is not set.
+#[instrument(skip_all, fields(tx_hash = %tx_hash))]
+pub async fn get_settlement<A>(
+    State(facilitator): State<A>,
+    headers: HeaderMap,
+    Path(tx_hash): Path<String>,
+) -> impl IntoResponse
+where
+    A: Facilitator + Sync,
+{
+    if let Err(status) = assert_admin(&headers) {
+        return status.into_response();
+    }
+    let not_found = |error: &str| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: error.to_string(),
+            }),
+        )
+            .into_response()
+    };
+    let Some(journal) = journal::global() else {
+        return not_found("Settlement journal is disabled");
+    };
+    let Ok(transaction) =
+        serde_json::from_value::<TransactionHash>(serde_json::Value::String(tx_hash))
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Invalid transaction hash".to_string(),
+            }),
+        )
+            .into_response();
+    };
+    match journal.get(&transaction).await {
+        Ok(Some(entry)) => {
+            let entry = refreshed(&facilitator, journal.as_ref(), entry).await;
+            (StatusCode::OK, Json(entry)).into_response()
+        }
+        Ok(None) => not_found("Settlement not found"),
+        Err(error) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: error.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// `GET /payments/{payment_id}`: The lifecycle of a settled payment, see [`crate::journal`].
+///
+/// Answers the [`PaymentLifecycle`](journal::PaymentLifecycle) of the payment with this
+/// [`PaymentId`], as returned by `/verify` and `/settle`: when it was verified, broadcast and
+/// settled, its transaction and status, and its history, replacements, reorgs and reverts
+/// included. Answers `404 Not Found` if the payment was not journaled or journaling is disabled.
+/// A `pending` payment is re-checked on-chain first, like with `GET /settlements/{tx_hash}`.
+///
+/// Needs no admin token: the lifecycle holds neither the payer's signed request nor the response.
+#[instrument(skip_all, fields(payment_id = %payment_id))]
+pub async fn get_payment<A>(
+    State(facilitator): State<A>,
+    Path(payment_id): Path<String>,
+) -> impl IntoResponse
+where
+    A: Facilitator + Sync,
+{
+    let not_found = |error: &str| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: error.to_string(),
+            }),
+        )
+            .into_response()
+    };
+    let Some(journal) = journal::global() else {
+        return not_found("Settlement journal is disabled");
+    };
+    let Ok(payment_id) = payment_id.parse::<PaymentId>() else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Invalid payment id".to_string(),
+            }),
+        )
+            .into_response();
+    };
+    match journal.payment(&payment_id).await {
+        Ok(Some(entry)) => {
+            let entry = refreshed(&facilitator, journal.as_ref(), entry).await;
+            (StatusCode::OK, Json(entry.lifecycle())).into_response()
+        }
+        Ok(None) => not_found("Payment not found"),
+        Err(error) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: error.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// `entry`, re-checked on-chain if it is pending and `SETTLEMENT_STATUS_REFRESH_SECS` is set.
+async fn refreshed<A>(
+    facilitator: &A,
+    journal: &dyn journal::SettlementJournal,
+    entry: journal::JournalEntry,
+) -> journal::JournalEntry
+where
+    A: Facilitator + Sync,
+{
+    let Some(refresh) = journal::status_refresh() else {
+        return entry;
+    };
+    let network = entry.network;
+    let check = |transaction: TransactionHash| async move {
+        let status = facilitator.settlement_status(network, &transaction).await;
+        status.unwrap_or(Ok(None))
+    };
+    refresh.refresh(journal, entry, check).await
+}
+
+/// `GET /health` and `GET /ready`: Probes the RPC of every configured network, see [`crate::health`].
+///
+/// Answers `200 OK` if every required network responds, `503 Service Unavailable` otherwise,
+/// with the status of each network in both cases.
 #[instrument(skip_all)]
-pub async fn get_health<A>(State(facilitator): State<A>) -> impl IntoResponse
+pub async fn get_ready<A>(State(facilitator): State<A>) -> impl IntoResponse
+where
+    A: Facilitator,
+{
+    let mut report = facilitator.health().await;
+    let status = if report.check_required() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
+
+/// `GET /chains`: Describes every known network, see [`ChainsResponse`].
+///
+/// Static configuration such as chain IDs and token addresses is always answered; configured networks are
+/// probed like `GET /health` for their block height and RPC status. Always answers `200 OK`.
+#[instrument(skip_all)]
+pub async fn get_chains<A>(State(facilitator): State<A>) -> impl IntoResponse
 where
     A: Facilitator,
-    A::Error: IntoResponse,
 {
-    get_supported(State(facilitator)).await
+    let report = facilitator.health().await;
+    Json(ChainsResponse::new(&report))
+}
+
+/// `GET /live`: Answers `200 OK` while the server is running, without probing any RPC.
+#[instrument(skip_all)]
+pub async fn get_live() -> impl IntoResponse {
+    Json(json!({ "status": "ok" }))
 }
 
 /// `POST /verify`: Facilitator-side verification of a proposed x402 payment.
@@ -346,53 +750,879 @@ where
 /// [`PaymentRequirements`], including signature validity, scheme match, and fund sufficiency.
 ///
 /// Responds with a [`VerifyResponse`] indicating whether the payment can be accepted.
+/// The request and response may be MessagePack-encoded, and the payload may come in an
+/// `Authorization` header, see [`crate::codec`].
 #[instrument(skip_all)]
 pub async fn post_verify<A>(
     State(facilitator): State<A>,
-    Json(body): Json<VerifyRequest>,
+    format: Format,
+    PaymentBody(body): PaymentBody,
 ) -> impl IntoResponse
 where
     A: Facilitator,
     A::Error: IntoResponse,
 {
+    let _in_flight = InFlight::global().verification();
     match facilitator.verify(&body).await {
-        Ok(valid_response) => (StatusCode::OK, Json(valid_response)).into_response(),
+        Ok(valid_response) => format.respond(StatusCode::OK, &valid_response),
         Err(error) => {
             tracing::warn!(
-                error = ?error,
+                error = %error,
+                endpoint = "/verify",
+                network = %body.network(),
+                payer = body.payer().map(tracing::field::display),
                 body = %serde_json::to_string(&body).unwrap_or_else(|_| "<can-not-serialize>".to_string()),
                 "Verification failed"
             );
+            FailureLog::global().record("/verify", error.to_string(), &body);
+            error.into_response()
+        }
+    }
+}
+
+/// `POST /verify/batch`: Verifies an array of [`VerifyRequest`]s, answering an array in the same order.
+///
+/// Requests are verified concurrently, at most `VERIFY_BATCH_CONCURRENCY` at a time. Each element of the
+/// response is what `POST /verify` would have answered for that request, so one failed verification does
+/// not fail the batch. Batches longer than `VERIFY_BATCH_MAX_SIZE` get a `413 Payload Too Large`, and
+/// batches over more than `BATCH_MAX_NETWORKS` networks a `400 Bad Request`.
+#[instrument(skip_all, fields(batch_size = body.len()))]
+pub async fn post_verify_batch<A>(
+    State(facilitator): State<A>,
+    format: Format,
+    Body(body): Body<Vec<VerifyRequest>>,
+) -> impl IntoResponse
+where
+    A: Facilitator + Sync,
+    A::Error: IntoResponse,
+{
+    verify_batch(
+        &facilitator,
+        format,
+        &body,
+        verify_batch_max_size(),
+        batch_max_networks(),
+    )
+    .await
+}
+
+/// [`post_verify_batch`] with batches limited to `max_size` requests over `max_networks` networks.
+async fn verify_batch<A>(
+    facilitator: &A,
+    format: Format,
+    body: &[VerifyRequest],
+    max_size: usize,
+    max_networks: Option<usize>,
+) -> Response
+where
+    A: Facilitator + Sync,
+    A::Error: IntoResponse,
+{
+    if body.len() > max_size {
+        return (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(ErrorResponse {
+                error: format!(
+                    "batch of {} requests exceeds the limit of {max_size}",
+                    body.len()
+                ),
+            }),
+        )
+            .into_response();
+    }
+    if let Some(rejection) =
+        reject_batch_networks(max_networks, body.iter().map(VerifyRequest::network))
+    {
+        return rejection;
+    }
+    let concurrency = std::env::var(ENV_VERIFY_BATCH_CONCURRENCY)
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|concurrency| *concurrency > 0)
+        .unwrap_or(10);
+    let verifications: Vec<_> = body
+        .iter()
+        .map(|request| verify_batch_element(facilitator, request))
+        .collect();
+    let results: Vec<serde_json::Value> = futures::stream::iter(verifications)
+        .buffered(concurrency)
+        .collect()
+        .await;
+    format.respond(StatusCode::OK, &results)
+}
+
+/// Verifies one request of a batch, answering what `POST /verify` would have as JSON.
+async fn verify_batch_element<A>(facilitator: &A, request: &VerifyRequest) -> serde_json::Value
+where
+    A: Facilitator,
+    A::Error: IntoResponse,
+{
+    let _in_flight = InFlight::global().verification();
+    let error_response = match facilitator.verify(request).await {
+        Ok(response) => return json!(response),
+        Err(error) => {
+            tracing::warn!(error = ?error, "Batch verification failed");
+            FailureLog::global().record("/verify/batch", error.to_string(), request);
+            error.into_response()
+        }
+    };
+    response_json(error_response).await
+}
+
+/// `BATCH_MAX_NETWORKS`: the most distinct networks a batch may span, `None` if unlimited (default).
+fn batch_max_networks() -> Option<usize> {
+    std::env::var(ENV_BATCH_MAX_NETWORKS)
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|max_networks| *max_networks > 0)
+}
+
+/// The `400 Bad Request` answer to a batch over more distinct `networks` than `max_networks`, if it is.
+fn reject_batch_networks(
+    max_networks: Option<usize>,
+    networks: impl Iterator<Item = Network>,
+) -> Option<Response> {
+    let max_networks = max_networks?;
+    let networks = networks.collect::<HashSet<_>>().len();
+    (networks > max_networks).then(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!(
+                    "batch spans {networks} networks, exceeding the limit of {max_networks}"
+                ),
+            }),
+        )
+            .into_response()
+    })
+}
+
+/// `VERIFY_BATCH_MAX_SIZE`: the longest batch accepted by `POST /verify/batch` (default: `100`).
+fn verify_batch_max_size() -> usize {
+    std::env::var(ENV_VERIFY_BATCH_MAX_SIZE)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(100)
+}
+
+/// The JSON body of `response`, to embed an error answer as an element of a batch response.
+async fn response_json(response: Response) -> serde_json::Value {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap_or_default();
+    serde_json::from_slice(&bytes)
+        .unwrap_or_else(|_| json!({ "error": String::from_utf8_lossy(&bytes) }))
+}
+
+/// `POST /verify/offline`: Checks an EVM payment's signature, value and timing without chain access.
+///
+/// The caller supplies the token's EIP-712 domain and decimals in an [`OfflineVerifyRequest`].
+/// No RPC calls are made, so the response always reports `balanceChecked: false`.
+#[instrument(skip_all)]
+pub async fn post_verify_offline(Body(body): Body<OfflineVerifyRequest>) -> impl IntoResponse {
+    let amount = scaled_amount(&body);
+    let response = match verify_offline(&body) {
+        Ok(payer) => OfflineVerifyResponse {
+            is_valid: true,
+            invalid_reason: None,
+            payer: Some(payer.into()),
+            amount,
+            balance_checked: false,
+        },
+        Err(error) => OfflineVerifyResponse {
+            is_valid: false,
+            invalid_reason: Some(error.to_string()),
+            payer: None,
+            amount,
+            balance_checked: false,
+        },
+    };
+    (StatusCode::OK, Json(response))
+}
+
+/// `POST /recover`: Recovers the signer of an EIP-191 message or EIP-712 typed data.
+///
+/// Read-only: the chain is only queried for the EIP-1271 fallback, when the expected `signer`
+/// is not the recovered address. Malformed requests get a `400 Bad Request`.
+#[instrument(skip_all)]
+pub async fn post_recover(Body(body): Body<RecoverRequest>) -> impl IntoResponse {
+    match recover(&body).await {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(error) => (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: error.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// `POST /quote`: Estimates what settling a payment would cost the facilitator, without sending anything.
+///
+/// Takes a [`QuoteRequest`], the payment's requirements and optionally its signed payload, and answers
+/// the expected gas, the current gas price and the total in native currency, and in USD if priced.
+#[instrument(skip_all)]
+pub async fn post_quote<A>(
+    State(facilitator): State<A>,
+    format: Format,
+    Body(body): Body<QuoteRequest>,
+) -> impl IntoResponse
+where
+    A: Facilitator,
+    A::Error: IntoResponse,
+{
+    match facilitator.quote(&body).await {
+        Some(Ok(quote)) => format.respond(StatusCode::OK, &quote),
+        Some(Err(error)) => {
+            tracing::warn!(error = ?error, "Quote failed");
             error.into_response()
         }
+        None => (
+            StatusCode::NOT_IMPLEMENTED,
+            Json(ErrorResponse {
+                error: "Quotes not supported".to_string(),
+            }),
+        )
+            .into_response(),
     }
 }
 
+/// `POST /cancel`: Submits the ERC-3009 `cancelAuthorization` signed by a payer who no longer wants to pay.
+///
+/// Takes a [`CancelRequest`] and answers a [`CancelResponse`](crate::types::CancelResponse) with the
+/// cancellation transaction. Once canceled, the authorization can no longer be verified or settled.
+#[instrument(skip_all)]
+pub async fn post_cancel<A>(
+    State(facilitator): State<A>,
+    format: Format,
+    Body(body): Body<CancelRequest>,
+) -> impl IntoResponse
+where
+    A: Facilitator,
+    A::Error: IntoResponse,
+{
+    match facilitator.cancel(&body).await {
+        Some(Ok(response)) => format.respond(StatusCode::OK, &response),
+        Some(Err(error)) => {
+            tracing::warn!(error = ?error, "Cancel failed");
+            error.into_response()
+        }
+        None => (
+            StatusCode::NOT_IMPLEMENTED,
+            Json(ErrorResponse {
+                error: "Cancellation not supported".to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// Authorized value of an EVM payload in whole tokens, if it fits a decimal.
+fn scaled_amount(request: &OfflineVerifyRequest) -> Option<String> {
+    let ExactPaymentPayload::Evm(payload) = &request.payment_payload.payload else {
+        return None;
+    };
+    let value = i128::try_from(payload.authorization.value.0).ok()?;
+    let amount = Decimal::try_from_i128_with_scale(value, request.decimals.into()).ok()?;
+    Some(amount.normalize().to_string())
+}
+
 /// `POST /settle`: Facilitator-side execution of a valid x402 payment on-chain.
 ///
 /// Given a valid [`SettleRequest`], this endpoint attempts to execute the payment
 /// via ERC-3009 `transferWithAuthorization`, and returns a [`SettleResponse`] with transaction details.
 ///
 /// This endpoint is typically called after a successful `/verify` step.
+/// The request and response may be MessagePack-encoded, and the payload may come in an
+/// `Authorization` header, see [`crate::codec`].
+///
+/// With `?wait=false`, answers `202 Accepted` with `"status": "submitted"` and the transaction hash
+/// as soon as an EVM settlement transaction is broadcast, and settles the rest in the background.
+/// If the settlement fails before broadcasting, it is answered as without `wait=false`.
+///
+/// A retry sent with the same `Idempotency-Key` header is answered the stored response instead of
+/// settling again, see [`crate::idempotency`]; a background settlement that fails releases the key.
+///
+/// With `?queue=true`, answers `202 Accepted` with the queued [`PaymentLifecycle`](journal::PaymentLifecycle)
+/// at once, and settles the payment from a queue, see [`crate::settle_queue`]; its `paymentId` is
+/// polled with `GET /payments/{payment_id}`. Answers `501 Not Implemented` if `SETTLE_QUEUE_WORKERS`
+/// is not set.
 #[instrument(skip_all)]
 pub async fn post_settle<A>(
     State(facilitator): State<A>,
-    Json(body): Json<SettleRequest>,
+    Query(query): Query<SettleQuery>,
+    headers: HeaderMap,
+    format: Format,
+    PaymentBody(body): PaymentBody,
+) -> impl IntoResponse
+where
+    A: Facilitator + Send + Sync + 'static,
+    A::Error: IntoResponse + Send + 'static,
+{
+    if query.queue.unwrap_or(false) {
+        return enqueue_settlement(format, &body).await;
+    }
+    let claim = match (IdempotencyStore::global(), IdempotencyStore::key(&headers)) {
+        (Some(store), Some(key)) => match store.claim(key, body.payment_id()) {
+            Claim::Claimed(claim) => Some(claim),
+            Claim::Replay(status, response) => {
+                let mut response = format.respond(status, &response);
+                response.headers_mut().insert(
+                    IDEMPOTENT_REPLAYED_HEADER,
+                    header::HeaderValue::from_static("true"),
+                );
+                return response;
+            }
+            Claim::InProgress => {
+                return (
+                    StatusCode::CONFLICT,
+                    Json(ErrorResponse {
+                        error: "A settlement with this Idempotency-Key is in progress".to_string(),
+                    }),
+                )
+                    .into_response();
+            }
+            Claim::Mismatch => {
+                return (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Json(ErrorResponse {
+                        error: "Idempotency-Key was used for another payment".to_string(),
+                    }),
+                )
+                    .into_response();
+            }
+        },
+        _ => None,
+    };
+    if query.wait.unwrap_or(true) {
+        let _in_flight = InFlight::global().settlement();
+        let result = facilitator.settle(&body).await;
+        if let (Some(claim), Ok(response)) = (claim, &result) {
+            claim.complete(StatusCode::OK, response);
+        }
+        return settle_response(format, &body, result);
+    }
+    let payer: Option<MixedAddress> = match &body.payment_payload.payload {
+        ExactPaymentPayload::Evm(payload) => Some(payload.authorization.from.into()),
+        ExactPaymentPayload::Solana(_) => None,
+    };
+    let network = body.network();
+    let payment_id = body.payment_id();
+    let extensions = body.payment_payload.extensions.clone();
+    let (submitted, on_submitted) = tokio::sync::oneshot::channel();
+    let settlement = tokio::spawn(async move {
+        let _in_flight = InFlight::global().settlement();
+        let result = submission::watch(submitted, facilitator.settle(&body)).await;
+        (body, result)
+    });
+    if let (Ok(transaction), Some(payer)) = (on_submitted.await, payer) {
+        let response = SettleResponse {
+            success: true,
+            error_reason: None,
+            payer,
+            transaction: Some(transaction),
+            block_number: None,
+            status: Some(SettleStatus::Submitted),
+            network,
+            payment_id: Some(payment_id),
+            cost: None,
+            extensions,
+        };
+        if let Some(claim) = &claim {
+            claim.store(StatusCode::ACCEPTED, &response);
+        }
+        tokio::spawn(async move {
+            match settlement.await {
+                Ok((_, Ok(response))) => {
+                    if let Some(claim) = claim {
+                        claim.complete(StatusCode::OK, &response);
+                    }
+                }
+                Ok((body, Err(error))) => {
+                    if let Some(claim) = claim {
+                        claim.release();
+                    }
+                    record_settle_failure(&error, &body);
+                }
+                Err(_) => {
+                    if let Some(claim) = claim {
+                        claim.release();
+                    }
+                }
+            }
+        });
+        return format.respond(StatusCode::ACCEPTED, &response);
+    }
+    match settlement.await {
+        Ok((body, result)) => {
+            if let (Some(claim), Ok(response)) = (claim, &result) {
+                claim.complete(StatusCode::OK, response);
+            }
+            settle_response(format, &body, result)
+        }
+        Err(error) => {
+            tracing::error!(%error, "Settlement task failed");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Answers `POST /settle?queue=true`, queueing the settlement of `request`.
+async fn enqueue_settlement(format: Format, request: &SettleRequest) -> Response {
+    let Some(settle_queue) = settle_queue::global() else {
+        return (
+            StatusCode::NOT_IMPLEMENTED,
+            Json(ErrorResponse {
+                error: "Settlement queue is disabled".to_string(),
+            }),
+        )
+            .into_response();
+    };
+    match settle_queue.enqueue(request).await {
+        Ok(entry) => format.respond(StatusCode::ACCEPTED, &entry.lifecycle()),
+        Err(error) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: error.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// Query parameters of `POST /settle`.
+#[derive(Debug, Deserialize)]
+pub struct SettleQuery {
+    /// Wait for the settlement to be confirmed before answering (default: `true`).
+    pub wait: Option<bool>,
+    /// Queue the settlement and answer at once (default: `false`), see [`crate::settle_queue`].
+    pub queue: Option<bool>,
+}
+
+/// `GET /ws/settle`: Settles a payment over a WebSocket, streaming its progress.
+///
+/// The client sends one [`SettleRequest`] as a JSON text message, or a MessagePack binary message when
+/// the `msgpack` feature is enabled, and receives [`SettleEvent`]s: `submitted` once an EVM settlement
+/// transaction is broadcast, `pending` with the confirmation count while waiting for finality, then
+/// `confirmed` with the [`SettleResponse`] or `failed` with the error `POST /settle` would have answered,
+/// after which the socket is closed. The settlement is the same as `POST /settle`'s, and it completes
+/// even if the client goes away.
+///
+/// The request is checked as `POST /settle`'s: messages are limited to `MAX_BODY_BYTES`, the payment is
+/// rate-limited, see [`rate_limit`], and an `Idempotency-Key` header sent with the upgrade is honored.
+#[instrument(skip_all)]
+pub async fn get_ws_settle<A>(
+    State(facilitator): State<A>,
+    headers: HeaderMap,
+    connect_info: Option<axum::Extension<ConnectInfo<SocketAddr>>>,
+    ws: WebSocketUpgrade,
+) -> Response
+where
+    A: Facilitator + Send + Sync + 'static,
+    A::Error: IntoResponse + Send + 'static,
+{
+    let peer = connect_info.map(|axum::Extension(ConnectInfo(addr))| addr.ip());
+    let client = SocketClient {
+        ip: rate_limit::RateLimiter::global()
+            .and_then(|limiter| limiter.client_ip(peer, &headers))
+            .or(peer),
+        idempotency_key: IdempotencyStore::key(&headers),
+    };
+    let max_message_size = codec::max_body_bytes();
+    ws.max_message_size(max_message_size)
+        .max_frame_size(max_message_size)
+        .on_upgrade(move |socket| settle_over_socket(facilitator, socket, client))
+}
+
+/// What `GET /ws/settle` needs to know of the upgrade request once the socket is open.
+struct SocketClient {
+    ip: Option<IpAddr>,
+    idempotency_key: Option<String>,
+}
+
+/// Answers the socket with a `failed` event carrying the body of `response`, and closes it.
+async fn fail_socket(socket: &mut WebSocket, response: Response) {
+    let error = response_json(response).await;
+    send_event(socket, &SettleEvent::Failed { error }).await;
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+async fn settle_over_socket<A>(facilitator: A, mut socket: WebSocket, client: SocketClient)
+where
+    A: Facilitator + Send + Sync + 'static,
+    A::Error: IntoResponse + Send + 'static,
+{
+    let body = loop {
+        match socket.recv().await {
+            Some(Ok(Message::Text(text))) => {
+                break codec::decode::<SettleRequest>(text.as_bytes(), Format::Json);
+            }
+            #[cfg(feature = "msgpack")]
+            Some(Ok(Message::Binary(bytes))) => {
+                break codec::decode::<SettleRequest>(&bytes, Format::MessagePack);
+            }
+            Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+            _ => return,
+        }
+    };
+    let body = match body {
+        Ok(body) => body,
+        Err(rejection) => return fail_socket(&mut socket, rejection).await,
+    };
+    // The socket may have been opened before draining began.
+    if InFlight::global().is_draining() {
+        return fail_socket(&mut socket, inflight::shutting_down()).await;
+    }
+    if let Some(limiter) = rate_limit::RateLimiter::global()
+        && let Err(throttled) = limiter.admit([recover_payer(&body)], client.ip)
+    {
+        tracing::warn!(ip = ?client.ip, endpoint = "/ws/settle", "Rate limit exceeded");
+        return fail_socket(&mut socket, rate_limit::too_many_requests(throttled)).await;
+    }
+    let claim = match (IdempotencyStore::global(), client.idempotency_key) {
+        (Some(store), Some(key)) => match store.claim(key, body.payment_id()) {
+            Claim::Claimed(claim) => Some(claim),
+            Claim::Replay(_, response) => {
+                let event = SettleEvent::Confirmed {
+                    confirmations: None,
+                    response,
+                };
+                send_event(&mut socket, &event).await;
+                let _ = socket.send(Message::Close(None)).await;
+                return;
+            }
+            Claim::InProgress => {
+                let error = "A settlement with this Idempotency-Key is in progress".to_string();
+                let rejection = (StatusCode::CONFLICT, Json(ErrorResponse { error }));
+                return fail_socket(&mut socket, rejection.into_response()).await;
+            }
+            Claim::Mismatch => {
+                let error = "Idempotency-Key was used for another payment".to_string();
+                let rejection = (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Json(ErrorResponse { error }),
+                );
+                return fail_socket(&mut socket, rejection.into_response()).await;
+            }
+        },
+        _ => None,
+    };
+    let (progress, mut updates) = tokio::sync::mpsc::unbounded_channel();
+    let settlement = async {
+        let _in_flight = InFlight::global().settlement();
+        submission::stream(progress, facilitator.settle(&body)).await
+    };
+    tokio::pin!(settlement);
+    let mut confirmations = None;
+    let result = loop {
+        tokio::select! {
+            result = &mut settlement => break result,
+            Some(update) = updates.recv() => {
+                send_event(&mut socket, &progress_event(update, &mut confirmations)).await;
+            }
+        }
+    };
+    while let Ok(update) = updates.try_recv() {
+        send_event(&mut socket, &progress_event(update, &mut confirmations)).await;
+    }
+    let event = match result {
+        Ok(response) => {
+            if let Some(claim) = claim {
+                claim.complete(StatusCode::OK, &response);
+            }
+            SettleEvent::Confirmed {
+                confirmations,
+                response: Box::new(response),
+            }
+        }
+        Err(error) => {
+            record_settle_failure(&error, &body);
+            SettleEvent::Failed {
+                error: response_json(error.into_response()).await,
+            }
+        }
+    };
+    send_event(&mut socket, &event).await;
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+/// The [`SettleEvent`] of `update`, remembering the latest confirmation count.
+fn progress_event(update: submission::Progress, confirmations: &mut Option<u64>) -> SettleEvent {
+    match update {
+        submission::Progress::Submitted(transaction) => SettleEvent::Submitted { transaction },
+        submission::Progress::Replaced {
+            transaction,
+            replacement,
+        } => SettleEvent::Replaced {
+            transaction,
+            replacement,
+        },
+        submission::Progress::Pending {
+            transaction,
+            confirmations: count,
+        } => {
+            *confirmations = Some(count);
+            SettleEvent::Pending {
+                transaction,
+                confirmations: count,
+            }
+        }
+    }
+}
+
+/// Sends `event` as a JSON text message; a client that went away is not an error.
+async fn send_event(socket: &mut WebSocket, event: &SettleEvent) {
+    if let Ok(text) = serde_json::to_string(event) {
+        let _ = socket.send(Message::Text(text.into())).await;
+    }
+}
+
+/// Answers a finished `POST /settle`.
+fn settle_response<E>(
+    format: Format,
+    body: &SettleRequest,
+    result: Result<SettleResponse, E>,
+) -> Response
+where
+    E: std::fmt::Debug + std::fmt::Display + IntoResponse,
+{
+    match result {
+        Ok(valid_response) => format.respond(StatusCode::OK, &valid_response),
+        Err(error) => {
+            record_settle_failure(&error, body);
+            error.into_response()
+        }
+    }
+}
+
+/// Logs a failed `POST /settle` and records it in the [`FailureLog`].
+fn record_settle_failure<E: std::fmt::Debug + std::fmt::Display>(error: &E, body: &SettleRequest) {
+    tracing::warn!(
+        error = %error,
+        endpoint = "/settle",
+        network = %body.network(),
+        payer = body.payer().map(tracing::field::display),
+        body = %serde_json::to_string(body).unwrap_or_else(|_| "<can-not-serialize>".to_string()),
+        "Settlement failed"
+    );
+    FailureLog::global().record("/settle", error.to_string(), body);
+}
+
+/// `POST /settle/batch`: Settles an array of [`SettleRequest`]s, answering an array in the same order.
+///
+/// Settlements on the same network run one after another: any of them may be sent by any signer of
+/// the network's pool, so running them in parallel would have them compete for signer nonces.
+/// Networks are settled in parallel, at most `SETTLE_BATCH_CONCURRENCY` at a time. Each element of the response is the
+/// [`SettleResponse`] of that request, or `{"success": false, "error": ...}` with what `POST /settle`
+/// would have answered, so a caller can retry only the entries that did not succeed.
+/// Batches longer than `SETTLE_BATCH_MAX_SIZE` get a `413 Payload Too Large`, and batches over more
+/// than `BATCH_MAX_NETWORKS` networks a `400 Bad Request`.
+#[instrument(skip_all, fields(batch_size = body.len()))]
+pub async fn post_settle_batch<A>(
+    State(facilitator): State<A>,
+    format: Format,
+    Body(body): Body<Vec<SettleRequest>>,
 ) -> impl IntoResponse
+where
+    A: Facilitator + Sync,
+    A::Error: IntoResponse,
+{
+    let max_size = settle_batch_max_size();
+    if body.len() > max_size {
+        return (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(ErrorResponse {
+                error: format!(
+                    "batch of {} requests exceeds the limit of {max_size}",
+                    body.len()
+                ),
+            }),
+        )
+            .into_response();
+    }
+    if let Some(rejection) = reject_batch_networks(
+        batch_max_networks(),
+        body.iter().map(SettleRequest::network),
+    ) {
+        return rejection;
+    }
+    let concurrency = std::env::var(ENV_SETTLE_BATCH_CONCURRENCY)
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|concurrency| *concurrency > 0)
+        .unwrap_or(4);
+    let results = settle_batch(&facilitator, &body, concurrency).await;
+    format.respond(StatusCode::OK, &results)
+}
+
+/// Settles `requests` one network after another, `concurrency` networks at a time, answering in order.
+async fn settle_batch<A>(
+    facilitator: &A,
+    requests: &[SettleRequest],
+    concurrency: usize,
+) -> Vec<serde_json::Value>
+where
+    A: Facilitator,
+    A::Error: IntoResponse,
+{
+    let mut groups: HashMap<Network, Vec<usize>> = HashMap::new();
+    for (index, request) in requests.iter().enumerate() {
+        groups.entry(request.network()).or_default().push(index);
+    }
+    let mut settlements = futures::stream::iter(groups.into_values())
+        .map(|indices| settle_batch_group(facilitator, requests, indices))
+        .buffer_unordered(concurrency);
+    let mut results = vec![serde_json::Value::Null; requests.len()];
+    while let Some(group) = settlements.next().await {
+        for (index, result) in group {
+            results[index] = result;
+        }
+    }
+    results
+}
+
+/// `SETTLE_BATCH_MAX_SIZE`: the longest batch accepted by `POST /settle/batch` (default: `100`).
+fn settle_batch_max_size() -> usize {
+    std::env::var(ENV_SETTLE_BATCH_MAX_SIZE)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(100)
+}
+
+/// Settles `requests[index]` for each of `indices` in turn.
+async fn settle_batch_group<A>(
+    facilitator: &A,
+    requests: &[SettleRequest],
+    indices: Vec<usize>,
+) -> Vec<(usize, serde_json::Value)>
 where
     A: Facilitator,
     A::Error: IntoResponse,
 {
-    match facilitator.settle(&body).await {
-        Ok(valid_response) => (StatusCode::OK, Json(valid_response)).into_response(),
+    let mut results = Vec::with_capacity(indices.len());
+    for index in indices {
+        results.push((
+            index,
+            settle_batch_element(facilitator, &requests[index]).await,
+        ));
+    }
+    results
+}
+
+/// Settles one request of a batch, answering its [`SettleResponse`] or the error `POST /settle` would have.
+async fn settle_batch_element<A>(facilitator: &A, request: &SettleRequest) -> serde_json::Value
+where
+    A: Facilitator,
+    A::Error: IntoResponse,
+{
+    let _in_flight = InFlight::global().settlement();
+    let error_response = match facilitator.settle(request).await {
+        Ok(response) => return json!(response),
         Err(error) => {
-            tracing::warn!(
-                error = ?error,
-                body = %serde_json::to_string(&body).unwrap_or_else(|_| "<can-not-serialize>".to_string()),
-                "Settlement failed"
-            );
+            tracing::warn!(error = ?error, "Batch settlement failed");
+            FailureLog::global().record("/settle/batch", error.to_string(), request);
             error.into_response()
         }
+    };
+    json!({ "success": false, "error": response_json(error_response).await })
+}
+
+/// `GET /admin/failures`: The most recent failed verifications and settlements, see [`crate::failures`].
+///
+/// Requires `Authorization: Bearer <ADMIN_API_TOKEN>`. Answers `404 Not Found` if `ADMIN_API_TOKEN` is not set.
+#[instrument(skip_all)]
+pub async fn get_admin_failures(headers: HeaderMap) -> impl IntoResponse {
+    if let Err(status) = assert_admin(&headers) {
+        return status.into_response();
+    }
+    (
+        StatusCode::OK,
+        Json(json!({ "failures": FailureLog::global().recent() })),
+    )
+        .into_response()
+}
+
+/// `GET /admin/inflight`: Number of verifications and settlements in progress, see [`crate::inflight`].
+///
+/// Requires `Authorization: Bearer <ADMIN_API_TOKEN>`, like `GET /admin/failures`.
+#[instrument(skip_all)]
+pub async fn get_admin_inflight(headers: HeaderMap) -> impl IntoResponse {
+    if let Err(status) = assert_admin(&headers) {
+        return status.into_response();
+    }
+    (StatusCode::OK, Json(InFlight::global().counts())).into_response()
+}
+
+/// `GET /admin/audit`: The most recent admin requests, most recent first, see [`crate::audit`].
+///
+/// At most `?limit=` records are answered (default: `100`), up to the last [`audit::MAX_RECENT`].
+/// Requires `Authorization: Bearer <ADMIN_API_TOKEN>`, like `GET /admin/failures`.
+#[instrument(skip_all)]
+pub async fn get_admin_audit(
+    headers: HeaderMap,
+    Query(query): Query<AuditQuery>,
+) -> impl IntoResponse {
+    if let Err(status) = assert_admin(&headers) {
+        return status.into_response();
+    }
+    let records = match audit::global() {
+        Some(audit_log) => audit_log.recent(query.limit.unwrap_or(100)).await,
+        None => Vec::new(),
+    };
+    (StatusCode::OK, Json(json!({ "records": records }))).into_response()
+}
+
+/// Query parameters of `GET /admin/audit`.
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    /// Most records answered (default: `100`).
+    pub limit: Option<usize>,
+}
+
+/// `GET /metrics`: Prometheus metrics, see [`crate::metrics`].
+///
+/// Answers in the OpenMetrics format, with exemplars if enabled, when the `Accept` header asks for it.
+#[instrument(skip_all)]
+pub async fn get_metrics(headers: HeaderMap) -> impl IntoResponse {
+    let openmetrics = headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|accept| accept.to_str().ok())
+        .any(|accept| accept.contains("application/openmetrics-text"));
+    if openmetrics {
+        (
+            [(
+                header::CONTENT_TYPE,
+                "application/openmetrics-text; version=1.0.0; charset=utf-8",
+            )],
+            Metrics::global().render_openmetrics(),
+        )
+    } else {
+        (
+            [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+            Metrics::global().render(),
+        )
+    }
+}
+
+/// Checks the admin bearer token: `404 Not Found` if `ADMIN_API_TOKEN` is not set, `401 Unauthorized` if it does not match.
+fn assert_admin(headers: &HeaderMap) -> Result<(), StatusCode> {
+    let Ok(admin_token) = std::env::var(ENV_ADMIN_API_TOKEN) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let authorized = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        // Compared in constant time, so response timing does not reveal how much of a guess is right.
+        .is_some_and(|token| {
+            !admin_token.is_empty() && bool::from(token.as_bytes().ct_eq(admin_token.as_bytes()))
+        });
+    if authorized {
+        Ok(())
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
     }
 }
 
@@ -413,15 +1643,67 @@ impl IntoResponse for FacilitatorLocalError {
             .into_response();
 
         match error {
-            FacilitatorLocalError::SchemeMismatch(payer, ..) => {
+            FacilitatorLocalError::SchemeMismatch(payer, ..)
+            | FacilitatorLocalError::UnsupportedScheme(payer, ..)
+            | FacilitatorLocalError::UnsupportedAuthorizationKind(payer, ..) => {
                 (StatusCode::OK, Json(invalid_schema(payer))).into_response()
             }
             FacilitatorLocalError::ReceiverMismatch(payer, ..)
             | FacilitatorLocalError::InvalidSignature(payer, ..)
             | FacilitatorLocalError::InvalidTiming(payer, ..)
+            | FacilitatorLocalError::InvalidSplits(payer, ..)
+            | FacilitatorLocalError::AuthorizationUsed(payer)
             | FacilitatorLocalError::InsufficientValue(payer) => {
                 (StatusCode::OK, Json(invalid_schema(Some(payer)))).into_response()
             }
+            FacilitatorLocalError::ValueOutOfRange(payer, _) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    Some(payer),
+                    FacilitatorErrorReason::ValueOutOfRange,
+                )),
+            )
+                .into_response(),
+            FacilitatorLocalError::BelowMinimum(payer, _) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    Some(payer),
+                    FacilitatorErrorReason::BelowMinimum,
+                )),
+            )
+                .into_response(),
+            FacilitatorLocalError::AboveMaximum(payer, _) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    Some(payer),
+                    FacilitatorErrorReason::AboveMaximum,
+                )),
+            )
+                .into_response(),
+            FacilitatorLocalError::ExcessValue(payer) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    Some(payer),
+                    FacilitatorErrorReason::ExcessValue,
+                )),
+            )
+                .into_response(),
+            FacilitatorLocalError::NonceReused(payer) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    Some(payer),
+                    FacilitatorErrorReason::NonceReused,
+                )),
+            )
+                .into_response(),
+            FacilitatorLocalError::ReceiverNotAllowed(_) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    None,
+                    FacilitatorErrorReason::ReceiverNotAllowed,
+                )),
+            )
+                .into_response(),
             FacilitatorLocalError::NetworkMismatch(payer, ..)
             | FacilitatorLocalError::UnsupportedNetwork(payer) => (
                 StatusCode::OK,
@@ -431,10 +1713,55 @@ impl IntoResponse for FacilitatorLocalError {
                 )),
             )
                 .into_response(),
-            FacilitatorLocalError::ContractCall(..)
-            | FacilitatorLocalError::InvalidAddress(..)
-            | FacilitatorLocalError::ClockError(_) => bad_request,
-            FacilitatorLocalError::DecodingError(reason) => (
+            // The node failed, not the client; its error may name the RPC URL, so it is not echoed.
+            FacilitatorLocalError::ContractCall(_) => (
+                StatusCode::BAD_GATEWAY,
+                Json(ErrorResponse {
+                    error: "The network's RPC failed".to_string(),
+                }),
+            )
+                .into_response(),
+            FacilitatorLocalError::InvalidAddress(..) | FacilitatorLocalError::ClockError(_) => {
+                bad_request
+            }
+            FacilitatorLocalError::UnsupportedAsset(payer, asset) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    payer,
+                    FacilitatorErrorReason::FreeForm(format!("Token {asset} is not approved")),
+                )),
+            )
+                .into_response(),
+            FacilitatorLocalError::UnexpectedNonce(payer) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    Some(payer),
+                    FacilitatorErrorReason::FreeForm(
+                        "Nonce does not match the order or the receivers it is forwarded to"
+                            .to_string(),
+                    ),
+                )),
+            )
+                .into_response(),
+            FacilitatorLocalError::ImplementationChanged(payer, reason) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    Some(payer),
+                    FacilitatorErrorReason::FreeForm(reason),
+                )),
+            )
+                .into_response(),
+            FacilitatorLocalError::SuspiciousNonce(payer, rule) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    Some(payer),
+                    FacilitatorErrorReason::FreeForm(format!("Nonce rejected by rule {rule}")),
+                )),
+            )
+                .into_response(),
+            FacilitatorLocalError::DecodingError(reason)
+            | FacilitatorLocalError::InvalidMerchantIntent(reason)
+            | FacilitatorLocalError::ResourceNotAllowed(reason) => (
                 StatusCode::OK,
                 Json(VerifyResponse::invalid(
                     None,
@@ -442,6 +1769,47 @@ impl IntoResponse for FacilitatorLocalError {
                 )),
             )
                 .into_response(),
+            FacilitatorLocalError::RpcTimeout(timeout) => (
+                StatusCode::GATEWAY_TIMEOUT,
+                Json(ErrorResponse { error: timeout }),
+            )
+                .into_response(),
+            FacilitatorLocalError::Reverted(payer, reason) => (
+                StatusCode::OK,
+                Json(VerifyResponse::invalid(
+                    payer,
+                    FacilitatorErrorReason::FreeForm(format!("Transaction reverted: {reason}")),
+                )),
+            )
+                .into_response(),
+            FacilitatorLocalError::Reorged(reason) => (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse {
+                    error: format!("Settlement reorged out: {reason}"),
+                }),
+            )
+                .into_response(),
+            FacilitatorLocalError::UnknownAuthorization(_) => (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: "Authorization not verified by this facilitator".to_string(),
+                }),
+            )
+                .into_response(),
+            FacilitatorLocalError::SettlementDisabled => (
+                StatusCode::NOT_IMPLEMENTED,
+                Json(ErrorResponse {
+                    error: "Settlement not enabled".to_string(),
+                }),
+            )
+                .into_response(),
+            FacilitatorLocalError::Maintenance => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ErrorResponse {
+                    error: "Settlement paused for maintenance".to_string(),
+                }),
+            )
+                .into_response(),
             FacilitatorLocalError::InsufficientFunds(payer) => (
                 StatusCode::OK,
                 Json(VerifyResponse::invalid(
@@ -453,3 +1821,249 @@ impl IntoResponse for FacilitatorLocalError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::Address;
+    use alloy::signers::local::PrivateKeySigner;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use crate::chain::evm::tests::offline_request;
+    use crate::types::{EvmAddress, SupportedPaymentKindsResponse, X402Version};
+
+    /// Settles after a pause, recording how many settlements ran at once, overall and per network.
+    #[derive(Default)]
+    struct ConcurrencyProbe {
+        per_network: Mutex<HashMap<Network, usize>>,
+        most_per_network: AtomicUsize,
+        running: AtomicUsize,
+        most_running: AtomicUsize,
+    }
+
+    impl Facilitator for ConcurrencyProbe {
+        type Error = FacilitatorLocalError;
+
+        async fn verify(&self, _: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
+            Err(FacilitatorLocalError::UnsupportedNetwork(None))
+        }
+
+        async fn settle(&self, request: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+            let network = request.network();
+            let on_network = {
+                let mut per_network = self.per_network.lock().unwrap();
+                let on_network = per_network.entry(network).or_default();
+                *on_network += 1;
+                *on_network
+            };
+            self.most_per_network
+                .fetch_max(on_network, Ordering::SeqCst);
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most_running.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            *self.per_network.lock().unwrap().get_mut(&network).unwrap() -= 1;
+            Ok(SettleResponse {
+                success: true,
+                error_reason: None,
+                payer: MixedAddress::Evm(EvmAddress(Address::ZERO)),
+                transaction: None,
+                block_number: None,
+                status: None,
+                network,
+                payment_id: None,
+                cost: None,
+                extensions: None,
+            })
+        }
+
+        async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
+            Err(FacilitatorLocalError::UnsupportedNetwork(None))
+        }
+    }
+
+    fn settle_request(network: Network) -> SettleRequest {
+        let request = offline_request(&PrivateKeySigner::random(), Address::ZERO);
+        let mut payment_payload = request.payment_payload;
+        payment_payload.network = network;
+        let mut payment_requirements = request.payment_requirements;
+        payment_requirements.network = network;
+        SettleRequest {
+            x402_version: X402Version::V1,
+            payment_payload,
+            payment_requirements,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_settles_one_network_at_a_time_up_to_the_concurrency() {
+        let networks = [
+            Network::BaseSepolia,
+            Network::Base,
+            Network::Avalanche,
+            Network::Polygon,
+        ];
+        let requests: Vec<SettleRequest> = networks
+            .iter()
+            .cycle()
+            .take(12)
+            .map(|network| settle_request(*network))
+            .collect();
+        let probe = ConcurrencyProbe::default();
+        let results = settle_batch(&probe, &requests, 2).await;
+
+        assert_eq!(probe.most_per_network.load(Ordering::SeqCst), 1);
+        assert_eq!(probe.most_running.load(Ordering::SeqCst), 2);
+        for (request, result) in requests.iter().zip(&results) {
+            assert_eq!(result["success"], true);
+            assert_eq!(result["network"], request.network().to_string());
+        }
+    }
+
+    #[test]
+    fn test_network_cards_use_configured_entries_once_per_name() {
+        unsafe { std::env::set_var(ENV_LANDING_NETWORKS, "[]") };
+        assert!(landing_networks_from_env().is_err());
+        unsafe { std::env::remove_var(ENV_LANDING_NETWORKS) };
+
+        let configured: HashMap<String, LandingNetwork> = serde_json::from_str(
+            r#"{"base": {"name": "Base <L2>", "logo": "/static/custom.png"}}"#,
+        )
+        .unwrap();
+        let networks = ["base", "base-sepolia", "base", "solana-devnet"].map(String::from);
+        let cards = network_cards(&configured, &networks);
+        assert_eq!(cards.matches("network-card").count(), 3);
+        assert!(cards.contains(r#"<img src="/static/custom.png" alt="Base &lt;L2&gt;">"#));
+        assert!(cards.contains(r#"<span class="network-name">Solana</span>"#));
+    }
+
+    #[tokio::test]
+    async fn test_verify_batch_answers_every_request_up_to_the_limit() {
+        let facilitator = &ConcurrencyProbe::default();
+        let batch = |networks: &[Network], max_size: usize| {
+            let requests: Vec<VerifyRequest> = networks
+                .iter()
+                .map(|network| {
+                    let request = settle_request(*network);
+                    VerifyRequest {
+                        x402_version: request.x402_version,
+                        payment_payload: request.payment_payload,
+                        payment_requirements: request.payment_requirements,
+                    }
+                })
+                .collect();
+            async move { verify_batch(facilitator, Format::Json, &requests, max_size, None).await }
+        };
+
+        let response = batch(&[Network::Base, Network::BaseSepolia, Network::Polygon], 3).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let results: Vec<serde_json::Value> = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| result["isValid"] == false));
+
+        let response = batch(&[Network::Base, Network::Base, Network::Base], 2).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_batches_over_too_many_networks_are_rejected() {
+        let networks = [Network::BaseSepolia, Network::Base, Network::BaseSepolia];
+        assert!(reject_batch_networks(None, networks.into_iter()).is_none());
+        assert!(reject_batch_networks(Some(2), networks.into_iter()).is_none());
+        let rejection = reject_batch_networks(Some(1), networks.into_iter()).unwrap();
+        assert_eq!(rejection.status(), StatusCode::BAD_REQUEST);
+        let same_network = [Network::Base, Network::Base];
+        assert!(reject_batch_networks(Some(1), same_network.into_iter()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_quote_is_not_implemented_by_default() {
+        let request = settle_request(Network::BaseSepolia);
+        let quote_request = QuoteRequest {
+            payment_requirements: request.payment_requirements,
+            payment_payload: None,
+        };
+        let response = post_quote(
+            State(std::sync::Arc::new(ConcurrencyProbe::default())),
+            Format::Json,
+            Body(quote_request),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+    }
+
+    #[test]
+    fn test_rpc_failures_answer_bad_gateway() {
+        let response = FacilitatorLocalError::ContractCall("http://node/key: refused".to_string())
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let response = FacilitatorLocalError::InvalidAddress("0x".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Broadcasts a transaction then settles slowly on Base Sepolia, fails before broadcasting elsewhere.
+    struct Broadcaster;
+
+    impl Facilitator for Broadcaster {
+        type Error = FacilitatorLocalError;
+
+        async fn verify(&self, _: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
+            Err(FacilitatorLocalError::UnsupportedNetwork(None))
+        }
+
+        async fn settle(&self, request: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+            if request.network() != Network::BaseSepolia {
+                return Err(FacilitatorLocalError::UnsupportedNetwork(None));
+            }
+            submission::notify(crate::types::TransactionHash::Evm([7; 32]));
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Err(FacilitatorLocalError::UnsupportedNetwork(None))
+        }
+
+        async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
+            Err(FacilitatorLocalError::UnsupportedNetwork(None))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_settle_without_waiting_answers_once_broadcast() {
+        let settle = |network: Network| {
+            post_settle(
+                State(std::sync::Arc::new(Broadcaster)),
+                Query(SettleQuery {
+                    wait: Some(false),
+                    queue: None,
+                }),
+                HeaderMap::new(),
+                Format::Json,
+                PaymentBody(settle_request(network)),
+            )
+        };
+
+        let response = settle(Network::BaseSepolia).await.into_response();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let answer: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(answer["success"], true);
+        assert_eq!(answer["status"], "submitted");
+        assert_eq!(answer["transaction"], format!("0x{}", "07".repeat(32)));
+        assert!(answer.get("blockNumber").is_none());
+
+        // Failing before any broadcast is answered as if waiting.
+        let response = settle(Network::Base).await.into_response();
+        assert_eq!(
+            response.status(),
+            FacilitatorLocalError::UnsupportedNetwork(None)
+                .into_response()
+                .status()
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! RPC connectivity probes behind `GET /health`, `GET /ready` and `GET /chains`.
+//!
+//! Each configured network is probed with its cheapest read, `eth_blockNumber` on EVM chains and
+//! `getSlot` on Solana, bounded by a short timeout. The facilitator is ready when every required
+//! network answers; otherwise it reports which ones did not. `GET /live` never probes, so a liveness
+//! check does not restart the facilitator over a single RPC blip.
+//!
+//! `GET /chains` lists every known network as a [`ChainInfo`], pairing static configuration, which is
+//! answered even while an RPC is down, with the live probe result of configured networks.
+//!
+//! Environment variables used:
+//! - `HEALTH_PROBE_TIMEOUT_MS` — how long each probe may take (default: `2000`),
+//! - `HEALTH_REQUIRED_NETWORKS` — comma-separated networks that must answer, e.g. `base,solana`
+//!   (default: every configured network).
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::chain::evm::EvmChain;
+use crate::from_env::{self, ENV_HEALTH_PROBE_TIMEOUT_MS, ENV_HEALTH_REQUIRED_NETWORKS};
+use crate::network::{Network, USDCDeployment};
+use crate::types::MixedAddress;
+
+/// Outcome of probing one network.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkHealth {
+    pub status: NetworkStatus,
+    /// Latest block number, or slot on Solana.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkStatus {
+    Ok,
+    Unreachable,
+    Timeout,
+    /// Required by `HEALTH_REQUIRED_NETWORKS` but not configured.
+    NotConfigured,
+}
+
+/// Probe results per network, keyed by network name.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    pub networks: BTreeMap<String, NetworkHealth>,
+}
+
+impl HealthReport {
+    /// Runs `probe` for `network` within `HEALTH_PROBE_TIMEOUT_MS`.
+    pub async fn probe<F, E>(network: Network, probe: F) -> Self
+    where
+        F: Future<Output = Result<u64, E>>,
+        E: std::fmt::Display,
+    {
+        let started = Instant::now();
+        let health = match tokio::time::timeout(probe_timeout(), probe).await {
+            Ok(Ok(block)) => NetworkHealth {
+                status: NetworkStatus::Ok,
+                block: Some(block),
+                latency_ms: Some(started.elapsed().as_millis() as u64),
+                error: None,
+            },
+            Ok(Err(error)) => NetworkHealth {
+                status: NetworkStatus::Unreachable,
+                block: None,
+                latency_ms: None,
+                error: Some(error.to_string()),
+            },
+            Err(_) => NetworkHealth {
+                status: NetworkStatus::Timeout,
+                block: None,
+                latency_ms: None,
+                error: None,
+            },
+        };
+        Self {
+            networks: BTreeMap::from([(network.to_string(), health)]),
+        }
+    }
+
+    /// Adds the networks probed in `other`.
+    pub fn merge(mut self, other: HealthReport) -> Self {
+        self.networks.extend(other.networks);
+        self
+    }
+
+    /// Whether every required network answered, marking required networks missing from the report.
+    pub fn check_required(&mut self) -> bool {
+        if let Some(required) = required_networks() {
+            for network in required {
+                self.networks
+                    .entry(network.to_string())
+                    .or_insert(NetworkHealth {
+                        status: NetworkStatus::NotConfigured,
+                        block: None,
+                        latency_ms: None,
+                        error: None,
+                    });
+            }
+            required.iter().all(|network| {
+                self.networks
+                    .get(&network.to_string())
+                    .is_some_and(|health| health.status == NetworkStatus::Ok)
+            })
+        } else {
+            self.networks
+                .values()
+                .all(|health| health.status == NetworkStatus::Ok)
+        }
+    }
+}
+
+/// A network in `GET /chains`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainInfo {
+    pub network: Network,
+    /// EIP-155 chain id; `None` on Solana.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    /// Whether this facilitator has an RPC endpoint for the network.
+    pub configured: bool,
+    /// Static token configuration, known without an RPC call.
+    pub usdc: ChainToken,
+    /// Whether the last probe answered; `false` for networks that are not configured.
+    pub healthy: bool,
+    /// Live probe result, only for configured networks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub live: Option<NetworkHealth>,
+}
+
+/// Static metadata of a token deployment.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainToken {
+    pub address: MixedAddress,
+    pub decimals: u8,
+    /// EIP-712 domain name, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// EIP-712 domain version, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// Body of `GET /chains`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainsResponse {
+    pub chains: Vec<ChainInfo>,
+}
+
+impl ChainsResponse {
+    /// Describes every known network, with the live status of the networks probed in `report`.
+    pub fn new(report: &HealthReport) -> Self {
+        let chains = Network::variants()
+            .iter()
+            .map(|network| {
+                let usdc = USDCDeployment::by_network(network);
+                let live = report.networks.get(&network.to_string()).cloned();
+                ChainInfo {
+                    network: *network,
+                    chain_id: EvmChain::try_from(*network)
+                        .ok()
+                        .map(|chain| chain.chain_id),
+                    configured: live.is_some(),
+                    usdc: ChainToken {
+                        address: usdc.address(),
+                        decimals: usdc.decimals,
+                        name: usdc.eip712.as_ref().map(|eip712| eip712.name.clone()),
+                        version: usdc.eip712.as_ref().map(|eip712| eip712.version.clone()),
+                    },
+                    healthy: live
+                        .as_ref()
+                        .is_some_and(|health| health.status == NetworkStatus::Ok),
+                    live,
+                }
+            })
+            .collect();
+        Self { chains }
+    }
+}
+
+fn probe_timeout() -> Duration {
+    let millis = std::env::var(ENV_HEALTH_PROBE_TIMEOUT_MS)
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(2000);
+    Duration::from_millis(millis)
+}
+
+/// Networks listed in `HEALTH_REQUIRED_NETWORKS`, `None` if unset. Unknown names are ignored.
+fn required_networks() -> Option<&'static [Network]> {
+    static REQUIRED: once_cell::sync::Lazy<Option<Vec<Network>>> =
+        once_cell::sync::Lazy::new(|| {
+            let raw = std::env::var(ENV_HEALTH_REQUIRED_NETWORKS).ok()?;
+            let networks = from_env::list_entries(&raw)
+                .filter_map(|(name, network)| {
+                    if network.is_err() {
+                        tracing::warn!(
+                            network = name,
+                            "Ignoring unknown network in {ENV_HEALTH_REQUIRED_NETWORKS}"
+                        );
+                    }
+                    network.ok()
+                })
+                .collect();
+            Some(networks)
+        });
+    REQUIRED.as_deref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_report_requires_every_probe_to_answer() {
+        let ok = HealthReport::probe(Network::Base, async { Ok::<_, String>(42) }).await;
+        let failed = HealthReport::probe(Network::Polygon, async {
+            Err::<u64, _>("refused".to_string())
+        })
+        .await;
+        assert_eq!(ok.networks["base"].block, Some(42));
+        assert!(ok.clone().check_required());
+
+        let mut report = ok.merge(failed);
+        assert_eq!(
+            report.networks["polygon"].status,
+            NetworkStatus::Unreachable
+        );
+        assert!(!report.check_required());
+    }
+
+    #[tokio::test]
+    async fn test_chains_describe_configured_and_unconfigured_networks() {
+        let ok = HealthReport::probe(Network::Base, async { Ok::<_, String>(42) }).await;
+        let failed = HealthReport::probe(Network::Polygon, async {
+            Err::<u64, _>("refused".to_string())
+        })
+        .await;
+        let report = ok.merge(failed);
+
+        let chains = ChainsResponse::new(&report);
+        let base = chains
+            .chains
+            .iter()
+            .find(|chain| chain.network == Network::Base)
+            .unwrap();
+        assert_eq!(base.chain_id, Some(8453));
+        assert!(base.configured && base.healthy);
+        let polygon = chains
+            .chains
+            .iter()
+            .find(|chain| chain.network == Network::Polygon)
+            .unwrap();
+        assert!(polygon.configured && !polygon.healthy);
+        assert_eq!(polygon.usdc.decimals, 6);
+        let solana = chains
+            .chains
+            .iter()
+            .find(|chain| chain.network == Network::Solana)
+            .unwrap();
+        assert!(!solana.configured && solana.live.is_none() && solana.chain_id.is_none());
+    }
+}
@@ -0,0 +1,268 @@
+//! `Idempotency-Key` support for `POST /settle`.
+//!
+//! A client that timed out waiting for `/settle` can retry with the same `Idempotency-Key` header
+//! without risking a second transaction: the first request with a key claims it and settles, and its
+//! [`SettleResponse`] is stored under the key. Later requests with the key are answered the stored
+//! response, with an `Idempotent-Replayed: true` header, and never reach the chain. While the first
+//! request is still settling, a retry gets `409 Conflict`; reusing a key for another payment gets
+//! `422 Unprocessable Entity`.
+//!
+//! Only answered settlements are stored: if settling fails with an error, or the request is dropped,
+//! the key is released so it can be retried. For `?wait=false` the `submitted` response is stored,
+//! then replaced by the final one.
+//!
+//! Environment variables used:
+//! - `IDEMPOTENCY_KEY_TTL_SECS` — how long a key is remembered after it was claimed (default: `3600`; `0` disables keys),
+//! - `IDEMPOTENCY_KEY_MAX_ENTRIES` — maximum number of keys remembered (default: `10000`).
+
+use axum::http::{HeaderMap, StatusCode};
+use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use once_cell::sync::Lazy;
+use std::time::{Duration, Instant};
+
+use crate::from_env::{ENV_IDEMPOTENCY_KEY_MAX_ENTRIES, ENV_IDEMPOTENCY_KEY_TTL_SECS};
+use crate::types::{PaymentId, SettleResponse};
+
+/// Request header carrying the idempotency key.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Response header set on a replayed response.
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Longest accepted key, in bytes.
+const MAX_KEY_LEN: usize = 255;
+
+#[derive(Debug)]
+struct StoredKey {
+    payment_id: PaymentId,
+    /// The stored answer; `None` while the first request is settling.
+    response: Option<(StatusCode, SettleResponse)>,
+    expires_at: Instant,
+}
+
+/// Idempotency keys seen by `/settle`, see the [module docs](self).
+#[derive(Debug)]
+pub struct IdempotencyStore {
+    ttl: Duration,
+    capacity: usize,
+    entries: DashMap<String, StoredKey>,
+}
+
+/// What to do with a `/settle` request carrying an idempotency key.
+#[derive(Debug)]
+pub enum Claim<'a> {
+    /// The key is new: settle, then [`complete`](IdempotencyClaim::complete) the claim.
+    Claimed(IdempotencyClaim<'a>),
+    /// The key was already answered with this response.
+    Replay(StatusCode, Box<SettleResponse>),
+    /// The first request with the key is still settling.
+    InProgress,
+    /// The key was used for another payment.
+    Mismatch,
+}
+
+static IDEMPOTENCY: Lazy<Option<IdempotencyStore>> = Lazy::new(IdempotencyStore::from_env);
+
+impl IdempotencyStore {
+    /// Creates a store remembering at most `capacity` keys for `ttl` each.
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            entries: DashMap::new(),
+        }
+    }
+
+    /// Reads `IDEMPOTENCY_KEY_TTL_SECS` and `IDEMPOTENCY_KEY_MAX_ENTRIES`; `None` if keys are disabled.
+    pub fn from_env() -> Option<Self> {
+        let ttl = std::env::var(ENV_IDEMPOTENCY_KEY_TTL_SECS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(3600);
+        let capacity = std::env::var(ENV_IDEMPOTENCY_KEY_MAX_ENTRIES)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(10_000);
+        (ttl > 0 && capacity > 0).then(|| Self::new(Duration::from_secs(ttl), capacity))
+    }
+
+    /// The store shared by all handlers, `None` if disabled.
+    pub fn global() -> Option<&'static IdempotencyStore> {
+        IDEMPOTENCY.as_ref()
+    }
+
+    /// The idempotency key of a request, if it sent a usable one.
+    pub fn key(headers: &HeaderMap) -> Option<String> {
+        let key = headers.get(IDEMPOTENCY_KEY_HEADER)?.to_str().ok()?.trim();
+        (!key.is_empty() && key.len() <= MAX_KEY_LEN).then(|| key.to_string())
+    }
+
+    /// Claims `key` for the settlement of `payment_id`, or tells how it was used before.
+    ///
+    /// When the store is full and no key has expired, the request proceeds unclaimed, as [`Claim::Claimed`]
+    /// with a claim that stores nothing.
+    pub fn claim(&self, key: String, payment_id: PaymentId) -> Claim<'_> {
+        let now = Instant::now();
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            self.entries.retain(|_, entry| entry.expires_at > now);
+            if self.entries.len() >= self.capacity {
+                return Claim::Claimed(IdempotencyClaim {
+                    store: self,
+                    key: None,
+                });
+            }
+        }
+        match self.entries.entry(key.clone()) {
+            Entry::Occupied(occupied) if occupied.get().expires_at > now => {
+                let entry = occupied.get();
+                if entry.payment_id != payment_id {
+                    Claim::Mismatch
+                } else if let Some((status, response)) = &entry.response {
+                    Claim::Replay(*status, Box::new(response.clone()))
+                } else {
+                    Claim::InProgress
+                }
+            }
+            Entry::Occupied(mut occupied) => {
+                occupied.insert(self.pending(payment_id, now));
+                Claim::Claimed(IdempotencyClaim {
+                    store: self,
+                    key: Some(key),
+                })
+            }
+            Entry::Vacant(vacant) => {
+                vacant.insert(self.pending(payment_id, now));
+                Claim::Claimed(IdempotencyClaim {
+                    store: self,
+                    key: Some(key),
+                })
+            }
+        }
+    }
+
+    fn pending(&self, payment_id: PaymentId, now: Instant) -> StoredKey {
+        StoredKey {
+            payment_id,
+            response: None,
+            expires_at: now + self.ttl,
+        }
+    }
+}
+
+/// A claimed key, released unless [`complete`](Self::complete)d before it is dropped.
+#[derive(Debug)]
+#[must_use]
+pub struct IdempotencyClaim<'a> {
+    store: &'a IdempotencyStore,
+    key: Option<String>,
+}
+
+impl IdempotencyClaim<'_> {
+    /// Stores the answer to the settlement, replayed to later requests with the key.
+    pub fn store(&self, status: StatusCode, response: &SettleResponse) {
+        if let Some(key) = &self.key
+            && let Some(mut entry) = self.store.entries.get_mut(key)
+        {
+            entry.response = Some((status, response.clone()));
+        }
+    }
+
+    /// Stores the final answer to the settlement and keeps the key claimed.
+    pub fn complete(mut self, status: StatusCode, response: &SettleResponse) {
+        self.store(status, response);
+        self.key = None;
+    }
+
+    /// Forgets the key even if a response was [`store`](Self::store)d, for a settlement that failed after
+    /// it was answered, so that retries settle again instead of replaying its success.
+    pub fn release(mut self) {
+        if let Some(key) = self.key.take() {
+            self.store.entries.remove(&key);
+        }
+    }
+}
+
+impl Drop for IdempotencyClaim<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.store
+                .entries
+                .remove_if(&key, |_, entry| entry.response.is_none());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Network;
+    use crate::types::MixedAddress;
+
+    fn response() -> SettleResponse {
+        SettleResponse {
+            success: true,
+            error_reason: None,
+            payer: MixedAddress::Offchain("payer".to_string()),
+            transaction: None,
+            block_number: None,
+            status: None,
+            network: Network::BaseSepolia,
+            payment_id: None,
+            cost: None,
+            extensions: None,
+        }
+    }
+
+    #[test]
+    fn test_key_replays_stored_response_and_is_released_on_failure() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 16);
+        let payment = PaymentId([1; 32]);
+
+        let Claim::Claimed(claim) = store.claim("retry".to_string(), payment) else {
+            panic!("new key must be claimed");
+        };
+        assert!(matches!(
+            store.claim("retry".to_string(), payment),
+            Claim::InProgress
+        ));
+        claim.complete(StatusCode::OK, &response());
+        assert!(matches!(
+            store.claim("retry".to_string(), payment),
+            Claim::Replay(StatusCode::OK, _)
+        ));
+        assert!(matches!(
+            store.claim("retry".to_string(), PaymentId([2; 32])),
+            Claim::Mismatch
+        ));
+
+        let Claim::Claimed(failed) = store.claim("failed".to_string(), payment) else {
+            panic!("new key must be claimed");
+        };
+        drop(failed);
+        assert!(matches!(
+            store.claim("failed".to_string(), payment),
+            Claim::Claimed(_)
+        ));
+    }
+
+    #[test]
+    fn test_released_key_stops_replaying_a_stored_submission() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 16);
+        let payment = PaymentId([1; 32]);
+
+        let Claim::Claimed(claim) = store.claim("background".to_string(), payment) else {
+            panic!("new key must be claimed");
+        };
+        claim.store(StatusCode::ACCEPTED, &response());
+        assert!(matches!(
+            store.claim("background".to_string(), payment),
+            Claim::Replay(StatusCode::ACCEPTED, _)
+        ));
+        claim.release();
+        assert!(matches!(
+            store.claim("background".to_string(), payment),
+            Claim::Claimed(_)
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+//! Counts of in-flight `/verify` and `/settle` requests and signer sweeps, for observable draining.
+//!
+//! The counts are served by `GET /admin/inflight`. On shutdown, the server stops accepting
+//! connections and [`drain`] logs how many requests are still running until they have finished,
+//! so operators can tell whether waiting is worthwhile during a deploy. Settlements running in the
+//! background, e.g. for `POST /settle?wait=false`, are waited for too, so a deploy does not kill a
+//! transaction mid-broadcast, and so are sweeps of signer balances, see [`crate::chain::sweep`].
+//! Settlement and cancellation requests arriving on open connections while draining are refused
+//! with `503 Service Unavailable` by [`reject_while_draining`], and so are settlements sent over
+//! WebSockets opened before draining began.
+//!
+//! Environment variables used:
+//! - `SHUTDOWN_DRAIN_TIMEOUT_SECS` — exit after this long even if requests are still running
+//!   (default: `0`, wait until they finish),
+//! - `SHUTDOWN_DRAIN_LOG_INTERVAL_SECS` — how often draining progress is logged (default: `5`).
+
+use axum::Json;
+use axum::extract::Request;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::from_env::{ENV_SHUTDOWN_DRAIN_LOG_INTERVAL_SECS, ENV_SHUTDOWN_DRAIN_TIMEOUT_SECS};
+use crate::types::ErrorResponse;
+
+/// Number of requests currently being handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InFlightCounts {
+    pub verifications: usize,
+    pub settlements: usize,
+    pub sweeps: usize,
+}
+
+impl InFlightCounts {
+    pub fn total(&self) -> usize {
+        self.verifications + self.settlements + self.sweeps
+    }
+}
+
+/// Process-wide in-flight request counters.
+#[derive(Debug, Default)]
+pub struct InFlight {
+    verifications: AtomicUsize,
+    settlements: AtomicUsize,
+    sweeps: AtomicUsize,
+    draining: AtomicBool,
+}
+
+static IN_FLIGHT: InFlight = InFlight {
+    verifications: AtomicUsize::new(0),
+    settlements: AtomicUsize::new(0),
+    sweeps: AtomicUsize::new(0),
+    draining: AtomicBool::new(false),
+};
+
+/// Decrements its counter when dropped, including when the request is cancelled.
+#[must_use]
+pub struct InFlightGuard(&'static AtomicUsize);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl InFlight {
+    /// The counters shared by all handlers.
+    pub fn global() -> &'static InFlight {
+        &IN_FLIGHT
+    }
+
+    /// Counts a verification until the guard is dropped.
+    pub fn verification(&'static self) -> InFlightGuard {
+        self.verifications.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard(&self.verifications)
+    }
+
+    /// Counts a settlement until the guard is dropped.
+    pub fn settlement(&'static self) -> InFlightGuard {
+        self.settlements.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard(&self.settlements)
+    }
+
+    /// Counts a sweep of signer balances until the guard is dropped.
+    pub fn sweep(&'static self) -> InFlightGuard {
+        self.sweeps.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard(&self.sweeps)
+    }
+
+    pub fn counts(&self) -> InFlightCounts {
+        InFlightCounts {
+            verifications: self.verifications.load(Ordering::Relaxed),
+            settlements: self.settlements.load(Ordering::Relaxed),
+            sweeps: self.sweeps.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Whether the server is shutting down and refuses new settlements.
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+
+    fn start_draining(&self) {
+        self.draining.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The `503 Service Unavailable` answer to a request refused while draining.
+pub fn shutting_down() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(ErrorResponse {
+            error: "Shutting down, not accepting settlements".to_string(),
+        }),
+    )
+        .into_response()
+}
+
+/// Middleware answering [`shutting_down`] instead of running the request once draining has started.
+pub async fn reject_while_draining(request: Request, next: Next) -> Response {
+    if InFlight::global().is_draining() {
+        return shutting_down();
+    }
+    next.run(request).await
+}
+
+/// How often [`drain`] checks whether requests are still in flight.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Once `cancellation_token` is cancelled, refuses new settlements and waits until no request is in flight,
+/// logging progress every `SHUTDOWN_DRAIN_LOG_INTERVAL_SECS`.
+///
+/// Exits the process if requests are still running after `SHUTDOWN_DRAIN_TIMEOUT_SECS`.
+pub async fn drain(cancellation_token: CancellationToken) {
+    let timeout = std::env::var(ENV_SHUTDOWN_DRAIN_TIMEOUT_SECS)
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs);
+    let log_interval = std::env::var(ENV_SHUTDOWN_DRAIN_LOG_INTERVAL_SECS)
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(5);
+    cancellation_token.cancelled().await;
+    InFlight::global().start_draining();
+    let started = tokio::time::Instant::now();
+    let log_interval = Duration::from_secs(log_interval);
+    let mut logged = started;
+    let mut poll = tokio::time::interval(DRAIN_POLL_INTERVAL);
+    loop {
+        poll.tick().await;
+        let counts = InFlight::global().counts();
+        if counts.total() == 0 {
+            tracing::info!("Drained all in-flight requests");
+            return;
+        }
+        if timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+            tracing::warn!(
+                verifications = counts.verifications,
+                settlements = counts.settlements,
+                sweeps = counts.sweeps,
+                "Drain timeout reached, exiting with requests in flight"
+            );
+            std::process::exit(1);
+        }
+        if logged.elapsed() < log_interval {
+            continue;
+        }
+        logged = tokio::time::Instant::now();
+        tracing::info!(
+            verifications = counts.verifications,
+            settlements = counts.settlements,
+            sweeps = counts.sweeps,
+            "Draining: waiting on {} settlements, {} verifications and {} sweeps",
+            counts.settlements,
+            counts.verifications,
+            counts.sweeps
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_guards_count_requests_until_dropped_or_cancelled() {
+        static COUNTERS: InFlight = InFlight {
+            verifications: AtomicUsize::new(0),
+            settlements: AtomicUsize::new(0),
+            sweeps: AtomicUsize::new(0),
+            draining: AtomicBool::new(false),
+        };
+        let verification = COUNTERS.verification();
+        let settlement = COUNTERS.settlement();
+        let cancelled = tokio::spawn(async {
+            let _sweep = COUNTERS.sweep();
+            std::future::pending::<()>().await
+        });
+        tokio::task::yield_now().await;
+        let counts = COUNTERS.counts();
+        assert_eq!(
+            counts,
+            InFlightCounts {
+                verifications: 1,
+                settlements: 1,
+                sweeps: 1,
+            }
+        );
+        assert_eq!(counts.total(), 3);
+        assert_eq!(
+            serde_json::to_value(counts).unwrap(),
+            serde_json::json!({"verifications": 1, "settlements": 1, "sweeps": 1})
+        );
+
+        cancelled.abort();
+        let _ = cancelled.await;
+        drop(verification);
+        drop(settlement);
+        assert_eq!(COUNTERS.counts(), InFlightCounts::default());
+    }
+
+    #[tokio::test]
+    async fn test_drain_refuses_settlements_and_waits_for_those_in_flight() {
+        let router = Router::new().route(
+            "/settle",
+            post(|| async { "ok" }).layer(axum::middleware::from_fn(reject_while_draining)),
+        );
+        let settle = || {
+            Request::builder()
+                .method("POST")
+                .uri("/settle")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let accepted = router.clone().oneshot(settle()).await.unwrap();
+        assert_eq!(accepted.status(), StatusCode::OK);
+
+        let in_flight = InFlight::global().settlement();
+        let cancellation_token = CancellationToken::new();
+        let drained = tokio::spawn(drain(cancellation_token.clone()));
+        cancellation_token.cancel();
+        tokio::time::sleep(DRAIN_POLL_INTERVAL * 3).await;
+        assert!(InFlight::global().is_draining());
+        assert!(!drained.is_finished());
+        let refused = router.oneshot(settle()).await.unwrap();
+        assert_eq!(refused.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        drop(in_flight);
+        tokio::time::timeout(Duration::from_secs(5), drained)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,994 @@
+//! Persistent journal of settlements, for reconciliation, auditing and crash recovery.
+//!
+//! Every settlement the facilitator broadcasts is recorded as `pending` in a [`SettlementJournal`]
+//! as soon as its transaction is sent, see [`track`], then as `confirmed` or `failed` once
+//! answered, see [`record_outcome`], or as `failed` with the error if it fails after broadcasting. Entries are kept per payment, by [`PaymentId`], as the answer
+//! may name another transaction than the one first broadcast: a fee-bump replacement, or the forward
+//! of a payment received by a facilitator signer. They are looked up by any of those transaction
+//! hashes with `GET /settlements/{tx_hash}`, and carry the original request, the payer, the amount
+//! and the final response. Settlements queued with `POST /settle?queue=true` are recorded as
+//! `queued`, with no transaction, until a worker broadcasts them, see [`crate::settle_queue`].
+//!
+//! Each entry also keeps the [history](JournalEvent) of its payment, verification, replacements
+//! and reorgs included, served as a [`PaymentLifecycle`] with `GET /payments/{payment_id}`.
+//!
+//! A settlement still `pending` when the facilitator stops, e.g. on a crash or a receipt timeout,
+//! is not lost: on startup, [`recover`] re-checks the on-chain status of every pending entry.
+//! With a [`StatusRefresh`], `GET /settlements/{tx_hash}` re-checks a pending entry too, at most
+//! once per interval per transaction; `confirmed` and `failed` entries are always served from the
+//! journal.
+//!
+//! [`FileJournal`], an append-only JSON Lines file compacted to one line per settlement whenever it
+//! is opened, is the default backend. There is no SQLite backend, as this crate does not depend on a
+//! SQLite driver; such stores, or Postgres, implement [`SettlementJournal`] and are installed with
+//! [`install`].
+//!
+//! Environment variables used:
+//! - `SETTLEMENT_JOURNAL_PATH` — file the journal is kept in (default: `settlements.jsonl`; empty
+//!   disables journaling).
+//! - `SETTLEMENT_STATUS_REFRESH_SECS` — minimum interval between on-chain re-checks of a pending
+//!   settlement looked up by `GET /settlements/{tx_hash}` (default: not re-checked).
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, mpsc, oneshot};
+
+use crate::chain::submission;
+use crate::chain::{FacilitatorLocalError, NetworkProvider};
+use crate::facilitator_local::FacilitatorLocal;
+use crate::from_env::{ENV_SETTLEMENT_JOURNAL_PATH, ENV_SETTLEMENT_STATUS_REFRESH_SECS};
+use crate::network::Network;
+use crate::provider_cache::ProviderMap;
+use crate::timestamp::UnixTimestamp;
+use crate::types::{
+    ExactPaymentPayload, MixedAddress, PaymentId, SettleRequest, SettleResponse, TokenAmount,
+    TransactionHash, VerifyRequest,
+};
+
+/// Where a journaled settlement stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalStatus {
+    /// The settlement is queued, see [`crate::settle_queue`]; nothing was broadcast yet.
+    Queued,
+    /// The transaction was broadcast; its outcome is not known yet.
+    Pending,
+    /// The transaction succeeded.
+    Confirmed,
+    /// The transaction reverted, or the settlement failed after broadcasting it, or while queued.
+    Failed,
+}
+
+/// A settlement recorded in the journal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    /// The last transaction of the settlement, `None` while it is queued.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionHash>,
+    /// Earlier transactions of the settlement, replaced by or followed by `transaction`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replaced: Vec<TransactionHash>,
+    pub network: Network,
+    pub payer: MixedAddress,
+    /// Authorized value of an EVM payment; the required amount otherwise.
+    pub amount: TokenAmount,
+    pub status: JournalStatus,
+    pub request: SettleRequest,
+    /// The answer to the settlement, once known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<SettleResponse>,
+    /// Why the settlement failed after broadcasting, without an answer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: UnixTimestamp,
+    pub updated_at: UnixTimestamp,
+    /// What happened to the payment, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<JournalEvent>,
+}
+
+/// A step in the lifecycle of a journaled payment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEvent {
+    pub at: UnixTimestamp,
+    #[serde(flatten)]
+    pub kind: JournalEventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum JournalEventKind {
+    /// The payment passed `/verify`.
+    Verified,
+    /// The settlement was queued, see [`crate::settle_queue`].
+    Queued,
+    /// The settlement transaction was broadcast.
+    Broadcast { transaction: TransactionHash },
+    /// The transaction was replaced by one with the same nonce and higher fees.
+    Replaced {
+        transaction: TransactionHash,
+        replacement: TransactionHash,
+    },
+    /// The transaction was orphaned by a reorg.
+    Reorged { transaction: TransactionHash },
+    /// The transaction succeeded.
+    Confirmed { transaction: TransactionHash },
+    /// The transaction reverted.
+    Reverted { transaction: TransactionHash },
+    /// The settlement failed after broadcasting or while queued, without an answer.
+    Failed { error: String },
+}
+
+impl JournalEntry {
+    /// A `pending` entry for the settlement of `request` in `transaction`.
+    pub fn pending(request: &SettleRequest, transaction: TransactionHash) -> Self {
+        Self::new(request).broadcast(transaction)
+    }
+
+    /// A `queued` entry for the settlement of `request`, see [`crate::settle_queue`].
+    pub fn queued(request: &SettleRequest) -> Self {
+        Self::new(request).with_event(JournalEventKind::Queued)
+    }
+
+    /// An entry for the settlement of `request`, with nothing broadcast yet.
+    fn new(request: &SettleRequest) -> Self {
+        let payload = &request.payment_payload.payload;
+        let (payer, amount) = match payload {
+            ExactPaymentPayload::Evm(payload) => (
+                payload.authorization.from.into(),
+                payload.authorization.value,
+            ),
+            ExactPaymentPayload::Solana(_) => (
+                MixedAddress::Offchain("unknown".to_string()),
+                request.payment_requirements.max_amount_required,
+            ),
+        };
+        let now = UnixTimestamp::try_now().unwrap_or(UnixTimestamp(0));
+        let verified = take_verified(&request.payment_id()).map(|at| JournalEvent {
+            at,
+            kind: JournalEventKind::Verified,
+        });
+        Self {
+            transaction: None,
+            replaced: Vec::new(),
+            network: request.network(),
+            payer,
+            amount,
+            status: JournalStatus::Queued,
+            request: request.clone(),
+            response: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+            history: verified.into_iter().collect(),
+        }
+    }
+
+    /// The payment this entry settles.
+    pub fn payment_id(&self) -> PaymentId {
+        self.request.payment_id()
+    }
+
+    /// This entry with `transaction` as its last transaction, e.g. the one mined of those it replaced.
+    fn with_transaction(mut self, transaction: TransactionHash) -> Self {
+        if self.transaction.as_ref() != Some(&transaction) {
+            self.replaced.retain(|replaced| *replaced != transaction);
+            if let Some(previous) = self.transaction.replace(transaction) {
+                self.replaced.push(previous);
+            }
+        }
+        self
+    }
+
+    /// This entry `pending` with `transaction` broadcast now.
+    fn broadcast(self, transaction: TransactionHash) -> Self {
+        self.with_transaction(transaction.clone())
+            .with_status(JournalStatus::Pending)
+            .with_event(JournalEventKind::Broadcast { transaction })
+    }
+
+    /// This entry with `status`, updated now.
+    fn with_status(mut self, status: JournalStatus) -> Self {
+        self.status = status;
+        self.updated_at = UnixTimestamp::try_now().unwrap_or(self.updated_at);
+        self
+    }
+
+    /// This entry with `kind` happening now.
+    fn with_event(mut self, kind: JournalEventKind) -> Self {
+        let at = UnixTimestamp::try_now().unwrap_or(self.updated_at);
+        self.updated_at = at;
+        self.history.push(JournalEvent { at, kind });
+        self
+    }
+
+    /// This entry with its transaction mined, `confirmed` if it succeeded and `failed` if it reverted.
+    fn mined(self, success: bool) -> Self {
+        let entry = self.with_status(if success {
+            JournalStatus::Confirmed
+        } else {
+            JournalStatus::Failed
+        });
+        match entry.transaction.clone() {
+            Some(transaction) if success => {
+                entry.with_event(JournalEventKind::Confirmed { transaction })
+            }
+            Some(transaction) => entry.with_event(JournalEventKind::Reverted { transaction }),
+            None => entry,
+        }
+    }
+
+    /// The lifecycle of the payment this entry settles.
+    pub fn lifecycle(&self) -> PaymentLifecycle {
+        let at = |matches: fn(&JournalEventKind) -> bool| {
+            self.history
+                .iter()
+                .rev()
+                .find(|event| matches(&event.kind))
+                .map(|event| event.at)
+        };
+        PaymentLifecycle {
+            payment_id: self.payment_id(),
+            network: self.network,
+            payer: self.payer.clone(),
+            amount: self.amount,
+            status: self.status,
+            transaction: self.transaction.clone(),
+            verified_at: at(|kind| matches!(kind, JournalEventKind::Verified)),
+            broadcast_at: at(|kind| matches!(kind, JournalEventKind::Broadcast { .. })),
+            settled_at: at(|kind| {
+                matches!(
+                    kind,
+                    JournalEventKind::Confirmed { .. } | JournalEventKind::Reverted { .. }
+                )
+            }),
+            history: self.history.clone(),
+        }
+    }
+}
+
+/// The lifecycle of a payment, served by `GET /payments/{payment_id}`.
+///
+/// Unlike [`JournalEntry`], it holds neither the payer's signed request nor the response.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentLifecycle {
+    pub payment_id: PaymentId,
+    pub network: Network,
+    pub payer: MixedAddress,
+    pub amount: TokenAmount,
+    pub status: JournalStatus,
+    /// The last transaction of the settlement, `None` while it is queued.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionHash>,
+    /// The last time the payment passed `/verify` before it was settled, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified_at: Option<UnixTimestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broadcast_at: Option<UnixTimestamp>,
+    /// When the settlement transaction was mined, whether it succeeded or reverted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settled_at: Option<UnixTimestamp>,
+    pub history: Vec<JournalEvent>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum JournalError {
+    /// The journal could not be read or written.
+    #[error("Settlement journal unavailable: {0}")]
+    Unavailable(String),
+}
+
+impl From<std::io::Error> for JournalError {
+    fn from(error: std::io::Error) -> Self {
+        JournalError::Unavailable(error.to_string())
+    }
+}
+
+/// Store of [`JournalEntry`]s, keyed by [`PaymentId`], see the [module docs](self).
+#[async_trait]
+pub trait SettlementJournal: Send + Sync + std::fmt::Debug {
+    /// Records `entry`, replacing any entry for the same payment.
+    async fn record(&self, entry: JournalEntry) -> Result<(), JournalError>;
+    /// The entry naming `transaction`, if journaled.
+    async fn get(
+        &self,
+        transaction: &TransactionHash,
+    ) -> Result<Option<JournalEntry>, JournalError>;
+    /// The entry of `payment_id`, if journaled.
+    async fn payment(&self, payment_id: &PaymentId) -> Result<Option<JournalEntry>, JournalError>;
+    /// Every entry still [`JournalStatus::Pending`].
+    async fn pending(&self) -> Result<Vec<JournalEntry>, JournalError>;
+    /// Every entry still [`JournalStatus::Queued`].
+    async fn queued(&self) -> Result<Vec<JournalEntry>, JournalError>;
+}
+
+/// [`SettlementJournal`] kept in an append-only JSON Lines file, and in memory for lookups.
+///
+/// Every record appends a line; when the file is opened, the last line of each payment wins,
+/// and the file is rewritten with those lines only.
+#[derive(Debug)]
+pub struct FileJournal {
+    entries: DashMap<PaymentId, JournalEntry>,
+    transactions: DashMap<TransactionHash, PaymentId>,
+    file: Mutex<tokio::fs::File>,
+}
+
+impl FileJournal {
+    /// Opens the journal at `path`, creating it if needed, and loads its entries.
+    pub async fn open(path: PathBuf) -> Result<Self, JournalError> {
+        let entries = DashMap::new();
+        match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => {
+                for (index, line) in contents.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str::<JournalEntry>(line) {
+                        Ok(entry) => {
+                            entries.insert(entry.payment_id(), entry);
+                        }
+                        // A crash may leave the last line cut short.
+                        Err(error) => tracing::warn!(
+                            path = %path.display(),
+                            line = index + 1,
+                            %error,
+                            "Skipping unreadable settlement journal line"
+                        ),
+                    }
+                }
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error.into()),
+        }
+        let mut compacted = Vec::new();
+        for entry in entries.iter() {
+            compacted.extend(Self::line(entry.value())?);
+        }
+        // Written aside then renamed, so a crash while compacting loses nothing.
+        let rewritten = path.with_extension("compacting");
+        tokio::fs::write(&rewritten, &compacted).await?;
+        tokio::fs::rename(&rewritten, &path).await?;
+        let file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .await?;
+        let transactions = DashMap::new();
+        for entry in entries.iter() {
+            for transaction in entry.replaced.iter().chain(&entry.transaction) {
+                transactions.insert(transaction.clone(), *entry.key());
+            }
+        }
+        Ok(Self {
+            entries,
+            transactions,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Opens the journal at `SETTLEMENT_JOURNAL_PATH`, `settlements.jsonl` if it is not set,
+    /// or returns `None` if it is set empty.
+    pub async fn from_env() -> Result<Option<Self>, JournalError> {
+        let path =
+            std::env::var(ENV_SETTLEMENT_JOURNAL_PATH).unwrap_or_else(|_| DEFAULT_PATH.to_string());
+        if path.trim().is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self::open(path.into()).await?))
+    }
+
+    fn with_status(&self, status: JournalStatus) -> Vec<JournalEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status == status)
+            .map(|entry| entry.clone())
+            .collect()
+    }
+
+    fn line(entry: &JournalEntry) -> Result<Vec<u8>, JournalError> {
+        let mut line =
+            serde_json::to_vec(entry).map_err(|e| JournalError::Unavailable(e.to_string()))?;
+        line.push(b'\n');
+        Ok(line)
+    }
+}
+
+/// Journal file used when `SETTLEMENT_JOURNAL_PATH` is not set.
+const DEFAULT_PATH: &str = "settlements.jsonl";
+
+#[async_trait]
+impl SettlementJournal for FileJournal {
+    async fn record(&self, entry: JournalEntry) -> Result<(), JournalError> {
+        let line = Self::line(&entry)?;
+        let mut file = self.file.lock().await;
+        file.write_all(&line).await?;
+        file.flush().await?;
+        let payment_id = entry.payment_id();
+        for transaction in entry.replaced.iter().chain(&entry.transaction) {
+            self.transactions.insert(transaction.clone(), payment_id);
+        }
+        self.entries.insert(payment_id, entry);
+        Ok(())
+    }
+
+    async fn get(
+        &self,
+        transaction: &TransactionHash,
+    ) -> Result<Option<JournalEntry>, JournalError> {
+        let Some(payment_id) = self.transactions.get(transaction).map(|id| *id) else {
+            return Ok(None);
+        };
+        self.payment(&payment_id).await
+    }
+
+    async fn payment(&self, payment_id: &PaymentId) -> Result<Option<JournalEntry>, JournalError> {
+        Ok(self.entries.get(payment_id).map(|entry| entry.clone()))
+    }
+
+    async fn pending(&self) -> Result<Vec<JournalEntry>, JournalError> {
+        Ok(self.with_status(JournalStatus::Pending))
+    }
+
+    async fn queued(&self) -> Result<Vec<JournalEntry>, JournalError> {
+        Ok(self.with_status(JournalStatus::Queued))
+    }
+}
+
+static JOURNAL: OnceCell<Arc<dyn SettlementJournal>> = OnceCell::new();
+
+/// Makes `journal` the process-wide journal. Only the first call has an effect.
+pub fn install(journal: Arc<dyn SettlementJournal>) {
+    let _ = JOURNAL.set(journal);
+}
+
+/// The process-wide journal, `None` if journaling is disabled.
+pub fn global() -> Option<&'static Arc<dyn SettlementJournal>> {
+    JOURNAL.get()
+}
+
+/// When payments passed `/verify`, until they are journaled.
+static VERIFIED: Lazy<DashMap<PaymentId, UnixTimestamp>> = Lazy::new(DashMap::new);
+
+/// Most verifications of payments not journaled yet kept at once, see [`record_verified`].
+const MAX_VERIFIED: usize = 10_000;
+
+/// How long the verification of a payment not settled is kept, once [`MAX_VERIFIED`] is reached.
+const VERIFIED_TTL_SECS: u64 = 3600;
+
+/// Keeps that the payment of `request` passed `/verify` now, for the history of its journal entry.
+pub fn record_verified(request: &VerifyRequest) {
+    if global().is_none() {
+        return;
+    }
+    let Ok(now) = UnixTimestamp::try_now() else {
+        return;
+    };
+    if VERIFIED.len() >= MAX_VERIFIED {
+        VERIFIED.retain(|_, at| at.0 + VERIFIED_TTL_SECS > now.0);
+        if VERIFIED.len() >= MAX_VERIFIED {
+            return;
+        }
+    }
+    VERIFIED.insert(request.payment_id(), now);
+}
+
+/// When the payment `payment_id` last passed `/verify`, forgetting it.
+fn take_verified(payment_id: &PaymentId) -> Option<UnixTimestamp> {
+    VERIFIED.remove(payment_id).map(|(_, at)| at)
+}
+
+/// Runs the settlement `settle` of `request`, journaling it as pending as soon as it broadcasts,
+/// again whenever that transaction is replaced, and as failed if it then fails.
+pub async fn track<F, E>(request: &SettleRequest, settle: F) -> Result<SettleResponse, E>
+where
+    F: Future<Output = Result<SettleResponse, E>>,
+    E: std::fmt::Display,
+{
+    match global() {
+        Some(journal) => track_in(journal.as_ref(), request, settle).await,
+        None => settle.await,
+    }
+}
+
+async fn track_in<F, E>(
+    journal: &dyn SettlementJournal,
+    request: &SettleRequest,
+    settle: F,
+) -> Result<SettleResponse, E>
+where
+    F: Future<Output = Result<SettleResponse, E>>,
+    E: std::fmt::Display,
+{
+    let (submitted, on_submitted) = oneshot::channel();
+    let (replaced, mut replacements) =
+        mpsc::unbounded_channel::<(TransactionHash, TransactionHash)>();
+    let (reorged, mut reorgs) = mpsc::unbounded_channel();
+    let record_pending = async {
+        // Resolves with an error once `settle` is done without broadcasting.
+        let transaction = on_submitted.await.ok()?;
+        let mut pending = match journal.payment(&request.payment_id()).await {
+            Ok(Some(queued)) if queued.status == JournalStatus::Queued => {
+                queued.broadcast(transaction)
+            }
+            _ => JournalEntry::pending(request, transaction),
+        };
+        record(journal, pending.clone()).await;
+        // Ends once `settle` is done.
+        loop {
+            tokio::select! {
+                biased;
+                Some((transaction, replacement)) = replacements.recv() => {
+                    if pending.transaction.as_ref() == Some(&transaction) {
+                        pending = pending
+                            .with_transaction(replacement.clone())
+                            .with_event(JournalEventKind::Replaced {
+                                transaction,
+                                replacement,
+                            });
+                        record(journal, pending.clone()).await;
+                    }
+                }
+                Some(transaction) = reorgs.recv() => {
+                    pending = pending.with_event(JournalEventKind::Reorged { transaction });
+                    record(journal, pending.clone()).await;
+                }
+                else => break,
+            }
+        }
+        Some(pending)
+    };
+    let settle = submission::track_reorgs(reorged, settle);
+    let settle = submission::track(submitted, submission::track_replacements(replaced, settle));
+    let (result, pending) = tokio::join!(settle, record_pending);
+    let failed = match (&result, pending) {
+        (Err(error), Some(pending)) => Some(failed(pending, error)),
+        _ => None,
+    };
+    if let Some(failed) = failed {
+        record(journal, failed).await;
+    }
+    result
+}
+
+/// `pending`, or queued, as failed with `error`, without an answer.
+fn failed(pending: JournalEntry, error: &impl std::fmt::Display) -> JournalEntry {
+    let mut entry =
+        pending
+            .with_status(JournalStatus::Failed)
+            .with_event(JournalEventKind::Failed {
+                error: error.to_string(),
+            });
+    entry.error = Some(error.to_string());
+    entry
+}
+
+/// Journals the queued settlement of `payment_id` as failed with `error`, if it is still queued,
+/// i.e. if it failed before broadcasting anything.
+pub async fn fail_queued(
+    journal: &dyn SettlementJournal,
+    payment_id: &PaymentId,
+    error: &impl std::fmt::Display,
+) {
+    if let Ok(Some(queued)) = journal.payment(payment_id).await
+        && queued.status == JournalStatus::Queued
+    {
+        record(journal, failed(queued, error)).await;
+    }
+}
+
+/// Journals the answer to the settlement of `request`, if it names a transaction.
+pub async fn record_outcome(request: &SettleRequest, response: &SettleResponse) {
+    if let Some(journal) = global() {
+        journal_outcome(journal.as_ref(), request, response).await;
+    }
+}
+
+async fn journal_outcome(
+    journal: &dyn SettlementJournal,
+    request: &SettleRequest,
+    response: &SettleResponse,
+) {
+    let Some(transaction) = response.transaction.clone() else {
+        return;
+    };
+    let entry = match journal.payment(&request.payment_id()).await {
+        Ok(Some(entry)) => entry.with_transaction(transaction),
+        _ => JournalEntry::pending(request, transaction),
+    };
+    let mut entry = entry.mined(response.success);
+    entry.payer = response.payer.clone();
+    entry.response = Some(response.clone());
+    record(journal, entry).await;
+}
+
+async fn record(journal: &dyn SettlementJournal, entry: JournalEntry) {
+    let payment_id = entry.payment_id();
+    if let Err(error) = journal.record(entry).await {
+        tracing::warn!(%error, %payment_id, "Failed to journal settlement");
+    }
+}
+
+/// On-chain re-checks of pending settlements, at most one per `interval` per transaction.
+///
+/// Settlement lookups are polled, and a settlement's outcome does not change once it is mined:
+/// a lookup only hits the RPC for a `pending` entry not re-checked in the last `interval`.
+pub struct StatusRefresh {
+    interval: Duration,
+    checked: DashMap<TransactionHash, Instant>,
+}
+
+impl StatusRefresh {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            checked: DashMap::new(),
+        }
+    }
+
+    /// `entry`, updated with the status `check` looks its transaction up with if it is due.
+    ///
+    /// A mined transaction makes the entry `confirmed` or `failed` in `journal`, from where it is
+    /// served without checking it again.
+    pub async fn refresh<F, Fut, E>(
+        &self,
+        journal: &dyn SettlementJournal,
+        entry: JournalEntry,
+        check: F,
+    ) -> JournalEntry
+    where
+        F: FnOnce(TransactionHash) -> Fut,
+        Fut: Future<Output = Result<Option<bool>, E>>,
+        E: std::fmt::Display,
+    {
+        let Some(transaction) = entry.transaction.clone() else {
+            return entry;
+        };
+        if entry.status != JournalStatus::Pending || !self.due(&transaction) {
+            return entry;
+        }
+        let success = match check(transaction.clone()).await {
+            Ok(Some(success)) => success,
+            Ok(None) => return entry,
+            Err(error) => {
+                tracing::warn!(%error, tx = %transaction, "Failed to check pending settlement");
+                return entry;
+            }
+        };
+        self.checked.remove(&transaction);
+        // The settlement may have been answered meanwhile, with its response.
+        if let Ok(Some(current)) = journal.get(&transaction).await
+            && current.status != JournalStatus::Pending
+        {
+            return current;
+        }
+        let entry = entry.mined(success);
+        record(journal, entry.clone()).await;
+        entry
+    }
+
+    /// Whether `transaction` was not checked in the last `interval`, marking it checked now if so.
+    fn due(&self, transaction: &TransactionHash) -> bool {
+        let now = Instant::now();
+        match self.checked.entry(transaction.clone()) {
+            Entry::Occupied(checked) if now.duration_since(*checked.get()) < self.interval => false,
+            Entry::Occupied(mut checked) => {
+                checked.insert(now);
+                true
+            }
+            Entry::Vacant(checked) => {
+                checked.insert(now);
+                true
+            }
+        }
+    }
+}
+
+static STATUS_REFRESH: OnceCell<StatusRefresh> = OnceCell::new();
+
+/// Reads the re-check interval of pending settlements from `SETTLEMENT_STATUS_REFRESH_SECS`, for [`status_refresh`].
+///
+/// # Errors
+/// Returns an error if the interval is not a number of seconds, or if it was already read.
+pub fn status_refresh_from_env() -> Result<(), String> {
+    let Ok(value) = std::env::var(ENV_SETTLEMENT_STATUS_REFRESH_SECS) else {
+        return Ok(());
+    };
+    let secs: u64 = value
+        .parse()
+        .map_err(|e| format!("env {ENV_SETTLEMENT_STATUS_REFRESH_SECS} is invalid: {e}"))?;
+    STATUS_REFRESH
+        .set(StatusRefresh::new(Duration::from_secs(secs)))
+        .map_err(|_| "Settlement status refresh is already set".to_string())
+}
+
+/// The process-wide re-checks of pending settlements, `None` if they are not re-checked.
+pub fn status_refresh() -> Option<&'static StatusRefresh> {
+    STATUS_REFRESH.get()
+}
+
+/// Re-checks the on-chain status of every pending settlement, e.g. after a restart.
+///
+/// Entries whose transaction is mined become `confirmed` or `failed`; the others stay pending.
+pub async fn recover<A>(facilitator: Arc<FacilitatorLocal<A>>)
+where
+    A: ProviderMap<Value = NetworkProvider>,
+{
+    let Some(journal) = global() else {
+        return;
+    };
+    let pending = match journal.pending().await {
+        Ok(pending) => pending,
+        Err(error) => {
+            tracing::warn!(%error, "Failed to read pending settlements");
+            return;
+        }
+    };
+    if !pending.is_empty() {
+        tracing::info!(
+            pending = pending.len(),
+            "Re-checking pending settlements from the journal"
+        );
+    }
+    for entry in pending {
+        let Some(provider) = facilitator.provider_map().by_network(entry.network) else {
+            continue;
+        };
+        let Some(transaction) = entry.transaction.clone() else {
+            continue;
+        };
+        let status: Result<Option<bool>, FacilitatorLocalError> =
+            provider.transaction_status(&transaction).await;
+        match status {
+            Ok(Some(success)) => {
+                let entry = entry.mined(success);
+                tracing::info!(tx = %transaction, status = ?entry.status, "Recovered pending settlement");
+                record(journal.as_ref(), entry).await;
+            }
+            Ok(None) => {
+                tracing::warn!(tx = %transaction, "Pending settlement is not mined");
+            }
+            Err(error) => {
+                tracing::warn!(%error, tx = %transaction, "Failed to check pending settlement");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::evm::tests::offline_request;
+    use alloy::primitives::{Address, U256};
+    use alloy::signers::local::PrivateKeySigner;
+
+    fn request() -> SettleRequest {
+        let offline = offline_request(&PrivateKeySigner::random(), Address::repeat_byte(1));
+        SettleRequest {
+            x402_version: offline.x402_version,
+            payment_payload: offline.payment_payload,
+            payment_requirements: offline.payment_requirements,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_journal_keeps_the_last_entry_across_reopens() {
+        let path = std::env::temp_dir().join(format!(
+            "x402-journal-{}.jsonl",
+            UnixTimestamp::try_now().unwrap().0 ^ u64::from(std::process::id())
+        ));
+        let transaction = TransactionHash::Evm([7; 32]);
+        let journal = FileJournal::open(path.clone()).await.unwrap();
+        let pending = JournalEntry::pending(&request(), transaction.clone());
+        journal.record(pending.clone()).await.unwrap();
+        assert_eq!(journal.pending().await.unwrap().len(), 1);
+        journal
+            .record(pending.with_status(JournalStatus::Confirmed))
+            .await
+            .unwrap();
+        drop(journal);
+
+        let reopened = FileJournal::open(path.clone()).await.unwrap();
+        let entry = reopened.get(&transaction).await.unwrap().unwrap();
+        assert_eq!(entry.status, JournalStatus::Confirmed);
+        assert_eq!(entry.amount, TokenAmount(U256::from(1_500_000u64)));
+        assert!(reopened.pending().await.unwrap().is_empty());
+        // Reopening compacts the file to one line per settlement.
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 1);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_settlement_failing_after_broadcast_is_journaled_failed() {
+        let pending = JournalEntry::pending(&request(), TransactionHash::Evm([7; 32]));
+        let entry = failed(pending, &"receipt timeout");
+        assert_eq!(entry.status, JournalStatus::Failed);
+        assert_eq!(entry.error.as_deref(), Some("receipt timeout"));
+        assert!(entry.response.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replacements_are_journaled_while_pending() {
+        let path = std::env::temp_dir().join(format!(
+            "x402-journal-tracked-{}.jsonl",
+            UnixTimestamp::try_now().unwrap().0 ^ u64::from(std::process::id())
+        ));
+        let broadcast = TransactionHash::Evm([7; 32]);
+        let replacement = TransactionHash::Evm([8; 32]);
+        let journal = FileJournal::open(path.clone()).await.unwrap();
+        let settle = async {
+            submission::notify(broadcast.clone());
+            submission::notify_replaced(broadcast.clone(), replacement.clone());
+            tokio::task::yield_now().await;
+            Err::<SettleResponse, _>("receipt timeout")
+        };
+        assert!(track_in(&journal, &request(), settle).await.is_err());
+
+        let entry = journal.get(&broadcast).await.unwrap().unwrap();
+        assert_eq!(entry.transaction, Some(replacement.clone()));
+        assert_eq!(entry.replaced, vec![broadcast.clone()]);
+        assert_eq!(entry.status, JournalStatus::Failed);
+        // The original transaction may still be the one mined.
+        let mined = entry.with_transaction(broadcast.clone());
+        assert_eq!(mined.transaction, Some(broadcast));
+        assert_eq!(mined.replaced, vec![replacement]);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_outcome_in_a_replacement_transaction_settles_the_pending_entry() {
+        let path = std::env::temp_dir().join(format!(
+            "x402-journal-replaced-{}.jsonl",
+            UnixTimestamp::try_now().unwrap().0 ^ u64::from(std::process::id())
+        ));
+        let broadcast = TransactionHash::Evm([7; 32]);
+        let replacement = TransactionHash::Evm([8; 32]);
+        let journal = FileJournal::open(path.clone()).await.unwrap();
+        journal
+            .record(JournalEntry::pending(&request(), broadcast.clone()))
+            .await
+            .unwrap();
+        let response = SettleResponse {
+            success: true,
+            error_reason: None,
+            payer: request().payer().unwrap(),
+            transaction: Some(replacement.clone()),
+            block_number: None,
+            status: None,
+            network: Network::BaseSepolia,
+            payment_id: Some(request().payment_id()),
+            cost: None,
+            extensions: None,
+        };
+        journal_outcome(&journal, &request(), &response).await;
+
+        assert!(journal.pending().await.unwrap().is_empty());
+        for transaction in [&broadcast, &replacement] {
+            let entry = journal.get(transaction).await.unwrap().unwrap();
+            assert_eq!(entry.status, JournalStatus::Confirmed);
+            assert_eq!(entry.transaction.as_ref(), Some(&replacement));
+            assert_eq!(entry.replaced, vec![broadcast.clone()]);
+        }
+        drop(journal);
+        let reopened = FileJournal::open(path.clone()).await.unwrap();
+        assert!(reopened.get(&broadcast).await.unwrap().is_some());
+        assert!(reopened.pending().await.unwrap().is_empty());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_history_follows_the_payment_lifecycle() {
+        let path = std::env::temp_dir().join(format!(
+            "x402-journal-history-{}.jsonl",
+            UnixTimestamp::try_now().unwrap().0 ^ u64::from(std::process::id())
+        ));
+        let broadcast = TransactionHash::Evm([7; 32]);
+        let replacement = TransactionHash::Evm([8; 32]);
+        let journal = FileJournal::open(path.clone()).await.unwrap();
+        let request = request();
+        VERIFIED.insert(request.payment_id(), UnixTimestamp(1));
+        let settle = async {
+            submission::notify(broadcast.clone());
+            submission::notify_replaced(broadcast.clone(), replacement.clone());
+            tokio::task::yield_now().await;
+            submission::notify_reorged(replacement.clone());
+            tokio::task::yield_now().await;
+            Err::<SettleResponse, _>("orphaned by reorg")
+        };
+        assert!(track_in(&journal, &request, settle).await.is_err());
+
+        let entry = journal
+            .payment(&request.payment_id())
+            .await
+            .unwrap()
+            .unwrap();
+        let kinds: Vec<_> = entry.history.iter().map(|event| &event.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &JournalEventKind::Verified,
+                &JournalEventKind::Broadcast {
+                    transaction: broadcast.clone()
+                },
+                &JournalEventKind::Replaced {
+                    transaction: broadcast,
+                    replacement: replacement.clone()
+                },
+                &JournalEventKind::Reorged {
+                    transaction: replacement.clone()
+                },
+                &JournalEventKind::Failed {
+                    error: "orphaned by reorg".to_string()
+                },
+            ]
+        );
+        let lifecycle = entry.lifecycle();
+        assert_eq!(lifecycle.payment_id, request.payment_id());
+        assert_eq!(lifecycle.verified_at, Some(UnixTimestamp(1)));
+        assert!(lifecycle.broadcast_at.is_some());
+        assert_eq!(lifecycle.settled_at, None);
+        assert!(take_verified(&request.payment_id()).is_none());
+
+        // Recovered later: the rebroadcast reverted.
+        let lifecycle = entry.mined(false).lifecycle();
+        assert_eq!(lifecycle.status, JournalStatus::Failed);
+        assert!(lifecycle.settled_at.is_some());
+        let reverted = serde_json::to_value(lifecycle.history.last().unwrap()).unwrap();
+        assert_eq!(reverted["event"], "reverted");
+        assert_eq!(reverted["transaction"], replacement.to_string());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_status_refresh_rechecks_pending_entries_at_most_once_per_interval() {
+        let path = std::env::temp_dir().join(format!(
+            "x402-journal-refresh-{}.jsonl",
+            UnixTimestamp::try_now().unwrap().0 ^ u64::from(std::process::id())
+        ));
+        let transaction = TransactionHash::Evm([7; 32]);
+        let journal = FileJournal::open(path.clone()).await.unwrap();
+        let pending = JournalEntry::pending(&request(), transaction.clone());
+        journal.record(pending.clone()).await.unwrap();
+        let checks = std::sync::atomic::AtomicUsize::new(0);
+        let check = |mined: Option<bool>| {
+            let checks = &checks;
+            move |_| async move {
+                checks.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                Ok::<_, JournalError>(mined)
+            }
+        };
+        let checks = || checks.load(std::sync::atomic::Ordering::Relaxed);
+
+        let throttled = StatusRefresh::new(Duration::from_secs(3600));
+        let entry = throttled
+            .refresh(&journal, pending.clone(), check(None))
+            .await;
+        assert_eq!(entry.status, JournalStatus::Pending);
+        let entry = throttled.refresh(&journal, entry, check(Some(true))).await;
+        assert_eq!(entry.status, JournalStatus::Pending);
+        assert_eq!(checks(), 1);
+
+        let refresh = StatusRefresh::new(Duration::ZERO);
+        let entry = refresh.refresh(&journal, pending, check(Some(true))).await;
+        assert_eq!(entry.status, JournalStatus::Confirmed);
+        assert_eq!(checks(), 2);
+        let journaled = journal.get(&transaction).await.unwrap().unwrap();
+        assert_eq!(journaled.status, JournalStatus::Confirmed);
+        // A mined settlement is served as journaled.
+        let entry = refresh
+            .refresh(&journal, journaled, check(Some(false)))
+            .await;
+        assert_eq!(entry.status, JournalStatus::Confirmed);
+        assert_eq!(checks(), 2);
+        let _ = std::fs::remove_file(path);
+    }
+}
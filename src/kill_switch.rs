@@ -0,0 +1,173 @@
+//! On-chain kill switch for emergency response.
+//!
+//! When configured, a background task polls a `paused()` flag on an operator-controlled contract,
+//! e.g. an OpenZeppelin `Pausable`. While the flag is set, the facilitator is in maintenance mode:
+//! `/settle` is refused, while `/verify` and discovery keep working. Every replica watching the
+//! same contract pauses together, and each pause is an auditable on-chain transaction.
+//!
+//! If the flag cannot be read, the last known state is kept.
+//!
+//! Environment variables used:
+//! - `KILL_SWITCH_CONTRACT` — address of the contract exposing `paused()`; enables the kill switch when set,
+//! - `KILL_SWITCH_NETWORK` — EVM network the contract lives on, e.g. `base`; its `RPC_URL_*` must be set,
+//! - `KILL_SWITCH_INTERVAL_SECS` — how often to read the flag (default: `15`).
+
+use alloy::primitives::Address;
+use alloy::providers::RootProvider;
+use alloy::sol;
+use std::env;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::from_env::{
+    ENV_KILL_SWITCH_CONTRACT, ENV_KILL_SWITCH_INTERVAL_SECS, ENV_KILL_SWITCH_NETWORK,
+    rpc_env_name_from_network,
+};
+use crate::network::{Network, NetworkFamily};
+
+sol! {
+    /// Pause flag read by the kill switch.
+    #[sol(rpc)]
+    interface IPausable {
+        function paused() external view returns (bool);
+    }
+}
+
+/// Watches an on-chain pause flag, see the [module docs](self).
+pub struct KillSwitch {
+    network: Network,
+    contract: Address,
+    interval: Duration,
+    provider: RootProvider,
+}
+
+impl KillSwitch {
+    /// Reads the kill switch configuration, or returns `None` if `KILL_SWITCH_CONTRACT` is not set.
+    pub fn from_env() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let Ok(contract) = env::var(ENV_KILL_SWITCH_CONTRACT) else {
+            return Ok(None);
+        };
+        let contract: Address = contract
+            .trim()
+            .parse()
+            .map_err(|e| format!("env {ENV_KILL_SWITCH_CONTRACT} is invalid: {e}"))?;
+        let network = env::var(ENV_KILL_SWITCH_NETWORK)
+            .map_err(|_| format!("env {ENV_KILL_SWITCH_NETWORK} not set"))?;
+        let network: Network = serde_json::from_value(serde_json::Value::String(network))
+            .map_err(|e| format!("env {ENV_KILL_SWITCH_NETWORK} is invalid: {e}"))?;
+        if !matches!(NetworkFamily::from(network), NetworkFamily::Evm) {
+            return Err(format!("env {ENV_KILL_SWITCH_NETWORK} must be an EVM network").into());
+        }
+        let rpc_env = rpc_env_name_from_network(network);
+        let rpc_url = env::var(rpc_env)
+            .map_err(|_| format!("env {rpc_env} not set, required by the kill switch"))?
+            .parse()
+            .map_err(|e| format!("env {rpc_env} is invalid: {e}"))?;
+        let interval = env::var(ENV_KILL_SWITCH_INTERVAL_SECS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(15);
+        Ok(Some(Self {
+            network,
+            contract,
+            interval: Duration::from_secs(interval),
+            provider: RootProvider::new_http(rpc_url),
+        }))
+    }
+
+    /// Reads the pause flag once.
+    pub async fn is_paused(&self) -> Result<bool, alloy::contract::Error> {
+        IPausable::new(self.contract, &self.provider)
+            .paused()
+            .call()
+            .await
+    }
+
+    /// Polls the flag until `cancellation_token` is cancelled, mirroring it into `maintenance`.
+    pub async fn watch(self, maintenance: Arc<AtomicBool>, cancellation_token: CancellationToken) {
+        tracing::info!(network = %self.network, contract = %self.contract, "Watching kill switch");
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = cancellation_token.cancelled() => break,
+            }
+            match self.is_paused().await {
+                Ok(paused) => {
+                    let was_paused = maintenance.swap(paused, Ordering::Relaxed);
+                    if paused && !was_paused {
+                        tracing::warn!(contract = %self.contract, "Kill switch engaged, refusing settlements");
+                    } else if !paused && was_paused {
+                        tracing::info!(contract = %self.contract, "Kill switch released, resuming settlements");
+                    }
+                }
+                Err(error) => {
+                    tracing::warn!(%error, "Failed to read kill switch, keeping current state");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::providers::mock::Asserter;
+    use alloy::rpc::client::RpcClient;
+    use alloy::sol_types::SolValue;
+
+    #[tokio::test]
+    async fn test_watch_mirrors_the_flag_and_keeps_it_when_unreadable() {
+        let asserter = Asserter::new();
+        let kill_switch = KillSwitch {
+            network: Network::BaseSepolia,
+            contract: Address::repeat_byte(1),
+            interval: Duration::from_millis(1),
+            provider: RootProvider::new(RpcClient::mocked(asserter.clone())),
+        };
+        asserter.push_success(&alloy::primitives::Bytes::from(true.abi_encode()));
+        asserter.push_failure_msg("node unavailable");
+        asserter.push_success(&alloy::primitives::Bytes::from(false.abi_encode()));
+        let maintenance = Arc::new(AtomicBool::new(false));
+        let cancellation_token = CancellationToken::new();
+        let watching =
+            tokio::spawn(kill_switch.watch(Arc::clone(&maintenance), cancellation_token.clone()));
+        let engaged_then_read = async {
+            while !maintenance.load(Ordering::Relaxed) {
+                tokio::task::yield_now().await;
+            }
+            while !asserter.read_q().is_empty() {
+                tokio::task::yield_now().await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(1), engaged_then_read)
+            .await
+            .unwrap();
+        // The failed read kept the switch engaged; the next one released it.
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!maintenance.load(Ordering::Relaxed));
+        cancellation_token.cancel();
+        watching.await.unwrap();
+    }
+
+    #[test]
+    fn test_kill_switch_is_off_unless_configured_on_an_evm_network() {
+        unsafe { env::remove_var(ENV_KILL_SWITCH_CONTRACT) };
+        assert!(KillSwitch::from_env().unwrap().is_none());
+        unsafe {
+            env::set_var(
+                ENV_KILL_SWITCH_CONTRACT,
+                Address::repeat_byte(1).to_string(),
+            )
+        };
+        unsafe { env::set_var(ENV_KILL_SWITCH_NETWORK, "solana") };
+        assert!(KillSwitch::from_env().is_err());
+        unsafe { env::set_var(ENV_KILL_SWITCH_CONTRACT, "0x12") };
+        assert!(KillSwitch::from_env().is_err());
+        unsafe { env::remove_var(ENV_KILL_SWITCH_CONTRACT) };
+        unsafe { env::remove_var(ENV_KILL_SWITCH_NETWORK) };
+    }
+}
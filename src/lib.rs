@@ -9,24 +9,64 @@
 //! - _Buyer_: a client that constructs and submits x402-compliant payments
 //!
 //! Modules:
+//! - [`audit`] — tamper-evident audit log of admin requests.
+//! - [`codec`] — JSON / MessagePack content negotiation for request and response bodies.
+//! - [`cors`] — configurable CORS for browser clients.
+//! - [`events`] — pluggable export of settlement events to message buses.
+//! - [`fallback`] — forwarding to a backup facilitator when a network's RPC or signer is down.
+//! - [`failures`] — bounded log of recent failed payments, served to operators.
 //! - [`facilitator`] — defines the [`facilitator::Facilitator`] trait used to validate and settle x402 payments.
 //! - [`facilitator_local`] — a concrete implementation of [`facilitator::Facilitator`].
+//! - [`health`] — RPC connectivity probes for readiness checks.
+//! - [`idempotency`] — `Idempotency-Key` support, so retried settlements are not broadcast twice.
+//! - [`inflight`] — counts of in-flight requests, logged while draining on shutdown.
+//! - [`journal`] — persistent journal of settlements, looked up by transaction hash or payment.
+//! - [`kill_switch`] — maintenance mode driven by an on-chain pause flag.
+//! - [`merchant_intent`] — merchant-signed payment requirements, guarding against rewritten `payTo`.
+//! - [`metrics`] — Prometheus metrics for requests, verifications and settlements.
 //! - [`network`] — enumerates supported Ethereum-compatible networks and known token deployments.
+//! - [`openapi`] — OpenAPI 3.1 description of the facilitator API.
 //! - [`provider_cache`] — dynamic initialization and caching of Ethereum JSON-RPC providers.
+//! - [`rate_limit`] — per-payer token-bucket rate limiting of `/verify`, `/settle` and their batches.
+//! - [`receiver_allowlist`] — per-network allowlist of payment receivers.
+//! - [`resource_policy`] — per-merchant allowed resource URLs.
+//! - [`settle_queue`] — durable queue of settlements for `POST /settle?queue=true`, and its workers.
 //! - [`telemetry`] — OpenTelemetry instrumentation setup for tracing and observability.
+//! - [`tokens`] — per-network settings for tokens that need special handling.
 //! - [`types`] — all shared x402 protocol structures and payload formats.
+//! - [`verify_cache`] — bounded TTL cache of `/verify` results.
 
+pub mod audit;
 pub mod chain;
+pub mod codec;
+pub mod cors;
+pub mod events;
 pub mod facilitator;
 pub mod facilitator_local;
+pub mod failures;
+pub mod fallback;
 pub mod from_env;
 pub mod handlers;
+pub mod health;
+pub mod idempotency;
+pub mod inflight;
+pub mod journal;
+pub mod kill_switch;
+pub mod merchant_intent;
+pub mod metrics;
 pub mod network;
+pub mod openapi;
 pub mod provider_cache;
+pub mod rate_limit;
+pub mod receiver_allowlist;
+pub mod resource_policy;
+pub mod settle_queue;
 pub mod sig_down;
 pub mod telemetry;
 pub mod timestamp;
+pub mod tokens;
 pub mod types;
+pub mod verify_cache;
 
 // Hidden re-exports just for macro expansion.
 #[doc(hidden)]
@@ -6,43 +6,80 @@
 //! Endpoints:
 //! - `GET /verify` – Supported verification schema
 //! - `POST /verify` – Verify a payment payload against requirements
+//! - `POST /verify/batch` – Verify an array of payment payloads, answering in the same order
+//! - `POST /verify/offline` – Check an EVM payload's signature, value and timing against a supplied EIP-712 domain
+//! - `POST /recover` – Recover the signer of an EIP-191 message or EIP-712 typed data
+//! - `POST /quote` – Estimate the native and USD cost of settling a payment
+//! - `POST /cancel` – Submit a payer-signed `cancelAuthorization` for an unsettled EVM authorization
 //! - `GET /settle` – Supported settlement schema
-//! - `POST /settle` – Settle an accepted payment payload on-chain
+//! - `POST /settle` – Settle an accepted payment payload on-chain; `?wait=false` answers once it is broadcast
+//! - `POST /settle/batch` – Settle an array of payment payloads, answering in the same order
+//! - `GET /ws/settle` – Settle over a WebSocket, streaming submitted, pending and confirmed events
 //! - `GET /supported` – List supported payment kinds (version/scheme/network)
+//! - `GET /supported/{network}` – List supported payment kinds on one network
+//! - `GET /settlements/{tx_hash}` – Journal entry of a settlement, if `SETTLEMENT_JOURNAL_PATH` is set
+//! - `GET /health`, `GET /ready` – Probe every network's RPC; `503` if a required one does not answer
+//! - `GET /live` – Liveness check, without RPC probes
+//! - `GET /chains` – Chain IDs, USDC metadata and RPC status of every network
+//! - `GET /metrics` – Prometheus metrics for requests, verifications and settlements
+//! - `GET /openapi.json` – OpenAPI 3.1 specification of `/verify`, `/settle`, `/supported` and `/health`
+//! - `GET /admin/failures` – Recent failed payments, with `ADMIN_API_TOKEN`
+//! - `GET /admin/inflight` – Verifications and settlements in progress, with `ADMIN_API_TOKEN`
 //!
 //! This server includes:
 //! - OpenTelemetry tracing via `TraceLayer`
-//! - CORS support for cross-origin clients
+//! - CORS support for cross-origin clients, configured by `CORS_ALLOWED_*`
 //! - Ethereum provider cache for per-network RPC routing
 //!
 //! Environment:
 //! - `.env` values loaded at startup
 //! - `HOST`, `PORT` control binding address
+//! - `SIGNER_TYPE` and private keys enable settlement; without them the server only verifies
+//! - `RATE_LIMIT_PER_SECOND` limits `/verify` and `/settle` per payer, see [`rate_limit`]
 //! - `OTEL_*` variables enable tracing to systems like Honeycomb
 
 use axum::Router;
-use axum::http::Method;
 use dotenvy::dotenv;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tower_http::cors;
+use std::time::Duration;
 
 use crate::facilitator_local::FacilitatorLocal;
 use crate::provider_cache::ProviderCache;
 use crate::sig_down::SigDown;
 use crate::telemetry::Telemetry;
 
+mod audit;
 mod chain;
+mod codec;
+mod cors;
+mod events;
 mod facilitator;
 mod facilitator_local;
+mod failures;
+mod fallback;
 mod from_env;
 mod handlers;
+mod health;
+mod idempotency;
+mod inflight;
+mod journal;
+mod kill_switch;
+mod merchant_intent;
+mod metrics;
 mod network;
+mod openapi;
 mod provider_cache;
+mod rate_limit;
+mod receiver_allowlist;
+mod resource_policy;
+mod settle_queue;
 mod sig_down;
 mod telemetry;
 mod timestamp;
+mod tokens;
 mod types;
+mod verify_cache;
 
 /// Initializes the x402 facilitator server.
 ///
@@ -62,6 +99,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with_version(env!("CARGO_PKG_VERSION"))
         .register();
 
+    // Without a signer, run as a verify-only facilitator. A signer that is set but invalid is an error.
+    let settlement_enabled = match from_env::SignerType::from_env_optional() {
+        Ok(signer_type) => signer_type.is_some(),
+        Err(e) => {
+            tracing::error!("Failed to read {}: {}", from_env::ENV_SIGNER_TYPE, e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = chain::evm::settings_from_env() {
+        tracing::error!("Failed to configure EVM payments: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = chain::native_usd_prices_from_env() {
+        tracing::error!("Failed to configure native currency prices: {}", e);
+        std::process::exit(1);
+    }
     let provider_cache = ProviderCache::from_env().await;
     // Abort if we can't initialise Ethereum providers early
     let provider_cache = match provider_cache {
@@ -71,18 +124,186 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             std::process::exit(1);
         }
     };
-    let facilitator = FacilitatorLocal::new(provider_cache);
+    let event_sink = match events::sink_from_env().await {
+        Ok(event_sink) => event_sink,
+        Err(e) => {
+            tracing::error!("Failed to create settlement event sink: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let merchant_intents = match merchant_intent::MerchantIntents::from_env() {
+        Ok(merchant_intents) => merchant_intents,
+        Err(e) => {
+            tracing::error!("Failed to load merchant intent signers: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let resource_policy = match resource_policy::ResourcePolicy::from_env() {
+        Ok(resource_policy) => resource_policy,
+        Err(e) => {
+            tracing::error!("Failed to load resource patterns: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let receiver_allowlist = match receiver_allowlist::ReceiverAllowlist::from_env() {
+        Ok(receiver_allowlist) => receiver_allowlist,
+        Err(e) => {
+            tracing::error!("Failed to load receiver allowlists: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let fallback = match fallback::Fallback::from_env() {
+        Ok(fallback) => fallback,
+        Err(e) => {
+            tracing::error!("Failed to configure fallback facilitators: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if !settlement_enabled {
+        tracing::warn!(
+            "{} not set, starting in verify-only mode: /settle is disabled",
+            from_env::ENV_SIGNER_TYPE
+        );
+    }
+    let facilitator = FacilitatorLocal::new(provider_cache)
+        .with_event_sink(event_sink)
+        .with_settlement(settlement_enabled)
+        .with_merchant_intents(merchant_intents)
+        .with_resource_policy(resource_policy)
+        .with_receiver_allowlist(receiver_allowlist)
+        .with_fallback(fallback)
+        .with_verify_cache(verify_cache::VerifyCache::from_env())
+        .with_single_flight_verify(
+            std::env::var(from_env::ENV_VERIFY_SINGLE_FLIGHT)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(true),
+        );
     let axum_state = Arc::new(facilitator);
 
+    match audit::AuditLog::from_env().await {
+        Ok(audit_log) => audit::install(audit_log),
+        Err(e) => {
+            tracing::error!("Failed to open admin audit log: {}", e);
+            std::process::exit(1);
+        }
+    }
+    if let Err(e) = journal::status_refresh_from_env() {
+        tracing::error!("Failed to configure the settlement journal: {}", e);
+        std::process::exit(1);
+    }
+    match journal::FileJournal::from_env().await {
+        Ok(Some(settlement_journal)) => {
+            journal::install(Arc::new(settlement_journal));
+            tokio::spawn(journal::recover(Arc::clone(&axum_state)));
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!("Failed to open settlement journal: {}", e);
+            std::process::exit(1);
+        }
+    }
+    match settle_queue::SettleQueue::from_env(journal::global()) {
+        Ok(Some(settle_queue)) => {
+            let settle_queue = Arc::new(settle_queue);
+            settle_queue::install(Arc::clone(&settle_queue));
+            settle_queue.spawn(Arc::clone(&axum_state));
+            tokio::spawn(async move { settle_queue.resume().await });
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!("Failed to configure the settlement queue: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    let sig_down = SigDown::try_new()?;
+
+    match chain::forwarding::PendingForwards::from_env().await {
+        Ok(pending_forwards) => {
+            chain::forwarding::install(Arc::new(pending_forwards));
+            tokio::spawn(chain::forwarding::retry(
+                Arc::clone(&axum_state),
+                sig_down.cancellation_token(),
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Failed to open pending forwards: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    match kill_switch::KillSwitch::from_env() {
+        Ok(Some(kill_switch)) => {
+            tokio::spawn(
+                kill_switch.watch(axum_state.maintenance_flag(), sig_down.cancellation_token()),
+            );
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!("Failed to configure kill switch: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    match chain::sweep::Sweeper::from_env() {
+        Ok(Some(sweeper)) => {
+            tokio::spawn(sweeper.watch(Arc::clone(&axum_state), sig_down.cancellation_token()));
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!("Failed to configure sweeping: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // Keep `/supported` answering from memory; refresh the cached value in the background.
+    let supported_refresh_interval = std::env::var(from_env::ENV_SUPPORTED_REFRESH_INTERVAL_SECS)
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(60);
+    {
+        let facilitator = Arc::clone(&axum_state);
+        let cancellation_token = sig_down.cancellation_token();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(supported_refresh_interval));
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        facilitator.refresh_supported().await;
+                    }
+                    _ = cancellation_token.cancelled() => break,
+                }
+            }
+        });
+    }
+
+    if let Err(e) = codec::authorization_payload_from_env() {
+        tracing::error!("Failed to configure request decoding: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = rate_limit::quota_state_from_env() {
+        tracing::error!("Failed to configure rate limiting: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = metrics::exemplars_from_env() {
+        tracing::error!("Failed to configure metrics: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = handlers::landing_networks_from_env() {
+        tracing::error!("Failed to configure the landing page: {}", e);
+        std::process::exit(1);
+    }
+    let cors = cors::layer_from_env().unwrap_or_else(|e| {
+        tracing::error!("Failed to configure CORS: {}", e);
+        std::process::exit(1);
+    });
     let http_endpoints = Router::new()
         .merge(handlers::routes().with_state(axum_state))
         .layer(telemetry.http_tracing())
-        .layer(
-            cors::CorsLayer::new()
-                .allow_origin(cors::Any)
-                .allow_methods([Method::GET, Method::POST])
-                .allow_headers(cors::Any),
-        );
+        .layer(cors);
 
     let host = std::env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
     let port = std::env::var("PORT")
@@ -100,12 +321,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             std::process::exit(1);
         });
 
-    let sig_down = SigDown::try_new()?;
+    let drained = tokio::spawn(inflight::drain(sig_down.cancellation_token()));
+
     let axum_cancellation_token = sig_down.cancellation_token();
     let axum_graceful_shutdown = async move { axum_cancellation_token.cancelled().await };
-    axum::serve(listener, http_endpoints)
-        .with_graceful_shutdown(axum_graceful_shutdown)
-        .await?;
+    // Connection info lets the rate limiter fall back to the source IP.
+    axum::serve(
+        listener,
+        http_endpoints.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(axum_graceful_shutdown)
+    .await?;
+    // Connections are closed, but settlements spawned by `wait=false` may still be broadcasting.
+    drained.await?;
 
     Ok(())
 }
@@ -0,0 +1,244 @@
+//! Merchant-signed payment intents.
+//!
+//! A compromised frontend could rewrite `payTo` in the payment requirements it hands to the buyer.
+//! To rule that out, a merchant can register a signing key for the origin its resources are served
+//! from: the facilitator then only verifies or settles payments for those resources if the requirements
+//! carry an EIP-712 [`MerchantIntent`] signature binding the receiver, splits, asset, amount, network
+//! and resource, made by the registered key. Keys are looked up by resource rather than by `payTo`,
+//! which is exactly what an attacker would rewrite. The check runs before any chain interaction.
+//!
+//! The signature is passed as a hex string in `extra.merchantSignature` of the payment requirements.
+//! It is computed over the EIP-712 domain `{ name: "x402 Merchant Intent", version: "1" }`.
+//!
+//! Environment variables used:
+//! - `MERCHANT_INTENT_SIGNERS` — JSON object mapping resource origins to the EVM address of the
+//!   merchant's signing key, e.g. `{"https://shop.example.com": "0xSigningKey"}`. Resources of other
+//!   origins are not checked.
+
+use alloy::primitives::{Address, Signature};
+use alloy::sol;
+use alloy::sol_types::{Eip712Domain, SolStruct, eip712_domain};
+use std::collections::HashMap;
+use url::Url;
+
+use crate::chain::FacilitatorLocalError;
+use crate::from_env::ENV_MERCHANT_INTENT_SIGNERS;
+use crate::types::PaymentRequirements;
+
+sol! {
+    /// A share of the payment, as signed in a [`MerchantIntent`].
+    #[derive(Debug)]
+    struct MerchantSplit {
+        string payTo;
+        uint256 amount;
+    }
+
+    /// Payment terms signed by the merchant.
+    #[derive(Debug)]
+    struct MerchantIntent {
+        string payTo;
+        MerchantSplit[] splits;
+        string asset;
+        uint256 maxAmountRequired;
+        string network;
+        string resource;
+    }
+}
+
+/// EIP-712 domain of [`MerchantIntent`] signatures.
+pub const MERCHANT_INTENT_DOMAIN: Eip712Domain = eip712_domain! {
+    name: "x402 Merchant Intent",
+    version: "1",
+};
+
+impl From<&PaymentRequirements> for MerchantIntent {
+    fn from(requirements: &PaymentRequirements) -> Self {
+        MerchantIntent {
+            payTo: requirements.pay_to.to_string(),
+            splits: requirements
+                .splits
+                .iter()
+                .flatten()
+                .map(|split| MerchantSplit {
+                    payTo: split.pay_to.to_string(),
+                    amount: split.amount.0,
+                })
+                .collect(),
+            asset: requirements.asset.to_string(),
+            maxAmountRequired: requirements.max_amount_required.0,
+            network: requirements.network.to_string(),
+            resource: requirements.resource.to_string(),
+        }
+    }
+}
+
+/// Registered merchant signing keys, keyed by resource origin, e.g. `https://shop.example.com`.
+#[derive(Debug, Clone, Default)]
+pub struct MerchantIntents {
+    signers: HashMap<String, Address>,
+}
+
+/// The origin of `resource`, as the keys of [`MerchantIntents`] are spelled.
+fn origin(resource: &Url) -> String {
+    resource.origin().ascii_serialization()
+}
+
+impl MerchantIntents {
+    /// Reads registered merchant keys from `MERCHANT_INTENT_SIGNERS`, or returns `None` if not set.
+    pub fn from_env() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let Ok(raw) = std::env::var(ENV_MERCHANT_INTENT_SIGNERS) else {
+            return Ok(None);
+        };
+        let entries: HashMap<String, Address> = serde_json::from_str(&raw)
+            .map_err(|e| format!("env {ENV_MERCHANT_INTENT_SIGNERS} is invalid: {e}"))?;
+        entries
+            .into_iter()
+            .map(|(resource, signer)| {
+                let resource: Url = resource.parse().map_err(|e| {
+                    format!("env {ENV_MERCHANT_INTENT_SIGNERS} is invalid: {resource}: {e}")
+                })?;
+                Ok((resource, signer))
+            })
+            .collect::<Result<Self, Box<dyn std::error::Error>>>()
+            .map(Some)
+    }
+
+    /// Checks the merchant signature on `requirements`, if its resource's origin has a registered key.
+    ///
+    /// Unsigned requirements for such a resource are rejected, whatever their `payTo`.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::InvalidMerchantIntent`] if the signature is missing, malformed,
+    /// or not made by the registered key over these exact requirements.
+    pub fn assert_signed(
+        &self,
+        requirements: &PaymentRequirements,
+    ) -> Result<(), FacilitatorLocalError> {
+        let Some(expected) = self.signers.get(&origin(&requirements.resource)) else {
+            return Ok(());
+        };
+        let invalid =
+            |reason: &str| FacilitatorLocalError::InvalidMerchantIntent(reason.to_string());
+        let signature = requirements
+            .extra
+            .as_ref()
+            .and_then(|extra| extra.get("merchantSignature"))
+            .and_then(|signature| signature.as_str())
+            .ok_or_else(|| invalid("missing merchant signature"))?;
+        let signature = alloy::hex::decode(signature)
+            .ok()
+            .and_then(|bytes| Signature::from_raw(&bytes).ok())
+            .ok_or_else(|| invalid("malformed merchant signature"))?;
+        let hash = MerchantIntent::from(requirements).eip712_signing_hash(&MERCHANT_INTENT_DOMAIN);
+        let signer = signature
+            .recover_address_from_prehash(&hash)
+            .map_err(|_| invalid("malformed merchant signature"))?;
+        if signer != *expected {
+            return Err(invalid("requirements are not signed by the merchant"));
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<(Url, Address)> for MerchantIntents {
+    fn from_iter<T: IntoIterator<Item = (Url, Address)>>(iter: T) -> Self {
+        Self {
+            signers: iter
+                .into_iter()
+                .map(|(resource, signer)| (origin(&resource), signer))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Network;
+    use crate::types::{EvmAddress, PaymentSplit, Scheme, TokenAmount};
+    use alloy::primitives::{U256, address};
+    use alloy::signers::SignerSync;
+    use alloy::signers::local::PrivateKeySigner;
+
+    fn requirements(pay_to: Address) -> PaymentRequirements {
+        PaymentRequirements {
+            scheme: Scheme::Exact,
+            network: Network::BaseSepolia,
+            max_amount_required: TokenAmount(U256::from(10_000u64)),
+            resource: "https://example.com/paid".parse().unwrap(),
+            description: String::new(),
+            mime_type: "application/json".to_string(),
+            output_schema: None,
+            pay_to: EvmAddress(pay_to).into(),
+            max_timeout_seconds: 60,
+            asset: EvmAddress(address!("0x036CbD53842c5426634e7929541eC2318f3dCF7e")).into(),
+            extra: None,
+            splits: None,
+            authorization_kind: None,
+        }
+    }
+
+    fn sign(signer: &PrivateKeySigner, requirements: &mut PaymentRequirements) {
+        let hash =
+            MerchantIntent::from(&*requirements).eip712_signing_hash(&MERCHANT_INTENT_DOMAIN);
+        let signature = signer.sign_hash_sync(&hash).unwrap();
+        requirements.extra = Some(serde_json::json!({
+            "merchantSignature": alloy::hex::encode_prefixed(signature.as_bytes()),
+        }));
+    }
+
+    #[test]
+    fn test_merchant_intent_binds_requirements() {
+        let merchant = PrivateKeySigner::random();
+        let pay_to = address!("0x0000000000000000000000000000000000000402");
+        let intents: MerchantIntents =
+            [("https://example.com/".parse().unwrap(), merchant.address())]
+                .into_iter()
+                .collect();
+
+        let mut signed = requirements(pay_to);
+        assert!(intents.assert_signed(&signed).is_err());
+        sign(&merchant, &mut signed);
+        assert!(intents.assert_signed(&signed).is_ok());
+
+        let mut tampered = signed.clone();
+        tampered.max_amount_required = TokenAmount(U256::from(1u64));
+        assert!(intents.assert_signed(&tampered).is_err());
+
+        let mut impostor = requirements(pay_to);
+        sign(&PrivateKeySigner::random(), &mut impostor);
+        assert!(intents.assert_signed(&impostor).is_err());
+
+        let mut split = signed.clone();
+        split.splits = Some(vec![PaymentSplit {
+            pay_to: EvmAddress(address!("0x0000000000000000000000000000000000000bad")).into(),
+            amount: TokenAmount(U256::from(1u64)),
+        }]);
+        assert!(intents.assert_signed(&split).is_err());
+
+        let mut unregistered = requirements(pay_to);
+        unregistered.resource = "https://other.example.com/paid".parse().unwrap();
+        assert!(intents.assert_signed(&unregistered).is_ok());
+    }
+
+    #[test]
+    fn test_rewritten_pay_to_is_rejected() {
+        let merchant = PrivateKeySigner::random();
+        let pay_to = address!("0x0000000000000000000000000000000000000402");
+        let intents: MerchantIntents =
+            [("https://example.com".parse().unwrap(), merchant.address())]
+                .into_iter()
+                .collect();
+        let mut signed = requirements(pay_to);
+        sign(&merchant, &mut signed);
+
+        let attacker = address!("0x0000000000000000000000000000000000000bad");
+        let mut rewritten = signed.clone();
+        rewritten.pay_to = EvmAddress(attacker).into();
+        assert!(intents.assert_signed(&rewritten).is_err());
+        let mut unsigned = requirements(attacker);
+        assert!(intents.assert_signed(&unsigned).is_err());
+        sign(&PrivateKeySigner::random(), &mut unsigned);
+        assert!(intents.assert_signed(&unsigned).is_err());
+    }
+}
@@ -0,0 +1,478 @@
+//! Prometheus metrics for HTTP requests, verifications and settlements.
+//!
+//! Served in the Prometheus text format by `GET /metrics`:
+//! - `x402_http_requests_total{endpoint, status}` — requests answered per route and status code,
+//! - `x402_operation_duration_seconds{operation, network}` — histogram of `verify` and `settle` latency,
+//! - `x402_operation_failures_total{operation, network, reason}` — verifications and settlements that did
+//!   not succeed, by [`FacilitatorErrorReason`],
+//! - `x402_verify_cache_hits_total` — verifications answered from the [`VerifyCache`](crate::verify_cache::VerifyCache).
+//!
+//! Verifications and settlements are observed around the [`Facilitator`](crate::facilitator::Facilitator)
+//! calls of [`FacilitatorLocal`](crate::facilitator_local::FacilitatorLocal), so failures returned early,
+//! before reaching the chain, are counted too. Free-form reasons are counted as `other`, and errors that
+//! are not a verdict on the payment get their own reason, e.g. `rpc_timeout`, to keep label values bounded.
+//!
+//! A scraper asking for `application/openmetrics-text` gets the OpenMetrics format instead. With
+//! `METRICS_EXEMPLARS` enabled, each latency bucket then carries an exemplar: the trace ID of the last
+//! verification or settlement that fell into it, so a latency spike links to the trace of a slow request.
+//! Only requests traced by OpenTelemetry have a trace ID, see [`crate::telemetry`].
+
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use opentelemetry::trace::TraceContextExt;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::chain::FacilitatorLocalError;
+use crate::from_env::ENV_METRICS_EXEMPLARS;
+use crate::network::Network;
+use crate::types::{FacilitatorErrorReason, SettleResponse, VerifyResponse};
+
+/// Upper bounds, in seconds, of the latency histogram buckets.
+const BUCKETS: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+];
+
+/// Whether latency buckets carry exemplars, set by [`exemplars_from_env`].
+static EXEMPLARS: AtomicBool = AtomicBool::new(false);
+
+/// Reads `METRICS_EXEMPLARS`, attaching trace IDs to latency buckets if it is `true`.
+///
+/// # Errors
+/// Returns an error if it is set to something other than `true` or `false`.
+pub fn exemplars_from_env() -> Result<(), String> {
+    let enabled = match std::env::var(ENV_METRICS_EXEMPLARS) {
+        Ok(raw) => raw
+            .trim()
+            .parse::<bool>()
+            .map_err(|e| format!("env {ENV_METRICS_EXEMPLARS} is invalid: {e}"))?,
+        Err(_) => false,
+    };
+    EXEMPLARS.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+/// An observation linked to the trace it was made in.
+#[derive(Debug, Clone, PartialEq)]
+struct Exemplar {
+    trace_id: String,
+    seconds: f64,
+    /// When it was observed, in seconds since the Unix epoch.
+    timestamp: f64,
+}
+
+/// A Prometheus histogram with the fixed [`BUCKETS`].
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Non-cumulative count per bucket; the last one counts observations above every bound.
+    buckets: [AtomicU64; BUCKETS.len() + 1],
+    /// Last traced observation per bucket.
+    exemplars: [Mutex<Option<Exemplar>>; BUCKETS.len() + 1],
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, duration: Duration, trace_id: Option<String>) {
+        let seconds = duration.as_secs_f64();
+        let bucket = BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(BUCKETS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+        if let Some(trace_id) = trace_id {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64();
+            *self.exemplars[bucket].lock().unwrap() = Some(Exemplar {
+                trace_id,
+                seconds,
+                timestamp,
+            });
+        }
+    }
+
+    /// The OpenMetrics exemplar suffix of `bucket`, empty if it has none.
+    fn exemplar(&self, bucket: usize) -> String {
+        match &*self.exemplars[bucket].lock().unwrap() {
+            Some(exemplar) => format!(
+                " # {{trace_id=\"{}\"}} {} {}",
+                exemplar.trace_id, exemplar.seconds, exemplar.timestamp
+            ),
+            None => String::new(),
+        }
+    }
+}
+
+/// The OpenTelemetry trace ID of the current span, if it is traced.
+fn current_trace_id() -> Option<String> {
+    let context = tracing::Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    span_context
+        .is_valid()
+        .then(|| span_context.trace_id().to_string())
+}
+
+/// Process-wide metric values.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    http_requests: DashMap<(String, u16), AtomicU64>,
+    durations: DashMap<(&'static str, Network), Histogram>,
+    failures: DashMap<(&'static str, Network, &'static str), AtomicU64>,
+    verify_cache_hits: AtomicU64,
+}
+
+static METRICS: Lazy<Metrics> = Lazy::new(Metrics::default);
+
+impl Metrics {
+    /// The metrics shared by all handlers.
+    pub fn global() -> &'static Metrics {
+        &METRICS
+    }
+
+    /// Records a finished verification.
+    pub fn observe_verify(
+        &self,
+        network: Network,
+        elapsed: Duration,
+        result: &Result<VerifyResponse, FacilitatorLocalError>,
+    ) {
+        let reason = match result {
+            Ok(VerifyResponse::Valid { .. }) => None,
+            Ok(VerifyResponse::Invalid { reason, .. }) => Some(reason_label(reason)),
+            Err(error) => Some(error_label(error)),
+        };
+        self.observe("verify", network, elapsed, reason);
+    }
+
+    /// Records a finished settlement.
+    pub fn observe_settle(
+        &self,
+        network: Network,
+        elapsed: Duration,
+        result: &Result<SettleResponse, FacilitatorLocalError>,
+    ) {
+        let reason = match result {
+            Ok(response) if response.success => None,
+            Ok(response) => Some(
+                response
+                    .error_reason
+                    .as_ref()
+                    .map_or("unexpected_settle_error", reason_label),
+            ),
+            Err(error) => Some(error_label(error)),
+        };
+        self.observe("settle", network, elapsed, reason);
+    }
+
+    fn observe(
+        &self,
+        operation: &'static str,
+        network: Network,
+        elapsed: Duration,
+        failure_reason: Option<&'static str>,
+    ) {
+        let trace_id = if EXEMPLARS.load(Ordering::Relaxed) {
+            current_trace_id()
+        } else {
+            None
+        };
+        self.observe_traced(operation, network, elapsed, failure_reason, trace_id);
+    }
+
+    fn observe_traced(
+        &self,
+        operation: &'static str,
+        network: Network,
+        elapsed: Duration,
+        failure_reason: Option<&'static str>,
+        trace_id: Option<String>,
+    ) {
+        self.durations
+            .entry((operation, network))
+            .or_default()
+            .observe(elapsed, trace_id);
+        if let Some(reason) = failure_reason {
+            self.failures
+                .entry((operation, network, reason))
+                .or_default()
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts a verification answered from the [`VerifyCache`](crate::verify_cache::VerifyCache).
+    pub fn count_verify_cache_hit(&self) {
+        self.verify_cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn count_request(&self, endpoint: &str, status: u16) {
+        self.http_requests
+            .entry((endpoint.to_string(), status))
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        self.render_as(false)
+    }
+
+    /// Renders all metrics in the OpenMetrics text format, with the latency exemplars.
+    pub fn render_openmetrics(&self) -> String {
+        let mut out = self.render_as(true);
+        out.push_str("# EOF\n");
+        out
+    }
+
+    fn render_as(&self, openmetrics: bool) -> String {
+        let mut out = String::new();
+        let family = |out: &mut String, name: &str, kind: &str, help: &str| {
+            // OpenMetrics names a counter after its samples without their `_total` suffix.
+            let name = if openmetrics && kind == "counter" {
+                name.trim_end_matches("_total")
+            } else {
+                name
+            };
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+        };
+        family(
+            &mut out,
+            "x402_http_requests_total",
+            "counter",
+            "HTTP requests answered, by route and status code.",
+        );
+        for entry in self.http_requests.iter() {
+            let (endpoint, status) = entry.key();
+            let _ = writeln!(
+                out,
+                "x402_http_requests_total{{endpoint=\"{}\",status=\"{status}\"}} {}",
+                escape(endpoint),
+                entry.value().load(Ordering::Relaxed)
+            );
+        }
+
+        family(
+            &mut out,
+            "x402_operation_duration_seconds",
+            "histogram",
+            "Duration of verifications and settlements.",
+        );
+        for entry in self.durations.iter() {
+            let (operation, network) = entry.key();
+            let labels = format!("operation=\"{operation}\",network=\"{network}\"");
+            let histogram = entry.value();
+            let exemplar = |bucket: usize| {
+                if openmetrics {
+                    histogram.exemplar(bucket)
+                } else {
+                    String::new()
+                }
+            };
+            let mut cumulative = 0;
+            for (bucket, (bound, count)) in BUCKETS.iter().zip(&histogram.buckets).enumerate() {
+                cumulative += count.load(Ordering::Relaxed);
+                let _ = writeln!(
+                    out,
+                    "x402_operation_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {cumulative}{}",
+                    exemplar(bucket)
+                );
+            }
+            cumulative += histogram.buckets[BUCKETS.len()].load(Ordering::Relaxed);
+            let _ = writeln!(
+                out,
+                "x402_operation_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {cumulative}{}",
+                exemplar(BUCKETS.len())
+            );
+            let sum = histogram.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+            let _ = writeln!(out, "x402_operation_duration_seconds_sum{{{labels}}} {sum}");
+            let _ = writeln!(
+                out,
+                "x402_operation_duration_seconds_count{{{labels}}} {cumulative}"
+            );
+        }
+
+        family(
+            &mut out,
+            "x402_operation_failures_total",
+            "counter",
+            "Verifications and settlements that did not succeed, by reason.",
+        );
+        for entry in self.failures.iter() {
+            let (operation, network, reason) = entry.key();
+            let _ = writeln!(
+                out,
+                "x402_operation_failures_total{{operation=\"{operation}\",network=\"{network}\",reason=\"{reason}\"}} {}",
+                entry.value().load(Ordering::Relaxed)
+            );
+        }
+        family(
+            &mut out,
+            "x402_verify_cache_hits_total",
+            "counter",
+            "Verifications answered from the verify cache.",
+        );
+        let _ = writeln!(
+            out,
+            "x402_verify_cache_hits_total {}",
+            self.verify_cache_hits.load(Ordering::Relaxed)
+        );
+        out
+    }
+}
+
+/// Middleware counting answered requests by matched route, so path parameters do not multiply label values.
+pub async fn count_requests(request: Request, next: Next) -> Response {
+    let endpoint = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
+    let response = next.run(request).await;
+    if let Some(endpoint) = endpoint {
+        Metrics::global().count_request(&endpoint, response.status().as_u16());
+    }
+    response
+}
+
+/// Label value for a [`FacilitatorErrorReason`].
+fn reason_label(reason: &FacilitatorErrorReason) -> &'static str {
+    match reason {
+        FacilitatorErrorReason::InsufficientFunds => "insufficient_funds",
+        FacilitatorErrorReason::InvalidScheme => "invalid_scheme",
+        FacilitatorErrorReason::InvalidNetwork => "invalid_network",
+        FacilitatorErrorReason::UnexpectedSettleError => "unexpected_settle_error",
+        FacilitatorErrorReason::ExcessValue => "excess_value",
+        FacilitatorErrorReason::ValueOutOfRange => "value_out_of_range",
+        FacilitatorErrorReason::BelowMinimum => "below_minimum",
+        FacilitatorErrorReason::AboveMaximum => "above_maximum",
+        FacilitatorErrorReason::NonceReused => "nonce_reused",
+        FacilitatorErrorReason::ReceiverNotAllowed => "receiver_not_allowed",
+        FacilitatorErrorReason::FreeForm(_) => "other",
+    }
+}
+
+/// Label value for a failed call, matching the [`FacilitatorErrorReason`] answered for it.
+fn error_label(error: &FacilitatorLocalError) -> &'static str {
+    match error {
+        FacilitatorLocalError::SchemeMismatch(..)
+        | FacilitatorLocalError::UnsupportedScheme(..)
+        | FacilitatorLocalError::UnsupportedAuthorizationKind(..)
+        | FacilitatorLocalError::ReceiverMismatch(..)
+        | FacilitatorLocalError::InvalidSignature(..)
+        | FacilitatorLocalError::InvalidTiming(..)
+        | FacilitatorLocalError::InvalidSplits(..)
+        | FacilitatorLocalError::AuthorizationUsed(..)
+        | FacilitatorLocalError::InsufficientValue(..) => "invalid_scheme",
+        FacilitatorLocalError::NetworkMismatch(..)
+        | FacilitatorLocalError::UnsupportedNetwork(..) => "invalid_network",
+        FacilitatorLocalError::InsufficientFunds(..) => "insufficient_funds",
+        FacilitatorLocalError::ExcessValue(..) => "excess_value",
+        FacilitatorLocalError::UnknownAuthorization(..) => "unknown_authorization",
+        FacilitatorLocalError::ValueOutOfRange(..) => "value_out_of_range",
+        FacilitatorLocalError::BelowMinimum(..) => "below_minimum",
+        FacilitatorLocalError::AboveMaximum(..) => "above_maximum",
+        FacilitatorLocalError::NonceReused(..) => "nonce_reused",
+        FacilitatorLocalError::ReceiverNotAllowed(..) => "receiver_not_allowed",
+        FacilitatorLocalError::UnsupportedAsset(..)
+        | FacilitatorLocalError::UnexpectedNonce(..)
+        | FacilitatorLocalError::SuspiciousNonce(..)
+        | FacilitatorLocalError::ImplementationChanged(..)
+        | FacilitatorLocalError::DecodingError(..)
+        | FacilitatorLocalError::InvalidMerchantIntent(..)
+        | FacilitatorLocalError::ResourceNotAllowed(..) => "other",
+        FacilitatorLocalError::ContractCall(..)
+        | FacilitatorLocalError::InvalidAddress(..)
+        | FacilitatorLocalError::ClockError(..) => "invalid_request",
+        FacilitatorLocalError::RpcTimeout(..) => "rpc_timeout",
+        FacilitatorLocalError::Reverted(..) => "reverted",
+        FacilitatorLocalError::Reorged(..) => "reorged",
+        FacilitatorLocalError::SettlementDisabled => "settlement_disabled",
+        FacilitatorLocalError::Maintenance => "maintenance",
+    }
+}
+
+/// Escapes a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counts_failures_and_cumulative_buckets() {
+        let metrics = Metrics::default();
+        let verdict = Err(FacilitatorLocalError::InsufficientFunds(
+            crate::types::MixedAddress::Offchain("payer".to_string()),
+        ));
+        metrics.observe_verify(Network::Base, Duration::from_millis(20), &verdict);
+        metrics.observe_verify(Network::Base, Duration::from_secs(200), &verdict);
+        metrics.count_request("/verify", 200);
+
+        let rendered = metrics.render();
+        assert!(rendered.contains(
+            "x402_operation_failures_total{operation=\"verify\",network=\"base\",reason=\"insufficient_funds\"} 2"
+        ));
+        assert!(rendered.contains(
+            "x402_operation_duration_seconds_bucket{operation=\"verify\",network=\"base\",le=\"0.025\"} 1"
+        ));
+        assert!(rendered.contains(
+            "x402_operation_duration_seconds_bucket{operation=\"verify\",network=\"base\",le=\"+Inf\"} 2"
+        ));
+        assert!(
+            rendered.contains("x402_http_requests_total{endpoint=\"/verify\",status=\"200\"} 1")
+        );
+    }
+
+    #[test]
+    fn test_openmetrics_buckets_carry_the_last_trace_id() {
+        let metrics = Metrics::default();
+        let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+        let traced = |elapsed, trace_id: Option<&str>| {
+            metrics.observe_traced(
+                "settle",
+                Network::Base,
+                elapsed,
+                None,
+                trace_id.map(str::to_string),
+            )
+        };
+        traced(Duration::from_millis(300), Some("older"));
+        traced(Duration::from_millis(400), Some(trace_id));
+        traced(Duration::from_millis(450), None);
+        traced(Duration::from_millis(20), None);
+
+        let rendered = metrics.render_openmetrics();
+        let bucket = rendered
+            .lines()
+            .find(|line| line.contains("le=\"0.5\""))
+            .unwrap();
+        assert!(bucket.starts_with(&format!(
+            "x402_operation_duration_seconds_bucket{{operation=\"settle\",network=\"base\",le=\"0.5\"}} 4 # {{trace_id=\"{trace_id}\"}} 0.4 "
+        )));
+        // Buckets without a traced observation have no exemplar.
+        assert!(rendered.contains("le=\"0.025\"} 1\n"));
+        assert!(rendered.contains("# TYPE x402_http_requests counter\n"));
+        assert!(rendered.ends_with("# EOF\n"));
+        // The Prometheus text format has no exemplars.
+        assert!(!metrics.render().contains(trace_id));
+        assert!(
+            metrics
+                .render()
+                .contains("# TYPE x402_http_requests_total counter\n")
+        );
+    }
+}
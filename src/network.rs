@@ -57,6 +57,12 @@ pub enum Network {
     /// Sei testnet (chain ID 1328).
     #[serde(rename = "sei-testnet")]
     SeiTestnet,
+    /// Arbitrum One mainnet (chain ID 42161).
+    #[serde(rename = "arbitrum")]
+    Arbitrum,
+    /// Optimism mainnet (chain ID 10).
+    #[serde(rename = "optimism")]
+    Optimism,
 }
 
 impl Display for Network {
@@ -75,6 +81,8 @@ impl Display for Network {
             Network::Celo => write!(f, "celo"),
             Network::Sei => write!(f, "sei"),
             Network::SeiTestnet => write!(f, "sei-testnet"),
+            Network::Arbitrum => write!(f, "arbitrum"),
+            Network::Optimism => write!(f, "optimism"),
         }
     }
 }
@@ -101,6 +109,8 @@ impl From<Network> for NetworkFamily {
             Network::Celo => NetworkFamily::Evm,
             Network::Sei => NetworkFamily::Evm,
             Network::SeiTestnet => NetworkFamily::Evm,
+            Network::Arbitrum => NetworkFamily::Evm,
+            Network::Optimism => NetworkFamily::Evm,
         }
     }
 }
@@ -122,6 +132,8 @@ impl Network {
             Network::Celo,
             Network::Sei,
             Network::SeiTestnet,
+            Network::Arbitrum,
+            Network::Optimism,
         ]
     }
 }
@@ -317,6 +329,36 @@ static USDC_SEI_TESTNET: Lazy<USDCDeployment> = Lazy::new(|| {
     })
 });
 
+/// Lazily initialized known USDC deployment on Arbitrum One as [`USDCDeployment`].
+static USDC_ARBITRUM: Lazy<USDCDeployment> = Lazy::new(|| {
+    USDCDeployment(TokenDeployment {
+        asset: TokenAsset {
+            address: address!("0xaf88d065e77c8cC2239327C5EDb3A432268e5831").into(),
+            network: Network::Arbitrum,
+        },
+        decimals: 6,
+        eip712: Some(TokenDeploymentEip712 {
+            name: "USD Coin".into(),
+            version: "2".into(),
+        }),
+    })
+});
+
+/// Lazily initialized known USDC deployment on Optimism mainnet as [`USDCDeployment`].
+static USDC_OPTIMISM: Lazy<USDCDeployment> = Lazy::new(|| {
+    USDCDeployment(TokenDeployment {
+        asset: TokenAsset {
+            address: address!("0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85").into(),
+            network: Network::Optimism,
+        },
+        decimals: 6,
+        eip712: Some(TokenDeploymentEip712 {
+            name: "USD Coin".into(),
+            version: "2".into(),
+        }),
+    })
+});
+
 /// A known USDC deployment as a wrapper around [`TokenDeployment`].
 #[derive(Clone, Debug)]
 pub struct USDCDeployment(pub TokenDeployment);
@@ -366,6 +408,8 @@ impl USDCDeployment {
             Network::Celo => &USDC_CELO,
             Network::Sei => &USDC_SEI,
             Network::SeiTestnet => &USDC_SEI_TESTNET,
+            Network::Arbitrum => &USDC_ARBITRUM,
+            Network::Optimism => &USDC_OPTIMISM,
         }
     }
 }
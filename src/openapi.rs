@@ -0,0 +1,570 @@
+//! OpenAPI 3.1 description of the facilitator API, served at `GET /openapi.json`.
+//!
+//! Covers `/verify`, `/settle`, `/supported` and `/health` with the schemas of their bodies. The schemas
+//! are written out here: the crate has no dependency deriving JSON schemas from the serde types, such as
+//! `schemars`, so they can drift from the types. The tests limit that: every schema is checked against
+//! samples of its type built field by field, whose serialized properties must be exactly the documented
+//! ones. A field added to a type breaks the test until its sample sets it, and it then fails until the
+//! field is documented. A property serialized only in cases the samples miss can still go unnoticed.
+//! Enumerations such as networks and schemes are generated from [`Network::variants`] and
+//! [`Scheme::variants`].
+
+use serde_json::{Value, json};
+
+use crate::network::Network;
+use crate::types::Scheme;
+
+/// The OpenAPI document.
+pub fn spec() -> Value {
+    let networks: Vec<Value> = Network::variants()
+        .iter()
+        .map(|network| json!(network))
+        .collect();
+    let schemes: Vec<Value> = Scheme::variants()
+        .iter()
+        .map(|scheme| json!(scheme))
+        .collect();
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "x402 facilitator",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Verifies and settles x402 payments on-chain.",
+        },
+        "paths": {
+            "/verify": {
+                "post": {
+                    "summary": "Verify a payment payload against payment requirements",
+                    "requestBody": json_body("VerifyRequest"),
+                    "responses": {
+                        "200": json_response("Verification result, valid or not", "VerifyResponse"),
+                        "400": json_response("Malformed request, naming the field that failed to decode", "ErrorResponse"),
+                        "406": json_response("STRICT_ACCEPT is set and no accepted media type is supported", "NotAcceptableResponse"),
+                        "413": json_response("Body larger than MAX_BODY_BYTES", "ErrorResponse"),
+                        "415": json_response("Body is neither JSON nor an enabled binary encoding", "ErrorResponse"),
+                        "429": rate_limited(),
+                        "500": json_response("Unexpected error", "ErrorResponse"),
+                        "502": json_response("The network's RPC failed", "ErrorResponse"),
+                        "504": json_response("The network's RPC timed out", "ErrorResponse"),
+                    },
+                },
+            },
+            "/settle": {
+                "post": {
+                    "summary": "Settle a verified payment on-chain",
+                    "parameters": [{
+                        "name": "wait",
+                        "in": "query",
+                        "description": "false to answer 202 once the transaction is broadcast (EVM only)",
+                        "schema": { "type": "boolean", "default": true },
+                    }, {
+                        "name": "queue",
+                        "in": "query",
+                        "description": "true to queue the settlement and answer 202 with its lifecycle at once, polled with GET /payments/{payment_id}",
+                        "schema": { "type": "boolean", "default": false },
+                    }, {
+                        "name": "Idempotency-Key",
+                        "in": "header",
+                        "description": "Retries with the same key are answered the stored response instead of settling again",
+                        "schema": { "type": "string" },
+                    }],
+                    "requestBody": json_body("SettleRequest"),
+                    "responses": {
+                        "200": json_response("Settlement result", "SettleResponse"),
+                        "202": json_response("Transaction broadcast, with status submitted; or, with queue=true, the queued payment's lifecycle", "SettleResponse"),
+                        "400": json_response("Malformed request, naming the field that failed to decode", "ErrorResponse"),
+                        "406": json_response("STRICT_ACCEPT is set and no accepted media type is supported", "NotAcceptableResponse"),
+                        "409": json_response("A settlement with this Idempotency-Key is in progress, or the settlement was reorged out", "ErrorResponse"),
+                        "413": json_response("Body larger than MAX_BODY_BYTES", "ErrorResponse"),
+                        "415": json_response("Body is neither JSON nor an enabled binary encoding", "ErrorResponse"),
+                        "422": json_response("The Idempotency-Key was used for another payment", "ErrorResponse"),
+                        "429": rate_limited(),
+                        "501": json_response("Settlement disabled, the facilitator is verify-only", "ErrorResponse"),
+                        "502": json_response("The network's RPC failed", "ErrorResponse"),
+                        "503": json_response("Maintenance mode, or shutting down", "ErrorResponse"),
+                        "504": json_response("The network's RPC timed out", "ErrorResponse"),
+                    },
+                },
+            },
+            "/supported": {
+                "get": {
+                    "summary": "List supported payment kinds",
+                    "responses": {
+                        "200": json_response("Supported payment kinds", "SupportedPaymentKindsResponse"),
+                    },
+                },
+            },
+            "/health": {
+                "get": {
+                    "summary": "RPC connectivity of every configured network",
+                    "responses": {
+                        "200": json_response("Every required network answers", "HealthReport"),
+                        "503": json_response("Some required network does not answer", "HealthReport"),
+                    },
+                },
+            },
+        },
+        "components": {
+            "schemas": {
+                "Network": { "type": "string", "enum": networks },
+                "Scheme": { "type": "string", "enum": schemes },
+                "Address": {
+                    "type": "string",
+                    "description": "0x-prefixed EVM address or base58 Solana address",
+                },
+                "TokenAmount": {
+                    "type": "string",
+                    "pattern": "^[0-9]+$",
+                    "description": "Amount in the token's smallest unit, as a decimal string",
+                },
+                "VerifyRequest": {
+                    "type": "object",
+                    "required": ["x402Version", "paymentPayload", "paymentRequirements"],
+                    "properties": {
+                        "x402Version": { "type": "integer", "const": 1 },
+                        "paymentPayload": schema_ref("PaymentPayload"),
+                        "paymentRequirements": schema_ref("PaymentRequirements"),
+                    },
+                },
+                "SettleRequest": schema_ref("VerifyRequest"),
+                "PaymentPayload": {
+                    "type": "object",
+                    "required": ["x402Version", "scheme", "network", "payload"],
+                    "properties": {
+                        "x402Version": { "type": "integer", "const": 1 },
+                        "scheme": schema_ref("Scheme"),
+                        "network": schema_ref("Network"),
+                        "payload": {
+                            "oneOf": [schema_ref("ExactEvmPayload"), schema_ref("ExactSolanaPayload")],
+                        },
+                        "extensions": { "type": "object" },
+                    },
+                },
+                "ExactEvmPayload": {
+                    "type": "object",
+                    "required": ["signature", "authorization"],
+                    "properties": {
+                        "signature": { "type": "string", "description": "0x-prefixed hex signature" },
+                        "authorization": schema_ref("ExactEvmPayloadAuthorization"),
+                    },
+                },
+                "ExactEvmPayloadAuthorization": {
+                    "type": "object",
+                    "required": ["from", "to", "value", "validAfter", "validBefore", "nonce"],
+                    "properties": {
+                        "from": schema_ref("Address"),
+                        "to": schema_ref("Address"),
+                        "value": schema_ref("TokenAmount"),
+                        "validAfter": { "type": "string", "description": "Unix timestamp in seconds" },
+                        "validBefore": { "type": "string", "description": "Unix timestamp in seconds" },
+                        "nonce": { "type": "string", "description": "0x-prefixed 32-byte hex nonce" },
+                    },
+                },
+                "ExactSolanaPayload": {
+                    "type": "object",
+                    "required": ["transaction"],
+                    "properties": {
+                        "transaction": { "type": "string", "description": "Base64-encoded partially signed transaction" },
+                    },
+                },
+                "PaymentRequirements": {
+                    "type": "object",
+                    "required": [
+                        "scheme", "network", "maxAmountRequired", "resource", "description",
+                        "mimeType", "payTo", "maxTimeoutSeconds", "asset",
+                    ],
+                    "properties": {
+                        "scheme": schema_ref("Scheme"),
+                        "network": schema_ref("Network"),
+                        "maxAmountRequired": schema_ref("TokenAmount"),
+                        "resource": { "type": "string", "format": "uri" },
+                        "description": { "type": "string" },
+                        "mimeType": { "type": "string" },
+                        "outputSchema": {},
+                        "payTo": schema_ref("Address"),
+                        "maxTimeoutSeconds": { "type": "integer", "minimum": 0 },
+                        "asset": schema_ref("Address"),
+                        "extra": {},
+                        "splits": { "type": "array", "items": schema_ref("PaymentSplit") },
+                        "authorizationKind": { "type": "string", "enum": ["transferWithAuthorization", "receiveWithAuthorization"] },
+                    },
+                },
+                "PaymentSplit": {
+                    "type": "object",
+                    "required": ["payTo", "amount"],
+                    "properties": {
+                        "payTo": schema_ref("Address"),
+                        "amount": schema_ref("TokenAmount"),
+                    },
+                },
+                "VerifyResponse": {
+                    "type": "object",
+                    "required": ["isValid"],
+                    "properties": {
+                        "isValid": { "type": "boolean" },
+                        "invalidReason": {
+                            "type": "string",
+                            "description": "Why the payment is invalid, e.g. insufficient_funds; present when isValid is false",
+                        },
+                        "payer": schema_ref("Address"),
+                        "paymentId": { "type": "string" },
+                        "extensions": { "type": "object" },
+                    },
+                },
+                "SettleResponse": {
+                    "type": "object",
+                    "required": ["success", "payer", "network"],
+                    "properties": {
+                        "success": { "type": "boolean" },
+                        "errorReason": { "type": "string" },
+                        "payer": schema_ref("Address"),
+                        "transaction": { "type": "string", "description": "Transaction hash" },
+                        "blockNumber": { "type": "integer" },
+                        "status": { "type": "string", "enum": ["submitted", "confirmed"] },
+                        "network": schema_ref("Network"),
+                        "paymentId": { "type": "string" },
+                        "cost": {
+                            "type": "object",
+                            "properties": {
+                                "gasUsed": { "type": "integer" },
+                                "effectiveGasPrice": schema_ref("TokenAmount"),
+                                "totalCost": schema_ref("TokenAmount"),
+                                "totalCostUsd": { "type": "number" },
+                                "facilitatorFee": schema_ref("TokenAmount"),
+                            },
+                        },
+                        "extensions": { "type": "object" },
+                    },
+                },
+                "ErrorResponse": {
+                    "type": "object",
+                    "required": ["error"],
+                    "properties": {
+                        "error": { "type": "string" },
+                    },
+                },
+                "NotAcceptableResponse": {
+                    "type": "object",
+                    "required": ["error", "supportedMediaTypes"],
+                    "properties": {
+                        "error": { "type": "string" },
+                        "supportedMediaTypes": { "type": "array", "items": { "type": "string" } },
+                    },
+                },
+                "SupportedPaymentKindsResponse": {
+                    "type": "object",
+                    "required": ["kinds"],
+                    "properties": {
+                        "kinds": { "type": "array", "items": schema_ref("SupportedPaymentKind") },
+                    },
+                },
+                "SupportedPaymentKind": {
+                    "type": "object",
+                    "required": ["x402Version", "scheme", "network"],
+                    "properties": {
+                        "x402Version": { "type": "integer" },
+                        "scheme": schema_ref("Scheme"),
+                        "network": { "type": "string" },
+                        "extra": {
+                            "type": "object",
+                            "properties": { "feePayer": schema_ref("Address") },
+                        },
+                        "assets": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "address": schema_ref("Address"),
+                                    "decimals": { "type": "integer" },
+                                },
+                            },
+                        },
+                        "allowedAssets": { "type": "array", "items": schema_ref("Address") },
+                        "authorizationKinds": {
+                            "type": "array",
+                            "items": { "type": "string", "enum": ["transferWithAuthorization", "receiveWithAuthorization"] },
+                        },
+                        "amountBounds": { "type": "array", "items": schema_ref("AssetAmountBounds") },
+                    },
+                },
+                "AssetAmountBounds": {
+                    "type": "object",
+                    "required": ["asset"],
+                    "properties": {
+                        "asset": schema_ref("Address"),
+                        "minAmount": schema_ref("TokenAmount"),
+                        "maxAmount": schema_ref("TokenAmount"),
+                    },
+                },
+                "HealthReport": {
+                    "type": "object",
+                    "required": ["networks"],
+                    "properties": {
+                        "networks": {
+                            "type": "object",
+                            "additionalProperties": schema_ref("NetworkHealth"),
+                        },
+                    },
+                },
+                "NetworkHealth": {
+                    "type": "object",
+                    "required": ["status"],
+                    "properties": {
+                        "status": {
+                            "type": "string",
+                            "enum": ["ok", "unreachable", "timeout", "not_configured"],
+                        },
+                        "block": { "type": "integer", "description": "Latest block number, or slot on Solana" },
+                        "latencyMs": { "type": "integer" },
+                        "error": { "type": "string" },
+                    },
+                },
+            },
+        },
+    })
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+fn json_body(schema: &str) -> Value {
+    json!({
+        "required": true,
+        "content": { "application/json": { "schema": schema_ref(schema) } },
+    })
+}
+
+fn json_response(description: &str, schema: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": schema_ref(schema) } },
+    })
+}
+
+fn rate_limited() -> Value {
+    let mut response = json_response(
+        "Rate limit exceeded for the payer or client IP",
+        "ErrorResponse",
+    );
+    response["headers"] = json!({
+        "Retry-After": {
+            "description": "Seconds to wait before retrying",
+            "schema": { "type": "integer" },
+        },
+    });
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health::{HealthReport, NetworkHealth, NetworkStatus};
+    use crate::timestamp::UnixTimestamp;
+    use crate::types::{
+        AssetAmountBounds, AuthorizationKind, ErrorResponse, EvmAddress, EvmSignature,
+        ExactEvmPayload, ExactEvmPayloadAuthorization, ExactPaymentPayload, ExactSolanaPayload,
+        Extensions, FacilitatorErrorReason, HexEncodedNonce, MixedAddress, NotAcceptableResponse,
+        PaymentPayload, PaymentRequirements, PaymentSplit, SettleResponse, SettleStatus,
+        SettlementCost, SupportedAsset, SupportedPaymentKind, SupportedPaymentKindExtra,
+        SupportedPaymentKindsResponse, TokenAmount, TransactionHash, VerifyRequest, VerifyResponse,
+        X402Version,
+    };
+    use alloy::primitives::{Address, U256, address};
+
+    /// Asserts that every sample has only properties documented by `schema` and every required one,
+    /// and that each documented property is set by some sample.
+    fn assert_documented<T: serde::Serialize>(spec: &Value, schema: &str, samples: &[T]) {
+        let schema_name = schema;
+        let schema = &spec["components"]["schemas"][schema];
+        let properties = schema["properties"].as_object().unwrap();
+        let mut covered = std::collections::HashSet::new();
+        for sample in samples {
+            let sample = serde_json::to_value(sample).unwrap();
+            for key in sample.as_object().unwrap().keys() {
+                assert!(
+                    properties.contains_key(key),
+                    "{schema_name}.{key} is not documented"
+                );
+                covered.insert(key.clone());
+            }
+            for required in schema["required"].as_array().unwrap() {
+                assert!(sample.get(required.as_str().unwrap()).is_some());
+            }
+        }
+        for property in properties.keys() {
+            assert!(
+                covered.contains(property),
+                "{schema_name}.{property} is not set by any sample"
+            );
+        }
+    }
+
+    #[test]
+    fn test_schemas_match_the_serialized_types() {
+        let spec = spec();
+        let payer: MixedAddress =
+            EvmAddress(address!("0x0000000000000000000000000000000000000402")).into();
+        let amount = TokenAmount(U256::from(10_000u64));
+        let extensions = Extensions(serde_json::Map::from_iter([(
+            "orderId".to_string(),
+            json!("order-42"),
+        )]));
+        let requirements = PaymentRequirements {
+            scheme: Scheme::Exact,
+            network: Network::BaseSepolia,
+            max_amount_required: amount,
+            resource: "https://example.com/paid".parse().unwrap(),
+            description: String::new(),
+            mime_type: "application/json".to_string(),
+            output_schema: Some(json!({})),
+            pay_to: payer.clone(),
+            max_timeout_seconds: 60,
+            asset: payer.clone(),
+            extra: Some(json!({})),
+            splits: Some(vec![PaymentSplit {
+                pay_to: payer.clone(),
+                amount,
+            }]),
+            authorization_kind: Some(AuthorizationKind::Receive),
+        };
+        assert_documented(&spec, "PaymentRequirements", &[&requirements]);
+        assert_documented(
+            &spec,
+            "PaymentSplit",
+            &[&requirements.splits.as_ref().unwrap()[0]],
+        );
+        let authorization = ExactEvmPayloadAuthorization {
+            from: EvmAddress(Address::repeat_byte(1)),
+            to: EvmAddress(Address::repeat_byte(2)),
+            value: amount,
+            valid_after: UnixTimestamp(0),
+            valid_before: UnixTimestamp(1),
+            nonce: HexEncodedNonce([7; 32]),
+        };
+        assert_documented(&spec, "ExactEvmPayloadAuthorization", &[&authorization]);
+        let evm_payload = ExactEvmPayload {
+            signature: EvmSignature::from([1u8; 65]),
+            authorization,
+        };
+        assert_documented(&spec, "ExactEvmPayload", &[&evm_payload]);
+        let solana_payload = ExactSolanaPayload {
+            transaction: "AQ==".to_string(),
+        };
+        assert_documented(&spec, "ExactSolanaPayload", &[&solana_payload]);
+        let payment_payload = PaymentPayload {
+            x402_version: X402Version::V1,
+            scheme: Scheme::Exact,
+            network: Network::BaseSepolia,
+            payload: ExactPaymentPayload::Evm(evm_payload),
+            extensions: Some(extensions.clone()),
+        };
+        assert_documented(&spec, "PaymentPayload", &[&payment_payload]);
+        let request = VerifyRequest {
+            x402_version: X402Version::V1,
+            payment_payload,
+            payment_requirements: requirements,
+        };
+        assert_documented(&spec, "VerifyRequest", &[&request]);
+        assert_documented(
+            &spec,
+            "VerifyResponse",
+            &[
+                VerifyResponse::Valid {
+                    payer: payer.clone(),
+                    payment_id: Some(request.payment_id()),
+                    extensions: Some(extensions.clone()),
+                },
+                VerifyResponse::Invalid {
+                    reason: FacilitatorErrorReason::InsufficientFunds,
+                    payer: Some(payer.clone()),
+                },
+            ],
+        );
+        assert_documented(
+            &spec,
+            "SettleResponse",
+            &[SettleResponse {
+                success: false,
+                error_reason: Some(FacilitatorErrorReason::InsufficientFunds),
+                payer: payer.clone(),
+                transaction: Some(TransactionHash::Evm([0; 32])),
+                block_number: Some(1),
+                status: Some(SettleStatus::Confirmed),
+                network: Network::BaseSepolia,
+                payment_id: Some(request.payment_id()),
+                cost: Some(SettlementCost {
+                    gas_used: 1,
+                    effective_gas_price: amount,
+                    total_cost: amount,
+                    total_cost_usd: Some(0.01),
+                    facilitator_fee: Some(amount),
+                }),
+                extensions: Some(extensions),
+            }],
+        );
+        assert_documented(
+            &spec,
+            "ErrorResponse",
+            &[ErrorResponse {
+                error: "Invalid request".to_string(),
+            }],
+        );
+        assert_documented(
+            &spec,
+            "NotAcceptableResponse",
+            &[NotAcceptableResponse {
+                error: "Not acceptable".to_string(),
+                supported_media_types: vec!["application/json".to_string()],
+            }],
+        );
+        let bounds = AssetAmountBounds {
+            asset: payer.clone(),
+            min_amount: Some(amount),
+            max_amount: Some(amount),
+        };
+        assert_documented(&spec, "AssetAmountBounds", &[&bounds]);
+        let supported = SupportedPaymentKindsResponse {
+            kinds: vec![SupportedPaymentKind {
+                x402_version: X402Version::V1,
+                scheme: Scheme::Exact,
+                network: Network::BaseSepolia.to_string(),
+                extra: Some(SupportedPaymentKindExtra {
+                    fee_payer: payer.clone(),
+                }),
+                assets: Some(vec![SupportedAsset {
+                    address: payer.clone(),
+                    decimals: 6,
+                }]),
+                allowed_assets: Some(vec![payer]),
+                authorization_kinds: Some(vec![AuthorizationKind::Transfer]),
+                amount_bounds: Some(vec![bounds]),
+            }],
+        };
+        assert_documented(&spec, "SupportedPaymentKindsResponse", &[&supported]);
+        assert_documented(&spec, "SupportedPaymentKind", &supported.kinds);
+        let mut report = HealthReport::default();
+        report.networks.insert(
+            Network::BaseSepolia.to_string(),
+            NetworkHealth {
+                status: NetworkStatus::Ok,
+                block: Some(1),
+                latency_ms: Some(1),
+                error: Some("slow".to_string()),
+            },
+        );
+        assert_documented(&spec, "HealthReport", &[&report]);
+        assert_documented(
+            &spec,
+            "NetworkHealth",
+            &[&report.networks[&Network::BaseSepolia.to_string()]],
+        );
+        assert_eq!(
+            spec["components"]["schemas"]["Network"]["enum"]
+                .as_array()
+                .unwrap()
+                .len(),
+            Network::variants().len()
+        );
+    }
+}
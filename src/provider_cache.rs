@@ -66,6 +66,8 @@ impl ProviderCache {
     /// - `EVM_PRIVATE_KEY` — comma-separated list of private keys used to sign transactions
     /// - `RPC_URL_BASE`, `RPC_URL_BASE_SEPOLIA` — RPC endpoints per network
     ///
+    /// If `SIGNER_TYPE` is not set, providers are created without signers, for verification only.
+    ///
     /// Fails if required env vars are missing or if the provider cannot connect.
     pub async fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
         let mut providers = HashMap::new();
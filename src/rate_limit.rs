@@ -0,0 +1,410 @@
+//! Per-payer rate limiting of `POST /verify`, `POST /settle`, their batches and `/ws/settle`, and of
+//! `POST /verify/offline`, `POST /recover` and `POST /quote`.
+//!
+//! Each caller gets a token bucket, refilled at `RATE_LIMIT_PER_SECOND` up to `RATE_LIMIT_BURST`
+//! requests; a request arriving at an empty bucket is answered `429 Too Many Requests` with a
+//! `Retry-After` header, before any RPC call is made. Every payment of a batch counts as a request.
+//! Callers are told apart by the payer recovered from the signature of an EVM payment, with the same
+//! EIP-712 recovery as verification, see [`recover_payer`]. A payer that can not be recovered, e.g. a
+//! Solana payment, a contract wallet or a forged `from`, is limited by source IP instead, so claiming
+//! someone else's address does not drain their bucket. So are the requests of the other endpoints,
+//! which carry no payment to recover a payer from. Behind a load balancer, the source IP is read
+//! from `X-Forwarded-For`, as set by the proxies listed in `RATE_LIMIT_TRUSTED_PROXIES`.
+//!
+//! At most [`MAX_BUCKETS`] buckets are kept; the least recently used one is dropped for a new caller.
+//!
+//! With `RATE_LIMIT_QUOTA_STATE` enabled, the `429` body also carries the caller's quota state, see
+//! [`Throttled`], so clients can pace themselves instead of retrying blindly.
+//!
+//! Environment variables used:
+//! - `RATE_LIMIT_PER_SECOND` — requests per second allowed per payer or IP (default: `0`, disabled),
+//! - `RATE_LIMIT_BURST` — requests allowed at once after a quiet period (default: the per-second rate, at least `1`),
+//! - `RATE_LIMIT_TRUSTED_PROXIES` — comma-separated IPs of the proxies in front of the facilitator (default: none),
+//! - `RATE_LIMIT_QUOTA_STATE` — whether `429` bodies include the quota state (default: `false`).
+
+use axum::Json;
+use axum::extract::{ConnectInfo, FromRequest, Request};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::chain::evm::recover_payer;
+use crate::codec::{self, Body, PaymentBody};
+use crate::from_env::{
+    ENV_RATE_LIMIT_BURST, ENV_RATE_LIMIT_PER_SECOND, ENV_RATE_LIMIT_QUOTA_STATE,
+    ENV_RATE_LIMIT_TRUSTED_PROXIES,
+};
+use crate::types::{ErrorResponse, EvmAddress, VerifyRequest};
+
+/// Number of buckets kept before the least recently used one is dropped.
+pub const MAX_BUCKETS: usize = 100_000;
+
+/// Who a request is counted against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateKey {
+    Payer(EvmAddress),
+    Ip(IpAddr),
+}
+
+/// The quota state of a caller whose bucket is empty.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Throttled {
+    /// Requests allowed at once, the bucket size.
+    pub limit: f64,
+    /// Requests taken from the bucket and not refilled yet.
+    pub used: f64,
+    /// Requests refilled per second.
+    pub per_second: f64,
+    /// How long until the bucket has a token again.
+    pub retry_after: Duration,
+    /// How long until the bucket is full again, if the caller sends nothing meanwhile.
+    pub reset_after: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets per [`RateKey`], see the [module docs](self).
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_second: f64,
+    burst: f64,
+    trusted_proxies: Vec<IpAddr>,
+    buckets: Mutex<LruCache<RateKey, Bucket>>,
+}
+
+static RATE_LIMITER: Lazy<Option<RateLimiter>> = Lazy::new(RateLimiter::from_env);
+
+impl RateLimiter {
+    /// Creates a limiter refilling `per_second` requests per second, up to `burst`.
+    pub fn new(per_second: f64, burst: f64) -> Self {
+        Self {
+            per_second,
+            burst,
+            trusted_proxies: Vec::new(),
+            buckets: Mutex::new(LruCache::new(
+                NonZeroUsize::new(MAX_BUCKETS).expect("MAX_BUCKETS is not zero"),
+            )),
+        }
+    }
+
+    /// Takes the source IP of requests from `X-Forwarded-For` when they come from one of `proxies`.
+    pub fn with_trusted_proxies(mut self, proxies: Vec<IpAddr>) -> Self {
+        self.trusted_proxies = proxies;
+        self
+    }
+
+    /// Reads `RATE_LIMIT_PER_SECOND`, `RATE_LIMIT_BURST` and `RATE_LIMIT_TRUSTED_PROXIES`;
+    /// `None` if rate limiting is disabled.
+    pub fn from_env() -> Option<Self> {
+        let per_second = std::env::var(ENV_RATE_LIMIT_PER_SECOND)
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .filter(|rate| rate.is_finite() && *rate > 0.0)?;
+        let burst = std::env::var(ENV_RATE_LIMIT_BURST)
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .filter(|burst| burst.is_finite() && *burst >= 1.0)
+            .unwrap_or(per_second.max(1.0));
+        let trusted_proxies = std::env::var(ENV_RATE_LIMIT_TRUSTED_PROXIES)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|ip| !ip.is_empty())
+            .filter_map(|ip| match ip.parse::<IpAddr>() {
+                Ok(ip) => Some(ip),
+                Err(error) => {
+                    tracing::warn!(%ip, %error, "Ignoring invalid {ENV_RATE_LIMIT_TRUSTED_PROXIES} entry");
+                    None
+                }
+            })
+            .collect();
+        Some(Self::new(per_second, burst).with_trusted_proxies(trusted_proxies))
+    }
+
+    /// The limiter shared by all handlers, `None` if disabled.
+    pub fn global() -> Option<&'static RateLimiter> {
+        RATE_LIMITER.as_ref()
+    }
+
+    /// Takes a token from the bucket of `key`, or returns its quota state if it is empty.
+    pub fn check(&self, key: RateKey) -> Result<(), Throttled> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.get_or_insert_mut(key, || Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        let tokens = self.refilled(bucket, now);
+        bucket.updated = now;
+        if tokens >= 1.0 {
+            bucket.tokens = tokens - 1.0;
+            Ok(())
+        } else {
+            bucket.tokens = tokens;
+            Err(Throttled {
+                limit: self.burst,
+                used: self.burst - tokens,
+                per_second: self.per_second,
+                retry_after: Duration::from_secs_f64((1.0 - tokens) / self.per_second),
+                reset_after: Duration::from_secs_f64((self.burst - tokens) / self.per_second),
+            })
+        }
+    }
+
+    /// Counts one request per payment of `payers` against its payer, or against `ip` if it has none.
+    ///
+    /// Stops at the first empty bucket, returning its quota state.
+    pub fn admit(
+        &self,
+        payers: impl IntoIterator<Item = Option<EvmAddress>>,
+        ip: Option<IpAddr>,
+    ) -> Result<(), Throttled> {
+        for payer in payers {
+            if let Some(key) = payer.map(RateKey::Payer).or(ip.map(RateKey::Ip)) {
+                self.check(key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The IP a request from `peer` was sent from: the last `X-Forwarded-For` hop not added by one
+    /// of the trusted proxies if `peer` is one, `peer` otherwise.
+    pub fn client_ip(&self, peer: Option<IpAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        let peer = peer?;
+        if !self.trusted_proxies.contains(&peer) {
+            return Some(peer);
+        }
+        let hops: Vec<IpAddr> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|hop| hop.trim().parse().ok())
+            .collect();
+        hops.iter()
+            .rev()
+            .find(|hop| !self.trusted_proxies.contains(hop))
+            .or(hops.first())
+            .copied()
+            .or(Some(peer))
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.per_second).min(self.burst)
+    }
+}
+
+/// Whether `429` bodies include the quota state, set by [`quota_state_from_env`].
+static QUOTA_STATE: AtomicBool = AtomicBool::new(false);
+
+/// Reads `RATE_LIMIT_QUOTA_STATE`, adding the quota state to `429` bodies if it is `true`.
+///
+/// # Errors
+/// Returns an error if it is set to something other than `true` or `false`.
+pub fn quota_state_from_env() -> Result<(), String> {
+    let enabled = match std::env::var(ENV_RATE_LIMIT_QUOTA_STATE) {
+        Ok(raw) => raw
+            .trim()
+            .parse::<bool>()
+            .map_err(|e| format!("env {ENV_RATE_LIMIT_QUOTA_STATE} is invalid: {e}"))?,
+        Err(_) => false,
+    };
+    QUOTA_STATE.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+/// The `429 Too Many Requests` answer to a `throttled` request, with a `Retry-After` header.
+pub fn too_many_requests(throttled: Throttled) -> Response {
+    throttled_response(throttled, QUOTA_STATE.load(Ordering::Relaxed))
+}
+
+/// [`too_many_requests`], with the quota state in the body if `quota_state` is set.
+fn throttled_response(throttled: Throttled, quota_state: bool) -> Response {
+    let error = "Rate limit exceeded".to_string();
+    let mut response = if quota_state {
+        let body = serde_json::json!({
+            "error": error,
+            "quota": {
+                "limit": throttled.limit,
+                "used": throttled.used,
+                "refillPerSecond": throttled.per_second,
+                "retryAfterSecs": throttled.retry_after.as_secs_f64(),
+                "resetAfterSecs": throttled.reset_after.as_secs_f64(),
+            },
+        });
+        (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response()
+    } else {
+        (StatusCode::TOO_MANY_REQUESTS, Json(ErrorResponse { error })).into_response()
+    };
+    let seconds = throttled.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+    response
+}
+
+/// Middleware answering `429 Too Many Requests` once the caller's bucket is empty.
+///
+/// Needs the server to provide [`ConnectInfo<SocketAddr>`] to fall back to the source IP; without it,
+/// requests with no recoverable payer are not limited.
+pub async fn limit(request: Request, next: Next) -> Response {
+    limit_payments(request, next, |request| async {
+        let payer = PaymentBody::from_request(request, &())
+            .await
+            .ok()
+            .and_then(|PaymentBody(body)| recover_payer(&body));
+        vec![payer]
+    })
+    .await
+}
+
+/// Like [`limit`], for a batch: every payment of the batch is counted against its own payer.
+pub async fn limit_batch(request: Request, next: Next) -> Response {
+    limit_payments(request, next, |request| async {
+        match Body::<Vec<VerifyRequest>>::from_request(request, &()).await {
+            Ok(Body(batch)) if !batch.is_empty() => batch.iter().map(recover_payer).collect(),
+            // Rejected by the handler, but still counted against the source IP.
+            _ => vec![None],
+        }
+    })
+    .await
+}
+
+async fn limit_payments<F, Fut>(request: Request, next: Next, payers: F) -> Response
+where
+    F: FnOnce(Request) -> Fut,
+    Fut: Future<Output = Vec<Option<EvmAddress>>>,
+{
+    let Some(limiter) = RateLimiter::global() else {
+        return next.run(request).await;
+    };
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let ip = limiter.client_ip(peer, request.headers());
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, codec::max_body_bytes()).await {
+        Ok(bytes) => bytes,
+        Err(_) => return codec::body_too_large(),
+    };
+    let probe = Request::from_parts(parts.clone(), axum::body::Body::from(bytes.clone()));
+    if let Err(throttled) = limiter.admit(payers(probe).await, ip) {
+        tracing::warn!(ip = ?ip, endpoint = %parts.uri.path(), "Rate limit exceeded");
+        return too_many_requests(throttled);
+    }
+    next.run(Request::from_parts(parts, axum::body::Body::from(bytes)))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_allows_burst_then_refills() {
+        let limiter = RateLimiter::new(1.0, 2.0);
+        let key = RateKey::Ip(IpAddr::from([127, 0, 0, 1]));
+        assert!(limiter.check(key).is_ok());
+        assert!(limiter.check(key).is_ok());
+        let retry_after = limiter.check(key).unwrap_err().retry_after;
+        assert!(retry_after > Duration::ZERO && retry_after <= Duration::from_secs(1));
+        assert!(
+            limiter
+                .check(RateKey::Ip(IpAddr::from([127, 0, 0, 2])))
+                .is_ok()
+        );
+
+        if let Some(bucket) = limiter.buckets.lock().unwrap().get_mut(&key) {
+            bucket.updated -= Duration::from_secs(1);
+        }
+        assert!(limiter.check(key).is_ok());
+        assert!(limiter.check(key).is_err());
+    }
+
+    #[test]
+    fn test_every_payment_of_a_batch_is_counted() {
+        let limiter = RateLimiter::new(1.0, 3.0);
+        let payer = EvmAddress(alloy::primitives::Address::repeat_byte(1));
+        let ip = Some(IpAddr::from([127, 0, 0, 1]));
+        assert!(limiter.admit([Some(payer), Some(payer), None], ip).is_ok());
+        assert!(limiter.admit([Some(payer)], ip).is_ok());
+        assert!(limiter.admit([Some(payer)], ip).is_err());
+        assert!(limiter.admit([None, None], ip).is_ok());
+        assert!(limiter.admit([None], ip).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_throttled_answers_carry_the_quota_state_if_enabled() {
+        let limiter = RateLimiter::new(0.5, 2.0);
+        let key = RateKey::Ip(IpAddr::from([127, 0, 0, 1]));
+        assert!(limiter.check(key).is_ok());
+        assert!(limiter.check(key).is_ok());
+        let throttled = limiter.check(key).unwrap_err();
+        assert_eq!(throttled.limit, 2.0);
+        assert!(throttled.used > 1.9 && throttled.used <= 2.0);
+        assert!(throttled.retry_after <= Duration::from_secs(2));
+        assert!(throttled.reset_after > Duration::from_secs(3));
+
+        let body = |response: Response| async {
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+            assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+        };
+        let opaque = body(throttled_response(throttled, false)).await;
+        assert!(opaque.get("quota").is_none());
+        let quota = &body(throttled_response(throttled, true)).await["quota"];
+        assert_eq!(quota["limit"], 2.0);
+        assert_eq!(quota["refillPerSecond"], 0.5);
+        assert!(quota["resetAfterSecs"].as_f64().unwrap() > 3.0);
+    }
+
+    #[test]
+    fn test_client_ip_is_forwarded_only_by_trusted_proxies() {
+        let proxy = IpAddr::from([10, 0, 0, 1]);
+        let limiter = RateLimiter::new(1.0, 1.0).with_trusted_proxies(vec![proxy]);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("1.1.1.1, 203.0.113.7, 10.0.0.1"),
+        );
+        assert_eq!(
+            limiter.client_ip(Some(proxy), &headers),
+            Some(IpAddr::from([203, 0, 113, 7]))
+        );
+        let direct = IpAddr::from([198, 51, 100, 2]);
+        assert_eq!(limiter.client_ip(Some(direct), &headers), Some(direct));
+        assert_eq!(
+            limiter.client_ip(Some(proxy), &HeaderMap::new()),
+            Some(proxy)
+        );
+    }
+
+    #[test]
+    fn test_least_recently_used_bucket_is_dropped_when_full() {
+        let limiter = RateLimiter::new(1.0, 1.0);
+        let first = RateKey::Ip(IpAddr::from([127, 0, 0, 1]));
+        assert!(limiter.check(first).is_ok());
+        assert!(limiter.check(first).is_err());
+        for index in 0..MAX_BUCKETS as u32 {
+            let _ = limiter.check(RateKey::Ip(IpAddr::from(index.to_be_bytes())));
+        }
+        assert!(limiter.buckets.lock().unwrap().len() <= MAX_BUCKETS);
+        assert!(limiter.check(first).is_ok());
+    }
+}
@@ -0,0 +1,126 @@
+//! Per-network allowlist of payment receivers.
+//!
+//! A facilitator operated for a fixed set of merchants can refuse to move funds anywhere else, whatever
+//! the payment requirements say. On a network with an allowlist, requirements whose `payTo`, or the
+//! `payTo` of any of their splits, is not listed are rejected with `receiver_not_allowed` before any
+//! chain interaction. Networks without an allowlist accept any receiver.
+//!
+//! Environment variables used:
+//! - `RECEIVER_ALLOWLIST_<NETWORK>` — comma-separated receiver addresses, e.g.
+//!   `RECEIVER_ALLOWLIST_BASE=0xMerchantA,0xMerchantB`.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::chain::FacilitatorLocalError;
+use crate::from_env::{self, ENV_RECEIVER_ALLOWLIST_PREFIX};
+use crate::network::Network;
+use crate::types::{MixedAddress, PaymentRequirements};
+
+/// Allowed receivers, keyed by network.
+#[derive(Debug, Clone, Default)]
+pub struct ReceiverAllowlist {
+    receivers: HashMap<Network, HashSet<MixedAddress>>,
+}
+
+impl ReceiverAllowlist {
+    /// Reads `RECEIVER_ALLOWLIST_<NETWORK>` for every network, or returns `None` if none is set.
+    pub fn from_env() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let mut allowlist = Self::default();
+        for network in Network::variants() {
+            let env_var = from_env::env_name_for_network(ENV_RECEIVER_ALLOWLIST_PREFIX, *network);
+            let Ok(raw) = std::env::var(&env_var) else {
+                continue;
+            };
+            let receivers = from_env::list_entries(&raw)
+                .map(|(_, receiver)| receiver.map_err(|e| format!("env {env_var} is invalid: {e}")))
+                .collect::<Result<_, _>>()?;
+            allowlist.receivers.insert(*network, receivers);
+        }
+        Ok((!allowlist.receivers.is_empty()).then_some(allowlist))
+    }
+
+    /// Checks that every receiver of `requirements` is allowed on their network.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::ReceiverNotAllowed`] naming the first receiver that is not.
+    pub fn assert_allowed(
+        &self,
+        requirements: &PaymentRequirements,
+    ) -> Result<(), FacilitatorLocalError> {
+        let Some(allowed) = self.receivers.get(&requirements.network) else {
+            return Ok(());
+        };
+        let split_receivers = requirements
+            .splits
+            .iter()
+            .flatten()
+            .map(|split| &split.pay_to);
+        match std::iter::once(&requirements.pay_to)
+            .chain(split_receivers)
+            .find(|receiver| !allowed.contains(*receiver))
+        {
+            Some(receiver) => Err(FacilitatorLocalError::ReceiverNotAllowed(format!(
+                "receiver {receiver} is not allowed on {}",
+                requirements.network
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EvmAddress, PaymentSplit, Scheme, TokenAmount};
+    use alloy::primitives::{U256, address};
+
+    #[test]
+    fn test_unlisted_receivers_and_splits_are_rejected() {
+        let merchant: MixedAddress =
+            EvmAddress(address!("0x0000000000000000000000000000000000000402")).into();
+        let stranger: MixedAddress =
+            EvmAddress(address!("0x0000000000000000000000000000000000000bad")).into();
+        let requirements =
+            |pay_to: &MixedAddress, splits: Option<Vec<PaymentSplit>>| PaymentRequirements {
+                scheme: Scheme::Exact,
+                network: Network::BaseSepolia,
+                max_amount_required: TokenAmount(U256::from(10_000u64)),
+                resource: "https://example.com/paid".parse().unwrap(),
+                description: String::new(),
+                mime_type: "application/json".to_string(),
+                output_schema: None,
+                pay_to: pay_to.clone(),
+                max_timeout_seconds: 60,
+                asset: EvmAddress(address!("0x036CbD53842c5426634e7929541eC2318f3dCF7e")).into(),
+                extra: None,
+                splits,
+                authorization_kind: None,
+            };
+        let allowlist = ReceiverAllowlist {
+            receivers: HashMap::from([(Network::BaseSepolia, HashSet::from([merchant.clone()]))]),
+        };
+
+        assert!(
+            allowlist
+                .assert_allowed(&requirements(&merchant, None))
+                .is_ok()
+        );
+        assert!(matches!(
+            allowlist.assert_allowed(&requirements(&stranger, None)),
+            Err(FacilitatorLocalError::ReceiverNotAllowed(_))
+        ));
+        let split_to_stranger = vec![PaymentSplit {
+            pay_to: stranger.clone(),
+            amount: TokenAmount(U256::from(1_000u64)),
+        }];
+        assert!(
+            allowlist
+                .assert_allowed(&requirements(&merchant, Some(split_to_stranger)))
+                .is_err()
+        );
+
+        let mut other_network = requirements(&stranger, None);
+        other_network.network = Network::Base;
+        assert!(allowlist.assert_allowed(&other_network).is_ok());
+    }
+}
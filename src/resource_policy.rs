@@ -0,0 +1,141 @@
+//! Per-merchant allowed resource URLs.
+//!
+//! A payment's requirements name the `resource` it pays for. A merchant can restrict which resources
+//! its payments may be for, so a payment to its `payTo` can't be diverted to pay for something else.
+//! Requirements whose `resource` matches none of the receiver's patterns are rejected before any
+//! chain interaction.
+//!
+//! Environment variables used:
+//! - `RESOURCE_PATTERNS` — JSON object mapping `payTo` addresses to lists of regular expressions,
+//!   e.g. `{"0xMerchant": ["https://api\\.example\\.com/.*"]}`. Each pattern must match the whole URL.
+//!   The key `"*"` applies to receivers not listed; without it, other receivers are not checked.
+
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::chain::FacilitatorLocalError;
+use crate::from_env::ENV_RESOURCE_PATTERNS;
+use crate::types::{MixedAddress, PaymentRequirements};
+
+/// Key of the patterns applied to receivers without patterns of their own.
+const ANY_RECEIVER: &str = "*";
+
+/// Allowed resource patterns, keyed by `payTo`.
+#[derive(Debug, Clone, Default)]
+pub struct ResourcePolicy {
+    patterns: HashMap<MixedAddress, Vec<Regex>>,
+    fallback: Option<Vec<Regex>>,
+}
+
+impl ResourcePolicy {
+    /// Reads allowed resource patterns from `RESOURCE_PATTERNS`, or returns `None` if not set.
+    pub fn from_env() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let Ok(raw) = std::env::var(ENV_RESOURCE_PATTERNS) else {
+            return Ok(None);
+        };
+        let entries: HashMap<String, Vec<String>> = serde_json::from_str(&raw)
+            .map_err(|e| format!("env {ENV_RESOURCE_PATTERNS} is invalid: {e}"))?;
+        let mut policy = Self::default();
+        for (receiver, patterns) in entries {
+            let patterns = patterns
+                .iter()
+                .map(|pattern| Regex::new(&format!("^(?:{pattern})$")))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("env {ENV_RESOURCE_PATTERNS} is invalid: {e}"))?;
+            if receiver == ANY_RECEIVER {
+                policy.fallback = Some(patterns);
+            } else {
+                let receiver: MixedAddress = serde_json::from_value(receiver.clone().into())
+                    .map_err(|_| {
+                        format!("env {ENV_RESOURCE_PATTERNS} is invalid: bad address {receiver}")
+                    })?;
+                policy.patterns.insert(receiver, patterns);
+            }
+        }
+        Ok(Some(policy))
+    }
+
+    /// Checks that the `resource` of `requirements` is allowed for its `payTo`.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::ResourceNotAllowed`] if the receiver has patterns
+    /// and none of them matches the resource.
+    pub fn assert_allowed(
+        &self,
+        requirements: &PaymentRequirements,
+    ) -> Result<(), FacilitatorLocalError> {
+        let Some(patterns) = self
+            .patterns
+            .get(&requirements.pay_to)
+            .or(self.fallback.as_ref())
+        else {
+            return Ok(());
+        };
+        let resource = requirements.resource.as_str();
+        if patterns.iter().any(|pattern| pattern.is_match(resource)) {
+            Ok(())
+        } else {
+            Err(FacilitatorLocalError::ResourceNotAllowed(format!(
+                "resource {resource} is not allowed for {}",
+                requirements.pay_to
+            )))
+        }
+    }
+}
+
+impl FromIterator<(MixedAddress, Vec<Regex>)> for ResourcePolicy {
+    fn from_iter<T: IntoIterator<Item = (MixedAddress, Vec<Regex>)>>(iter: T) -> Self {
+        Self {
+            patterns: iter.into_iter().collect(),
+            fallback: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Network;
+    use crate::types::{EvmAddress, Scheme, TokenAmount};
+    use alloy::primitives::{Address, U256, address};
+
+    fn requirements(pay_to: Address, resource: &str) -> PaymentRequirements {
+        PaymentRequirements {
+            scheme: Scheme::Exact,
+            network: Network::BaseSepolia,
+            max_amount_required: TokenAmount(U256::from(10_000u64)),
+            resource: resource.parse().unwrap(),
+            description: String::new(),
+            mime_type: "application/json".to_string(),
+            output_schema: None,
+            pay_to: EvmAddress(pay_to).into(),
+            max_timeout_seconds: 60,
+            asset: EvmAddress(address!("0x036CbD53842c5426634e7929541eC2318f3dCF7e")).into(),
+            extra: None,
+            splits: None,
+            authorization_kind: None,
+        }
+    }
+
+    #[test]
+    fn test_resource_must_match_whole_url() {
+        let merchant = address!("0x0000000000000000000000000000000000000402");
+        let policy: ResourcePolicy = [(
+            EvmAddress(merchant).into(),
+            vec![Regex::new("^(?:https://api\\.example\\.com/.*)$").unwrap()],
+        )]
+        .into_iter()
+        .collect();
+
+        let allowed = requirements(merchant, "https://api.example.com/weather");
+        assert!(policy.assert_allowed(&allowed).is_ok());
+        let diverted = requirements(merchant, "https://evil.example/?https://api.example.com/");
+        assert!(policy.assert_allowed(&diverted).is_err());
+
+        let other = requirements(
+            address!("0x0000000000000000000000000000000000000bad"),
+            "https://elsewhere.example/",
+        );
+        assert!(policy.assert_allowed(&other).is_ok());
+    }
+}
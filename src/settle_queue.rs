@@ -0,0 +1,268 @@
+//! Queued settlement, for `POST /settle?queue=true`.
+//!
+//! A queued settlement is recorded as `queued` in the settlement [journal](crate::journal), which
+//! keeps the queue across restarts, and answered at once with the lifecycle of its payment, see
+//! [`PaymentLifecycle`](crate::journal::PaymentLifecycle): its `paymentId` is the tracking ID,
+//! polled with `GET /payments/{payment_id}`. A pool of workers settles queued payments in the
+//! background, journaling them like any other settlement.
+//!
+//! Delivery is at least once: payments still `queued` when the facilitator stops are queued again
+//! on startup, see [`SettleQueue::resume`]. A payment settles at most once, keyed on its
+//! [`PaymentId`], i.e. on its authorization nonce: a payment already journaled is not queued again,
+//! unless it failed before anything was broadcast, and a worker only settles a payment still
+//! `queued`. A queued settlement failing before broadcasting is journaled `failed` with its error.
+//!
+//! Environment variables used:
+//! - `SETTLE_QUEUE_WORKERS` — number of workers settling queued payments (default: unset, queueing
+//!   disabled). Needs the settlement journal.
+
+use dashmap::DashSet;
+use once_cell::sync::OnceCell;
+use std::sync::Arc;
+use tokio::sync::{Mutex, mpsc};
+
+use crate::facilitator::Facilitator;
+use crate::from_env::ENV_SETTLE_QUEUE_WORKERS;
+use crate::inflight::InFlight;
+use crate::journal::{self, JournalEntry, JournalError, JournalStatus, SettlementJournal};
+use crate::types::{PaymentId, SettleRequest};
+
+/// Payments waiting to be settled, journaled as `queued`, and the workers settling them.
+pub struct SettleQueue {
+    journal: Arc<dyn SettlementJournal>,
+    workers: usize,
+    sender: mpsc::UnboundedSender<SettleRequest>,
+    receiver: Mutex<mpsc::UnboundedReceiver<SettleRequest>>,
+    /// Payments queued or being settled by a worker.
+    queued: DashSet<PaymentId>,
+}
+
+impl SettleQueue {
+    pub fn new(journal: Arc<dyn SettlementJournal>, workers: usize) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            journal,
+            workers,
+            sender,
+            receiver: Mutex::new(receiver),
+            queued: DashSet::new(),
+        }
+    }
+
+    /// Reads the number of workers from `SETTLE_QUEUE_WORKERS`, or returns `None` if it is not set.
+    ///
+    /// # Errors
+    /// Returns an error if the number is not positive, or if journaling is disabled.
+    pub fn from_env(journal: Option<&Arc<dyn SettlementJournal>>) -> Result<Option<Self>, String> {
+        let Ok(value) = std::env::var(ENV_SETTLE_QUEUE_WORKERS) else {
+            return Ok(None);
+        };
+        let workers: usize = value
+            .parse()
+            .map_err(|e| format!("env {ENV_SETTLE_QUEUE_WORKERS} is invalid: {e}"))?;
+        if workers == 0 {
+            return Err(format!("env {ENV_SETTLE_QUEUE_WORKERS} must be at least 1"));
+        }
+        let Some(journal) = journal else {
+            return Err(format!(
+                "env {ENV_SETTLE_QUEUE_WORKERS} needs the settlement journal, which is disabled"
+            ));
+        };
+        Ok(Some(Self::new(Arc::clone(journal), workers)))
+    }
+
+    /// Queues the settlement of `request`, returning its journal entry.
+    ///
+    /// A payment already journaled is not queued again: its entry is returned as it is, unless it
+    /// failed before anything was broadcast.
+    pub async fn enqueue(&self, request: &SettleRequest) -> Result<JournalEntry, JournalError> {
+        let payment_id = request.payment_id();
+        if !self.queued.insert(payment_id) {
+            if let Some(entry) = self.journal.payment(&payment_id).await? {
+                return Ok(entry);
+            }
+            return Ok(JournalEntry::queued(request));
+        }
+        if let Some(entry) = self.journal.payment(&payment_id).await?
+            && (entry.status != JournalStatus::Failed || entry.transaction.is_some())
+        {
+            self.queued.remove(&payment_id);
+            return Ok(entry);
+        }
+        let entry = JournalEntry::queued(request);
+        if let Err(error) = self.journal.record(entry.clone()).await {
+            self.queued.remove(&payment_id);
+            return Err(error);
+        }
+        let _ = self.sender.send(request.clone());
+        Ok(entry)
+    }
+
+    /// Queues again the payments journaled as `queued`, e.g. after a restart.
+    pub async fn resume(&self) {
+        let queued = match self.journal.queued().await {
+            Ok(queued) => queued,
+            Err(error) => {
+                tracing::warn!(%error, "Failed to read queued settlements");
+                return;
+            }
+        };
+        if !queued.is_empty() {
+            tracing::info!(
+                queued = queued.len(),
+                "Resuming queued settlements from the journal"
+            );
+        }
+        for entry in queued {
+            if self.queued.insert(entry.payment_id()) {
+                let _ = self.sender.send(entry.request);
+            }
+        }
+    }
+
+    /// Starts the workers, settling queued payments with `facilitator`.
+    pub fn spawn<A>(self: &Arc<Self>, facilitator: A)
+    where
+        A: Facilitator + Clone + Send + Sync + 'static,
+    {
+        for _ in 0..self.workers {
+            let queue = Arc::clone(self);
+            let facilitator = facilitator.clone();
+            tokio::spawn(async move { queue.work(&facilitator).await });
+        }
+    }
+
+    async fn work<A: Facilitator + Sync>(&self, facilitator: &A) {
+        loop {
+            let Some(request) = self.receiver.lock().await.recv().await else {
+                return;
+            };
+            self.settle(facilitator, &request).await;
+            self.queued.remove(&request.payment_id());
+        }
+    }
+
+    /// Settles `request` if its payment is still `queued`.
+    async fn settle<A: Facilitator + Sync>(&self, facilitator: &A, request: &SettleRequest) {
+        let payment_id = request.payment_id();
+        match self.journal.payment(&payment_id).await {
+            Ok(Some(entry)) if entry.status == JournalStatus::Queued => {}
+            Ok(_) => return,
+            Err(error) => {
+                // Still queued in the journal: settled once the facilitator restarts.
+                tracing::warn!(%error, %payment_id, "Failed to read queued settlement");
+                return;
+            }
+        }
+        let _in_flight = InFlight::global().settlement();
+        // Journaled as pending once broadcast, then with the outcome, by the settlement itself.
+        let error = match facilitator.settle(request).await {
+            Ok(response) if response.transaction.is_some() => return,
+            Ok(response) => response
+                .error_reason
+                .map_or("Settled without a transaction".to_string(), |reason| {
+                    reason.to_string()
+                }),
+            Err(error) => error.to_string(),
+        };
+        tracing::warn!(%error, %payment_id, "Queued settlement failed");
+        journal::fail_queued(self.journal.as_ref(), &payment_id, &error).await;
+    }
+}
+
+static SETTLE_QUEUE: OnceCell<Arc<SettleQueue>> = OnceCell::new();
+
+/// Makes `settle_queue` the process-wide queue. Only the first call has an effect.
+pub fn install(settle_queue: Arc<SettleQueue>) {
+    let _ = SETTLE_QUEUE.set(settle_queue);
+}
+
+/// The process-wide queue, `None` if queueing is disabled.
+pub fn global() -> Option<&'static Arc<SettleQueue>> {
+    SETTLE_QUEUE.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::FacilitatorLocalError;
+    use crate::chain::evm::tests::offline_request;
+    use crate::journal::FileJournal;
+    use crate::timestamp::UnixTimestamp;
+    use crate::types::{
+        SettleResponse, SupportedPaymentKindsResponse, VerifyRequest, VerifyResponse,
+    };
+    use alloy::primitives::Address;
+    use alloy::signers::local::PrivateKeySigner;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts settlements, failing them all before broadcasting.
+    #[derive(Default)]
+    struct Rejecting {
+        settles: AtomicUsize,
+    }
+
+    impl Facilitator for Rejecting {
+        type Error = FacilitatorLocalError;
+
+        async fn verify(&self, _: &VerifyRequest) -> Result<VerifyResponse, Self::Error> {
+            Err(FacilitatorLocalError::UnsupportedNetwork(None))
+        }
+
+        async fn settle(&self, _: &SettleRequest) -> Result<SettleResponse, Self::Error> {
+            self.settles.fetch_add(1, Ordering::Relaxed);
+            Err(FacilitatorLocalError::UnsupportedNetwork(None))
+        }
+
+        async fn supported(&self) -> Result<SupportedPaymentKindsResponse, Self::Error> {
+            Err(FacilitatorLocalError::UnsupportedNetwork(None))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queued_payments_settle_once_and_resume_after_a_restart() {
+        let path = std::env::temp_dir().join(format!(
+            "x402-settle-queue-{}.jsonl",
+            UnixTimestamp::try_now().unwrap().0 ^ u64::from(std::process::id())
+        ));
+        let journal: Arc<dyn SettlementJournal> =
+            Arc::new(FileJournal::open(path.clone()).await.unwrap());
+        let queue = SettleQueue::new(Arc::clone(&journal), 1);
+        let offline = offline_request(&PrivateKeySigner::random(), Address::repeat_byte(1));
+        let request = SettleRequest {
+            x402_version: offline.x402_version,
+            payment_payload: offline.payment_payload,
+            payment_requirements: offline.payment_requirements,
+        };
+        let payment_id = request.payment_id();
+
+        let entry = queue.enqueue(&request).await.unwrap();
+        assert_eq!(entry.status, JournalStatus::Queued);
+        assert_eq!(entry.lifecycle().payment_id, payment_id);
+        assert!(entry.transaction.is_none());
+        // Queued again while queued: delivered once.
+        let entry = queue.enqueue(&request).await.unwrap();
+        assert_eq!(entry.status, JournalStatus::Queued);
+        let delivered = queue.receiver.lock().await.try_recv().unwrap();
+        assert!(queue.receiver.lock().await.try_recv().is_err());
+
+        let facilitator = Rejecting::default();
+        queue.settle(&facilitator, &delivered).await;
+        // Delivered twice: settled once.
+        queue.settle(&facilitator, &delivered).await;
+        queue.queued.remove(&payment_id);
+        assert_eq!(facilitator.settles.load(Ordering::Relaxed), 1);
+        let entry = journal.payment(&payment_id).await.unwrap().unwrap();
+        assert_eq!(entry.status, JournalStatus::Failed);
+        assert!(entry.error.is_some());
+
+        // Failed with nothing broadcast: queued again, and resumed after a restart.
+        let entry = queue.enqueue(&request).await.unwrap();
+        assert_eq!(entry.status, JournalStatus::Queued);
+        let restarted = SettleQueue::new(journal, 1);
+        restarted.resume().await;
+        let resumed = restarted.receiver.lock().await.try_recv().unwrap();
+        assert_eq!(resumed.payment_id(), payment_id);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -14,9 +14,13 @@ use serde::{Deserialize, Serialize};
 use std::env;
 use std::time::Duration;
 use tower_http::trace::{MakeSpan, OnResponse, TraceLayer};
-use tracing::Span;
+use tracing::{Span, Subscriber};
 use tracing_opentelemetry::{MetricsLayer, OpenTelemetryLayer, OpenTelemetrySpanExt};
-use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer, layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::from_env::ENV_LOG_FORMAT;
 
 /// Supported telemetry transport protocols for exporting OTLP data.
 ///
@@ -57,6 +61,52 @@ impl TelemetryProtocol {
     }
 }
 
+/// Format of the console logs, set by `LOG_FORMAT`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum LogFormat {
+    /// Human-readable lines, the default.
+    #[default]
+    Text,
+    /// Newline-delimited JSON, one object per event with its fields as top-level keys.
+    Json,
+}
+
+impl LogFormat {
+    /// Reads `LOG_FORMAT`: `json` selects [`LogFormat::Json`], anything else [`LogFormat::Text`].
+    fn from_env() -> Self {
+        match env::var(ENV_LOG_FORMAT) {
+            Ok(format) if format.eq_ignore_ascii_case("json") => LogFormat::Json,
+            _ => LogFormat::Text,
+        }
+    }
+
+    /// The console formatting layer for this format.
+    fn layer<S>(self) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        self.layer_with_writer(std::io::stdout)
+    }
+
+    /// The formatting layer for this format, printing to `writer`.
+    fn layer_with_writer<S, W>(self, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+    {
+        match self {
+            LogFormat::Text => tracing_subscriber::fmt::layer().with_writer(writer).boxed(),
+            LogFormat::Json => tracing_subscriber::fmt::layer()
+                .with_writer(writer)
+                .json()
+                .flatten_event(true)
+                .with_current_span(true)
+                .with_span_list(false)
+                .boxed(),
+        }
+    }
+}
+
 /// Describes the local service's identity and metadata for telemetry purposes.
 ///
 /// This includes:
@@ -249,7 +299,8 @@ impl Telemetry {
     /// - Distributed tracing via `tracing-opentelemetry`
     /// - Metrics collection via `opentelemetry_sdk::metrics`
     ///
-    /// Otherwise, it defaults to console logging via `tracing-subscriber`.
+    /// Otherwise, it defaults to console logging via `tracing-subscriber`. Console logs are printed
+    /// as text, or as newline-delimited JSON with `LOG_FORMAT=json`.
     ///
     /// Returns a [`TelemetryProviders`] struct that performs graceful exporter shutdown on `Drop`.
     pub fn register(&self) -> TelemetryProviders {
@@ -270,7 +321,7 @@ impl Telemetry {
                     // per-layer filtering to target the telemetry layer specifically,
                     // e.g. by target matching.
                     .with(tracing_subscriber::filter::LevelFilter::INFO)
-                    .with(tracing_subscriber::fmt::layer())
+                    .with(LogFormat::from_env().layer())
                     .with(MetricsLayer::new(meter_provider.clone()))
                     .with(OpenTelemetryLayer::new(tracer))
                     .init();
@@ -288,7 +339,7 @@ impl Telemetry {
                 // Fallback: just use local logging
                 tracing_subscriber::registry()
                     .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| "trace".into()))
-                    .with(tracing_subscriber::fmt::layer())
+                    .with(LogFormat::from_env().layer())
                     .init();
 
                 tracing::info!("OpenTelemetry is not enabled");
@@ -395,3 +446,50 @@ impl<A> OnResponse<A> for FacilitatorHttpOnResponse {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_json_logs_put_event_fields_at_the_top_level() {
+        unsafe { env::set_var(ENV_LOG_FORMAT, "JSON") };
+        assert_eq!(LogFormat::from_env(), LogFormat::Json);
+        unsafe { env::set_var(ENV_LOG_FORMAT, "xml") };
+        assert_eq!(LogFormat::from_env(), LogFormat::Text);
+        unsafe { env::remove_var(ENV_LOG_FORMAT) };
+        assert_eq!(LogFormat::from_env(), LogFormat::Text);
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let writer = {
+            let output = output.clone();
+            move || SharedBuffer(output.clone())
+        };
+        let subscriber =
+            tracing_subscriber::registry().with(LogFormat::Json.layer_with_writer(writer));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(error = "boom", endpoint = "/settle", "Settlement failed");
+        });
+        let output = output.lock().unwrap();
+        let event: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(event["level"], "WARN");
+        assert_eq!(event["message"], "Settlement failed");
+        assert_eq!(event["error"], "boom");
+        assert_eq!(event["endpoint"], "/settle");
+    }
+
+    /// Collects everything written to it.
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
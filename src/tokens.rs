@@ -0,0 +1,682 @@
+//! Per-network token configuration.
+//!
+//! Most tokens need no configuration at all. Tokens that need special handling on a network
+//! are described by a JSON array in the `TOKENS_<NETWORK>` environment variable,
+//! e.g. `TOKENS_BASE` or `TOKENS_POLYGON_AMOY`:
+//!
+//! ```text
+//! TOKENS_BASE='[{"address": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913", "gasLimit": 250000}]'
+//! ```
+//!
+//! Supported per-token settings:
+//! - `gasLimit` — minimum gas limit for settlement transactions; the larger of this and the node's estimate is used.
+//! - `unwrapNative` — the token is wrapped native currency (WETH-style); the payer authorizes a transfer
+//!   to a facilitator signer, which unwraps it and forwards native currency to `payTo`. EVM only.
+//! - `schemes` — payment schemes offered for the token, e.g. `["exact"]`. Payments in any other scheme
+//!   are rejected, and `/supported` only advertises a scheme if some known token on the network offers it.
+//!   If omitted, every scheme the network supports is offered.
+//! - `valueModel` — how `balanceOf` relates to transfer amounts: `"standard"` (default, same units) or
+//!   `"convertToAssets"` for share-based tokens, whose balance is converted with `convertToAssets(shares)`. EVM only.
+//! - `sweepThreshold` — token units, e.g. `"1000000"`. When a sweep treasury is configured, a facilitator signer
+//!   holding more than this of the token transfers its whole balance to the treasury. EVM only.
+//! - `implementationCheck` — `"warn"` or `"reject"`. For upgradeable tokens, if the requirements carry
+//!   `extra.tokenImplementation`, the implementation in the token's EIP-1967 slot must still be that address;
+//!   otherwise the payment is logged or rejected. EVM only.
+//! - `maxValue` — token units, e.g. `"1000000000000"`. Authorizations of a larger value are rejected with
+//!   `value_out_of_range`, as are, if `EVM_CHECK_TOTAL_SUPPLY` is enabled, values above the token's total supply. EVM only.
+//! - `verifyingContract` — address used as `verifyingContract` in the EIP-712 domain that authorizations are
+//!   signed against, for tokens settled through a proxy or wrapper bound to another address. Transfers are still
+//!   executed against `address`. Defaults to `address`. EVM only.
+//! - `transferFeeBps` — fee a fee-on-transfer token deducts from every transfer, in basis points, e.g. `100` for 1%.
+//!   The receiver gets the transferred value less the fee, rounded down as tokens do, so the authorized value must be
+//!   the gross amount that leaves `payTo` with `maxAmountRequired`; less is rejected with `insufficient_value`.
+//!   Payments forwarded by a facilitator signer pay the fee twice, and forward what the signer received.
+//!   Must be below `10000`. EVM only. Rebasing tokens, whose balances change without transfers, are not
+//!   supported; share-based ones can be described with `valueModel: "convertToAssets"`.
+//! - `authorizationKinds` — ERC-3009 functions the token implements, of `"transferWithAuthorization"` and
+//!   `"receiveWithAuthorization"`, e.g. `["transferWithAuthorization"]`. Requirements asking for another
+//!   `authorizationKind` are rejected, and `/supported` advertises the kinds some known token offers.
+//!   If omitted, both are offered. EVM only.
+//! - `minAmount`, `maxAmount` — token units, e.g. `"10000"`. Payments of a smaller amount, not worth settling, are
+//!   rejected with `below_minimum`, and of a larger one with `above_maximum`. The amount is the authorized value on
+//!   EVM and the transferred amount on Solana. Unlike `maxValue`, a sanity ceiling, these are policy and are
+//!   advertised as `amountBounds` in `/supported`, so clients know the accepted range before paying. A `minAmount`
+//!   above the `maxAmount` fails startup.
+//!
+//! Independently of per-token settings, `ASSET_ALLOWLIST_<NETWORK>` restricts a network to the listed token
+//! addresses, comma-separated, e.g. `ASSET_ALLOWLIST_BASE=0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913`.
+//! Payments in any other token are rejected before signatures are checked, and the list is advertised as
+//! `allowedAssets` in `/supported`. If unset, any token may be paid in.
+
+use alloy::primitives::U256;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::chain::FacilitatorLocalError;
+use crate::chain::value_model::{ValueModel, ValueModelKind};
+use crate::from_env;
+use crate::network::{Network, USDCDeployment};
+use crate::types::{
+    AssetAmountBounds, AuthorizationKind, EvmAddress, MixedAddress, PaymentRequirements, Scheme,
+    TokenAmount,
+};
+
+/// Settings for a single token on a single network.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenConfig {
+    /// Token contract (EVM) or mint (Solana) address.
+    pub address: MixedAddress,
+    /// Gas limit floor for settlement transactions, for tokens with transfer hooks
+    /// (e.g. ERC-1363) that consume more gas than estimates suggest.
+    #[serde(default)]
+    pub gas_limit: Option<u64>,
+    /// Settle by unwrapping the token (`withdraw`) and forwarding native currency to `payTo`.
+    #[serde(default)]
+    pub unwrap_native: bool,
+    /// Schemes offered for this token. `None` offers every scheme.
+    #[serde(default)]
+    pub schemes: Option<Vec<Scheme>>,
+    /// How balances of the token compare to transfer amounts.
+    #[serde(default)]
+    pub value_model: ValueModelKind,
+    /// Signer balance above which the token is swept to the treasury. `None` never sweeps.
+    #[serde(default)]
+    pub sweep_threshold: Option<TokenAmount>,
+    /// What to do if the token's proxy implementation changed since the requirements were issued.
+    /// `None` does not check.
+    #[serde(default)]
+    pub implementation_check: Option<ImplementationCheck>,
+    /// Largest authorized value accepted for the token, in token units. `None` accepts up to the total supply.
+    #[serde(default)]
+    pub max_value: Option<TokenAmount>,
+    /// `verifyingContract` of the token's EIP-712 domain. `None` uses the token address.
+    #[serde(default)]
+    pub verifying_contract: Option<EvmAddress>,
+    /// Fee deducted from every transfer of the token, in basis points. `None` for standard tokens.
+    #[serde(default)]
+    pub transfer_fee_bps: Option<u16>,
+    /// Smallest payment accepted in the token, in token units. `None` accepts any amount.
+    #[serde(default)]
+    pub min_amount: Option<TokenAmount>,
+    /// Largest payment accepted in the token, in token units. `None` accepts any amount.
+    #[serde(default)]
+    pub max_amount: Option<TokenAmount>,
+    /// ERC-3009 functions the token implements. `None` offers both.
+    #[serde(default)]
+    pub authorization_kinds: Option<Vec<AuthorizationKind>>,
+}
+
+impl TokenConfig {
+    /// Checks the settings that parse but can not work: a `transferFeeBps` of the whole transfer or more,
+    /// and a `minAmount` above the `maxAmount`, which would reject every payment.
+    fn validate(&self) -> Result<(), String> {
+        if let Some(bps) = self.transfer_fee_bps
+            && bps >= BPS
+        {
+            return Err(format!(
+                "transferFeeBps of {} must be below {BPS}, got {bps}",
+                self.address
+            ));
+        }
+        if let (Some(min_amount), Some(max_amount)) = (self.min_amount, self.max_amount)
+            && min_amount > max_amount
+        {
+            return Err(format!(
+                "minAmount {min_amount} of {} is above its maxAmount {max_amount}",
+                self.address
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Basis points in a whole, an upper bound of `transferFeeBps`.
+const BPS: u16 = 10_000;
+
+/// Handling of a token whose EIP-1967 implementation differs from `extra.tokenImplementation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImplementationCheck {
+    /// Log a warning and accept the payment.
+    Warn,
+    /// Reject the payment.
+    Reject,
+}
+
+impl TokenConfig {
+    fn offers(&self, scheme: Scheme) -> bool {
+        self.schemes
+            .as_ref()
+            .is_none_or(|schemes| schemes.contains(&scheme))
+    }
+
+    fn offers_authorization_kind(&self, kind: AuthorizationKind) -> bool {
+        self.authorization_kinds
+            .as_ref()
+            .is_none_or(|kinds| kinds.contains(&kind))
+    }
+}
+
+/// Token settings for a network, keyed by token address.
+#[derive(Debug, Clone, Default)]
+pub struct TokenConfigs {
+    tokens: HashMap<MixedAddress, TokenConfig>,
+    /// Tokens payments are restricted to. `None` allows any token.
+    allowlist: Option<HashSet<MixedAddress>>,
+}
+
+impl TokenConfigs {
+    /// Reads token settings for `network` from `TOKENS_<NETWORK>`, and its allowlist from `ASSET_ALLOWLIST_<NETWORK>`.
+    ///
+    /// Returns an empty set if the variables are not defined, and an error if they do not parse.
+    pub fn from_env(network: Network) -> Result<Self, Box<dyn std::error::Error>> {
+        let env_var = from_env::env_name_for_network(from_env::ENV_TOKENS_PREFIX, network);
+        let mut configs: Self = match std::env::var(&env_var) {
+            Ok(raw) => {
+                let tokens: Vec<TokenConfig> = serde_json::from_str(&raw)
+                    .map_err(|e| format!("env {env_var} is invalid: {e}"))?;
+                for token in &tokens {
+                    token
+                        .validate()
+                        .map_err(|e| format!("env {env_var} is invalid: {e}"))?;
+                }
+                tokens.into_iter().collect()
+            }
+            Err(_) => Self::default(),
+        };
+        let env_var = from_env::env_name_for_network(from_env::ENV_ASSET_ALLOWLIST_PREFIX, network);
+        if let Ok(raw) = std::env::var(&env_var) {
+            let allowlist = from_env::list_entries(&raw)
+                .map(|(_, address)| address.map_err(|e| format!("env {env_var} is invalid: {e}")))
+                .collect::<Result<_, _>>()?;
+            configs = configs.with_allowlist(allowlist);
+        }
+        Ok(configs)
+    }
+
+    /// Restricts payments to the tokens in `allowlist`.
+    pub fn with_allowlist(mut self, allowlist: HashSet<MixedAddress>) -> Self {
+        self.allowlist = Some(allowlist);
+        self
+    }
+
+    /// The tokens payments are restricted to, sorted, or `None` if any token is allowed.
+    pub fn allowed_assets(&self) -> Option<Vec<MixedAddress>> {
+        self.allowlist.as_ref().map(|allowlist| {
+            let mut assets: Vec<_> = allowlist.iter().cloned().collect();
+            assets.sort_by_key(|asset| asset.to_string());
+            assets
+        })
+    }
+
+    /// Whether payments in the token at `address` are allowed.
+    pub fn allows_asset(&self, address: &MixedAddress) -> bool {
+        self.allowlist
+            .as_ref()
+            .is_none_or(|allowlist| allowlist.contains(address))
+    }
+
+    /// Checks that the asset in `requirements` is on the network's allowlist, if one is configured.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::UnsupportedAsset`] if the allowlist does not contain it.
+    pub fn assert_asset_allowed(
+        &self,
+        requirements: &PaymentRequirements,
+    ) -> Result<(), FacilitatorLocalError> {
+        if self.allows_asset(&requirements.asset) {
+            Ok(())
+        } else {
+            Err(FacilitatorLocalError::UnsupportedAsset(
+                None,
+                requirements.asset.clone(),
+            ))
+        }
+    }
+
+    /// Returns settings for the token at `address`, if any.
+    pub fn get(&self, address: &MixedAddress) -> Option<&TokenConfig> {
+        self.tokens.get(address)
+    }
+
+    /// Returns the [`ValueModel`] for the token at `address`, [`ValueModelKind::Standard`] if not configured.
+    pub fn value_model(&self, address: &MixedAddress) -> &'static dyn ValueModel {
+        self.get(address)
+            .map(|token| token.value_model)
+            .unwrap_or_default()
+            .model()
+    }
+
+    /// The `maxValue` ceiling for the token at `address`, if configured.
+    pub fn max_value(&self, address: &MixedAddress) -> Option<TokenAmount> {
+        self.get(address).and_then(|token| token.max_value)
+    }
+
+    /// The `verifyingContract` override for the token at `address`, if configured.
+    pub fn verifying_contract(&self, address: &MixedAddress) -> Option<EvmAddress> {
+        self.get(address).and_then(|token| token.verifying_contract)
+    }
+
+    /// The value authorized transfers of the token at `address` must have for the receiver to get `amount`:
+    /// `amount` itself, or for a fee-on-transfer token the smallest value that leaves `amount` after the fee.
+    pub fn gross_amount(&self, address: &MixedAddress, amount: U256) -> U256 {
+        let Some(fee_bps) = self.get(address).and_then(|token| token.transfer_fee_bps) else {
+            return amount;
+        };
+        if amount.is_zero() {
+            return amount;
+        }
+        // The receiver gets `value - floor(value * fee / BPS)`, i.e. `ceil(value * (BPS - fee) / BPS)`.
+        let kept = U256::from(BPS - fee_bps);
+        (amount - U256::from(1u8)).saturating_mul(U256::from(BPS)) / kept + U256::from(1u8)
+    }
+
+    /// What the receiver of a transfer of `value` of the token at `address` gets: `value` itself, or for a
+    /// fee-on-transfer token `value` less the fee, rounded down.
+    pub fn net_amount(&self, address: &MixedAddress, value: U256) -> U256 {
+        let Some(fee_bps) = self.get(address).and_then(|token| token.transfer_fee_bps) else {
+            return value;
+        };
+        value - value.saturating_mul(U256::from(fee_bps)) / U256::from(BPS)
+    }
+
+    /// The `minAmount` and `maxAmount` of allowed tokens offering `scheme`, sorted by address,
+    /// or `None` if no such token has either.
+    pub fn amount_bounds(&self, scheme: Scheme) -> Option<Vec<AssetAmountBounds>> {
+        let mut bounds: Vec<_> = self
+            .tokens
+            .values()
+            .filter(|token| token.min_amount.is_some() || token.max_amount.is_some())
+            .filter(|token| self.allows_asset(&token.address) && token.offers(scheme))
+            .map(|token| AssetAmountBounds {
+                asset: token.address.clone(),
+                min_amount: token.min_amount,
+                max_amount: token.max_amount,
+            })
+            .collect();
+        bounds.sort_by_key(|bounds| bounds.asset.to_string());
+        (!bounds.is_empty()).then_some(bounds)
+    }
+
+    /// Checks that a payment of `amount` in the token at `asset` is within its `minAmount` and `maxAmount`.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::BelowMinimum`] or [`FacilitatorLocalError::AboveMaximum`],
+    /// attributed to `payer`, naming the bound that was crossed.
+    pub fn assert_amount_in_bounds(
+        &self,
+        payer: MixedAddress,
+        asset: &MixedAddress,
+        amount: TokenAmount,
+    ) -> Result<(), FacilitatorLocalError> {
+        let Some(token) = self.get(asset) else {
+            return Ok(());
+        };
+        if let Some(min_amount) = token.min_amount
+            && amount < min_amount
+        {
+            return Err(FacilitatorLocalError::BelowMinimum(
+                payer,
+                format!("amount {amount} is below the token's minimum of {min_amount}"),
+            ));
+        }
+        if let Some(max_amount) = token.max_amount
+            && amount > max_amount
+        {
+            return Err(FacilitatorLocalError::AboveMaximum(
+                payer,
+                format!("amount {amount} is above the token's maximum of {max_amount}"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// An iterator over all configured tokens.
+    pub fn iter(&self) -> impl Iterator<Item = &TokenConfig> {
+        self.tokens.values()
+    }
+
+    /// Whether `scheme` is offered for the token at `address`.
+    pub fn offers_scheme(&self, address: &MixedAddress, scheme: Scheme) -> bool {
+        self.get(address).is_none_or(|token| token.offers(scheme))
+    }
+
+    /// Whether `scheme` is offered for any known token on `network`: its USDC deployment or a configured token.
+    pub fn network_offers_scheme(&self, network: Network, scheme: Scheme) -> bool {
+        let usdc = USDCDeployment::by_network(network).address();
+        self.offers_scheme(&usdc, scheme) || self.tokens.values().any(|token| token.offers(scheme))
+    }
+
+    /// Whether the ERC-3009 function `kind` is offered for the token at `address`.
+    pub fn offers_authorization_kind(
+        &self,
+        address: &MixedAddress,
+        kind: AuthorizationKind,
+    ) -> bool {
+        self.get(address)
+            .is_none_or(|token| token.offers_authorization_kind(kind))
+    }
+
+    /// The ERC-3009 functions offered for some allowed known token on `network` that offers `scheme`:
+    /// its USDC deployment or a configured token.
+    pub fn authorization_kinds(&self, network: Network, scheme: Scheme) -> Vec<AuthorizationKind> {
+        let usdc = USDCDeployment::by_network(network).address();
+        let mut tokens = self.tokens.keys().collect::<Vec<_>>();
+        if !self.tokens.contains_key(&usdc) {
+            tokens.push(&usdc);
+        }
+        [AuthorizationKind::Transfer, AuthorizationKind::Receive]
+            .into_iter()
+            .filter(|kind| {
+                tokens.iter().any(|address| {
+                    self.allows_asset(address)
+                        && self.offers_scheme(address, scheme)
+                        && self.offers_authorization_kind(address, *kind)
+                })
+            })
+            .collect()
+    }
+
+    /// Checks that the ERC-3009 function `requirements` ask for is offered for its asset.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::UnsupportedAuthorizationKind`] if the token's `authorizationKinds` excludes it.
+    pub fn assert_authorization_kind_offered(
+        &self,
+        requirements: &PaymentRequirements,
+    ) -> Result<(), FacilitatorLocalError> {
+        let kind = requirements.authorization_kind.unwrap_or_default();
+        if self.offers_authorization_kind(&requirements.asset, kind) {
+            Ok(())
+        } else {
+            Err(FacilitatorLocalError::UnsupportedAuthorizationKind(
+                None,
+                kind,
+                requirements.asset.clone(),
+            ))
+        }
+    }
+
+    /// Checks that the scheme in `requirements` is offered for its asset.
+    ///
+    /// # Errors
+    /// Returns [`FacilitatorLocalError::UnsupportedScheme`] if the token's `schemes` list excludes it.
+    pub fn assert_scheme_offered(
+        &self,
+        requirements: &PaymentRequirements,
+    ) -> Result<(), FacilitatorLocalError> {
+        if self.offers_scheme(&requirements.asset, requirements.scheme) {
+            Ok(())
+        } else {
+            Err(FacilitatorLocalError::UnsupportedScheme(
+                None,
+                requirements.scheme,
+                requirements.asset.clone(),
+            ))
+        }
+    }
+}
+
+impl FromIterator<TokenConfig> for TokenConfigs {
+    fn from_iter<T: IntoIterator<Item = TokenConfig>>(iter: T) -> Self {
+        let tokens = iter
+            .into_iter()
+            .map(|token| (token.address.clone(), token))
+            .collect();
+        Self {
+            tokens,
+            allowlist: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::address;
+
+    #[test]
+    fn test_gross_amount_covers_the_transfer_fee() {
+        let token =
+            MixedAddress::Evm(address!("0x0000000000000000000000000000000000000fee").into());
+        let configs: TokenConfigs = [TokenConfig {
+            transfer_fee_bps: Some(100),
+            ..serde_json::from_value(serde_json::json!({"address": token})).unwrap()
+        }]
+        .into_iter()
+        .collect();
+        let received = |value: u64| value - value * 100 / 10_000;
+
+        assert_eq!(
+            configs.gross_amount(&token, U256::from(990u64)),
+            U256::from(999u64)
+        );
+        for amount in [1u64, 99, 1_000_000, 123_456_789] {
+            let gross: u64 = configs
+                .gross_amount(&token, U256::from(amount))
+                .try_into()
+                .unwrap();
+            assert!(received(gross) >= amount);
+            assert!(received(gross - 1) < amount);
+        }
+        let standard = MixedAddress::Offchain("standard".to_string());
+        assert_eq!(
+            configs.gross_amount(&standard, U256::from(990u64)),
+            U256::from(990u64)
+        );
+        assert_eq!(
+            configs.net_amount(&token, U256::from(1000u64)),
+            U256::from(990u64)
+        );
+        assert_eq!(
+            configs.net_amount(&token, U256::from(999u64)),
+            U256::from(990u64)
+        );
+        assert_eq!(
+            configs.net_amount(&standard, U256::from(1000u64)),
+            U256::from(1000u64)
+        );
+    }
+
+    #[test]
+    fn test_amounts_outside_bounds_are_rejected_and_advertised() {
+        let token =
+            MixedAddress::Evm(address!("0x0000000000000000000000000000000000000b0d").into());
+        let configs: TokenConfigs = [serde_json::from_value::<TokenConfig>(serde_json::json!({
+            "address": token,
+            "minAmount": "1000",
+            "maxAmount": "5000000",
+            "schemes": ["exact"],
+        }))
+        .unwrap()]
+        .into_iter()
+        .collect();
+        let payer = MixedAddress::Offchain("payer".to_string());
+        let check = |amount: u64| {
+            configs.assert_amount_in_bounds(payer.clone(), &token, TokenAmount::from(amount))
+        };
+
+        assert!(matches!(
+            check(999),
+            Err(FacilitatorLocalError::BelowMinimum(..))
+        ));
+        assert!(check(1_000).is_ok());
+        assert!(check(5_000_000).is_ok());
+        assert!(matches!(
+            check(5_000_001),
+            Err(FacilitatorLocalError::AboveMaximum(..))
+        ));
+        let unbounded = MixedAddress::Offchain("unbounded".to_string());
+        assert!(
+            configs
+                .assert_amount_in_bounds(payer.clone(), &unbounded, TokenAmount::from(1u64))
+                .is_ok()
+        );
+
+        assert_eq!(
+            configs.amount_bounds(Scheme::Exact),
+            Some(vec![AssetAmountBounds {
+                asset: token.clone(),
+                min_amount: Some(TokenAmount::from(1_000u64)),
+                max_amount: Some(TokenAmount::from(5_000_000u64)),
+            }])
+        );
+        assert_eq!(configs.amount_bounds(Scheme::Upto), None);
+    }
+
+    #[test]
+    fn test_unworkable_token_settings_are_refused() {
+        let config = |settings: serde_json::Value| {
+            let mut token =
+                serde_json::json!({ "address": "0x0000000000000000000000000000000000000b0d" });
+            token
+                .as_object_mut()
+                .unwrap()
+                .extend(settings.as_object().unwrap().clone());
+            serde_json::from_value::<TokenConfig>(token).unwrap()
+        };
+        let bounds =
+            |min: &str, max: &str| serde_json::json!({ "minAmount": min, "maxAmount": max });
+        assert!(config(bounds("1000", "1000")).validate().is_ok());
+        assert!(config(bounds("1001", "1000")).validate().is_err());
+        assert!(
+            config(serde_json::json!({ "minAmount": "1001" }))
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            config(serde_json::json!({ "transferFeeBps": 9_999 }))
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            config(serde_json::json!({ "transferFeeBps": 10_000 }))
+                .validate()
+                .is_err()
+        );
+    }
+
+    fn requirements(asset: MixedAddress) -> PaymentRequirements {
+        PaymentRequirements {
+            scheme: Scheme::Exact,
+            network: Network::Base,
+            max_amount_required: TokenAmount::from(1u64),
+            resource: "https://example.com/paid".parse().unwrap(),
+            description: String::new(),
+            mime_type: "application/json".to_string(),
+            output_schema: None,
+            pay_to: MixedAddress::Offchain("merchant".to_string()),
+            max_timeout_seconds: 60,
+            asset,
+            extra: None,
+            splits: None,
+            authorization_kind: None,
+        }
+    }
+
+    #[test]
+    fn test_schemes_follow_token_config() {
+        let usdc = USDCDeployment::by_network(Network::Base).address();
+        assert!(TokenConfigs::default().network_offers_scheme(Network::Base, Scheme::Upto));
+
+        let configs: TokenConfigs = [serde_json::from_value::<TokenConfig>(serde_json::json!({
+            "address": usdc,
+            "schemes": ["exact"],
+        }))
+        .unwrap()]
+        .into_iter()
+        .collect();
+        let mut requirements = requirements(usdc);
+        assert!(configs.assert_scheme_offered(&requirements).is_ok());
+        assert!(configs.network_offers_scheme(Network::Base, Scheme::Exact));
+        requirements.scheme = Scheme::Upto;
+        assert!(matches!(
+            configs.assert_scheme_offered(&requirements),
+            Err(FacilitatorLocalError::UnsupportedScheme(
+                None,
+                Scheme::Upto,
+                _
+            ))
+        ));
+        assert!(!configs.network_offers_scheme(Network::Base, Scheme::Upto));
+        // Unconfigured tokens offer every scheme.
+        requirements.asset =
+            MixedAddress::Evm(address!("0x0000000000000000000000000000000000000b0d").into());
+        assert!(configs.assert_scheme_offered(&requirements).is_ok());
+    }
+
+    #[test]
+    fn test_authorization_kinds_follow_token_config() {
+        let usdc = USDCDeployment::by_network(Network::Base).address();
+        let transfer_only = |address: &MixedAddress| {
+            serde_json::from_value::<TokenConfig>(serde_json::json!({
+                "address": address,
+                "authorizationKinds": ["transferWithAuthorization"],
+            }))
+            .unwrap()
+        };
+        let both = vec![AuthorizationKind::Transfer, AuthorizationKind::Receive];
+        assert_eq!(
+            TokenConfigs::default().authorization_kinds(Network::Base, Scheme::Exact),
+            both
+        );
+
+        let configs: TokenConfigs = [transfer_only(&usdc)].into_iter().collect();
+        assert_eq!(
+            configs.authorization_kinds(Network::Base, Scheme::Exact),
+            vec![AuthorizationKind::Transfer]
+        );
+        let mut requirements = requirements(usdc.clone());
+        assert!(
+            configs
+                .assert_authorization_kind_offered(&requirements)
+                .is_ok()
+        );
+        requirements.authorization_kind = Some(AuthorizationKind::Receive);
+        assert!(matches!(
+            configs.assert_authorization_kind_offered(&requirements),
+            Err(FacilitatorLocalError::UnsupportedAuthorizationKind(..))
+        ));
+
+        // Another token still offering both is advertised, unless it is not allowed.
+        let other =
+            MixedAddress::Evm(address!("0x0000000000000000000000000000000000000b0d").into());
+        let configs: TokenConfigs = [
+            transfer_only(&usdc),
+            serde_json::from_value(serde_json::json!({"address": other})).unwrap(),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            configs.authorization_kinds(Network::Base, Scheme::Exact),
+            both
+        );
+        let configs = configs.with_allowlist(HashSet::from([usdc]));
+        assert_eq!(
+            configs.authorization_kinds(Network::Base, Scheme::Exact),
+            vec![AuthorizationKind::Transfer]
+        );
+    }
+
+    #[test]
+    fn test_allowlist_rejects_unlisted_assets() {
+        let usdc = MixedAddress::Evm(address!("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913").into());
+        let open = TokenConfigs::default();
+        assert!(open.allowed_assets().is_none());
+        assert!(
+            open.assert_asset_allowed(&requirements(usdc.clone()))
+                .is_ok()
+        );
+
+        let restricted = open.with_allowlist(HashSet::from([usdc.clone()]));
+        assert_eq!(restricted.allowed_assets(), Some(vec![usdc.clone()]));
+        assert!(restricted.assert_asset_allowed(&requirements(usdc)).is_ok());
+        assert!(matches!(
+            restricted.assert_asset_allowed(&requirements(MixedAddress::Evm(
+                address!("0x0000000000000000000000000000000000000bad").into()
+            ))),
+            Err(FacilitatorLocalError::UnsupportedAsset(None, _))
+        ));
+    }
+}
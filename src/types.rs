@@ -7,7 +7,7 @@
 //! This module supports ERC-3009 style authorization for tokens (EIP-712 typed signatures),
 //! and provides serialization logic compatible with external clients.
 
-use alloy::primitives::{Bytes, U256};
+use alloy::primitives::{Bytes, U256, keccak256};
 use alloy::{hex, sol};
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD as b64;
@@ -30,25 +30,46 @@ use crate::network::Network;
 use crate::timestamp::UnixTimestamp;
 
 /// Represents the protocol version. Currently only version 1 is supported.
-#[derive(Debug, Copy, Clone)]
+///
+/// Supporting a new version means adding a variant, its number in [`X402Version::number`],
+/// and listing it in [`X402Version::SUPPORTED`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum X402Version {
     /// Version `1`.
     V1,
 }
 
-impl Serialize for X402Version {
-    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+impl X402Version {
+    /// Versions accepted by this facilitator, oldest first.
+    pub const SUPPORTED: &[X402Version] = &[X402Version::V1];
+
+    /// The version number used on the wire.
+    pub fn number(self) -> u8 {
         match self {
-            X402Version::V1 => serializer.serialize_u8(1),
+            X402Version::V1 => 1,
         }
     }
+
+    /// The oldest supported version.
+    pub fn min_supported() -> X402Version {
+        Self::SUPPORTED[0]
+    }
+
+    /// The newest supported version.
+    pub fn max_supported() -> X402Version {
+        Self::SUPPORTED[Self::SUPPORTED.len() - 1]
+    }
+}
+
+impl Serialize for X402Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.number())
+    }
 }
 
 impl Display for X402Version {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            X402Version::V1 => write!(f, "1"),
-        }
+        write!(f, "{}", self.number())
     }
 }
 
@@ -57,7 +78,13 @@ pub struct X402VersionError(pub u8);
 
 impl Display for X402VersionError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Unsupported x402Version: {}", self.0)
+        write!(
+            f,
+            "Unsupported x402Version: {}, supported versions are {} to {}",
+            self.0,
+            X402Version::min_supported(),
+            X402Version::max_supported()
+        )
     }
 }
 
@@ -67,10 +94,11 @@ impl TryFrom<u8> for X402Version {
     type Error = X402VersionError;
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            1 => Ok(X402Version::V1),
-            _ => Err(X402VersionError(value)),
-        }
+        X402Version::SUPPORTED
+            .iter()
+            .copied()
+            .find(|version| version.number() == value)
+            .ok_or(X402VersionError(value))
     }
 }
 
@@ -84,23 +112,67 @@ impl<'de> Deserialize<'de> for X402Version {
     }
 }
 
-/// Enumerates payment schemes. Only "exact" is supported in this implementation,
-/// meaning the amount to be transferred must match exactly.
+/// Enumerates payment schemes: "exact", where the amount to be transferred must match exactly,
+/// and "upto", where any amount up to `maxAmountRequired` is accepted.
+///
+/// Deserialization is strict: any other string, such as a mistyped `"exacct"`, is refused, and a
+/// request body carrying one is rejected with an [`UnknownSchemeResponse`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Scheme {
     Exact,
+    Upto,
+}
+
+impl Scheme {
+    /// Return all known [`Scheme`] variants.
+    pub fn variants() -> &'static [Scheme] {
+        &[Scheme::Exact, Scheme::Upto]
+    }
 }
 
 impl Display for Scheme {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self {
             Scheme::Exact => "exact",
+            Scheme::Upto => "upto",
         };
         write!(f, "{s}")
     }
 }
 
+/// How the transferred amount must relate to `maxAmountRequired`, as implied by a [`Scheme`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmountScheme {
+    /// The amount must equal `maxAmountRequired`.
+    Exact,
+    /// The amount must not exceed `maxAmountRequired`.
+    UpTo,
+}
+
+impl From<Scheme> for AmountScheme {
+    fn from(scheme: Scheme) -> Self {
+        match scheme {
+            Scheme::Exact => AmountScheme::Exact,
+            Scheme::Upto => AmountScheme::UpTo,
+        }
+    }
+}
+
+impl AmountScheme {
+    /// Compares `sent` to `max_amount_required`: [`Ordering::Less`] if too little was sent,
+    /// [`Ordering::Greater`] if too much, and [`Ordering::Equal`] if the amount is acceptable.
+    /// Nothing sent is too little for either scheme.
+    pub fn compare<T: Ord + Default>(self, sent: T, max_amount_required: T) -> std::cmp::Ordering {
+        match self {
+            AmountScheme::Exact => sent.cmp(&max_amount_required),
+            AmountScheme::UpTo if sent > max_amount_required => std::cmp::Ordering::Greater,
+            AmountScheme::UpTo if sent == T::default() => std::cmp::Ordering::Less,
+            AmountScheme::UpTo => std::cmp::Ordering::Equal,
+        }
+    }
+}
+
 /// Represents an EVM signature used in EIP-712 typed data.
 /// Serialized as 0x-prefixed hex string.
 /// Used to authorize an ERC-3009 transferWithAuthorization.
@@ -297,6 +369,36 @@ pub struct PaymentPayload {
     pub scheme: Scheme,
     pub network: Network,
     pub payload: ExactPaymentPayload,
+    /// Ecosystem-specific fields, echoed back in responses and settlement events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Extensions>,
+}
+
+/// Ecosystem-specific fields of a [`PaymentPayload`], such as an order reference, that the facilitator
+/// carries through verification and settlement without interpreting them.
+///
+/// Keys are namespaced as `<namespace>.<name>`, e.g. `acme.orderRef`, so extensions from different
+/// ecosystems do not collide. Values are arbitrary JSON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Extensions(pub serde_json::Map<String, serde_json::Value>);
+
+impl<'de> Deserialize<'de> for Extensions {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let fields = serde_json::Map::deserialize(deserializer)?;
+        if let Some(key) = fields.keys().find(|key| {
+            !key.split_once('.')
+                .is_some_and(|(namespace, name)| !namespace.is_empty() && !name.is_empty())
+        }) {
+            return Err(serde::de::Error::custom(format!(
+                "extension `{key}` is not namespaced as `<namespace>.<name>`"
+            )));
+        }
+        Ok(Extensions(fields))
+    }
 }
 
 /// Error returned when decoding a base64-encoded [`PaymentPayload`] fails.
@@ -762,7 +864,7 @@ impl Serialize for MixedAddress {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TransactionHash {
     /// A 32-byte EVM transaction hash, encoded as 0x-prefixed hex string.
     Evm([u8; 32]),
@@ -843,6 +945,47 @@ pub struct PaymentRequirements {
     pub max_timeout_seconds: u64,
     pub asset: MixedAddress,
     pub extra: Option<serde_json::Value>,
+    /// Optional split of the payment among several receivers, replacing `payTo`.
+    ///
+    /// The amounts must sum to the authorized value. The payer authorizes the transfer to a
+    /// facilitator signer, which forwards each share after settlement. EVM only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub splits: Option<Vec<PaymentSplit>>,
+    /// ERC-3009 function the payment is settled with, `transferWithAuthorization` if not set. EVM only.
+    ///
+    /// With `receiveWithAuthorization`, the payer authorizes the transfer to a facilitator signer, which
+    /// submits it itself and forwards the payment to `payTo`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorization_kind: Option<AuthorizationKind>,
+}
+
+/// The ERC-3009 function an authorization is signed for, see [`PaymentRequirements::authorization_kind`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum AuthorizationKind {
+    /// `transferWithAuthorization`: anyone may submit the authorization.
+    #[default]
+    #[serde(rename = "transferWithAuthorization")]
+    Transfer,
+    /// `receiveWithAuthorization`: only the receiver, a facilitator signer, may submit the authorization on-chain.
+    #[serde(rename = "receiveWithAuthorization")]
+    Receive,
+}
+
+impl Display for AuthorizationKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthorizationKind::Transfer => write!(f, "transferWithAuthorization"),
+            AuthorizationKind::Receive => write!(f, "receiveWithAuthorization"),
+        }
+    }
+}
+
+/// A share of a split payment, see [`PaymentRequirements::splits`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentSplit {
+    pub pay_to: MixedAddress,
+    pub amount: TokenAmount,
 }
 
 impl PaymentRequirements {
@@ -896,13 +1039,159 @@ impl VerifyRequest {
     pub fn network(&self) -> Network {
         self.payment_payload.network
     }
+
+    /// The payer named by the payload, before any verification.
+    ///
+    /// `None` for Solana, where the payer is only known once the transaction is decoded.
+    pub fn payer(&self) -> Option<MixedAddress> {
+        match &self.payment_payload.payload {
+            ExactPaymentPayload::Evm(payload) => Some(payload.authorization.from.into()),
+            ExactPaymentPayload::Solana(_) => None,
+        }
+    }
+
+    /// Deterministic [`PaymentId`] of the payment carried by this request.
+    ///
+    /// The same payment yields the same identifier whether it is sent to `/verify` or `/settle`.
+    pub fn payment_id(&self) -> PaymentId {
+        let network = self.payment_payload.network.to_string();
+        let asset = self.payment_requirements.asset.to_string();
+        let mut preimage = Vec::new();
+        preimage.extend_from_slice(network.as_bytes());
+        preimage.push(0);
+        preimage.extend_from_slice(asset.as_bytes());
+        preimage.push(0);
+        match &self.payment_payload.payload {
+            ExactPaymentPayload::Evm(payload) => {
+                preimage.extend_from_slice(payload.authorization.from.0.as_slice());
+                preimage.extend_from_slice(&payload.authorization.nonce.0);
+            }
+            ExactPaymentPayload::Solana(payload) => {
+                // The transfer and blockhash, which stay the same when the transaction is signed again.
+                match crate::chain::solana::transfer_preimage(&payload.transaction) {
+                    Some(transfer) => preimage.extend_from_slice(&transfer),
+                    None => preimage.extend_from_slice(payload.transaction.as_bytes()),
+                }
+            }
+        }
+        PaymentId(keccak256(preimage).0)
+    }
+}
+
+/// Stable identifier of a payment, returned in [`VerifyResponse`] and [`SettleResponse`].
+///
+/// Derived as `keccak256` over the network, the token, the payer, and the authorization nonce; for
+/// Solana, over the mint, source, destination and amount of the transfer, and the recent blockhash.
+/// For Solana, the payer-signed transaction takes the place of payer and nonce.
+/// Serialized as a 0x-prefixed hex string.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PaymentId(pub [u8; 32]);
+
+impl Debug for PaymentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PaymentId(0x{})", hex::encode(self.0))
+    }
+}
+
+impl Display for PaymentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", hex::encode(self.0))
+    }
+}
+
+impl FromStr for PaymentId {
+    type Err = hex::FromHexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode_to_array::<_, 32>(s)?;
+        Ok(PaymentId(bytes))
+    }
+}
+
+impl<'de> Deserialize<'de> for PaymentId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        PaymentId::from_str(&s).map_err(|_| serde::de::Error::custom("Invalid payment id"))
+    }
+}
+
+impl Serialize for PaymentId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
 }
 
 /// Wrapper for a payment payload and requirements sent by the client
 /// to be used for settlement.
 pub type SettleRequest = VerifyRequest;
 
-#[derive(Debug, Serialize, Deserialize, thiserror::Error)]
+/// Body of `POST /quote`: the requirements of a payment, and optionally its signed payload.
+///
+/// With an EVM payload, the settlement transaction itself is estimated; otherwise a default for the token is quoted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteRequest {
+    pub payment_requirements: PaymentRequirements,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_payload: Option<PaymentPayload>,
+}
+
+/// What settling a payment is expected to cost the facilitator, in the network's native currency.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteResponse {
+    pub network: Network,
+    /// Gas on EVM networks, compute units on Solana.
+    pub gas: u64,
+    /// Whether `gas` was estimated for the signed payload, rather than a default for the token.
+    pub gas_estimated: bool,
+    /// Of `gas`, what the transactions forwarding the payment from a facilitator signer take, e.g. for
+    /// split payments. Always `0` on Solana.
+    #[serde(default)]
+    pub forwarding_gas: u64,
+    /// Current price per unit of gas: wei on EVM networks, micro-lamports per compute unit on Solana.
+    pub gas_price: TokenAmount,
+    /// Expected total cost in the smallest native unit: wei, or lamports including the signature fees on Solana.
+    pub total_cost: TokenAmount,
+    /// Decimals of the native currency, to scale `totalCost`.
+    pub native_decimals: u8,
+    /// `totalCost` in USD, if a native currency price is configured in `NATIVE_USD_PRICE_<NETWORK>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_cost_usd: Option<f64>,
+}
+
+/// Body of `POST /cancel`: an ERC-3009 `cancelAuthorization` signed by the authorizer.
+///
+/// Cancels the authorization `nonce` of `authorizer` for the token `asset`, so it can no longer be settled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelRequest {
+    pub network: Network,
+    pub asset: MixedAddress,
+    pub authorizer: EvmAddress,
+    pub nonce: HexEncodedNonce,
+    /// The authorizer's signature of the EIP-712 `CancelAuthorization(address authorizer,bytes32 nonce)`.
+    pub signature: EvmSignature,
+}
+
+/// Result of `POST /cancel`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelResponse {
+    pub success: bool,
+    pub authorizer: EvmAddress,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionHash>,
+    pub network: Network,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, thiserror::Error)]
 #[serde(untagged, rename_all = "camelCase")]
 pub enum FacilitatorErrorReason {
     /// Payer doesn't have sufficient funds.
@@ -921,13 +1210,113 @@ pub enum FacilitatorErrorReason {
     #[error("unexpected_settle_error")]
     #[serde(rename = "unexpected_settle_error")]
     UnexpectedSettleError,
+    /// The payload transfers more than an `exact` scheme requires, or more than an `upto` maximum.
+    #[error("excess_value")]
+    #[serde(rename = "excess_value")]
+    ExcessValue,
+    /// The payload transfers more than the token's configured ceiling or total supply.
+    #[error("value_out_of_range")]
+    #[serde(rename = "value_out_of_range")]
+    ValueOutOfRange,
+    /// The payment's amount is below the token's `minAmount`, advertised in `/supported`.
+    #[error("below_minimum")]
+    #[serde(rename = "below_minimum")]
+    BelowMinimum,
+    /// The payment's amount is above the token's `maxAmount`, advertised in `/supported`.
+    #[error("above_maximum")]
+    #[serde(rename = "above_maximum")]
+    AboveMaximum,
+    /// The authorization was already submitted for settlement.
+    #[error("nonce_reused")]
+    #[serde(rename = "nonce_reused")]
+    NonceReused,
+    /// The payment's `payTo` is not on the facilitator's receiver allowlist.
+    #[error("receiver_not_allowed")]
+    #[serde(rename = "receiver_not_allowed")]
+    ReceiverNotAllowed,
     #[error("{0}")]
     FreeForm(String),
 }
 
+/// EIP-712 domain of a token, supplied by the caller instead of being read from the chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip712DomainParams {
+    pub name: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub verifying_contract: EvmAddress,
+}
+
+/// Request to verify an EVM payment without chain access, see `POST /verify/offline`.
+///
+/// Everything the facilitator would normally read on-chain — the token's EIP-712 domain
+/// and decimals — is provided explicitly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineVerifyRequest {
+    pub x402_version: X402Version,
+    pub payment_payload: PaymentPayload,
+    pub payment_requirements: PaymentRequirements,
+    pub domain: Eip712DomainParams,
+    pub decimals: u8,
+}
+
+/// Result of an offline verification.
+///
+/// `balanceChecked` is always `false`: the payer's balance, the authorization nonce state,
+/// and contract-wallet signatures can only be checked on-chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineVerifyResponse {
+    pub is_valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invalid_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payer: Option<MixedAddress>,
+    /// Authorized value scaled by `decimals`, e.g. `1.5` for 1500000 units of a 6-decimal token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+    pub balance_checked: bool,
+}
+
+/// Request to recover the signer of an arbitrary message, see `POST /recover`.
+///
+/// Exactly one of `message` and `typedData` is expected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverRequest {
+    /// UTF-8 text signed with EIP-191 `personal_sign`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// EIP-712 typed data, as passed to `eth_signTypedData_v4`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typed_data: Option<alloy::dyn_abi::TypedData>,
+    pub signature: EvmSignature,
+    /// Expected signer. If it is not the recovered address, it is checked as an EIP-1271 contract wallet on `network`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<EvmAddress>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<Network>,
+}
+
+/// Result of `POST /recover`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverResponse {
+    /// Digest that was signed.
+    pub hash: alloy::primitives::B256,
+    /// Address the signature recovers to, if it is an ECDSA signature.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovered: Option<EvmAddress>,
+    /// Whether `signer` signed the message, directly or through EIP-1271. Omitted if no `signer` was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid: Option<bool>,
+}
+
 /// Returned from a facilitator after attempting to settle a payment on-chain.
 /// Indicates success/failure, transaction hash, and payer identity.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SettleResponse {
     pub success: bool,
@@ -936,7 +1325,76 @@ pub struct SettleResponse {
     pub payer: MixedAddress,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction: Option<TransactionHash>,
+    /// Block the transaction was included in, once it is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// Whether the transaction was only broadcast or is confirmed, for successful settlements.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<SettleStatus>,
     pub network: Network,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_id: Option<PaymentId>,
+    /// What the settlement cost the facilitator, if cost breakdowns are enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<SettlementCost>,
+    /// The payload's [`Extensions`], echoed back.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Extensions>,
+}
+
+/// Progress of a settlement transaction reported in a [`SettleResponse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettleStatus {
+    /// Broadcast to the network, not yet confirmed, see `POST /settle?wait=false`.
+    Submitted,
+    /// Included in a block and confirmed.
+    Confirmed,
+}
+
+/// A message sent over `GET /ws/settle` while a settlement progresses, tagged by `event`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum SettleEvent {
+    /// The settlement transaction was broadcast.
+    Submitted { transaction: TransactionHash },
+    /// The settlement transaction was replaced by `replacement`, with higher fees; only one of them is mined.
+    Replaced {
+        transaction: TransactionHash,
+        replacement: TransactionHash,
+    },
+    /// The settlement transaction is included in a block, not yet final.
+    Pending {
+        transaction: TransactionHash,
+        confirmations: u64,
+    },
+    /// The settlement succeeded, with what `POST /settle` would have answered.
+    Confirmed {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        confirmations: Option<u64>,
+        response: Box<SettleResponse>,
+    },
+    /// The settlement failed, with the body of the error `POST /settle` would have answered.
+    Failed { error: serde_json::Value },
+}
+
+/// Native-currency cost of the transactions sent to settle a payment, summed over all of them
+/// (e.g. the transfer and any forwarding transfers).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettlementCost {
+    /// Total gas used.
+    pub gas_used: u64,
+    /// Average price paid per unit of gas, in wei.
+    pub effective_gas_price: TokenAmount,
+    /// Total cost in wei, `gasUsed * effectiveGasPrice`.
+    pub total_cost: TokenAmount,
+    /// `totalCost` in USD, if a native currency price is configured in `NATIVE_USD_PRICE_<NETWORK>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_cost_usd: Option<f64>,
+    /// Amount of the payment token the facilitator kept of a payment it received and forwarded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facilitator_fee: Option<TokenAmount>,
 }
 
 /// Error returned when encoding a [`SettleResponse`] into base64 fails.
@@ -969,10 +1427,15 @@ impl TryInto<Base64Bytes<'static>> for SettleResponse {
 ///
 /// This response indicates whether the payment authorization is valid and identifies the payer. If invalid,
 /// it includes a reason describing why verification failed (e.g., wrong network, an invalid scheme, insufficient funds).
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum VerifyResponse {
     /// The payload matches the requirements and passes all checks.
-    Valid { payer: MixedAddress },
+    Valid {
+        payer: MixedAddress,
+        payment_id: Option<PaymentId>,
+        /// The payload's [`Extensions`], echoed back.
+        extensions: Option<Extensions>,
+    },
     /// The payload was well-formed but failed verification due to the specified [`FacilitatorErrorReason`]
     Invalid {
         reason: FacilitatorErrorReason,
@@ -985,7 +1448,11 @@ impl VerifyResponse {
     ///
     /// Indicates that the provided payment payload has been validated against the payment requirements.
     pub fn valid(payer: MixedAddress) -> Self {
-        VerifyResponse::Valid { payer }
+        VerifyResponse::Valid {
+            payer,
+            payment_id: None,
+            extensions: None,
+        }
     }
 
     /// Constructs a failed verification response with the given `payer` address and error `reason`.
@@ -995,6 +1462,38 @@ impl VerifyResponse {
     pub fn invalid(payer: Option<MixedAddress>, reason: FacilitatorErrorReason) -> Self {
         VerifyResponse::Invalid { reason, payer }
     }
+
+    /// Attaches `payment_id` to a successful verification response.
+    ///
+    /// Invalid responses are returned unchanged.
+    pub fn with_payment_id(self, payment_id: PaymentId) -> Self {
+        match self {
+            VerifyResponse::Valid {
+                payer, extensions, ..
+            } => VerifyResponse::Valid {
+                payer,
+                payment_id: Some(payment_id),
+                extensions,
+            },
+            invalid => invalid,
+        }
+    }
+
+    /// Attaches the payload's `extensions` to a successful verification response.
+    ///
+    /// Invalid responses are returned unchanged.
+    pub fn with_extensions(self, extensions: Option<Extensions>) -> Self {
+        match self {
+            VerifyResponse::Valid {
+                payer, payment_id, ..
+            } => VerifyResponse::Valid {
+                payer,
+                payment_id,
+                extensions,
+            },
+            invalid => invalid,
+        }
+    }
 }
 
 impl Serialize for VerifyResponse {
@@ -1003,14 +1502,24 @@ impl Serialize for VerifyResponse {
         S: Serializer,
     {
         let mut s = match self {
-            VerifyResponse::Valid { .. } => serializer.serialize_struct("VerifyResponse", 2)?,
+            VerifyResponse::Valid { .. } => serializer.serialize_struct("VerifyResponse", 3)?,
             VerifyResponse::Invalid { .. } => serializer.serialize_struct("VerifyResponse", 3)?,
         };
 
         match self {
-            VerifyResponse::Valid { payer } => {
+            VerifyResponse::Valid {
+                payer,
+                payment_id,
+                extensions,
+            } => {
                 s.serialize_field("isValid", &true)?;
                 s.serialize_field("payer", payer)?;
+                if let Some(payment_id) = payment_id {
+                    s.serialize_field("paymentId", payment_id)?
+                }
+                if let Some(extensions) = extensions {
+                    s.serialize_field("extensions", extensions)?
+                }
             }
             VerifyResponse::Invalid { reason, payer } => {
                 s.serialize_field("isValid", &false)?;
@@ -1038,6 +1547,10 @@ impl<'de> Deserialize<'de> for VerifyResponse {
             payer: Option<MixedAddress>,
             #[serde(default)]
             invalid_reason: Option<FacilitatorErrorReason>,
+            #[serde(default)]
+            payment_id: Option<PaymentId>,
+            #[serde(default)]
+            extensions: Option<Extensions>,
         }
 
         let raw = Raw::deserialize(deserializer)?;
@@ -1047,7 +1560,11 @@ impl<'de> Deserialize<'de> for VerifyResponse {
                 None => Err(serde::de::Error::custom(
                     "`payer` must be present when `isValid` is true",
                 )),
-                Some(payer) => Ok(VerifyResponse::Valid { payer }),
+                Some(payer) => Ok(VerifyResponse::Valid {
+                    payer,
+                    payment_id: raw.payment_id,
+                    extensions: raw.extensions,
+                }),
             },
             (false, Some(reason)) => Ok(VerifyResponse::Invalid {
                 payer: raw.payer,
@@ -1071,6 +1588,64 @@ pub struct ErrorResponse {
     pub error: String,
 }
 
+/// Returned with `400 Bad Request` for a request in an `x402Version` this facilitator does not support.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsupportedVersionResponse {
+    pub error: String,
+    /// The version sent.
+    pub x402_version: u8,
+    pub min_supported_version: X402Version,
+    pub max_supported_version: X402Version,
+}
+
+impl From<&X402VersionError> for UnsupportedVersionResponse {
+    fn from(error: &X402VersionError) -> Self {
+        Self {
+            error: error.to_string(),
+            x402_version: error.0,
+            min_supported_version: X402Version::min_supported(),
+            max_supported_version: X402Version::max_supported(),
+        }
+    }
+}
+
+/// Returned with `400 Bad Request` for a request whose payload or requirements name an unknown scheme.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnknownSchemeResponse {
+    pub error: String,
+    /// Always `invalid_scheme`, see [`FacilitatorErrorReason::InvalidScheme`].
+    pub invalid_reason: String,
+    /// The scheme sent.
+    pub scheme: String,
+    pub supported_schemes: Vec<Scheme>,
+}
+
+impl UnknownSchemeResponse {
+    pub fn new(scheme: String) -> Self {
+        let supported = Scheme::variants()
+            .iter()
+            .map(Scheme::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        Self {
+            error: format!("Unknown scheme {scheme:?}, expected one of: {supported}"),
+            invalid_reason: FacilitatorErrorReason::InvalidScheme.to_string(),
+            scheme,
+            supported_schemes: Scheme::variants().to_vec(),
+        }
+    }
+}
+
+/// Returned with `406 Not Acceptable` when the `Accept` header rules out every supported response media type.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotAcceptableResponse {
+    pub error: String,
+    pub supported_media_types: Vec<String>,
+}
+
 /// Contains bytes of base64 encoded some other bytes.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Base64Bytes<'a>(pub Cow<'a, [u8]>);
@@ -1128,7 +1703,8 @@ impl MoneyAmount {
     /// For example, `$0.01` becomes `10000` when targeting a token with 6 decimals.
     ///
     /// Returns an error if the precision of the money amount exceeds the allowed token precision,
-    /// to prevent unintentional truncation or rounding errors.
+    /// to prevent unintentional truncation or rounding errors. Trailing zeros do not count towards
+    /// precision: `"0.0100000"` is accepted for a 6-decimal token.
     ///
     /// This method is useful for converting user-input values like `"0.01"` into
     /// canonical [`U256`] token amounts that are expected in protocol-layer messages.
@@ -1137,7 +1713,7 @@ impl MoneyAmount {
         &self,
         token_decimals: u32,
     ) -> Result<TokenAmount, MoneyAmountParseError> {
-        let money_amount = self;
+        let money_amount = MoneyAmount(self.0.normalize());
         let money_decimals = money_amount.scale();
         if money_decimals > token_decimals {
             return Err(MoneyAmountParseError::WrongPrecision {
@@ -1402,6 +1978,37 @@ pub struct SupportedPaymentKind {
     pub network: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extra: Option<SupportedPaymentKindExtra>,
+    /// Tokens accepted for this kind, when enumerated on-chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assets: Option<Vec<SupportedAsset>>,
+    /// Tokens the network is restricted to, when an asset allowlist is configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_assets: Option<Vec<MixedAddress>>,
+    /// ERC-3009 functions payments can be settled with, on EVM networks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorization_kinds: Option<Vec<AuthorizationKind>>,
+    /// Payment amounts accepted per token, for tokens with a configured minimum or maximum.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_bounds: Option<Vec<AssetAmountBounds>>,
+}
+
+/// A token accepted for a [`SupportedPaymentKind`], with the decimals of its amounts.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportedAsset {
+    pub address: MixedAddress,
+    pub decimals: u8,
+}
+
+/// The range of payment amounts accepted in a token, in token units; a missing bound is unlimited.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetAmountBounds {
+    pub asset: MixedAddress,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_amount: Option<TokenAmount>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_amount: Option<TokenAmount>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -1437,3 +2044,95 @@ sol!(
         bytes32 nonce;
     }
 );
+
+sol!(
+    /// Solidity-compatible struct definition for ERC-3009 `receiveWithAuthorization`.
+    ///
+    /// Same fields as [`TransferWithAuthorization`], under another EIP-712 type hash, so an
+    /// authorization signed for one function can not be submitted to the other.
+    #[derive(Serialize, Deserialize)]
+    struct ReceiveWithAuthorization {
+        address from;
+        address to;
+        uint256 value;
+        uint256 validAfter;
+        uint256 validBefore;
+        bytes32 nonce;
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn money_amount_within_token_precision() {
+        let amount = MoneyAmount::parse("0.01").unwrap();
+        assert_eq!(
+            amount.as_token_amount(6).unwrap(),
+            TokenAmount::from(10_000u64)
+        );
+        let amount = MoneyAmount::parse("1.123456").unwrap();
+        assert_eq!(
+            amount.as_token_amount(6).unwrap(),
+            TokenAmount::from(1_123_456u64)
+        );
+    }
+
+    #[test]
+    fn money_amount_over_precise_is_rejected() {
+        let amount = MoneyAmount::parse("0.0000001").unwrap();
+        let err = amount.as_token_amount(6).unwrap_err();
+        assert!(matches!(
+            err,
+            MoneyAmountParseError::WrongPrecision { money: 7, token: 6 }
+        ));
+        let amount = MoneyAmount::parse("1.5").unwrap();
+        assert!(matches!(
+            amount.as_token_amount(0),
+            Err(MoneyAmountParseError::WrongPrecision { money: 1, token: 0 })
+        ));
+    }
+
+    #[test]
+    fn money_amount_trailing_zeros_do_not_count_as_precision() {
+        let amount = MoneyAmount::parse("0.0100000").unwrap();
+        assert_eq!(
+            amount.as_token_amount(6).unwrap(),
+            TokenAmount::from(10_000u64)
+        );
+        let amount = MoneyAmount::parse("2.000").unwrap();
+        assert_eq!(amount.as_token_amount(0).unwrap(), TokenAmount::from(2u64));
+    }
+
+    #[test]
+    fn amount_scheme_bounds_value() {
+        use std::cmp::Ordering;
+        let exact = AmountScheme::from(Scheme::Exact);
+        assert_eq!(exact.compare(9, 10), Ordering::Less);
+        assert_eq!(exact.compare(10, 10), Ordering::Equal);
+        assert_eq!(exact.compare(11, 10), Ordering::Greater);
+        let upto = AmountScheme::from(Scheme::Upto);
+        assert_eq!(upto.compare(1, 10), Ordering::Equal);
+        assert_eq!(upto.compare(10, 10), Ordering::Equal);
+        assert_eq!(upto.compare(11, 10), Ordering::Greater);
+        assert_eq!(upto.compare(0, 10), Ordering::Less);
+    }
+
+    #[test]
+    fn extensions_must_be_namespaced_and_are_echoed() {
+        let extensions: Extensions =
+            serde_json::from_value(serde_json::json!({"acme.orderRef": "A-1042"})).unwrap();
+        for key in ["orderRef", ".orderRef", "acme."] {
+            assert!(serde_json::from_value::<Extensions>(serde_json::json!({ key: 1 })).is_err());
+        }
+        let response = VerifyResponse::valid(MixedAddress::Offchain("payer".to_string()))
+            .with_extensions(Some(extensions.clone()));
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["extensions"]["acme.orderRef"], "A-1042");
+        assert!(matches!(
+            serde_json::from_value(json).unwrap(),
+            VerifyResponse::Valid { extensions: Some(echoed), .. } if echoed == extensions
+        ));
+    }
+}
@@ -0,0 +1,178 @@
+//! Bounded TTL cache of `/verify` results.
+//!
+//! Clients retrying a verification, e.g. after a network hiccup, send the same request again.
+//! [`FacilitatorLocal`](crate::facilitator_local::FacilitatorLocal) answers such repeats from this cache,
+//! keyed by the hash of the request, instead of recovering the signature and querying the chain again.
+//! Failed verifications, i.e. errors rather than [`VerifyResponse::Invalid`], are not cached.
+//!
+//! An entry lives for the configured TTL, but never past the `validBefore` of an EVM authorization, so an
+//! expired authorization is never reported valid. A settlement of the request drops its entry.
+//! Hits are counted in [`Metrics`].
+//!
+//! Environment variables used:
+//! - `VERIFY_CACHE_TTL_SECS` — how long a result is reused (default: `0`, disabled),
+//! - `VERIFY_CACHE_SIZE` — maximum number of cached results (default: `10000`).
+
+use alloy::primitives::B256;
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+
+use crate::from_env::{ENV_VERIFY_CACHE_SIZE, ENV_VERIFY_CACHE_TTL_SECS};
+use crate::metrics::Metrics;
+use crate::timestamp::UnixTimestamp;
+use crate::types::{ExactPaymentPayload, VerifyRequest, VerifyResponse};
+
+#[derive(Debug)]
+struct CachedVerify {
+    response: VerifyResponse,
+    expires_at: Instant,
+}
+
+/// Cache of verification results, see the [module docs](self).
+#[derive(Debug)]
+pub struct VerifyCache {
+    ttl: Duration,
+    capacity: usize,
+    entries: DashMap<B256, CachedVerify>,
+}
+
+impl VerifyCache {
+    /// Creates a cache keeping at most `capacity` results for up to `ttl` each.
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            entries: DashMap::new(),
+        }
+    }
+
+    /// Reads `VERIFY_CACHE_TTL_SECS` and `VERIFY_CACHE_SIZE`; `None` if caching is disabled.
+    pub fn from_env() -> Option<Self> {
+        let ttl = std::env::var(ENV_VERIFY_CACHE_TTL_SECS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(0);
+        let capacity = std::env::var(ENV_VERIFY_CACHE_SIZE)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(10_000);
+        (ttl > 0 && capacity > 0).then(|| Self::new(Duration::from_secs(ttl), capacity))
+    }
+
+    /// The cached result for the request hashed to `key`, if still live.
+    pub fn get(&self, key: &B256) -> Option<VerifyResponse> {
+        let response = {
+            let entry = self.entries.get(key)?;
+            (entry.expires_at > Instant::now()).then(|| entry.response.clone())
+        };
+        match response {
+            Some(response) => {
+                Metrics::global().count_verify_cache_hit();
+                Some(response)
+            }
+            None => {
+                self.entries
+                    .remove_if(key, |_, entry| entry.expires_at <= Instant::now());
+                None
+            }
+        }
+    }
+
+    /// Caches `response` to `request`, hashed to `key`.
+    ///
+    /// When full, expired entries are dropped first; if none are, the result is not cached.
+    pub fn insert(&self, key: B256, request: &VerifyRequest, response: VerifyResponse) {
+        let Some(lifetime) = self.lifetime(request) else {
+            return;
+        };
+        if self.entries.len() >= self.capacity {
+            let now = Instant::now();
+            self.entries.retain(|_, entry| entry.expires_at > now);
+            if self.entries.len() >= self.capacity {
+                return;
+            }
+        }
+        let expires_at = Instant::now() + lifetime;
+        self.entries.insert(
+            key,
+            CachedVerify {
+                response,
+                expires_at,
+            },
+        );
+    }
+
+    /// Drops the result cached for the request hashed to `key`, e.g. once it is settled.
+    pub fn invalidate(&self, key: &B256) {
+        self.entries.remove(key);
+    }
+
+    /// How long a result to `request` may be reused: the TTL, capped by the authorization's `validBefore`.
+    fn lifetime(&self, request: &VerifyRequest) -> Option<Duration> {
+        match &request.payment_payload.payload {
+            ExactPaymentPayload::Evm(payload) => {
+                let now = UnixTimestamp::try_now().ok()?;
+                let remaining = payload.authorization.valid_before.0.checked_sub(now.0)?;
+                let lifetime = self.ttl.min(Duration::from_secs(remaining));
+                (!lifetime.is_zero()).then_some(lifetime)
+            }
+            ExactPaymentPayload::Solana(_) => Some(self.ttl),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(valid_before: u64) -> VerifyRequest {
+        serde_json::from_value(serde_json::json!({
+            "x402Version": 1,
+            "paymentPayload": {
+                "x402Version": 1,
+                "scheme": "exact",
+                "network": "base-sepolia",
+                "payload": {
+                    "signature": format!("0x{}", "11".repeat(65)),
+                    "authorization": {
+                        "from": "0x0000000000000000000000000000000000000001",
+                        "to": "0x0000000000000000000000000000000000000402",
+                        "value": "1000000",
+                        "validAfter": "0",
+                        "validBefore": valid_before.to_string(),
+                        "nonce": format!("0x{}", "07".repeat(32)),
+                    }
+                }
+            },
+            "paymentRequirements": {
+                "scheme": "exact",
+                "network": "base-sepolia",
+                "maxAmountRequired": "1000000",
+                "resource": "https://example.com/paid",
+                "description": "",
+                "mimeType": "application/json",
+                "payTo": "0x0000000000000000000000000000000000000402",
+                "maxTimeoutSeconds": 60,
+                "asset": "0x036CbD53842c5426634e7929541eC2318f3dCF7e",
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cache_never_outlives_valid_before() {
+        let cache = VerifyCache::new(Duration::from_secs(60), 16);
+        let now = UnixTimestamp::try_now().unwrap().0;
+        let response = VerifyResponse::valid(crate::types::MixedAddress::Offchain("payer".into()));
+
+        let live = B256::repeat_byte(1);
+        cache.insert(live, &request(now + 600), response.clone());
+        assert!(cache.get(&live).is_some());
+        cache.invalidate(&live);
+        assert!(cache.get(&live).is_none());
+
+        let expired = B256::repeat_byte(2);
+        cache.insert(expired, &request(now - 1), response);
+        assert!(cache.get(&expired).is_none());
+    }
+}